@@ -2,21 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::beacon_entries::BeaconEntry;
+use super::drand_servers::DrandServers;
 use ahash::AHashMap;
+use async_std::future;
 use async_std::sync::RwLock;
 use async_trait::async_trait;
 use bls_signatures::{PublicKey, Serialize, Signature};
 use byteorder::{BigEndian, WriteBytesExt};
 use clock::ChainEpoch;
-use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
+use db::{Column, Store};
+use log::warn;
+use serde::{Deserialize as SerdeDeserialize, Deserializer, Serialize as SerdeSerialize};
 use sha2::Digest;
 use std::borrow::Cow;
 use std::error;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Enviromental Variable to ignore Drand. Lotus parallel is LOTUS_IGNORE_DRAND
 pub const IGNORE_DRAND_VAR: &str = "IGNORE_DRAND";
 
+/// Time after which a request to a drand server is abandoned for the next server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Coeffiencients of the publicly available Drand keys.
 /// This is shared by all participants on the Drand network.
 #[derive(Clone, Debug, SerdeSerialize, SerdeDeserialize)]
@@ -32,15 +40,34 @@ impl DrandPublic {
     }
 }
 
-#[derive(Clone)]
+#[derive(SerdeDeserialize, SerdeSerialize, Debug, Clone, PartialEq)]
 /// Config used when initializing a Drand beacon.
 pub struct DrandConfig<'a> {
-    /// Url endpoint to send JSON http requests to.
-    pub server: &'static str,
+    /// Url endpoints to send JSON http requests to, tried in turn when one of them fails.
+    /// Configs written for a single server, as `server = "<url>"`, are also accepted.
+    #[serde(alias = "server", deserialize_with = "deserialize_servers")]
+    pub servers: Vec<Cow<'a, str>>,
     /// Info about the beacon chain, used to verify correctness of endpoint.
     pub chain_info: ChainInfo<'a>,
 }
 
+/// Deserializes either a list of drand servers or a single one.
+fn deserialize_servers<'de, 'a, D>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(SerdeDeserialize)]
+    #[serde(untagged)]
+    enum Servers {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Servers::deserialize(deserializer)? {
+        Servers::One(server) => vec![Cow::Owned(server)],
+        Servers::Many(servers) => servers.into_iter().map(Cow::Owned).collect(),
+    })
+}
+
 /// Contains the vector of BeaconPoints, which are mappings of epoch to the Randomness beacons used.
 pub struct BeaconSchedule<T>(pub Vec<BeaconPoint<T>>);
 
@@ -92,6 +119,22 @@ where
         Ok(out)
     }
 
+    /// Fetches the beacon entries of the given epoch ahead of time, so that they are cached when
+    /// the blocks of the epoch are validated.
+    pub async fn prefetch(&self, epoch: ChainEpoch) -> Result<(), Box<dyn error::Error>> {
+        let (_, beacon) = self.beacon_for_epoch(epoch)?;
+        let max_round = beacon.max_beacon_round_for_epoch(epoch);
+        let first_round = if epoch > 1 {
+            beacon.max_beacon_round_for_epoch(epoch - 1) + 1
+        } else {
+            max_round
+        };
+        for round in first_round..=max_round {
+            beacon.entry(round).await?;
+        }
+        Ok(())
+    }
+
     pub fn beacon_for_epoch(
         &self,
         epoch: ChainEpoch,
@@ -160,7 +203,7 @@ pub struct BeaconEntryJson {
 /// Drand randomness beacon that can be used to generate randomness for the Filecoin chain.
 /// Primary use is to satisfy the [Beacon] trait.
 pub struct DrandBeacon {
-    url: &'static str,
+    servers: DrandServers,
 
     pub_key: DrandPublic,
     /// Interval between beacons, in seconds.
@@ -171,6 +214,8 @@ pub struct DrandBeacon {
 
     /// Keeps track of computed beacon entries.
     local_cache: RwLock<AHashMap<u64, BeaconEntry>>,
+    /// Persists the verified beacon entries across restarts.
+    store: Option<Box<dyn EntryStore>>,
 }
 
 impl DrandBeacon {
@@ -186,15 +231,19 @@ impl DrandBeacon {
 
         let chain_info = &config.chain_info;
 
+        if config.servers.is_empty() {
+            return Err("no drand server configured".into());
+        }
+
         if cfg!(debug_assertions) {
-            let remote_chain_info: ChainInfo = surf::get(&format!("{}/info", &config.server))
+            let remote_chain_info: ChainInfo = surf::get(&format!("{}/info", &config.servers[0]))
                 .recv_json()
                 .await?;
             debug_assert!(&remote_chain_info == chain_info);
         }
 
         Ok(Self {
-            url: config.server,
+            servers: DrandServers::new(config.servers.iter().map(|s| s.to_string()).collect()),
             pub_key: DrandPublic {
                 coefficient: hex::decode(chain_info.public_key.as_ref())?,
             },
@@ -203,8 +252,161 @@ impl DrandBeacon {
             fil_round_time: interval,
             fil_gen_time: genesis_ts,
             local_cache: Default::default(),
+            store: None,
         })
     }
+
+    /// Persists the verified beacon entries in the given database, from which they are read
+    /// back instead of being fetched again from the drand servers.
+    pub fn with_db<DB>(mut self, db: Arc<DB>) -> Self
+    where
+        DB: Store + Send + Sync + 'static,
+    {
+        self.store = Some(Box::new(DbEntryStore {
+            db,
+            prefix: format!("drand/{}", hex::encode(&self.pub_key.coefficient)),
+        }));
+        self
+    }
+
+    /// Verifies the signature of a beacon entry, chained to the signature of the previous one.
+    fn verify_signature(
+        &self,
+        curr: &BeaconEntry,
+        prev: &BeaconEntry,
+    ) -> Result<bool, Box<dyn error::Error>> {
+        // Hash the messages
+        let mut msg: Vec<u8> = Vec::with_capacity(104);
+        msg.extend_from_slice(prev.data());
+        msg.write_u64::<BigEndian>(curr.round())?;
+        // H(prev sig | curr_round)
+        let digest = sha2::Sha256::digest(&msg);
+        // Signature
+        let sig = Signature::from_bytes(curr.data())?;
+        Ok(bls_signatures::verify_messages(
+            &sig,
+            &[&digest],
+            &[self.pub_key.key()?],
+        ))
+    }
+
+    /// Caches a verified beacon entry, and persists it if a database is set.
+    async fn cache_entry(&self, entry: &BeaconEntry) {
+        if self.local_cache.read().await.contains_key(&entry.round()) {
+            return;
+        }
+        if let Some(store) = &self.store {
+            if let Err(e) = store.put(entry) {
+                warn!("Failed to persist beacon entry {}: {}", entry.round(), e);
+            }
+        }
+        self.local_cache
+            .write()
+            .await
+            .insert(entry.round(), entry.clone());
+    }
+
+    /// Fetches the entry of a round from the drand servers, trying each server in turn until one
+    /// returns a valid entry.
+    async fn fetch_entry(&self, round: u64) -> Result<BeaconEntry, String> {
+        // Servers are not at fault for failing to return a round which may not be published yet
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let recent = self.is_recent_round(round, now);
+        let mut errors = Vec::new();
+        for index in self.servers.ordered(Instant::now()).await {
+            let url = format!("{}/public/{}", self.servers.url(index), round);
+            match self.fetch_entry_from(&url, round).await {
+                Ok(entry) => {
+                    self.servers.record_success(index).await;
+                    return Ok(entry);
+                }
+                Err(e) => {
+                    if !recent {
+                        self.servers.record_failure(index, Instant::now()).await;
+                    }
+                    errors.push(format!("{}: {}", url, e));
+                }
+            }
+        }
+        Err(format!(
+            "failed to fetch beacon entry {}: {}",
+            round,
+            errors.join(", ")
+        ))
+    }
+
+    /// Returns whether the round is due less than one period before `now`, in seconds since the
+    /// unix epoch, or is not due yet, so that drand may not have published it.
+    fn is_recent_round(&self, round: u64, now: u64) -> bool {
+        let round_time = self.drand_gen_time + round.saturating_sub(1) * self.interval;
+        round_time + self.interval > now
+    }
+
+    /// Fetches the entry of a round from a drand server, verifying it against the previous
+    /// signature returned with it.
+    async fn fetch_entry_from(&self, url: &str, round: u64) -> Result<BeaconEntry, String> {
+        let resp: BeaconEntryJson = future::timeout(REQUEST_TIMEOUT, surf::get(url).recv_json())
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        if resp.round != round {
+            return Err(format!("expected round {}, got {}", round, resp.round));
+        }
+        let entry = BeaconEntry::new(
+            resp.round,
+            hex::decode(resp.signature).map_err(|e| e.to_string())?,
+        );
+        let prev = BeaconEntry::new(
+            round.saturating_sub(1),
+            hex::decode(resp.previous_signature).map_err(|e| e.to_string())?,
+        );
+        match self.verify_signature(&entry, &prev) {
+            Ok(true) => Ok(entry),
+            Ok(false) => Err("invalid entry signature".to_owned()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Storage of the verified entries of a drand beacon.
+trait EntryStore: Send + Sync {
+    fn get(&self, round: u64) -> Result<Option<BeaconEntry>, Box<dyn error::Error>>;
+    fn put(&self, entry: &BeaconEntry) -> Result<(), Box<dyn error::Error>>;
+}
+
+/// Stores the entries of a drand beacon in the node database, keyed by the public key of the
+/// drand network and the round.
+struct DbEntryStore<DB> {
+    db: Arc<DB>,
+    prefix: String,
+}
+
+impl<DB> DbEntryStore<DB> {
+    fn key(&self, round: u64) -> String {
+        format!("{}/{}", self.prefix, round)
+    }
+}
+
+impl<DB> EntryStore for DbEntryStore<DB>
+where
+    DB: Store + Send + Sync,
+{
+    fn get(&self, round: u64) -> Result<Option<BeaconEntry>, Box<dyn error::Error>> {
+        match self.db.read_column(Column::Indices, self.key(round))? {
+            Some(bz) => Ok(Some(encoding::from_slice(&bz)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, entry: &BeaconEntry) -> Result<(), Box<dyn error::Error>> {
+        let bz = encoding::to_vec(entry)?;
+        Ok(self
+            .db
+            .write_column(Column::Indices, self.key(entry.round()), bz)?)
+    }
 }
 
 #[async_trait]
@@ -219,37 +421,39 @@ impl Beacon for DrandBeacon {
             return Ok(true);
         }
 
-        // Hash the messages
-        let mut msg: Vec<u8> = Vec::with_capacity(104);
-        msg.extend_from_slice(prev.data());
-        msg.write_u64::<BigEndian>(curr.round())?;
-        // H(prev sig | curr_round)
-        let digest = sha2::Sha256::digest(&msg);
-        // Signature
-        let sig = Signature::from_bytes(curr.data())?;
-        let sig_match = bls_signatures::verify_messages(&sig, &[&digest], &[self.pub_key.key()?]);
+        let sig_match = self.verify_signature(curr, prev)?;
 
         // Cache the result
-        let contains_curr = self.local_cache.read().await.contains_key(&curr.round());
-        if sig_match && !contains_curr {
-            self.local_cache
-                .write()
-                .await
-                .insert(curr.round(), curr.clone());
+        if sig_match {
+            self.cache_entry(curr).await;
         }
         Ok(sig_match)
     }
 
     async fn entry(&self, round: u64) -> Result<BeaconEntry, Box<dyn error::Error>> {
         let cached: Option<BeaconEntry> = self.local_cache.read().await.get(&round).cloned();
-        match cached {
-            Some(cached_entry) => Ok(cached_entry),
-            None => {
-                let url = format!("{}/public/{}", self.url, round);
-                let resp: BeaconEntryJson = surf::get(&url).recv_json().await?;
-                Ok(BeaconEntry::new(resp.round, hex::decode(resp.signature)?))
-            }
+        if let Some(cached_entry) = cached {
+            return Ok(cached_entry);
         }
+
+        let stored = match &self.store {
+            Some(store) => store.get(round).unwrap_or_else(|e| {
+                warn!("Failed to read persisted beacon entry {}: {}", round, e);
+                None
+            }),
+            None => None,
+        };
+        if let Some(stored_entry) = stored {
+            self.local_cache
+                .write()
+                .await
+                .insert(round, stored_entry.clone());
+            return Ok(stored_entry);
+        }
+
+        let entry = self.fetch_entry(round).await?;
+        self.cache_entry(&entry).await;
+        Ok(entry)
     }
 
     fn max_beacon_round_for_epoch(&self, fil_epoch: ChainEpoch) -> u64 {
@@ -258,3 +462,28 @@ impl Beacon for DrandBeacon {
         (latest_ts - self.drand_gen_time) / self.interval
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_rounds() {
+        let beacon = DrandBeacon {
+            servers: DrandServers::new(vec!["a".to_owned()]),
+            pub_key: DrandPublic {
+                coefficient: Vec::new(),
+            },
+            interval: 30,
+            drand_gen_time: 1000,
+            fil_gen_time: 1000,
+            fil_round_time: 30,
+            local_cache: Default::default(),
+            store: None,
+        };
+        // Round 3 is due at 1060
+        assert!(beacon.is_recent_round(3, 1000));
+        assert!(beacon.is_recent_round(3, 1089));
+        assert!(!beacon.is_recent_round(3, 1090));
+    }
+}
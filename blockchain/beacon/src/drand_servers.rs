@@ -0,0 +1,84 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use async_std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// Delay before a server which failed once is tried first again.
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+/// The backoff of a failing server doubles with each consecutive failure, up to this delay.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// HTTP endpoints of a drand network, with the health of each of them.
+///
+/// Servers which failed are tried after the healthy ones until their backoff elapses, so that a
+/// server which is down doesn't add its timeout to every request.
+pub(crate) struct DrandServers {
+    urls: Vec<String>,
+    health: RwLock<Vec<Health>>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Health {
+    /// Number of consecutive failed requests.
+    failures: u32,
+    /// Time before which the server is only tried after the healthy ones.
+    backoff_until: Option<Instant>,
+}
+
+impl DrandServers {
+    pub(crate) fn new(urls: Vec<String>) -> Self {
+        let health = RwLock::new(vec![Health::default(); urls.len()]);
+        Self { urls, health }
+    }
+
+    pub(crate) fn url(&self, index: usize) -> &str {
+        &self.urls[index]
+    }
+
+    /// Returns the indices of the servers in the order they should be tried: healthy servers
+    /// first, then the ones backing off, each by increasing number of consecutive failures.
+    pub(crate) async fn ordered(&self, now: Instant) -> Vec<usize> {
+        let health = self.health.read().await;
+        let mut indices: Vec<usize> = (0..self.urls.len()).collect();
+        indices.sort_by_key(|&i| {
+            let backing_off = health[i].backoff_until.map_or(false, |until| now < until);
+            (backing_off, health[i].failures)
+        });
+        indices
+    }
+
+    pub(crate) async fn record_success(&self, index: usize) {
+        self.health.write().await[index] = Health::default();
+    }
+
+    pub(crate) async fn record_failure(&self, index: usize, now: Instant) {
+        let mut health = self.health.write().await;
+        let server = &mut health[index];
+        let backoff = BASE_BACKOFF * 2u32.pow(server.failures.min(6));
+        server.failures += 1;
+        server.backoff_until = Some(now + backoff.min(MAX_BACKOFF));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[async_std::test]
+    async fn failover_order() {
+        let servers = DrandServers::new(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        let now = Instant::now();
+        assert_eq!(servers.ordered(now).await, vec![0, 1, 2]);
+
+        servers.record_failure(0, now).await;
+        servers.record_failure(0, now).await;
+        servers.record_failure(1, now).await;
+        assert_eq!(servers.ordered(now).await, vec![2, 1, 0]);
+
+        // Once their backoff elapsed, the servers are ordered by failures
+        assert_eq!(servers.ordered(now + MAX_BACKOFF).await, vec![2, 1, 0]);
+        servers.record_success(0).await;
+        assert_eq!(servers.ordered(now).await, vec![0, 2, 1]);
+    }
+}
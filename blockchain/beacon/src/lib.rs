@@ -3,6 +3,7 @@
 
 pub mod beacon_entries;
 mod drand;
+mod drand_servers;
 mod mock_beacon;
 
 pub use beacon_entries::*;
@@ -10,7 +10,7 @@ async fn new_beacon() -> DrandBeacon {
         25,
         // TODO this could maybe be referencing existing config
         &DrandConfig {
-            server: "https://pl-us.incentinet.drand.sh",
+            servers: vec!["https://pl-us.incentinet.drand.sh".into()],
             chain_info: ChainInfo {
                 public_key: "922a2e93828ff83345bae533f5172669a26c02dc76d6bf59c80892e12ab1455c229211886f35bb56af6d5bea981024df"
                     .into(),
@@ -55,3 +55,21 @@ async fn ask_and_verify_beacon_entry_fail() {
     let e3 = beacon.entry(3).await.unwrap();
     assert!(!beacon.verify_entry(&e2, &e3).await.unwrap());
 }
+
+#[test]
+fn deserialize_single_server_config() {
+    let config: DrandConfig = serde_json::from_str(
+        r#"{
+            "server": "https://api.drand.sh",
+            "chain_info": {
+                "public_key": "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31",
+                "period": 30,
+                "genesis_time": 1595431050,
+                "hash": "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce",
+                "groupHash": "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.servers, vec!["https://api.drand.sh"]);
+}
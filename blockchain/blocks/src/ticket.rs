@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crypto::VRFProof;
-use encoding::tuple::*;
+use encoding::{blake2b_256, tuple::*};
 
 /// A Ticket is a marker of a tick of the blockchain's clock.  It is the source
 /// of randomness for proofs of storage and leader election.  It is generated
@@ -18,6 +18,16 @@ impl Ticket {
     pub fn new(vrfproof: VRFProof) -> Self {
         Self { vrfproof }
     }
+
+    /// Returns the quality of the ticket, between 0 and 1. Smaller tickets, which come first in
+    /// their tipset, have a higher quality.
+    pub fn quality(&self) -> f64 {
+        let hash = blake2b_256(self.vrfproof.as_bytes());
+        // The 64 most significant bits of the hash are all an f64 can represent anyway
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&hash[..8]);
+        1.0 - u64::from_be_bytes(head) as f64 / 2f64.powi(64)
+    }
 }
 
 #[cfg(feature = "json")]
@@ -5,21 +5,23 @@ use super::{index::ChainIndex, tipset_tracker::TipsetTracker, Error};
 use actor::{miner, power};
 use address::Address;
 use async_std::channel::{self, bounded, Receiver};
-use async_std::sync::RwLock;
+use async_std::sync::{RwLock, RwLockReadGuard};
 use async_std::task;
 use beacon::{BeaconEntry, IGNORE_DRAND_VAR};
 use blake2b_simd::Params;
 use blocks::{Block, BlockHeader, FullTipset, Tipset, TipsetKeys, TxMeta};
+use bls_signatures::Serialize as SerializeBls;
 use byteorder::{BigEndian, WriteBytesExt};
 use cid::Cid;
 use cid::Code::Blake2b256;
 use clock::ChainEpoch;
 use crossbeam::atomic::AtomicCell;
-use crypto::DomainSeparationTag;
+use crypto::{DomainSeparationTag, Signature, SignatureType};
+use db::Column;
 use encoding::{blake2b_256, de::DeserializeOwned, from_slice, Cbor};
 use forest_car::CarHeader;
 use forest_ipld::recurse_links;
-use futures::AsyncWrite;
+use futures::{AsyncWrite, AsyncWriteExt};
 use interpreter::BlockMessages;
 use ipld_amt::Amt;
 use ipld_blockstore::BlockStore;
@@ -42,6 +44,7 @@ use tokio::sync::broadcast::{self, error::RecvError, Sender as Publisher};
 
 const GENESIS_KEY: &str = "gen_block";
 const HEAD_KEY: &str = "head";
+const CHECKPOINT_KEY: &str = "checkpoint";
 const BLOCK_VAL_PREFIX: &[u8] = b"block_val/";
 
 // constants for Weight calculation
@@ -54,6 +57,9 @@ const BLOCKS_PER_EPOCH: u64 = 5;
 // A cap on the size of the future_sink
 const SINK_CAP: usize = 200;
 
+/// Number of blocks written between progress logs during a chain export.
+const EXPORT_PROGRESS_INTERVAL: u64 = 100_000;
+
 const DEFAULT_TIPSET_CACHE_SIZE: usize = 8192;
 
 /// Enum for pubsub channel that defines message type variant and data contained in message type.
@@ -81,7 +87,10 @@ pub struct ChainStore<DB> {
     pub db: Arc<DB>,
 
     /// Tipset at the head of the best-known chain.
-    heaviest: RwLock<Option<Arc<Tipset>>>,
+    pub(super) heaviest: RwLock<Option<Arc<Tipset>>>,
+
+    /// Tipset set by the operator which every new head must include.
+    checkpoint: RwLock<Option<Arc<Tipset>>>,
 
     /// Caches loaded tipsets for fast retrieval.
     ts_cache: Arc<TipsetCache>,
@@ -90,7 +99,13 @@ pub struct ChainStore<DB> {
     chain_index: ChainIndex<DB>,
 
     /// Tracks blocks for the purpose of forming tipsets.
-    tipset_tracker: TipsetTracker<DB>,
+    pub(super) tipset_tracker: TipsetTracker<DB>,
+
+    /// Set while a garbage collection is running, to prevent concurrent collections.
+    pub(super) gc_running: AtomicCell<bool>,
+
+    /// Held for writing by the sweep of a garbage collection, see [ChainStore::pause_gc].
+    pub(super) gc_guard: RwLock<()>,
 }
 
 impl<DB> ChainStore<DB>
@@ -109,24 +124,112 @@ where
             db,
             ts_cache,
             heaviest: Default::default(),
+            checkpoint: Default::default(),
+            gc_running: AtomicCell::new(false),
+            gc_guard: Default::default(),
         };
 
         // Result intentionally ignored, doesn't matter if heaviest doesn't exist in store yet
         let _ = task::block_on(cs.load_heaviest_tipset());
+        if let Err(e) = task::block_on(cs.load_checkpoint()) {
+            warn!("Failed to load the checkpoint: {}", e);
+        }
 
         cs
     }
 
     /// Sets heaviest tipset within ChainStore and store its tipset cids under HEAD_KEY
     pub async fn set_heaviest_tipset(&self, ts: Arc<Tipset>) -> Result<(), Error> {
-        self.db.write(HEAD_KEY, ts.key().marshal_cbor()?)?;
+        self.db
+            .write_column(Column::Metadata, HEAD_KEY, ts.key().marshal_cbor()?)?;
         *self.heaviest.write().await = Some(ts.clone());
+        if let Err(e) = self.index_head(ts.clone()).await {
+            warn!("failed to index messages of new head: {}", e);
+        }
         if self.publisher.send(HeadChange::Apply(ts)).is_err() {
             debug!("did not publish head change, no active receivers");
         }
         Ok(())
     }
 
+    /// Forcibly sets the heaviest tipset, even if it is lighter than the current one. The new
+    /// head must include the checkpoint, if one is set.
+    pub async fn set_head(&self, ts: Arc<Tipset>) -> Result<(), Error> {
+        if !self.includes_checkpoint(ts.clone()).await? {
+            return Err(Error::Other(format!(
+                "tipset {:?} does not include the checkpoint",
+                ts.cids()
+            )));
+        }
+        info!("Setting head to tipset {:?}", ts.key());
+        self.set_heaviest_tipset(ts).await
+    }
+
+    /// Returns a guard which keeps a garbage collection from sweeping until it is dropped.
+    ///
+    /// Blocks written before a collection starts are only kept if they are reachable from the
+    /// head or from a tracked tipset. Hold the guard from before writing the blocks of a tipset
+    /// until it is put in the store or set as the head. Don't acquire it twice in a task.
+    pub async fn pause_gc(&self) -> RwLockReadGuard<'_, ()> {
+        self.gc_guard.read().await
+    }
+
+    /// Returns the tipset checkpointed by the operator, if any.
+    pub async fn checkpoint(&self) -> Option<Arc<Tipset>> {
+        self.checkpoint.read().await.clone()
+    }
+
+    /// Sets a tipset as checkpoint. Until the checkpoint is changed, the chain store refuses
+    /// heads that do not include it. If the tipset is not in the current chain, it becomes the
+    /// head.
+    pub async fn set_checkpoint(&self, ts: Arc<Tipset>) -> Result<(), Error> {
+        let head = self
+            .heaviest_tipset()
+            .await
+            .ok_or_else(|| Error::Other("no heaviest tipset".to_owned()))?;
+        if ts.epoch() > head.epoch() {
+            return Err(Error::Other(
+                "cannot set a checkpoint in the future".to_owned(),
+            ));
+        }
+        if !self.is_ancestor_of(&ts, head).await? {
+            info!("Checkpoint is not in the current chain, setting it as head");
+            self.set_heaviest_tipset(ts.clone()).await?;
+        }
+        self.db
+            .write_column(Column::Metadata, CHECKPOINT_KEY, ts.key().marshal_cbor()?)?;
+        info!("Checkpointed tipset {:?}", ts.key());
+        *self.checkpoint.write().await = Some(ts);
+        Ok(())
+    }
+
+    /// Loads the checkpoint from the datastore.
+    async fn load_checkpoint(&self) -> Result<(), Error> {
+        if let Some(bz) = read_metadata(self.blockstore(), CHECKPOINT_KEY)? {
+            let ts = self.tipset_from_keys(&from_slice(&bz)?).await?;
+            *self.checkpoint.write().await = Some(ts);
+        }
+        Ok(())
+    }
+
+    /// Returns true if `ts` is the checkpoint or one of its descendants, or if there is no
+    /// checkpoint.
+    async fn includes_checkpoint(&self, ts: Arc<Tipset>) -> Result<bool, Error> {
+        match self.checkpoint().await {
+            Some(checkpoint) => self.is_ancestor_of(&checkpoint, ts).await,
+            None => Ok(true),
+        }
+    }
+
+    /// Returns true if `a` is `b` or one of its ancestors.
+    pub async fn is_ancestor_of(&self, a: &Tipset, b: Arc<Tipset>) -> Result<bool, Error> {
+        if a.epoch() > b.epoch() {
+            return Ok(false);
+        }
+        let ts = self.tipset_by_height(a.epoch(), b, false).await?;
+        Ok(ts.key() == a.key())
+    }
+
     /// Writes genesis to blockstore.
     pub fn set_genesis(&self, header: &BlockHeader) -> Result<Cid, Error> {
         set_genesis(self.blockstore(), header)
@@ -140,10 +243,12 @@ where
     /// Writes tipset block headers to data store and updates heaviest tipset with other
     /// compatible tracked headers.
     pub async fn put_tipset(&self, ts: &Tipset) -> Result<(), Error> {
-        // TODO: we could add the blocks of `ts` to the tipset tracker from here,
-        // making `add_to_tipset_tracker` redundant and decreasing the number of
-        // blockstore reads
         persist_objects(self.blockstore(), ts.blocks())?;
+        // Tracked blocks are also kept by the garbage collection, which keeps the forks
+        // within its retention window.
+        for header in ts.blocks() {
+            self.tipset_tracker.add(header).await;
+        }
 
         // Expand tipset to include other compatible blocks at the epoch.
         let expanded = self.expand_tipset(ts.min_ticket_block().clone()).await?;
@@ -158,7 +263,7 @@ where
 
     /// Loads heaviest tipset from datastore and sets as heaviest in chainstore.
     async fn load_heaviest_tipset(&self) -> Result<(), Error> {
-        let heaviest_ts = match self.db.read(HEAD_KEY)? {
+        let heaviest_ts = match read_metadata(self.blockstore(), HEAD_KEY)? {
             Some(bz) => self.tipset_from_keys(&from_slice(&bz)?).await?,
             None => {
                 warn!("No previous chain state found");
@@ -219,6 +324,13 @@ where
                 let new_weight = weight(self.blockstore(), ts.as_ref())?;
                 let curr_weight = heaviest?;
                 if new_weight > curr_weight {
+                    if !self.includes_checkpoint(ts.clone()).await? {
+                        warn!(
+                            "Ignoring heavier tipset {:?} which does not include the checkpoint",
+                            ts.key()
+                        );
+                        return Ok(());
+                    }
                     // TODO potentially need to deal with re-orgs here
                     info!("New heaviest tipset: {:?}", ts.key());
                     self.set_heaviest_tipset(ts).await?;
@@ -236,14 +348,21 @@ where
     pub fn is_block_validated(&self, cid: &Cid) -> Result<bool, Error> {
         let key = block_validation_key(cid);
 
-        Ok(self.db.exists(key)?)
+        Ok(self.db.exists_column(Column::Indices, key)?)
     }
 
     /// Marks block as validated in the store. This is retrieved using the block validation prefix.
     pub fn mark_block_as_validated(&self, cid: &Cid) -> Result<(), Error> {
         let key = block_validation_key(cid);
 
-        Ok(self.db.write(key, &[])?)
+        Ok(self.db.write_column(Column::Indices, key, &[])?)
+    }
+
+    /// Unmarks a block as validated, so that it is validated again if it is synced.
+    pub fn unmark_block_as_validated(&self, cid: &Cid) -> Result<(), Error> {
+        let key = block_validation_key(cid);
+
+        Ok(self.db.delete_column(Column::Indices, key)?)
     }
 
     /// Returns the tipset behind `tsk` at a given `height`.
@@ -556,12 +675,18 @@ where
         let header = CarHeader::from(tipset.key().cids().to_vec());
 
         // Spawns task which receives blocks to write to the car writer.
-        let write_task =
-            task::spawn(async move { header.write_stream_async(&mut writer, &mut rx).await });
+        let write_task = task::spawn(async move {
+            header.write_stream_async(&mut writer, &mut rx).await?;
+            writer.flush().await?;
+            Ok::<_, forest_car::Error>(())
+        });
 
         let global_pre_time = SystemTime::now();
         info!("chain export started");
 
+        let mut blocks_written: u64 = 0;
+        let mut bytes_written: u64 = 0;
+
         // Walks over tipset and historical data, sending all blocks visited into the car writer.
         Self::walk_snapshot(tipset, recent_roots, skip_old_msgs, |cid| {
             let block = self
@@ -569,6 +694,15 @@ where
                 .get_bytes(&cid)?
                 .ok_or_else(|| format!("Cid {} not found in blockstore", cid))?;
 
+            blocks_written += 1;
+            bytes_written += block.len() as u64;
+            if blocks_written % EXPORT_PROGRESS_INTERVAL == 0 {
+                info!(
+                    "chain export progress: {} blocks, {} bytes written",
+                    blocks_written, bytes_written
+                );
+            }
+
             // * If cb can return a generic type, deserializing would remove need to clone.
             // Ignore error intentionally, if receiver dropped, error will be handled below
             let _ = task::block_on(tx.send((cid, block.clone())));
@@ -587,7 +721,12 @@ where
         let time = SystemTime::now()
             .duration_since(global_pre_time)
             .expect("time cannot go backwards");
-        info!("export finished, took {} seconds", time.as_secs());
+        info!(
+            "export finished, wrote {} blocks ({} bytes), took {} seconds",
+            blocks_written,
+            bytes_written,
+            time.as_secs()
+        );
 
         Ok(())
     }
@@ -680,12 +819,28 @@ where
         tipset: &Tipset,
         recent_roots: ChainEpoch,
         skip_old_msgs: bool,
-        mut load_block: F,
+        load_block: F,
     ) -> Result<(), Error>
     where
         F: FnMut(Cid) -> Result<Vec<u8>, Box<dyn StdError>>,
     {
         let mut seen = HashSet::<Cid>::new();
+        Self::walk_snapshot_with_seen(tipset, recent_roots, skip_old_msgs, &mut seen, load_block)
+            .await
+    }
+
+    /// Same as [ChainStore::walk_snapshot], but tracks visited blocks in the given set. Blocks
+    /// already in `seen` are not loaded again, which allows walking from multiple heads.
+    pub(super) async fn walk_snapshot_with_seen<F>(
+        tipset: &Tipset,
+        recent_roots: ChainEpoch,
+        skip_old_msgs: bool,
+        seen: &mut HashSet<Cid>,
+        mut load_block: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(Cid) -> Result<Vec<u8>, Box<dyn StdError>>,
+    {
         let mut blocks_to_walk: VecDeque<Cid> = tipset.cids().to_vec().into();
         let mut current_min_height = tipset.epoch();
         let incl_roots_epoch = tipset.epoch() - recent_roots;
@@ -704,7 +859,7 @@ where
             }
 
             if !skip_old_msgs || h.epoch() > incl_roots_epoch {
-                recurse_links(&mut seen, *h.messages(), &mut load_block)?;
+                recurse_links(seen, *h.messages(), &mut load_block)?;
             }
 
             if h.epoch() > 0 {
@@ -713,12 +868,15 @@ where
                 }
             } else {
                 for p in h.parents().cids() {
-                    load_block(*p)?;
+                    if seen.insert(*p) {
+                        load_block(*p)?;
+                    }
                 }
             }
 
             if h.epoch() == 0 || h.epoch() > incl_roots_epoch {
-                recurse_links(&mut seen, *h.state_root(), &mut load_block)?;
+                recurse_links(seen, *h.state_root(), &mut load_block)?;
+                recurse_links(seen, *h.message_receipts(), &mut load_block)?;
             }
         }
         Ok(())
@@ -757,6 +915,18 @@ where
     Ok(ts)
 }
 
+/// Reads a key of the metadata column. Databases created before the store had columns kept
+/// the chain metadata with the blocks, where the key is looked up if it isn't found.
+fn read_metadata<DB>(db: &DB, key: &str) -> Result<Option<Vec<u8>>, Error>
+where
+    DB: BlockStore,
+{
+    match db.read_column(Column::Metadata, key)? {
+        Some(bz) => Ok(Some(bz)),
+        None => Ok(db.read(key)?),
+    }
+}
+
 /// Helper to ensure consistent Cid -> db key translation.
 fn block_validation_key(cid: &Cid) -> Vec<u8> {
     let mut key = Vec::new();
@@ -825,7 +995,7 @@ pub fn set_genesis<DB>(db: &DB, header: &BlockHeader) -> Result<Cid, Error>
 where
     DB: BlockStore,
 {
-    db.write(GENESIS_KEY, header.marshal_cbor()?)?;
+    db.write_column(Column::Metadata, GENESIS_KEY, header.marshal_cbor()?)?;
     db.put(&header, Blake2b256)
         .map_err(|e| Error::Other(e.to_string()))
 }
@@ -843,6 +1013,50 @@ where
     Ok(())
 }
 
+/// Persists the messages of a new block. Returns the Cid of the [TxMeta] of the block, the
+/// aggregate signature of its BLS messages, and the Cids of its BLS and secp messages.
+pub fn persist_block_messages<DB>(
+    db: &DB,
+    messages: Vec<SignedMessage>,
+) -> Result<(Cid, Signature, Vec<Cid>, Vec<Cid>), Error>
+where
+    DB: BlockStore,
+{
+    let mut bls_sigs = Vec::new();
+    let mut bls_cids = Vec::new();
+    let mut secp_cids = Vec::new();
+    for msg in messages {
+        if msg.signature().signature_type() == SignatureType::BLS {
+            bls_cids.push(db.put(&msg.message, Blake2b256)?);
+            bls_sigs.push(msg.signature);
+        } else {
+            secp_cids.push(db.put(&msg, Blake2b256)?);
+        }
+    }
+
+    let meta = db.put(
+        &TxMeta {
+            bls_message_root: Amt::new_from_iter(db, bls_cids.iter().copied())?,
+            secp_message_root: Amt::new_from_iter(db, secp_cids.iter().copied())?,
+        },
+        Blake2b256,
+    )?;
+
+    let bls_aggregate = if bls_sigs.is_empty() {
+        Signature::new_bls(vec![])
+    } else {
+        let sigs = bls_sigs
+            .iter()
+            .map(|s| bls_signatures::Signature::from_bytes(s.bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::Other(format!("invalid BLS message signature: {}", e)))?;
+        let aggregate = bls_signatures::aggregate(&sigs)
+            .map_err(|e| Error::Other(format!("failed to aggregate BLS signatures: {}", e)))?;
+        Signature::new_bls(aggregate.as_bytes())
+    };
+    Ok((meta, bls_aggregate, bls_cids, secp_cids))
+}
+
 /// Computes a pseudorandom 32 byte Vec.
 pub fn draw_randomness(
     rbase: &[u8],
@@ -883,8 +1097,7 @@ pub fn genesis<DB>(db: &DB) -> Result<Option<BlockHeader>, Error>
 where
     DB: BlockStore,
 {
-    Ok(db
-        .read(GENESIS_KEY)?
+    Ok(read_metadata(db, GENESIS_KEY)?
         .map(|bz| BlockHeader::unmarshal_cbor(&bz))
         .transpose()?)
 }
@@ -1098,5 +1311,44 @@ mod tests {
 
         cs.mark_block_as_validated(&cid).unwrap();
         assert_eq!(cs.is_block_validated(&cid).unwrap(), true);
+
+        cs.unmark_block_as_validated(&cid).unwrap();
+        assert_eq!(cs.is_block_validated(&cid).unwrap(), false);
+    }
+
+    #[test]
+    fn checkpoint() {
+        let header = |miner| {
+            BlockHeader::builder()
+                .epoch(1)
+                .messages(cid::new_from_cbor(&[], Identity))
+                .message_receipts(cid::new_from_cbor(&[], Identity))
+                .state_root(cid::new_from_cbor(&[], Identity))
+                .miner_address(Address::new_id(miner))
+                .build()
+                .unwrap()
+        };
+        let main = Arc::new(Tipset::new(vec![header(0)]).unwrap());
+        let fork = Arc::new(Tipset::new(vec![header(1)]).unwrap());
+
+        task::block_on(async {
+            let cs = ChainStore::new(Arc::new(db::MemoryDB::default()));
+            persist_objects(cs.blockstore(), main.blocks()).unwrap();
+            persist_objects(cs.blockstore(), fork.blocks()).unwrap();
+            cs.set_heaviest_tipset(main.clone()).await.unwrap();
+
+            // Checkpointing a fork switches the head to it
+            cs.set_checkpoint(fork.clone()).await.unwrap();
+            assert_eq!(cs.heaviest_tipset().await, Some(fork.clone()));
+
+            cs.set_checkpoint(main.clone()).await.unwrap();
+            assert_eq!(cs.heaviest_tipset().await, Some(main.clone()));
+            assert!(cs.set_head(fork).await.is_err());
+            cs.set_head(main.clone()).await.unwrap();
+
+            // The checkpoint is loaded back on restart
+            let cs = ChainStore::new(cs.blockstore_cloned());
+            assert_eq!(cs.checkpoint().await, Some(main));
+        });
     }
 }
@@ -0,0 +1,243 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{ChainStore, Error};
+use blocks::{BlockHeader, Tipset, TipsetKeys};
+use cid::multihash::MultihashDigest;
+use cid::{Cid, Code, DAG_CBOR};
+use clock::ChainEpoch;
+use encoding::{from_slice, Cbor};
+use forest_ipld::Ipld;
+use ipld_blockstore::BlockStore;
+use log::{info, warn};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::time::SystemTime;
+
+/// Summary of a blockstore integrity check.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockstoreCheck {
+    /// Number of blocks read and verified.
+    pub checked: u64,
+    /// Blocks referenced by the chain which are not in the store.
+    pub missing: Vec<Cid>,
+    /// Blocks whose data does not match their cid, or cannot be decoded.
+    pub corrupt: Vec<Cid>,
+    /// Tipsets with missing or corrupt headers or messages. These can be refetched from peers
+    /// over chain exchange, unlike state trees and receipts.
+    pub damaged_tipsets: Vec<TipsetKeys>,
+    /// Duration of the check, in seconds.
+    pub duration: u64,
+}
+
+impl BlockstoreCheck {
+    /// Returns true if no missing or corrupt block was found.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.corrupt.is_empty()
+    }
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: BlockStore + Send + Sync + 'static,
+{
+    /// Verifies the blocks of the chain from `head` back to the tipsets `depth` epochs below
+    /// it. The headers, messages, receipts and state trees of every tipset in that range are
+    /// read from the store and re-hashed against their cid.
+    ///
+    /// Missing and corrupt blocks are reported rather than returned as errors, the walk only
+    /// stops early when none of the headers of a tipset can be read.
+    pub fn check_blockstore(
+        &self,
+        head: &Tipset,
+        depth: ChainEpoch,
+    ) -> Result<BlockstoreCheck, Error> {
+        let start = SystemTime::now();
+        info!(
+            "blockstore check started at epoch {} with depth {}",
+            head.epoch(),
+            depth
+        );
+
+        let mut check = BlockstoreCheck::default();
+        let mut seen = HashSet::new();
+        let stop_epoch = head.epoch() - depth;
+        let mut tsk = head.key().clone();
+        loop {
+            let mut damaged = false;
+            let mut headers = Vec::with_capacity(tsk.cids().len());
+            for cid in tsk.cids() {
+                seen.insert(*cid);
+                match self.check_block(cid, &mut check)? {
+                    Some(bz) => match BlockHeader::unmarshal_cbor(&bz) {
+                        Ok(header) => headers.push(header),
+                        Err(_) => {
+                            check.corrupt.push(*cid);
+                            damaged = true;
+                        }
+                    },
+                    None => damaged = true,
+                }
+            }
+
+            // All the blocks of a tipset share their epoch and parents
+            let (epoch, parents) = match headers.first() {
+                Some(h) => (h.epoch(), h.parents().clone()),
+                None => {
+                    warn!("no header of tipset {:?} could be read", tsk.cids());
+                    check.damaged_tipsets.push(tsk);
+                    break;
+                }
+            };
+            if epoch < stop_epoch {
+                break;
+            }
+
+            for header in &headers {
+                damaged |= !self.check_dag(*header.messages(), &mut seen, &mut check)?;
+                self.check_dag(*header.message_receipts(), &mut seen, &mut check)?;
+                self.check_dag(*header.state_root(), &mut seen, &mut check)?;
+            }
+            if damaged {
+                check.damaged_tipsets.push(tsk);
+            }
+            if epoch == 0 {
+                break;
+            }
+            tsk = parents;
+        }
+
+        check.duration = SystemTime::now()
+            .duration_since(start)
+            .expect("time cannot go backwards")
+            .as_secs();
+        info!(
+            "blockstore check finished, checked {} blocks, {} missing, {} corrupt, took {} seconds",
+            check.checked,
+            check.missing.len(),
+            check.corrupt.len(),
+            check.duration
+        );
+        Ok(check)
+    }
+
+    /// Verifies every block of the dag under `root` which was not seen yet. Returns false if
+    /// any of them is missing or corrupt.
+    fn check_dag(
+        &self,
+        root: Cid,
+        seen: &mut HashSet<Cid>,
+        check: &mut BlockstoreCheck,
+    ) -> Result<bool, Error> {
+        let mut intact = true;
+        let mut to_check = vec![root];
+        while let Some(cid) = to_check.pop() {
+            // Identity cids carry their data inline and are never stored
+            if matches!(Code::try_from(cid.hash().code()), Ok(Code::Identity)) || !seen.insert(cid)
+            {
+                continue;
+            }
+            let bz = match self.check_block(&cid, check)? {
+                Some(bz) => bz,
+                None => {
+                    intact = false;
+                    continue;
+                }
+            };
+            if cid.codec() != DAG_CBOR {
+                continue;
+            }
+            match from_slice::<Ipld>(&bz) {
+                Ok(ipld) => push_links(&ipld, &mut to_check),
+                Err(_) => {
+                    check.corrupt.push(cid);
+                    intact = false;
+                }
+            }
+        }
+        Ok(intact)
+    }
+
+    /// Reads a block and checks that its data hashes to its cid. Returns `None` if the block
+    /// is missing or corrupt, after recording it in the check.
+    fn check_block(
+        &self,
+        cid: &Cid,
+        check: &mut BlockstoreCheck,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        check.checked += 1;
+        match self.blockstore().get_bytes(cid)? {
+            Some(bz) if block_matches_cid(cid, &bz) => Ok(Some(bz)),
+            Some(_) => {
+                check.corrupt.push(*cid);
+                Ok(None)
+            }
+            None => {
+                check.missing.push(*cid);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Re-hashes a block with the hash function of its cid. Blocks hashed with a function which
+/// is not supported are assumed to be valid.
+fn block_matches_cid(cid: &Cid, bz: &[u8]) -> bool {
+    match Code::try_from(cid.hash().code()) {
+        Ok(code) => code.digest(bz) == *cid.hash(),
+        Err(_) => true,
+    }
+}
+
+/// Pushes the cids linked from an Ipld node.
+fn push_links(ipld: &Ipld, links: &mut Vec<Cid>) {
+    match ipld {
+        Ipld::Map(m) => m.values().for_each(|v| push_links(v, links)),
+        Ipld::List(l) => l.iter().for_each(|v| push_links(v, links)),
+        Ipld::Link(cid) => links.push(*cid),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use address::Address;
+    use cid::Code::Blake2b256;
+    use db::{MemoryDB, Store};
+    use std::sync::Arc;
+
+    #[test]
+    fn check_blockstore_reports_missing_and_corrupt() {
+        let db = Arc::new(MemoryDB::default());
+        let cs = ChainStore::new(db.clone());
+
+        let empty = db.put(&Vec::<u8>::new(), Blake2b256).unwrap();
+        let state = db.put(&vec![Ipld::Link(empty)], Blake2b256).unwrap();
+        let missing = cid::new_from_cbor(&[1, 2, 3], Blake2b256);
+        let genesis = BlockHeader::builder()
+            .epoch(0)
+            .messages(empty)
+            .message_receipts(missing)
+            .state_root(state)
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        db.put(&genesis, Blake2b256).unwrap();
+        let ts = Tipset::new(vec![genesis]).unwrap();
+
+        let check = cs.check_blockstore(&ts, 10).unwrap();
+        assert_eq!(check.checked, 4);
+        assert_eq!(check.missing, vec![missing]);
+        assert!(check.corrupt.is_empty());
+        // Receipts can't be refetched with chain exchange
+        assert!(check.damaged_tipsets.is_empty());
+
+        // Overwrite the messages with data which doesn't match their cid
+        db.write(empty.to_bytes(), vec![0x80]).unwrap();
+        let check = cs.check_blockstore(&ts, 10).unwrap();
+        assert_eq!(check.corrupt, vec![empty]);
+        assert_eq!(check.damaged_tipsets, vec![ts.key().clone()]);
+        assert!(!check.is_ok());
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{ChainStore, Error};
+use actor::CHAIN_FINALITY;
+use async_std::task;
+use blocks::{BlockHeader, Tipset};
+use cid::Cid;
+use clock::ChainEpoch;
+use forest_ipld::recurse_links;
+use ipld_blockstore::BlockStore;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Number of keys deleted from the store in a single batch during the sweep.
+const SWEEP_BATCH_SIZE: usize = 10_000;
+
+/// Configuration of the periodic chain garbage collection run by the daemon.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GcConfig {
+    /// Run garbage collection on a schedule.
+    pub enabled: bool,
+    /// Interval between garbage collection runs, in seconds.
+    pub interval: u64,
+    /// Number of epochs behind the head whose state roots and forks are kept.
+    pub recent_roots: ChainEpoch,
+    /// Deletes messages of tipsets older than the kept state roots.
+    pub skip_old_msgs: bool,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 6 * 60 * 60,
+            recent_roots: 2000,
+            skip_old_msgs: false,
+        }
+    }
+}
+
+/// Summary of a garbage collection run.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GcStats {
+    /// Number of blocks reachable from the head.
+    pub marked: u64,
+    /// Number of blocks deleted from the store.
+    pub swept: u64,
+    /// Duration of the run, in seconds.
+    pub duration: u64,
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: BlockStore + Send + Sync + 'static,
+{
+    /// Deletes the blocks from the store which are not reachable from the heaviest tipset, from
+    /// the tracked tipsets within `recent_roots` epochs of it, or from the roots returned by
+    /// `keep`.
+    ///
+    /// Block headers are kept back to genesis, state trees and receipts are kept for the last
+    /// `recent_roots` epochs and messages are kept unless `skip_old_msgs` is set. Tracked
+    /// tipsets include the forks seen by the node. `keep` returns the roots of data which is not
+    /// referenced by the chain yet, such as the pending messages of the message pool or the
+    /// computed state of the head, and everything reachable from them is kept. It is called
+    /// once the candidates for deletion are listed, so data created before then is included.
+    ///
+    /// Only keys that decode as a [Cid] and exist when the collection starts are considered, so
+    /// chain metadata and blocks written while the collection runs are never deleted.
+    pub async fn collect_garbage<F, Fut>(
+        &self,
+        recent_roots: ChainEpoch,
+        skip_old_msgs: bool,
+        keep: F,
+    ) -> Result<GcStats, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<HashSet<Cid>, Error>>,
+    {
+        if recent_roots < CHAIN_FINALITY {
+            return Err(Error::Other(format!(
+                "recent roots must be at least the chain finality ({})",
+                CHAIN_FINALITY
+            )));
+        }
+        if self.gc_running.compare_exchange(false, true).is_err() {
+            return Err(Error::Other(
+                "garbage collection is already running".to_owned(),
+            ));
+        }
+        let res = self.mark_and_sweep(recent_roots, skip_old_msgs, keep).await;
+        self.gc_running.store(false);
+        res
+    }
+
+    async fn mark_and_sweep<F, Fut>(
+        &self,
+        recent_roots: ChainEpoch,
+        skip_old_msgs: bool,
+        keep: F,
+    ) -> Result<GcStats, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<HashSet<Cid>, Error>>,
+    {
+        let start = SystemTime::now();
+        info!("chain garbage collection started");
+
+        // Only keys which exist before marking starts are candidates for deletion, blocks
+        // written while the collection is running are never swept.
+        let db = self.blockstore_cloned();
+        let candidates = task::spawn_blocking(move || -> Result<Vec<Cid>, Error> {
+            let mut candidates = Vec::new();
+            db.for_each_key(|key| {
+                if let Ok(cid) = Cid::try_from(key) {
+                    candidates.push(cid);
+                }
+                Ok(())
+            })?;
+            Ok(candidates)
+        })
+        .await?;
+        debug!("{} blocks are candidates for collection", candidates.len());
+
+        let keep = keep().await?;
+        let head = self
+            .heaviest_tipset()
+            .await
+            .ok_or_else(|| Error::Other("no heaviest tipset to collect from".to_owned()))?;
+        let marked = self
+            .mark(head, recent_roots, skip_old_msgs, keep, HashSet::new())
+            .await?;
+
+        // Blocks of a tipset which is being synced may have been written before the collection
+        // started without being reachable yet. Wait for the syncs holding `pause_gc` to put
+        // their tipsets and keep new syncs from starting until the sweep is done. Syncs being
+        // paused, the head can only move to tipsets whose blocks are already stored, so the
+        // head is marked again from a snapshot instead of being locked during the sweep.
+        let _gc_guard = self.gc_guard.write().await;
+        let head = self
+            .heaviest_tipset()
+            .await
+            .ok_or_else(|| Error::Other("no heaviest tipset to collect from".to_owned()))?;
+        let window_start = head.epoch() - recent_roots;
+        // Blocks which are already marked are not walked again.
+        let marked = self
+            .mark(head, recent_roots, skip_old_msgs, HashSet::new(), marked)
+            .await?;
+
+        let db = self.blockstore_cloned();
+        let marked_len = marked.len() as u64;
+        let swept = task::spawn_blocking(move || -> Result<u64, Error> {
+            let mut swept = 0;
+            let unreachable: Vec<Vec<u8>> = candidates
+                .into_iter()
+                .filter(|cid| !marked.contains(cid))
+                .map(|cid| cid.to_bytes())
+                .collect();
+            for batch in unreachable.chunks(SWEEP_BATCH_SIZE) {
+                db.bulk_delete(batch)?;
+                swept += batch.len() as u64;
+            }
+            Ok(swept)
+        })
+        .await?;
+        self.tipset_tracker.prune(window_start).await;
+
+        let stats = GcStats {
+            marked: marked_len,
+            swept,
+            duration: SystemTime::now()
+                .duration_since(start)
+                .expect("time cannot go backwards")
+                .as_secs(),
+        };
+        info!(
+            "chain garbage collection finished, kept {} blocks, deleted {} blocks, took {} seconds",
+            stats.marked, stats.swept, stats.duration
+        );
+        Ok(stats)
+    }
+
+    /// Marks every block reachable from `head`, from the tracked tipsets within the retention
+    /// window and from the `keep` roots, adding them to `marked`. The walk runs on a blocking
+    /// task.
+    async fn mark(
+        &self,
+        head: Arc<Tipset>,
+        recent_roots: ChainEpoch,
+        skip_old_msgs: bool,
+        keep: HashSet<Cid>,
+        mut marked: HashSet<Cid>,
+    ) -> Result<HashSet<Cid>, Error> {
+        let tracked = self
+            .tipset_tracker
+            .cids_from(head.epoch() - recent_roots)
+            .await;
+        let db = self.blockstore_cloned();
+        task::spawn_blocking(move || -> Result<HashSet<Cid>, Error> {
+            let mut load_block = |cid: Cid| -> Result<Vec<u8>, Box<dyn StdError>> {
+                db.get_bytes(&cid)?
+                    .ok_or_else(|| format!("Cid {} not found in blockstore", cid).into())
+            };
+            for root in keep {
+                recurse_links(&mut marked, root, &mut load_block)?;
+            }
+
+            let mut roots = vec![head];
+            for cid in tracked {
+                if marked.contains(&cid) {
+                    continue;
+                }
+                let header = db
+                    .get::<BlockHeader>(&cid)?
+                    .ok_or_else(|| Error::NotFound(format!("Tracked block {}", cid)))?;
+                roots.push(Arc::new(Tipset::new(vec![header])?));
+            }
+            for ts in roots {
+                task::block_on(Self::walk_snapshot_with_seen(
+                    &ts,
+                    recent_roots,
+                    skip_old_msgs,
+                    &mut marked,
+                    &mut load_block,
+                ))?;
+            }
+            Ok(marked)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::persist_objects;
+    use super::*;
+    use address::Address;
+    use blocks::TipsetKeys;
+    use cid::Code::Blake2b256;
+    use db::{MemoryDB, Store};
+    use std::time::Duration;
+
+    fn header(epoch: ChainEpoch, parents: TipsetKeys, miner: u64, root: Cid) -> BlockHeader {
+        BlockHeader::builder()
+            .epoch(epoch)
+            .parents(parents)
+            .messages(root)
+            .message_receipts(root)
+            .state_root(root)
+            .miner_address(Address::new_id(miner))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn collect_garbage_keeps_reachable_blocks() {
+        let db = Arc::new(MemoryDB::default());
+        let cs = ChainStore::new(db.clone());
+
+        let empty = db.put(&Vec::<u8>::new(), Blake2b256).unwrap();
+        let genesis = header(0, TipsetKeys::default(), 0, empty);
+        cs.set_genesis(&genesis).unwrap();
+        task::block_on(cs.set_heaviest_tipset(Arc::new(Tipset::new(vec![genesis]).unwrap())))
+            .unwrap();
+
+        let orphan = db.put(&[1u8, 2, 3], Blake2b256).unwrap();
+        let pending = db.put(&[4u8], Blake2b256).unwrap();
+        // A computed state which no block references yet is kept along with its links
+        let state_leaf = db.put(&[5u8], Blake2b256).unwrap();
+        let state_root = db.put(&vec![state_leaf], Blake2b256).unwrap();
+        let keep = vec![pending, state_root].into_iter().collect();
+
+        let stats = task::block_on(
+            cs.collect_garbage(CHAIN_FINALITY, false, || async { Ok::<_, Error>(keep) }),
+        )
+        .unwrap();
+        assert_eq!(stats.swept, 1);
+        assert!(!db.exists(orphan.to_bytes()).unwrap());
+        for cid in &[pending, state_root, state_leaf] {
+            assert!(db.exists(cid.to_bytes()).unwrap());
+        }
+        assert!(db.exists(empty.to_bytes()).unwrap());
+        assert!(cs.genesis().unwrap().is_some());
+    }
+
+    #[test]
+    fn collect_garbage_keeps_forks_and_moving_head() {
+        let db = Arc::new(MemoryDB::default());
+        let cs = Arc::new(ChainStore::new(db.clone()));
+
+        let empty = db.put(&Vec::<u8>::new(), Blake2b256).unwrap();
+        let genesis = header(0, TipsetKeys::default(), 0, empty);
+        cs.set_genesis(&genesis).unwrap();
+        let genesis = Tipset::new(vec![genesis]).unwrap();
+        task::block_on(cs.set_heaviest_tipset(Arc::new(genesis.clone()))).unwrap();
+
+        // A fork which never becomes the head is only known to the tipset tracker
+        let fork_root = db.put(&[1u8], Blake2b256).unwrap();
+        let fork = header(1, genesis.key().clone(), 1, fork_root);
+        persist_objects(db.as_ref(), &[fork.clone()]).unwrap();
+        task::block_on(cs.add_to_tipset_tracker(&fork));
+
+        // The blocks of the next head are written before the collection starts, and the head
+        // is set while it runs
+        let head_root = db.put(&[2u8], Blake2b256).unwrap();
+        let next = header(1, genesis.key().clone(), 2, head_root);
+        let orphan = db.put(&[3u8], Blake2b256).unwrap();
+
+        task::block_on(async {
+            let pause = cs.pause_gc().await;
+            persist_objects(db.as_ref(), &[next.clone()]).unwrap();
+            let gc = {
+                let cs = cs.clone();
+                task::spawn(async move {
+                    cs.collect_garbage(CHAIN_FINALITY, false, || async {
+                        Ok::<_, Error>(HashSet::new())
+                    })
+                    .await
+                })
+            };
+            task::sleep(Duration::from_millis(100)).await;
+            cs.set_heaviest_tipset(Arc::new(Tipset::new(vec![next.clone()]).unwrap()))
+                .await
+                .unwrap();
+            drop(pause);
+
+            let stats = gc.await.unwrap();
+            assert_eq!(stats.swept, 1);
+        });
+
+        assert!(!db.exists(orphan.to_bytes()).unwrap());
+        for cid in &[*fork.cid(), fork_root, *next.cid(), head_root] {
+            assert!(db.exists(cid.to_bytes()).unwrap());
+        }
+    }
+
+    #[test]
+    fn collect_garbage_rejects_short_retention() {
+        let cs = ChainStore::new(Arc::new(MemoryDB::default()));
+        assert!(
+            task::block_on(cs.collect_garbage(CHAIN_FINALITY - 1, false, || async {
+                Ok::<_, Error>(HashSet::new())
+            }))
+            .is_err()
+        );
+    }
+}
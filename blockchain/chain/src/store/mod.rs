@@ -3,10 +3,16 @@
 
 pub mod base_fee;
 mod chain_store;
+mod check;
 mod errors;
+mod gc;
 mod index;
+mod msg_index;
 mod tipset_tracker;
 
 pub use self::base_fee::*;
 pub use self::chain_store::*;
+pub use self::check::*;
 pub use self::errors::*;
+pub use self::gc::*;
+pub use self::msg_index::*;
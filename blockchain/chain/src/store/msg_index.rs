@@ -0,0 +1,242 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{messages_for_tipset, ChainStore, Error, HeadChange};
+use blocks::{Tipset, TipsetKeys};
+use cid::Cid;
+use clock::ChainEpoch;
+use db::Column;
+use encoding::{from_slice, to_vec, tuple::*, Cbor};
+use ipld_blockstore::BlockStore;
+use log::{debug, info, warn};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+const MSG_INDEX_PREFIX: &[u8] = b"/msgidx/msg/";
+const MSG_INDEX_TIPSET_PREFIX: &[u8] = b"/msgidx/ts/";
+
+/// Number of tipsets behind a new head which are indexed when the head changes. Older tipsets
+/// are indexed by [ChainStore::backfill_msg_index], which [ChainStore::run_msg_indexer] calls
+/// when the head jumps further.
+const HEAD_CHANGE_INDEX_DEPTH: usize = 20;
+
+/// Location of an executed message in the chain.
+#[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct MessageLocation {
+    /// Key of the tipset which contains the receipt of the message, that is, the child of the
+    /// tipset which included it.
+    pub tipset: TipsetKeys,
+    /// Epoch of the tipset which contains the receipt.
+    pub epoch: ChainEpoch,
+    /// Index of the receipt in the parent receipts of the tipset.
+    pub index: u64,
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: BlockStore + Send + Sync + 'static,
+{
+    /// Returns the indexed location of a message. The location may belong to a tipset which
+    /// has since been reverted, callers are expected to check it against their chain.
+    pub fn get_msg_location(&self, msg_cid: &Cid) -> Result<Option<MessageLocation>, Error> {
+        match self
+            .db
+            .read_column(Column::Indices, msg_index_key(msg_cid))?
+        {
+            Some(bz) => Ok(Some(from_slice(&bz)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Indexes the messages executed by `ts`, that is, the messages of its parent tipset.
+    /// Returns `false` if the tipset had already been indexed.
+    pub async fn index_tipset_messages(&self, ts: &Tipset) -> Result<bool, Error> {
+        if ts.epoch() == 0 {
+            return Ok(false);
+        }
+        let ts_key = msg_index_tipset_key(ts.key())?;
+        if self.db.exists_column(Column::Indices, &ts_key)? {
+            return Ok(false);
+        }
+        let parent = self.tipset_from_keys(ts.parents()).await?;
+        let messages = messages_for_tipset(self.blockstore(), &parent)?;
+
+        let mut entries = Vec::with_capacity(messages.len() + 1);
+        for (index, msg) in messages.iter().enumerate() {
+            let location = MessageLocation {
+                tipset: ts.key().clone(),
+                epoch: ts.epoch(),
+                index: index as u64,
+            };
+            entries.push((msg_index_key(&msg.cid()?), to_vec(&location)?));
+        }
+        entries.push((ts_key, Vec::new()));
+        self.db.bulk_write_column(Column::Indices, &entries)?;
+        Ok(true)
+    }
+
+    /// Indexes the messages of `ts` and of up to `depth` of its ancestors. The walk stops at
+    /// genesis, when the messages of a tipset are not in the store, as is the case for tipsets
+    /// older than an imported snapshot, or at the first indexed tipset below those indexed on
+    /// head changes. Returns the number of tipsets indexed.
+    pub async fn backfill_msg_index(&self, ts: Arc<Tipset>, depth: usize) -> Result<usize, Error> {
+        let indexed = self
+            .index_chain(ts, depth, HEAD_CHANGE_INDEX_DEPTH as ChainEpoch)
+            .await?;
+        if indexed > 0 {
+            info!("indexed the messages of {} tipsets", indexed);
+        }
+        Ok(indexed)
+    }
+
+    /// Backfills the message index from the current head, then from every new head which is
+    /// more than the depth indexed on head changes above the previous one, as happens when a
+    /// large range of tipsets is synced at once. Runs until the chain store is dropped.
+    pub async fn run_msg_indexer(self: Arc<Self>) {
+        let mut head_changes = self.publisher().subscribe();
+        let mut last_epoch = match self.heaviest_tipset().await {
+            Some(head) => {
+                let epoch = head.epoch();
+                self.backfill_or_warn(head).await;
+                epoch
+            }
+            None => 0,
+        };
+        loop {
+            match head_changes.recv().await {
+                Ok(HeadChange::Apply(ts)) => {
+                    if ts.epoch() - last_epoch > HEAD_CHANGE_INDEX_DEPTH as ChainEpoch {
+                        self.backfill_or_warn(ts.clone()).await;
+                    }
+                    last_epoch = ts.epoch();
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => {
+                    // Missed head changes may have skipped tipsets, index from the current head
+                    if let Some(head) = self.heaviest_tipset().await {
+                        last_epoch = head.epoch();
+                        self.backfill_or_warn(head).await;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn backfill_or_warn(&self, ts: Arc<Tipset>) {
+        if let Err(e) = self.backfill_msg_index(ts, usize::MAX).await {
+            warn!("Failed to backfill the message index: {}", e);
+        }
+    }
+
+    /// Indexes the tipsets leading to a new head, until one which was already indexed.
+    pub(super) async fn index_head(&self, ts: Arc<Tipset>) -> Result<(), Error> {
+        self.index_chain(ts, HEAD_CHANGE_INDEX_DEPTH, 0)
+            .await
+            .map(|_| ())
+    }
+
+    /// Walks back from `ts` indexing up to `depth` tipsets. Indexed tipsets less than
+    /// `skip_indexed` epochs below `ts` are skipped, the walk stops at the next ones.
+    async fn index_chain(
+        &self,
+        ts: Arc<Tipset>,
+        depth: usize,
+        skip_indexed: ChainEpoch,
+    ) -> Result<usize, Error> {
+        let start = ts.epoch();
+        let mut indexed = 0;
+        let mut current = ts;
+        for _ in 0..depth {
+            if current.epoch() == 0 {
+                break;
+            }
+            match self.index_tipset_messages(&current).await {
+                Ok(true) => indexed += 1,
+                Ok(false) if start - current.epoch() < skip_indexed => (),
+                Ok(false) => break,
+                Err(e) => {
+                    debug!(
+                        "stopped indexing messages at epoch {}: {}",
+                        current.epoch(),
+                        e
+                    );
+                    break;
+                }
+            }
+            current = self.tipset_from_keys(current.parents()).await?;
+        }
+        Ok(indexed)
+    }
+}
+
+fn msg_index_key(msg_cid: &Cid) -> Vec<u8> {
+    [MSG_INDEX_PREFIX, &msg_cid.to_bytes()].concat()
+}
+
+fn msg_index_tipset_key(tsk: &TipsetKeys) -> Result<Vec<u8>, Error> {
+    Ok([MSG_INDEX_TIPSET_PREFIX, &to_vec(tsk)?].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::persist_objects;
+    use super::*;
+    use address::Address;
+    use async_std::task;
+    use blocks::{BlockHeader, TxMeta};
+    use cid::Code::Blake2b256;
+    use db::MemoryDB;
+    use ipld_amt::Amt;
+    use message::UnsignedMessage;
+
+    #[test]
+    fn index_tipset_messages() {
+        let db = Arc::new(MemoryDB::default());
+        let cs = ChainStore::new(db.clone());
+
+        let msg = UnsignedMessage::builder()
+            .to(Address::new_id(1))
+            .from(Address::new_id(2))
+            .build()
+            .unwrap();
+        let msg_cid = db.put(&msg, Blake2b256).unwrap();
+        let meta = TxMeta {
+            bls_message_root: Amt::new_from_iter(db.as_ref(), vec![msg_cid]).unwrap(),
+            secp_message_root: Amt::new_from_iter(db.as_ref(), Vec::<Cid>::new()).unwrap(),
+        };
+        let empty = db.put(&Vec::<u8>::new(), Blake2b256).unwrap();
+
+        let parent = BlockHeader::builder()
+            .epoch(1)
+            .messages(db.put(&meta, Blake2b256).unwrap())
+            .message_receipts(empty)
+            .state_root(empty)
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let parent_ts = Tipset::new(vec![parent.clone()]).unwrap();
+        let child = BlockHeader::builder()
+            .epoch(2)
+            .parents(parent_ts.key().clone())
+            .messages(empty)
+            .message_receipts(empty)
+            .state_root(empty)
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        persist_objects(db.as_ref(), &[parent, child.clone()]).unwrap();
+        let child_ts = Tipset::new(vec![child]).unwrap();
+
+        assert!(task::block_on(cs.index_tipset_messages(&child_ts)).unwrap());
+        assert!(!task::block_on(cs.index_tipset_messages(&child_ts)).unwrap());
+        assert_eq!(
+            cs.get_msg_location(&msg_cid).unwrap(),
+            Some(MessageLocation {
+                tipset: child_ts.key().clone(),
+                epoch: 2,
+                index: 0,
+            })
+        );
+    }
+}
@@ -60,6 +60,22 @@ impl<DB: BlockStore> TipsetTracker<DB> {
         cids.push(*header.cid());
     }
 
+    /// Returns the tracked blocks at or above the given epoch.
+    pub async fn cids_from(&self, epoch: ChainEpoch) -> Vec<Cid> {
+        self.entries
+            .read()
+            .await
+            .iter()
+            .filter(|(e, _)| **e >= epoch)
+            .flat_map(|(_, cids)| cids.iter().copied())
+            .collect()
+    }
+
+    /// Stops tracking the blocks below the given epoch.
+    pub async fn prune(&self, epoch: ChainEpoch) {
+        self.entries.write().await.retain(|e, _| *e >= epoch);
+    }
+
     /// Expands the given block header into the largest possible tipset by
     /// combining it with known blocks at the same height with the same parents.
     pub async fn expand(&self, header: BlockHeader) -> Result<Tipset, Error> {
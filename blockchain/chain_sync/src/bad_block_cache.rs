@@ -3,42 +3,206 @@
 
 use async_std::sync::RwLock;
 use cid::Cid;
+use db::{Column, Error, Store};
+use encoding::{from_slice, to_vec};
+use log::warn;
 use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BAD_BLOCK_KEY_PREFIX: &[u8] = b"/badblocks/";
+
+/// Default maximum number of bad blocks remembered.
+const DEFAULT_CAPACITY: usize = 1 << 15;
+
+/// Reason and time a block was marked bad.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadBlock {
+    pub reason: String,
+    /// Unix time, in seconds, the block was marked bad.
+    pub timestamp: u64,
+}
 
 /// Threadsafe cache for tracking bad blocks.
 /// This cache is checked before validating a block, to ensure no duplicate work.
-#[derive(Debug)]
-pub struct BadBlockCache {
-    cache: RwLock<LruCache<Cid, String>>,
+///
+/// Bad blocks are persisted in the metadata column of the store and loaded back on creation, so
+/// that a restarted node does not sync a chain it already found invalid. Once the capacity is
+/// reached, the least recently used bad blocks are forgotten.
+pub struct BadBlockCache<DB> {
+    db: Arc<DB>,
+    cache: RwLock<LruCache<Cid, BadBlock>>,
 }
 
-impl Default for BadBlockCache {
-    fn default() -> Self {
-        Self::new(1 << 15)
+impl<DB> BadBlockCache<DB>
+where
+    DB: Store,
+{
+    pub fn new(db: Arc<DB>) -> Self {
+        Self::with_capacity(db, DEFAULT_CAPACITY)
     }
-}
 
-impl BadBlockCache {
-    pub fn new(cap: usize) -> Self {
+    /// Creates a cache holding at most `cap` bad blocks, loading the persisted ones.
+    pub fn with_capacity(db: Arc<DB>, cap: usize) -> Self {
+        let mut cache = LruCache::new(cap);
+        match load_bad_blocks(db.as_ref()) {
+            Ok(mut bad_blocks) => {
+                // The most recently marked blocks are kept if there are too many
+                bad_blocks.sort_by_key(|(_, bad)| bad.timestamp);
+                let excess = bad_blocks.len().saturating_sub(cap);
+                for (cid, _) in bad_blocks.drain(..excess) {
+                    if let Err(e) = db.delete_column(Column::Metadata, bad_block_key(&cid)) {
+                        warn!("Failed to forget bad block {}: {}", cid, e);
+                    }
+                }
+                for (cid, bad) in bad_blocks {
+                    cache.put(cid, bad);
+                }
+            }
+            Err(e) => warn!("Failed to load the persisted bad blocks: {}", e),
+        }
         Self {
-            cache: RwLock::new(LruCache::new(cap)),
+            db,
+            cache: RwLock::new(cache),
         }
     }
 
     /// Puts a bad block Cid in the cache with a given reason.
-    pub async fn put(&self, c: Cid, reason: String) -> Option<String> {
-        self.cache.write().await.put(c, reason)
+    pub async fn put(&self, c: Cid, reason: String) -> Result<(), Error> {
+        let bad = BadBlock {
+            reason,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        let mut cache = self.cache.write().await;
+        if cache.len() == cache.cap() && !cache.contains(&c) {
+            if let Some((evicted, _)) = cache.pop_lru() {
+                self.db
+                    .delete_column(Column::Metadata, bad_block_key(&evicted))?;
+            }
+        }
+        self.db
+            .write_column(Column::Metadata, bad_block_key(&c), to_vec(&bad)?)?;
+        cache.put(c, bad);
+        Ok(())
     }
 
     /// Returns `Some` with the reason if the block cid is in bad block cache.
     /// This also updates the key to the head of the cache.
     pub async fn get(&self, c: &Cid) -> Option<String> {
-        self.cache.write().await.get(c).cloned()
+        self.cache
+            .write()
+            .await
+            .get(c)
+            .map(|bad| bad.reason.clone())
     }
 
     /// Returns `Some` with the reason if the block cid is in bad block cache.
     /// This function does not update the head position of the `Cid` key.
     pub async fn peek(&self, c: &Cid) -> Option<String> {
-        self.cache.read().await.peek(c).cloned()
+        self.cache
+            .read()
+            .await
+            .peek(c)
+            .map(|bad| bad.reason.clone())
+    }
+
+    /// Removes a block from the bad blocks. Returns false if it was not marked bad.
+    pub async fn remove(&self, c: &Cid) -> Result<bool, Error> {
+        let mut cache = self.cache.write().await;
+        self.db.delete_column(Column::Metadata, bad_block_key(c))?;
+        Ok(cache.pop(c).is_some())
+    }
+
+    /// Removes all the bad blocks, returning how many were removed.
+    pub async fn clear(&self) -> Result<usize, Error> {
+        let mut cache = self.cache.write().await;
+        let keys: Vec<_> = cache.iter().map(|(cid, _)| bad_block_key(cid)).collect();
+        self.db.bulk_delete_column(Column::Metadata, &keys)?;
+        cache.clear();
+        Ok(keys.len())
+    }
+}
+
+fn bad_block_key(c: &Cid) -> Vec<u8> {
+    [BAD_BLOCK_KEY_PREFIX, &c.to_bytes()].concat()
+}
+
+/// Reads the bad blocks persisted in the store.
+fn load_bad_blocks<DB: Store>(db: &DB) -> Result<Vec<(Cid, BadBlock)>, Error> {
+    let mut cids = Vec::new();
+    db.for_each_key_column(Column::Metadata, |key| {
+        if let Some(bytes) = key.strip_prefix(BAD_BLOCK_KEY_PREFIX) {
+            let cid = Cid::try_from(bytes).map_err(|e| Error::Other(e.to_string()))?;
+            cids.push(cid);
+        }
+        Ok(())
+    })?;
+
+    let mut bad_blocks = Vec::with_capacity(cids.len());
+    for cid in cids {
+        if let Some(bz) = db.read_column(Column::Metadata, bad_block_key(&cid))? {
+            bad_blocks.push((cid, from_slice(&bz)?));
+        }
+    }
+    Ok(bad_blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task;
+    use cid::Code::Identity;
+    use db::MemoryDB;
+
+    fn cid(i: u8) -> Cid {
+        cid::new_from_cbor(&[i], Identity)
+    }
+
+    #[test]
+    fn persisted_across_restarts() {
+        task::block_on(async {
+            let db = Arc::new(MemoryDB::default());
+            let cache = BadBlockCache::new(db.clone());
+            cache
+                .put(cid(0), "invalid state root".to_owned())
+                .await
+                .unwrap();
+            cache
+                .put(cid(1), "chain contained bad block".to_owned())
+                .await
+                .unwrap();
+            assert!(cache.remove(&cid(1)).await.unwrap());
+            assert!(!cache.remove(&cid(1)).await.unwrap());
+
+            let cache = BadBlockCache::new(db.clone());
+            assert_eq!(cache.peek(&cid(0)).await.unwrap(), "invalid state root");
+            assert_eq!(cache.peek(&cid(1)).await, None);
+
+            assert_eq!(cache.clear().await.unwrap(), 1);
+            let cache = BadBlockCache::new(db);
+            assert_eq!(cache.peek(&cid(0)).await, None);
+        });
+    }
+
+    #[test]
+    fn evicted_blocks_are_forgotten() {
+        task::block_on(async {
+            let db = Arc::new(MemoryDB::default());
+            let cache = BadBlockCache::with_capacity(db.clone(), 2);
+            for i in 0..3 {
+                cache.put(cid(i), "bad".to_owned()).await.unwrap();
+            }
+            assert_eq!(cache.peek(&cid(0)).await, None);
+
+            let cache = BadBlockCache::with_capacity(db, 2);
+            assert_eq!(cache.peek(&cid(0)).await, None);
+            assert!(cache.peek(&cid(1)).await.is_some());
+            assert!(cache.peek(&cid(2)).await.is_some());
+        });
     }
 }
@@ -4,6 +4,7 @@
 use crate::bad_block_cache::BadBlockCache;
 use crate::metrics;
 use crate::network_context::SyncNetworkContext;
+use crate::peer_manager::PeerManager;
 use crate::sync_state::SyncState;
 use crate::tipset_syncer::{
     TipsetProcessor, TipsetProcessorError, TipsetRangeSyncer, TipsetRangeSyncerError,
@@ -16,12 +17,13 @@ use chain::{ChainStore, Error as ChainStoreError};
 use cid::Cid;
 use fil_types::verifier::ProofVerifier;
 use forest_libp2p::{
-    hello::HelloRequest, rpc::RequestResponseError, NetworkEvent, NetworkMessage, PubsubMessage,
+    hello::HelloRequest, rpc::RequestResponseError, MessageAcceptance, MessageId, NetworkEvent,
+    NetworkMessage, PeerStore, PubsubMessage,
 };
 use ipld_blockstore::BlockStore;
 use libp2p::core::PeerId;
 use message::{SignedMessage, UnsignedMessage};
-use message_pool::{MessagePool, Provider};
+use message_pool::{Error as MpoolError, MessagePool, Provider};
 use state_manager::StateManager;
 
 use async_std::channel::{Receiver, Sender};
@@ -139,7 +141,7 @@ pub struct ChainMuxer<DB, TBeacon, V, M> {
 
     /// Bad blocks cache, updates based on invalid state transitions.
     /// Will mark any invalid blocks and all childen as bad in this bounded cache
-    bad_blocks: Arc<BadBlockCache>,
+    bad_blocks: Arc<BadBlockCache<DB>>,
 
     /// Incoming network events to be handled by syncer
     net_handler: Receiver<NetworkEvent>,
@@ -179,11 +181,13 @@ where
         tipset_receiver: Receiver<Arc<Tipset>>,
         cfg: SyncConfig,
     ) -> Result<Self, ChainMuxerError> {
+        let peer_store = PeerStore::new(state_manager.blockstore_cloned());
         let network = SyncNetworkContext::new(
             network_send,
-            Default::default(),
+            Arc::new(PeerManager::new(peer_store)),
             state_manager.blockstore_cloned(),
         );
+        let bad_blocks = Arc::new(BadBlockCache::new(state_manager.blockstore_cloned()));
 
         Ok(Self {
             state: ChainMuxerState::Idle,
@@ -192,7 +196,7 @@ where
             network,
             genesis,
             state_manager,
-            bad_blocks: Arc::new(BadBlockCache::default()),
+            bad_blocks,
             net_handler: network_rx,
             verifier: Default::default(),
             mpool,
@@ -203,7 +207,7 @@ where
     }
 
     /// Returns a clone of the bad blocks cache to be used outside of chain sync.
-    pub fn bad_blocks_cloned(&self) -> Arc<BadBlockCache> {
+    pub fn bad_blocks_cloned(&self) -> Arc<BadBlockCache<DB>> {
         self.bad_blocks.clone()
     }
 
@@ -346,13 +350,23 @@ where
         Ok(FullTipset::new(vec![block]).unwrap())
     }
 
-    async fn handle_pubsub_message(mem_pool: Arc<MessagePool<M>>, message: SignedMessage) {
-        if let Err(why) = mem_pool.add(message).await {
+    async fn handle_pubsub_message(
+        network: SyncNetworkContext<DB>,
+        mem_pool: Arc<MessagePool<M>>,
+        source: PeerId,
+        message_id: MessageId,
+        message: SignedMessage,
+    ) {
+        let res = mem_pool.add(message).await;
+        if let Err(why) = &res {
             debug!(
                 "GossipSub message could not be added to the mem pool: {}",
                 why
             );
         }
+        network
+            .report_pubsub_validation(message_id, source, message_acceptance(&res))
+            .await;
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -360,12 +374,14 @@ where
         event: NetworkEvent,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         mem_pool: Arc<MessagePool<M>>,
         genesis: Arc<Tipset>,
         message_processing_strategy: PubsubMessageProcessingStrategy,
     ) -> Result<Option<(FullTipset, PeerId)>, ChainMuxerError> {
-        let (tipset, source) = match event {
+        // Gossip blocks are validated along with the tipsets of hello requests, the result is
+        // reported to the p2p service once known
+        let (tipset, source, message_id) = match event {
             NetworkEvent::HelloRequest { request, source } => {
                 metrics::LIBP2P_MESSAGE_TOTAL
                     .with_label_values(&[metrics::values::HELLO_REQUEST])
@@ -385,7 +401,7 @@ where
                         return Err(why);
                     }
                 };
-                (tipset, source)
+                (tipset, source, None)
             }
             NetworkEvent::PeerConnected(peer_id) => {
                 metrics::LIBP2P_MESSAGE_TOTAL
@@ -411,15 +427,37 @@ where
                 ));
                 return Ok(None);
             }
-            NetworkEvent::PubsubMessage { source, message } => match message {
+            NetworkEvent::PubsubMessage {
+                source,
+                message_id,
+                message,
+            } => match message {
                 PubsubMessage::Block(b) => {
                     metrics::LIBP2P_MESSAGE_TOTAL
                         .with_label_values(&[metrics::values::PUBSUB_BLOCK])
                         .inc();
                     // Assemble full tipset from block
-                    let tipset =
-                        Self::gossipsub_block_to_full_tipset(b, source, network.clone()).await?;
-                    (tipset, source)
+                    let tipset = match Self::gossipsub_block_to_full_tipset(
+                        b,
+                        source,
+                        network.clone(),
+                    )
+                    .await
+                    {
+                        Ok(tipset) => tipset,
+                        Err(why) => {
+                            // Messages which could not be fetched do not make the block invalid
+                            network
+                                .report_pubsub_validation(
+                                    message_id,
+                                    source,
+                                    MessageAcceptance::Ignore,
+                                )
+                                .await;
+                            return Err(why);
+                        }
+                    };
+                    (tipset, source, Some(message_id))
                 }
                 PubsubMessage::Message(m) => {
                     metrics::LIBP2P_MESSAGE_TOTAL
@@ -427,7 +465,18 @@ where
                         .inc();
                     if let PubsubMessageProcessingStrategy::Process = message_processing_strategy {
                         // Spawn and immediately move on to the next event
-                        async_std::task::spawn(Self::handle_pubsub_message(mem_pool.clone(), m));
+                        async_std::task::spawn(Self::handle_pubsub_message(
+                            network.clone(),
+                            mem_pool.clone(),
+                            source,
+                            message_id,
+                            m,
+                        ));
+                    } else {
+                        // Messages are not validated while bootstrapping, nor forwarded
+                        network
+                            .report_pubsub_validation(message_id, source, MessageAcceptance::Ignore)
+                            .await;
                     }
                     return Ok(None);
                 }
@@ -449,14 +498,23 @@ where
         };
 
         // Validate tipset
-        if let Err(why) = TipsetValidator(&tipset)
+        let validation = TipsetValidator(&tipset)
             .validate(
                 chain_store.clone(),
                 bad_block_cache.clone(),
                 genesis.clone(),
             )
-            .await
-        {
+            .await;
+        if let Some(message_id) = message_id {
+            let acceptance = match &validation {
+                Ok(()) => MessageAcceptance::Accept,
+                Err(why) => block_acceptance(why),
+            };
+            network
+                .report_pubsub_validation(message_id, source, acceptance)
+                .await;
+        }
+        if let Err(why) = validation {
             metrics::INVALID_TIPSET_TOTAL.inc();
             warn!(
                 "Validating tipset received through GossipSub failed: {}",
@@ -870,14 +928,49 @@ where
     }
 }
 
+/// Returns the validation result of a gossip block which failed validation. Blocks which are
+/// invalid are rejected, which penalizes the peer which sent them, while blocks which could not
+/// be validated because of the local clock or store are ignored.
+fn block_acceptance(why: &TipsetValidationError) -> MessageAcceptance {
+    match why {
+        TipsetValidationError::EpochTooLarge
+        | TipsetValidationError::Blockstore(_)
+        | TipsetValidationError::IpldAmt(_) => MessageAcceptance::Ignore,
+        _ => MessageAcceptance::Reject,
+    }
+}
+
+/// Returns the validation result of a gossip message from the result of adding it to the
+/// message pool. Invalid messages are rejected, while valid messages which the pool does not
+/// take, such as duplicates or messages of senders with too many pending messages, are ignored.
+fn message_acceptance(res: &Result<(), MpoolError>) -> MessageAcceptance {
+    match res {
+        Ok(()) => MessageAcceptance::Accept,
+        Err(MpoolError::MessageTooBig)
+        | Err(MpoolError::MessageValueTooHigh)
+        | Err(MpoolError::InvalidToAddr)
+        | Err(MpoolError::InvalidFromAddr)
+        | Err(MpoolError::InvalidSignature(_))
+        | Err(MpoolError::InvalidMessage(_)) => MessageAcceptance::Reject,
+        Err(_) => MessageAcceptance::Ignore,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
 
+    use super::message_acceptance;
     use crate::validation::TipsetValidator;
+    use address::Address;
+    use async_std::channel::bounded;
+    use async_std::task;
     use cid::Cid;
+    use crypto::Signature;
     use db::MemoryDB;
+    use forest_libp2p::MessageAcceptance;
     use message::{SignedMessage, UnsignedMessage};
+    use message_pool::{test_provider::TestApi, Error as MpoolError, MessagePool};
     use test_utils::construct_messages;
 
     #[test]
@@ -895,6 +988,38 @@ mod tests {
         assert_eq!(root, expected_root);
     }
 
+    #[test]
+    fn gossip_message_with_invalid_signature_is_rejected() {
+        task::block_on(async {
+            let (tx, _rx) = bounded(50);
+            let mpool = MessagePool::new(
+                TestApi::default(),
+                "mptest".to_owned(),
+                tx,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+            let message = SignedMessage {
+                message: UnsignedMessage::builder()
+                    .to(Address::new_id(1))
+                    .from(Address::new_secp256k1(&[0; 65]).unwrap())
+                    .gas_limit(1_000_000)
+                    .gas_fee_cap(1000.into())
+                    .build()
+                    .unwrap(),
+                signature: Signature::new_secp256k1(vec![0; 65]),
+            };
+
+            let res = mpool.add(message).await;
+            assert!(matches!(res, Err(MpoolError::InvalidSignature(_))));
+            assert!(matches!(
+                message_acceptance(&res),
+                MessageAcceptance::Reject
+            ));
+        })
+    }
+
     #[test]
     fn empty_msg_meta_vector() {
         let blockstore = MemoryDB::default();
@@ -18,4 +18,7 @@ extern crate serde;
 pub use self::bad_block_cache::BadBlockCache;
 pub use self::chain_muxer::{ChainMuxer, SyncConfig};
 pub use self::sync_state::{SyncStage, SyncState};
+pub use self::tipset_syncer::{
+    validate_mined_block, validate_stored_tipset, TipsetRangeSyncerError,
+};
 pub use self::validation::TipsetValidator;
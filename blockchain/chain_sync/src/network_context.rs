@@ -12,7 +12,7 @@ use forest_libp2p::{
     },
     hello::{HelloRequest, HelloResponse},
     rpc::RequestResponseError,
-    NetworkMessage,
+    MessageAcceptance, MessageId, NetworkMessage,
 };
 use futures::channel::oneshot::channel as oneshot_channel;
 use ipld_blockstore::BlockStore;
@@ -38,7 +38,7 @@ pub(crate) struct SyncNetworkContext<DB> {
     network_send: Sender<NetworkMessage>,
 
     /// Manages peers to send requests to and updates request stats for the respective peers.
-    pub peer_manager: Arc<PeerManager>,
+    pub peer_manager: Arc<PeerManager<DB>>,
     db: Arc<DB>,
 }
 
@@ -58,7 +58,7 @@ where
 {
     pub fn new(
         network_send: Sender<NetworkMessage>,
-        peer_manager: Arc<PeerManager>,
+        peer_manager: Arc<PeerManager<DB>>,
         db: Arc<DB>,
     ) -> Self {
         Self {
@@ -69,7 +69,7 @@ where
     }
 
     /// Returns a reference to the peer manager of the network context.
-    pub fn peer_manager(&self) -> &PeerManager {
+    pub fn peer_manager(&self) -> &PeerManager<DB> {
         self.peer_manager.as_ref()
     }
 
@@ -278,6 +278,27 @@ where
         }
     }
 
+    /// Reports the result of the validation of a gossip message to the p2p service.
+    pub async fn report_pubsub_validation(
+        &self,
+        message_id: MessageId,
+        source: PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        if self
+            .network_send
+            .send(NetworkMessage::PubsubValidation {
+                message_id,
+                source,
+                acceptance,
+            })
+            .await
+            .is_err()
+        {
+            warn!("Failed to report gossip message validation: receiver dropped");
+        }
+    }
+
     /// Send a hello request to the network (does not immediately await response).
     pub async fn hello_request(
         &self,
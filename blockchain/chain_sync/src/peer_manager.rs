@@ -8,8 +8,10 @@ use std::{cmp::Ordering, collections::HashSet};
 
 use async_std::sync::RwLock;
 use blocks::Tipset;
+use db::Store;
+use forest_libp2p::{unix_now, PeerStats, PeerStore};
 use libp2p::core::PeerId;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use rand::seq::SliceRandom;
 use smallvec::SmallVec;
 
@@ -39,12 +41,22 @@ struct PeerInfo {
 }
 
 impl PeerInfo {
-    fn new(head: Arc<Tipset>) -> Self {
+    /// Creates the info of a peer, starting from its persisted request stats.
+    fn from_stats(stats: PeerStats) -> Self {
         Self {
-            head: Some(head),
-            successes: 0,
-            failures: 0,
-            average_time: Default::default(),
+            head: None,
+            successes: stats.successes,
+            failures: stats.failures,
+            average_time: stats.average_time,
+        }
+    }
+
+    fn stats(&self) -> PeerStats {
+        PeerStats {
+            successes: self.successes,
+            failures: self.failures,
+            average_time: self.average_time,
+            bad_since: None,
         }
     }
 }
@@ -60,25 +72,61 @@ struct PeerSets {
 }
 
 /// Thread safe peer manager which handles peer management for the `ChainExchange` protocol.
-#[derive(Default)]
-pub(crate) struct PeerManager {
+///
+/// The request stats and bad state of peers are persisted in the [PeerStore], so that they
+/// survive restarts.
+pub(crate) struct PeerManager<DB> {
     /// Full and bad peer sets.
     peers: RwLock<PeerSets>,
     /// Average response time from peers.
     avg_global_time: RwLock<Duration>,
+    /// Store the request stats of peers are persisted in.
+    store: PeerStore<DB>,
 }
 
-impl PeerManager {
+impl<DB> PeerManager<DB>
+where
+    DB: Store,
+{
+    /// Creates a peer manager, loading the peers which were recently marked bad from the store.
+    pub fn new(store: PeerStore<DB>) -> Self {
+        let mut bad_peers = HashSet::new();
+        match store.known_peers() {
+            Ok(known) => {
+                for peer in known {
+                    if let Ok(Some(stats)) = store.stats(&peer) {
+                        if stats.is_bad() {
+                            bad_peers.insert(peer);
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to load known peers: {}", e),
+        }
+        metrics::BAD_PEERS.set(bad_peers.len() as u64);
+
+        Self {
+            peers: RwLock::new(PeerSets {
+                full_peers: HashMap::new(),
+                bad_peers,
+            }),
+            avg_global_time: Default::default(),
+            store,
+        }
+    }
+
     /// Updates peer's heaviest tipset. If the peer does not exist in the set, a new `PeerInfo`
     /// will be generated.
     pub async fn update_peer_head(&self, peer_id: PeerId, ts: Arc<Tipset>) {
         let mut peers = self.peers.write().await;
         trace!("Updating head for PeerId {}", &peer_id);
+        if !peers.full_peers.contains_key(&peer_id) {
+            let info = self.load_peer(&peer_id);
+            peers.full_peers.insert(peer_id, info);
+            metrics::FULL_PEERS.inc();
+        }
         if let Some(pi) = peers.full_peers.get_mut(&peer_id) {
             pi.head = Some(ts);
-        } else {
-            peers.full_peers.insert(peer_id, PeerInfo::new(ts));
-            metrics::FULL_PEERS.inc();
         }
     }
 
@@ -156,9 +204,13 @@ impl PeerManager {
         if !peers.full_peers.contains_key(&peer) {
             metrics::FULL_PEERS.inc();
         }
-        let peer_stats = peers.full_peers.entry(peer).or_default();
+        let peer_stats = peers
+            .full_peers
+            .entry(peer)
+            .or_insert_with(|| self.load_peer(&peer));
         peer_stats.successes += 1;
         log_time(peer_stats, dur);
+        self.save_stats(&peer, peer_stats.stats());
     }
 
     /// Logs a failure for the given peer, and updates the average request duration.
@@ -170,15 +222,23 @@ impl PeerManager {
             if !peers.full_peers.contains_key(&peer) {
                 metrics::FULL_PEERS.inc();
             }
-            let peer_stats = peers.full_peers.entry(peer).or_default();
+            let peer_stats = peers
+                .full_peers
+                .entry(peer)
+                .or_insert_with(|| self.load_peer(&peer));
             peer_stats.failures += 1;
             log_time(peer_stats, dur);
+            self.save_stats(&peer, peer_stats.stats());
         }
     }
 
     /// Removes a peer from the set and returns true if the value was present previously
     pub async fn mark_peer_bad(&self, peer_id: PeerId) -> bool {
         let mut peers = self.peers.write().await;
+        let stats = match peers.full_peers.get(&peer_id) {
+            Some(info) => info.stats(),
+            None => self.load_peer(&peer_id).stats(),
+        };
         let removed = remove_peer(&mut peers, &peer_id);
         if removed {
             metrics::FULL_PEERS.dec();
@@ -189,6 +249,13 @@ impl PeerManager {
         if peers.bad_peers.insert(peer_id) {
             metrics::BAD_PEERS.inc();
         }
+        self.save_stats(
+            &peer_id,
+            PeerStats {
+                bad_since: Some(unix_now()),
+                ..stats
+            },
+        );
 
         removed
     }
@@ -209,6 +276,23 @@ impl PeerManager {
     pub async fn len(&self) -> usize {
         self.peers.read().await.full_peers.len()
     }
+
+    /// Returns the info of a peer which is not managed yet, with its persisted request stats.
+    fn load_peer(&self, peer_id: &PeerId) -> PeerInfo {
+        match self.store.stats(peer_id) {
+            Ok(stats) => PeerInfo::from_stats(stats.unwrap_or_default()),
+            Err(e) => {
+                warn!("Failed to load the stats of peer {}: {}", peer_id, e);
+                PeerInfo::default()
+            }
+        }
+    }
+
+    fn save_stats(&self, peer_id: &PeerId, stats: PeerStats) {
+        if let Err(e) = self.store.put_stats(peer_id, &stats) {
+            warn!("Failed to save the stats of peer {}: {}", peer_id, e);
+        }
+    }
 }
 
 fn remove_peer(peers: &mut PeerSets, peer_id: &PeerId) -> bool {
@@ -42,7 +42,7 @@ use forest_libp2p::chain_exchange::TipsetBundle;
 use interpreter::price_list_by_epoch;
 use ipld_blockstore::BlockStore;
 use message::{Message, UnsignedMessage};
-use networks::{get_network_version_default, BLOCK_DELAY_SECS, UPGRADE_SMOKE_HEIGHT};
+use networks::BLOCK_DELAY_SECS;
 use state_manager::Error as StateManagerError;
 use state_manager::StateManager;
 use state_tree::StateTree;
@@ -87,6 +87,8 @@ pub enum TipsetRangeSyncerError {
     TipsetWithoutTicket,
     #[error("Validation error: {0}")]
     Validation(String),
+    #[error("Block is invalid: {0}")]
+    InvalidBlock(String),
     #[error("Processing error: {0}")]
     Calculation(String),
     #[error("Chain store error: {0}")]
@@ -115,6 +117,8 @@ pub enum TipsetRangeSyncerError {
     ChainForkLengthExceedsFinalityThreshold,
     #[error("Chain for block forked from local chain at genesis, refusing to sync block: {0}")]
     ForkAtGenesisBlock(String),
+    #[error("Chain forked from local chain before the checkpoint at epoch {0}")]
+    ForkBeforeCheckpoint(ChainEpoch),
     #[error("Querying miner power failed: {0}")]
     MinerPowerUnavailable(String),
     #[error("Power actor not found")]
@@ -145,6 +149,36 @@ pub enum TipsetRangeSyncerError {
     TipsetParentNotFound(ChainStoreError),
 }
 
+impl TipsetRangeSyncerError {
+    /// Whether the error shows the block breaks the consensus rules, rather than that it
+    /// couldn't be validated. Only such blocks are invalid for good and can be marked bad.
+    fn is_invalid_block(&self) -> bool {
+        use TipsetRangeSyncerError::*;
+        matches!(
+            self,
+            BlockWithoutElectionProof
+                | BlockWithoutSignature
+                | BlockWithoutBlsAggregate
+                | BlockWithoutTicket
+                | UnequalBlockTimestamps(_, _)
+                | TipsetWithoutTicket
+                | InvalidBlock(_)
+                | WinnerElectionProofVerificationFailed(_)
+                | InvalidOrSlashedMiner
+                | MinerPowerNotAvailable
+                | MinerWinClaimsIncorrect(_, _)
+                | MinerNotEligibleToMine
+                | VrfValidation(_)
+                | BlsAggregateSignatureInvalid(_, _)
+                | MessageSignatureInvalid(_)
+                | BlockMessageRootInvalid(_, _)
+                | BlockMessageValidationFailed(_, _)
+                | InsecurePostValidation(_)
+                | BlockError(ForestBlockError::InvalidSignature(_))
+        )
+    }
+}
+
 struct TipsetGroup {
     tipsets: Vec<Arc<Tipset>>,
     epoch: ChainEpoch,
@@ -235,7 +269,7 @@ pub(crate) struct TipsetProcessor<DB, TBeacon, V> {
     beacon: Arc<BeaconSchedule<TBeacon>>,
     network: SyncNetworkContext<DB>,
     chain_store: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
     verifier: PhantomData<V>,
 }
@@ -254,7 +288,7 @@ where
         beacon: Arc<BeaconSchedule<TBeacon>>,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis: Arc<Tipset>,
     ) -> Self {
         Self {
@@ -610,7 +644,7 @@ pub(crate) struct TipsetRangeSyncer<DB, TBeacon, V> {
     beacon: Arc<BeaconSchedule<TBeacon>>,
     network: SyncNetworkContext<DB>,
     chain_store: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
     verifier: PhantomData<V>,
 }
@@ -630,7 +664,7 @@ where
         beacon: Arc<BeaconSchedule<TBeacon>>,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis: Arc<Tipset>,
     ) -> Result<Self, TipsetRangeSyncerError> {
         let tipset_tasks = Box::pin(FuturesUnordered::new());
@@ -753,11 +787,13 @@ fn sync_tipset_range<
     state_manager: Arc<StateManager<DB>>,
     chain_store: Arc<ChainStore<DB>>,
     network: SyncNetworkContext<DB>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     beacon: Arc<BeaconSchedule<TBeacon>>,
     genesis: Arc<Tipset>,
 ) -> TipsetRangeSyncerFuture {
     Box::pin(async move {
+        // The synced blocks are only reachable once the proposed head is put in the store
+        let _gc_pause = chain_store.pause_gc().await;
         tracker
             .write()
             .await
@@ -835,7 +871,7 @@ async fn sync_headers_in_reverse<DB: BlockStore + Sync + Send + 'static>(
     tipset_range_length: u64,
     proposed_head: Arc<Tipset>,
     current_head: Arc<Tipset>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     chain_store: Arc<ChainStore<DB>>,
     network: SyncNetworkContext<DB>,
 ) -> Result<Vec<Arc<Tipset>>, TipsetRangeSyncerError> {
@@ -903,6 +939,7 @@ async fn sync_headers_in_reverse<DB: BlockStore + Sync + Send + 'static>(
             .map_err(TipsetRangeSyncerError::NetworkTipsetQueryFailed)?;
         let mut potential_common_ancestor =
             chain_store.tipset_from_keys(current_head.parents()).await?;
+        let checkpoint_epoch = chain_store.checkpoint().await.map(|ts| ts.epoch());
         let mut fork_length = 1;
         for (i, tipset) in fork_tipsets.iter().enumerate() {
             if tipset.epoch() == 0 {
@@ -911,6 +948,13 @@ async fn sync_headers_in_reverse<DB: BlockStore + Sync + Send + 'static>(
                     oldest_tipset.cids()
                 )));
             }
+            // The common ancestor must not be older than the checkpoint, otherwise the fork
+            // does not include it
+            if let Some(epoch) = checkpoint_epoch {
+                if potential_common_ancestor.epoch() < epoch {
+                    return Err(TipsetRangeSyncerError::ForkBeforeCheckpoint(epoch));
+                }
+            }
             if potential_common_ancestor == *tipset {
                 // Remove elements from the vector since the Drain
                 // iterator is immediately dropped
@@ -953,11 +997,13 @@ fn sync_tipset<
     state_manager: Arc<StateManager<DB>>,
     chain_store: Arc<ChainStore<DB>>,
     network: SyncNetworkContext<DB>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     beacon: Arc<BeaconSchedule<TBeacon>>,
     genesis: Arc<Tipset>,
 ) -> TipsetRangeSyncerFuture {
     Box::pin(async move {
+        // The synced blocks are only reachable once the proposed head is put in the store
+        let _gc_pause = chain_store.pause_gc().await;
         // Persist the blocks from the proposed tipsets into the store
         let headers: Vec<&BlockHeader> = proposed_head.blocks().iter().collect();
         persist_objects(chain_store.blockstore(), &headers)?;
@@ -1007,7 +1053,7 @@ async fn sync_messages_check_state<
     beacon_scheduler: Arc<BeaconSchedule<TBeacon>>,
     network: SyncNetworkContext<DB>,
     chainstore: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     tipsets: Vec<Arc<Tipset>>,
     genesis: Arc<Tipset>,
     invalid_block_strategy: InvalidBlockStrategy,
@@ -1106,7 +1152,7 @@ async fn validate_tipset<
     state_manager: Arc<StateManager<DB>>,
     beacon_scheduler: Arc<BeaconSchedule<TBeacon>>,
     chainstore: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     full_tipset: FullTipset,
     genesis: Arc<Tipset>,
     invalid_block_strategy: InvalidBlockStrategy,
@@ -1142,13 +1188,11 @@ async fn validate_tipset<
                     why
                 );
                 // Only do bad block accounting if the function was called with
-                // `is_strict` = true
+                // `is_strict` = true, and only for blocks breaking the consensus rules
                 if let InvalidBlockStrategy::Strict = invalid_block_strategy {
-                    match &why {
-                        TipsetRangeSyncerError::TimeTravellingBlock(_, _)
-                        | TipsetRangeSyncerError::TipsetParentNotFound(_) => (),
-                        why => {
-                            bad_block_cache.put(cid, why.to_string()).await;
+                    if why.is_invalid_block() {
+                        if let Err(e) = bad_block_cache.put(cid, why.to_string()).await {
+                            warn!("Failed to mark block {} as bad: {}", cid, e);
                         }
                     }
                 }
@@ -1163,6 +1207,68 @@ async fn validate_tipset<
     Ok(())
 }
 
+/// Validates a tipset whose blocks and messages are in the store, without syncing it. Unlike
+/// during sync, invalid blocks are not marked bad, which allows checking a block that was.
+pub async fn validate_stored_tipset<
+    DB: BlockStore + Send + Sync + 'static,
+    TBeacon: Beacon + Sync + Send + 'static,
+    V: ProofVerifier + Sync + Send + 'static,
+>(
+    state_manager: Arc<StateManager<DB>>,
+    beacon_scheduler: Arc<BeaconSchedule<TBeacon>>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
+    tipset: &Tipset,
+    genesis: Arc<Tipset>,
+) -> Result<(), TipsetRangeSyncerError> {
+    let chainstore = state_manager.chain_store().clone();
+    let full_tipset = chainstore.fill_tipset(tipset).ok_or_else(|| {
+        TipsetRangeSyncerError::Validation(format!(
+            "Messages of tipset {:?} are missing from the store",
+            tipset.cids()
+        ))
+    })?;
+    validate_tipset::<_, _, V>(
+        state_manager,
+        beacon_scheduler,
+        chainstore,
+        bad_block_cache,
+        full_tipset,
+        genesis,
+        InvalidBlockStrategy::Forgiving,
+    )
+    .await
+}
+
+/// Validates a block which was just mined, before it is submitted to the network. Its parent
+/// tipset has to be in the store. Unlike during sync, an invalid block is not marked bad.
+pub async fn validate_mined_block<
+    DB: BlockStore + Send + Sync + 'static,
+    TBeacon: Beacon + Sync + Send + 'static,
+    V: ProofVerifier + Sync + Send + 'static,
+>(
+    state_manager: Arc<StateManager<DB>>,
+    beacon_schedule: Arc<BeaconSchedule<TBeacon>>,
+    block: Block,
+) -> Result<(), TipsetRangeSyncerError> {
+    let msg_root = TipsetValidator::compute_msg_root(
+        state_manager.blockstore(),
+        block.bls_msgs(),
+        block.secp_msgs(),
+    )
+    .map_err(|e| TipsetRangeSyncerError::Validation(e.to_string()))?;
+    if block.header().messages() != &msg_root {
+        return Err(TipsetRangeSyncerError::Validation(format!(
+            "Block message root does not match the computed: {} (header), {} (computed)",
+            block.header().messages(),
+            msg_root
+        )));
+    }
+    validate_block::<_, _, V>(state_manager, beacon_schedule, Arc::new(block))
+        .await
+        .map(|_| ())
+        .map_err(|(_, e)| e)
+}
+
 /// Validates block semantically according to https://github.com/filecoin-project/specs/blob/6ab401c0b92efb6420c6e198ec387cf56dc86057/validation.md
 /// Returns the validated block if `Ok`.
 /// Returns the block cid (for marking bad) and `Error` if invalid (`Err`).
@@ -1266,7 +1372,6 @@ async fn validate_block<
     let v_state_manager = Arc::clone(&state_manager);
     validations.push(task::spawn_blocking(move || {
         check_block_messages::<_, V>(v_state_manager, &v_block, &v_base_tipset)
-            .map_err(|e| TipsetRangeSyncerError::Validation(e.to_string()))
     }));
 
     // Miner validations
@@ -1286,17 +1391,20 @@ async fn validate_block<
     let v_base_tipset = Arc::clone(&base_tipset);
     let v_block_store = state_manager.blockstore_cloned();
     let v_block = Arc::clone(&block);
+    let smoke_height = state_manager.network_config().upgrades.smoke;
     validations.push(task::spawn_blocking(move || {
         let base_fee =
-            chain::compute_base_fee(v_block_store.as_ref(), &v_base_tipset).map_err(|e| {
-                TipsetRangeSyncerError::Validation(format!(
-                    "Could not compute base fee: {}",
-                    e.to_string()
-                ))
-            })?;
+            chain::compute_base_fee(v_block_store.as_ref(), &v_base_tipset, smoke_height).map_err(
+                |e| {
+                    TipsetRangeSyncerError::Validation(format!(
+                        "Could not compute base fee: {}",
+                        e.to_string()
+                    ))
+                },
+            )?;
         let parent_base_fee = v_block.header.parent_base_fee();
         if &base_fee != parent_base_fee {
-            return Err(TipsetRangeSyncerError::Validation(format!(
+            return Err(TipsetRangeSyncerError::InvalidBlock(format!(
                 "base fee doesn't match: {} (header), {} (computed)",
                 parent_base_fee, base_fee
             )));
@@ -1313,7 +1421,7 @@ async fn validate_block<
             TipsetRangeSyncerError::Calculation(format!("Error calculating weight: {}", e))
         })?;
         if weight != calc_weight {
-            return Err(TipsetRangeSyncerError::Validation(format!(
+            return Err(TipsetRangeSyncerError::InvalidBlock(format!(
                 "Parent weight doesn't match: {} (header), {} (computed)",
                 weight, calc_weight
             )));
@@ -1334,14 +1442,14 @@ async fn validate_block<
                 TipsetRangeSyncerError::Calculation(format!("Failed to calculate state: {}", e))
             })?;
         if &state_root != header.state_root() {
-            return Err(TipsetRangeSyncerError::Validation(format!(
+            return Err(TipsetRangeSyncerError::InvalidBlock(format!(
                 "Parent state root did not match computed state: {} (header), {} (computed)",
                 header.state_root(),
                 state_root,
             )));
         }
         if &receipt_root != header.message_receipts() {
-            return Err(TipsetRangeSyncerError::Validation(format!(
+            return Err(TipsetRangeSyncerError::InvalidBlock(format!(
                 "Parent receipt root did not match computed root: {} (header), {} (computed)",
                 header.message_receipts(),
                 receipt_root
@@ -1362,7 +1470,7 @@ async fn validate_block<
         // Safe to unwrap because checked to `Some` in sanity check
         let election_proof = header.election_proof().as_ref().unwrap();
         if election_proof.win_count < 1 {
-            return Err(TipsetRangeSyncerError::Validation(
+            return Err(TipsetRangeSyncerError::InvalidBlock(
                 "Block is not claiming to be a winner".to_string(),
             ));
         }
@@ -1434,11 +1542,12 @@ async fn validate_block<
     // Ticket election proof validations
     let v_block = Arc::clone(&block);
     let v_prev_beacon = Arc::clone(&prev_beacon);
+    let smoke_height = state_manager.network_config().upgrades.smoke;
     validations.push(task::spawn_blocking(move || {
         let header = v_block.header();
         let mut miner_address_buf = header.miner_address().marshal_cbor()?;
 
-        if header.epoch() > UPGRADE_SMOKE_HEIGHT {
+        if header.epoch() > smoke_height {
             let vrf_proof = base_tipset
                 .min_ticket()
                 .ok_or(TipsetRangeSyncerError::TipsetWithoutTicket)?
@@ -1482,16 +1591,24 @@ async fn validate_block<
     }));
 
     // Collect the errors from the async validations
+    let mut invalid = false;
     while let Some(result) = validations.next().await {
         if let Err(e) = result {
+            invalid |= e.is_invalid_block();
             error_vec.push(e.to_string());
         }
     }
 
-    // Combine the vector of error strings and return Validation error with this resultant string
+    // Combine the vector of error strings into a single error, the block is invalid if any of
+    // the failed validations shows it breaks the consensus rules
     if !error_vec.is_empty() {
         let error_string = error_vec.join(", ");
-        return Err((*block_cid, TipsetRangeSyncerError::Validation(error_string)));
+        let error = if invalid {
+            TipsetRangeSyncerError::InvalidBlock(error_string)
+        } else {
+            TipsetRangeSyncerError::Validation(error_string)
+        };
+        return Err((*block_cid, error));
     }
 
     chain_store
@@ -1569,7 +1686,7 @@ fn verify_winning_post_proof<DB: BlockStore + Send + Sync + 'static, V: ProofVer
     )
     .map_err(|e| TipsetRangeSyncerError::DrawingChainRandomness(e.to_string()))?;
     let id = header.miner_address().id().map_err(|e| {
-        TipsetRangeSyncerError::Validation(format!(
+        TipsetRangeSyncerError::InvalidBlock(format!(
             "failed to get ID from miner address {}: {}",
             header.miner_address(),
             e
@@ -1596,7 +1713,7 @@ fn verify_winning_post_proof<DB: BlockStore + Send + Sync + 'static, V: ProofVer
         id,
     )
     .map_err(|e| {
-        TipsetRangeSyncerError::Validation(format!(
+        TipsetRangeSyncerError::InvalidBlock(format!(
             "Failed to verify winning PoSt: {}",
             e.to_string()
         ))
@@ -1611,7 +1728,7 @@ fn check_block_messages<
     block: &Block,
     base_tipset: &Arc<Tipset>,
 ) -> Result<(), TipsetRangeSyncerError> {
-    let network_version = get_network_version_default(block.header.epoch());
+    let network_version = state_manager.get_network_version(block.header.epoch());
 
     // Do the initial loop here
     // check block message and signatures in them
@@ -1648,7 +1765,10 @@ fn check_block_messages<
     } else {
         return Err(TipsetRangeSyncerError::BlockWithoutBlsAggregate);
     }
-    let price_list = price_list_by_epoch(base_tipset.epoch());
+    let price_list = price_list_by_epoch(
+        base_tipset.epoch(),
+        &state_manager.network_config().upgrades,
+    );
     let mut sum_gas_limit = 0;
 
     // Check messages for validity
@@ -1708,7 +1828,7 @@ fn check_block_messages<
     // Check validity for BLS messages
     for (i, msg) in block.bls_msgs().iter().enumerate() {
         check_msg(msg, &mut account_sequences, &tree).map_err(|e| {
-            TipsetRangeSyncerError::Validation(format!(
+            TipsetRangeSyncerError::InvalidBlock(format!(
                 "Block had invalid BLS message at index {}: {}",
                 i, e
             ))
@@ -1718,7 +1838,7 @@ fn check_block_messages<
     // Check validity for SECP messages
     for (i, msg) in block.secp_msgs().iter().enumerate() {
         check_msg(msg.message(), &mut account_sequences, &tree).map_err(|e| {
-            TipsetRangeSyncerError::Validation(format!(
+            TipsetRangeSyncerError::InvalidBlock(format!(
                 "block had an invalid secp message at index {}: {}",
                 i, e
             ))
@@ -1764,17 +1884,20 @@ fn block_sanity_checks(header: &BlockHeader) -> Result<(), TipsetRangeSyncerErro
     Ok(())
 }
 
-async fn validate_tipset_against_cache(
-    bad_block_cache: Arc<BadBlockCache>,
+async fn validate_tipset_against_cache<DB: BlockStore>(
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     tipset: &TipsetKeys,
     descendant_blocks: &[Cid],
 ) -> Result<(), TipsetRangeSyncerError> {
     for cid in tipset.cids() {
         if let Some(reason) = bad_block_cache.get(cid).await {
             for block_cid in descendant_blocks {
-                bad_block_cache
+                if let Err(e) = bad_block_cache
                     .put(*block_cid, format!("chain contained {}", cid))
-                    .await;
+                    .await
+                {
+                    warn!("Failed to mark block {} as bad: {}", block_cid, e);
+                }
             }
             return Err(TipsetRangeSyncerError::TipsetRangeWithBadBlock(
                 *cid, reason,
@@ -57,7 +57,7 @@ impl<'a> TipsetValidator<'a> {
     pub async fn validate<DB: BlockStore + Send + Sync + 'static>(
         &self,
         chainstore: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis_tipset: Arc<Tipset>,
     ) -> Result<(), TipsetValidationError> {
         // No empty blocks
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use address::Address;
-use db::Store;
+use db::{Column, Store};
 use encoding::{from_slice, to_vec};
 use serde::{Deserialize, Serialize};
 use std::error::Error as StdError;
@@ -14,6 +14,8 @@ const SIZE_LIMIT_HIGH: i64 = 30000;
 const PRUNE_COOLDOWN: Duration = Duration::from_secs(60); // 1 minute
 const REPLACE_BY_FEE_RATIO: f64 = 1.25;
 const GAS_LIMIT_OVERESTIMATION: f64 = 1.25;
+const MAX_LOCAL_SENDER_PENDING: u64 = 5000;
+const MAX_REMOTE_SENDER_PENDING: u64 = 1000;
 
 /// Config available for the [MessagePool].
 ///
@@ -26,6 +28,20 @@ pub struct MpoolConfig {
     pub replace_by_fee_ratio: f64,
     pub prune_cooldown: Duration,
     pub gas_limit_overestimation: f64,
+    /// Maximum number of pending messages of a sender whose messages are pushed by this node.
+    #[serde(default = "default_max_local_sender_pending")]
+    pub max_local_sender_pending: u64,
+    /// Maximum number of pending messages of any other sender.
+    #[serde(default = "default_max_remote_sender_pending")]
+    pub max_remote_sender_pending: u64,
+}
+
+fn default_max_local_sender_pending() -> u64 {
+    MAX_LOCAL_SENDER_PENDING
+}
+
+fn default_max_remote_sender_pending() -> u64 {
+    MAX_REMOTE_SENDER_PENDING
 }
 
 impl Default for MpoolConfig {
@@ -37,11 +53,14 @@ impl Default for MpoolConfig {
             replace_by_fee_ratio: REPLACE_BY_FEE_RATIO,
             prune_cooldown: PRUNE_COOLDOWN,
             gas_limit_overestimation: GAS_LIMIT_OVERESTIMATION,
+            max_local_sender_pending: MAX_LOCAL_SENDER_PENDING,
+            max_remote_sender_pending: MAX_REMOTE_SENDER_PENDING,
         }
     }
 }
 
 impl MpoolConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         priority_addrs: Vec<Address>,
         size_limit_high: i64,
@@ -49,6 +68,8 @@ impl MpoolConfig {
         replace_by_fee_ratio: f64,
         prune_cooldown: Duration,
         gas_limit_overestimation: f64,
+        max_local_sender_pending: u64,
+        max_remote_sender_pending: u64,
     ) -> Result<Self, String> {
         // Validate if parameters are valid
         if replace_by_fee_ratio < REPLACE_BY_FEE_RATIO {
@@ -63,6 +84,9 @@ impl MpoolConfig {
                 gas_limit_overestimation, 1
             ));
         }
+        if max_local_sender_pending == 0 || max_remote_sender_pending == 0 {
+            return Err("the pending message limits of senders must be greater than 0".to_owned());
+        }
         Ok(Self {
             priority_addrs,
             size_limit_high,
@@ -70,18 +94,31 @@ impl MpoolConfig {
             replace_by_fee_ratio,
             prune_cooldown,
             gas_limit_overestimation,
+            max_local_sender_pending,
+            max_remote_sender_pending,
         })
     }
 
     /// Saves message pool config to the database, to easily reload.
     pub fn save_config<DB: Store>(&self, store: &DB) -> Result<(), Box<dyn StdError>> {
-        Ok(store.write(MPOOL_CONFIG_KEY, to_vec(&self)?)?)
+        Ok(store.write_column(Column::Metadata, MPOOL_CONFIG_KEY, to_vec(&self)?)?)
     }
 
     /// Load config from store, if exists. If there is no config, uses default.
+    ///
+    /// Databases created before the store had columns kept the config with the blocks, where
+    /// it is looked up if it isn't found and moved to the metadata column.
     pub fn load_config<DB: Store>(store: &DB) -> Result<Self, Box<dyn StdError>> {
+        if let Some(v) = store.read_column(Column::Metadata, MPOOL_CONFIG_KEY)? {
+            return Ok(from_slice(&v)?);
+        }
         match store.read(MPOOL_CONFIG_KEY)? {
-            Some(v) => Ok(from_slice(&v)?),
+            Some(v) => {
+                let config: Self = from_slice(&v)?;
+                config.save_config(store)?;
+                store.delete(MPOOL_CONFIG_KEY)?;
+                Ok(config)
+            }
             None => Ok(Default::default()),
         }
     }
@@ -91,8 +128,43 @@ impl MpoolConfig {
         self.size_limit_low
     }
 
+    /// Returns the maximum number of pending messages of a sender, depending on whether its
+    /// messages are pushed by this node.
+    pub fn max_sender_pending(&self, local: bool) -> u64 {
+        if local {
+            self.max_local_sender_pending
+        } else {
+            self.max_remote_sender_pending
+        }
+    }
+
     /// Returns slice of [Address]es to prioritize when selecting messages.
     pub fn priority_addrs(&self) -> &[Address] {
         &self.priority_addrs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+
+    #[test]
+    fn load_config_migrates_legacy_key() {
+        let db = MemoryDB::default();
+        let config = MpoolConfig {
+            size_limit_high: 10,
+            ..Default::default()
+        };
+        db.write(MPOOL_CONFIG_KEY, to_vec(&config).unwrap())
+            .unwrap();
+
+        assert_eq!(MpoolConfig::load_config(&db).unwrap().size_limit_high, 10);
+        assert!(db.read(MPOOL_CONFIG_KEY).unwrap().is_none());
+        assert!(db
+            .read_column(Column::Metadata, MPOOL_CONFIG_KEY)
+            .unwrap()
+            .is_some());
+        assert_eq!(MpoolConfig::load_config(&db).unwrap().size_limit_high, 10);
+    }
+}
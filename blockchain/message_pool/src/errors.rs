@@ -25,8 +25,16 @@ pub enum Error {
     InvalidToAddr,
     #[error("Invalid from address")]
     InvalidFromAddr,
+    #[error("Invalid message signature: {0}")]
+    InvalidSignature(String),
+    #[error("Message is not valid for block inclusion: {0}")]
+    InvalidMessage(String),
     #[error("Message with sequence already in mempool")]
     DuplicateSequence,
+    #[error("Message sequence is too far ahead of the sender's next sequence")]
+    NonceGapTooLarge,
+    #[error("Too many pending messages for the sender")]
+    TooManyPendingMessages,
     #[error("State inconsistency with message. Try again")]
     TryAgain,
     #[error("Validation Error: {0}")]
@@ -0,0 +1,73 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use lazy_static::lazy_static;
+use prometheus::core::{AtomicU64, GenericCounter, GenericCounterVec, Opts};
+
+lazy_static! {
+    pub static ref MPOOL_REPUBLISHED_MESSAGE_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let mpool_republished_message_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "mpool_republished_message_total",
+                "Total number of local messages republished by the message pool",
+            )
+            .expect("Defining the mpool_republished_message_total metric must succeed"),
+        );
+        prometheus::default_registry().register(mpool_republished_message_total.clone()).expect(
+            "Registering the mpool_republished_message_total metric with the metrics registry must succeed",
+        );
+        mpool_republished_message_total
+    };
+    pub static ref MPOOL_REPUBLISH_DEFERRED_MESSAGE_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let mpool_republish_deferred_message_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "mpool_republish_deferred_message_total",
+                "Total number of local message republishes deferred by the republish backoff",
+            )
+            .expect("Defining the mpool_republish_deferred_message_total metric must succeed"),
+        );
+        prometheus::default_registry().register(mpool_republish_deferred_message_total.clone()).expect(
+            "Registering the mpool_republish_deferred_message_total metric with the metrics registry must succeed",
+        );
+        mpool_republish_deferred_message_total
+    };
+    pub static ref MPOOL_REPUBLISH_FAILURE_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let mpool_republish_failure_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "mpool_republish_failure_total",
+                "Total number of failed republishes of the local messages",
+            )
+            .expect("Defining the mpool_republish_failure_total metric must succeed"),
+        );
+        prometheus::default_registry().register(mpool_republish_failure_total.clone()).expect(
+            "Registering the mpool_republish_failure_total metric with the metrics registry must succeed",
+        );
+        mpool_republish_failure_total
+    };
+    pub static ref MPOOL_SENDER_LIMIT_REJECTED_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
+        let mpool_sender_limit_rejected_total = Box::new(
+            GenericCounterVec::<AtomicU64>::new(
+                Opts::new(
+                    "mpool_sender_limit_rejected_total",
+                    "Total number of messages rejected because their sender has too many pending messages",
+                ),
+                &[labels::SENDER],
+            )
+            .expect("Defining the mpool_sender_limit_rejected_total metric must succeed"),
+        );
+        prometheus::default_registry().register(mpool_sender_limit_rejected_total.clone()).expect(
+            "Registering the mpool_sender_limit_rejected_total metric with the metrics registry must succeed",
+        );
+        mpool_sender_limit_rejected_total
+    };
+}
+
+pub mod labels {
+    pub const SENDER: &str = "sender";
+}
+
+pub mod values {
+    // mpool_sender_limit_rejected_total
+    pub const LOCAL: &str = "local";
+    pub const REMOTE: &str = "remote";
+}
@@ -357,6 +357,7 @@ where
     // - the total gasReward cannot exceed the actor's balance; drop all messages that exceed
     //   the balance
     let actor_state = api.read().await.get_actor_after(&actor, &ts)?;
+    let upgrades = api.read().await.upgrade_schedule();
     let mut cur_seq = actor_state.sequence;
     let mut balance = actor_state.balance;
 
@@ -384,7 +385,7 @@ where
         }
         cur_seq += 1;
 
-        let min_gas = interpreter::price_list_by_epoch(ts.epoch())
+        let min_gas = interpreter::price_list_by_epoch(ts.epoch(), &upgrades)
             .on_chain_message(m.marshal_cbor()?.len())
             .total();
 
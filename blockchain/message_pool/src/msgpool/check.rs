@@ -0,0 +1,417 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Contains the preflight checks of messages.
+//! For each message, the checks report whether it is well formed, whether its fees allow it to be
+//! included in the next blocks, and whether its sender can pay for it given its nonce and the
+//! messages it already has pending, so that wallets can fix a message before pushing it.
+
+use super::msg_pool::MessagePool;
+use super::provider::Provider;
+use crate::msgpool::{rbf_min_premium, BASE_FEE_LOWER_BOUND_FACTOR};
+use crate::utils::get_base_fee_lower_bound;
+use crate::Error;
+use address::Address;
+use async_std::sync::Arc;
+use blocks::Tipset;
+use chain::MINIMUM_BASE_FEE;
+use cid::Cid;
+use encoding::Cbor;
+use interpreter::PriceList;
+use message::{Message, UnsignedMessage};
+use num_bigint::BigInt;
+use std::collections::{BTreeMap, HashMap};
+use types::NetworkVersion;
+
+/// Size above which a message is too big to be propagated, keeping some room for its signature.
+const MAX_UNSIGNED_MESSAGE_SIZE: usize = 32 * 1024 - 128;
+const BASE_FEE_UPPER_BOUND_FACTOR: i64 = 10;
+
+/// Identifies the check a [CheckStatus] is the result of.
+/// The values match the Lotus `CheckStatusCode`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatusCode {
+    MessageSerialize = 1,
+    MessageSize = 2,
+    MessageValidity = 3,
+    MessageMinGas = 4,
+    MessageMinBaseFee = 5,
+    MessageBaseFee = 6,
+    MessageBaseFeeLowerBound = 7,
+    MessageBaseFeeUpperBound = 8,
+    MessageGetStateNonce = 9,
+    MessageNonce = 10,
+    MessageGetStateBalance = 11,
+    MessageBalance = 12,
+    /// The gas premium of a replacing message is high enough to replace the pending one.
+    MessageReplaceByFee = 13,
+}
+
+/// Result of a check, with hints such as the expected nonce or the current base fee.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckStatus {
+    pub code: CheckStatusCode,
+    pub ok: bool,
+    pub err: String,
+    pub hint: BTreeMap<String, String>,
+}
+
+impl CheckStatus {
+    fn new(code: CheckStatusCode) -> Self {
+        Self {
+            code,
+            ok: true,
+            err: String::new(),
+            hint: BTreeMap::new(),
+        }
+    }
+
+    fn hint(mut self, key: &str, value: impl ToString) -> Self {
+        self.hint.insert(key.to_owned(), value.to_string());
+        self
+    }
+
+    fn fail(mut self, err: impl Into<String>) -> Self {
+        self.ok = false;
+        self.err = err.into();
+        self
+    }
+}
+
+/// Result of a check done on the message with the given Cid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageCheckStatus {
+    pub cid: Cid,
+    pub status: CheckStatus,
+}
+
+/// Message proposed to be pushed to the message pool. The nonce of the message is only checked
+/// if it is marked valid, otherwise it is assumed it will be assigned when pushing the message.
+#[derive(Debug, Clone)]
+pub struct MessagePrototype {
+    pub message: UnsignedMessage,
+    pub valid_nonce: bool,
+}
+
+/// Nonce and funds expected of a sender, as messages of the checked set are accounted for.
+struct SenderState {
+    next_sequence: u64,
+    required_funds: BigInt,
+    balance: Option<BigInt>,
+}
+
+/// Values shared by the checks of all the messages of a set.
+struct CheckContext {
+    cur_ts: Arc<Tipset>,
+    network_version: NetworkVersion,
+    price_list: PriceList,
+    base_fee: BigInt,
+}
+
+impl<T> MessagePool<T>
+where
+    T: Provider + Send + Sync + 'static,
+{
+    /// Checks messages proposed to be pushed, accounting for the messages already pending for
+    /// their senders.
+    pub async fn check_messages(
+        &self,
+        protos: Vec<MessagePrototype>,
+    ) -> Result<Vec<Vec<MessageCheckStatus>>, Error> {
+        let flexible_nonces: Vec<bool> = protos.iter().map(|p| !p.valid_nonce).collect();
+        let msgs: Vec<UnsignedMessage> = protos.into_iter().map(|p| p.message).collect();
+        self.check_message_set(&msgs, false, &flexible_nonces).await
+    }
+
+    /// Checks the pending messages of a sender, ordered by sequence.
+    pub async fn check_pending_messages(
+        &self,
+        from: &Address,
+    ) -> Result<Vec<Vec<MessageCheckStatus>>, Error> {
+        let msgs: Vec<UnsignedMessage> = self
+            .pending_for(from)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|m| m.into_message())
+            .collect();
+        self.check_message_set(&msgs, true, &[]).await
+    }
+
+    /// Checks the pending messages of the senders of the given messages, as if the given
+    /// messages replaced the pending ones with the same sequence. The replacing messages are
+    /// additionally checked to pay enough gas premium to replace the pending ones.
+    pub async fn check_replace_messages(
+        &self,
+        replace: Vec<UnsignedMessage>,
+    ) -> Result<Vec<Vec<MessageCheckStatus>>, Error> {
+        let mut senders = Vec::new();
+        let mut by_sender: HashMap<Address, BTreeMap<u64, UnsignedMessage>> = HashMap::new();
+        let mut replaced_premiums = HashMap::new();
+        {
+            let pending = self.pending.read().await;
+            for m in replace {
+                let mset = pending.get(m.from());
+                if let Some(pending_msg) = mset.and_then(|mset| mset.msgs.get(&m.sequence())) {
+                    replaced_premiums.insert(m.cid()?, pending_msg.message().gas_premium().clone());
+                }
+                let msgs = by_sender.entry(*m.from()).or_insert_with(|| {
+                    senders.push(*m.from());
+                    mset.map(|mset| {
+                        mset.msgs
+                            .values()
+                            .map(|sm| (sm.sequence(), sm.message().clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+                });
+                msgs.insert(m.sequence(), m);
+            }
+        }
+
+        let msgs: Vec<UnsignedMessage> = senders
+            .iter()
+            .filter_map(|from| by_sender.remove(from))
+            .flat_map(|msgs| msgs.into_iter().map(|(_, m)| m))
+            .collect();
+        let mut result = self.check_message_set(&msgs, true, &[]).await?;
+
+        let rbf_ratio = self.config.read().await.replace_by_fee_ratio;
+        for (m, checks) in msgs.iter().zip(result.iter_mut()) {
+            let cid = m.cid()?;
+            if let Some(premium) = replaced_premiums.get(&cid) {
+                let min_premium = rbf_min_premium(premium, rbf_ratio);
+                let mut status = CheckStatus::new(CheckStatusCode::MessageReplaceByFee)
+                    .hint("minGasPremium", &min_premium);
+                if m.gas_premium() < &min_premium {
+                    status = status.fail(format!(
+                        "GasPremium too low to replace the pending message (ratio: {})",
+                        rbf_ratio
+                    ));
+                }
+                checks.push(MessageCheckStatus { cid, status });
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Checks a set of messages in order. Unless the messages are `interned` in the message pool,
+    /// the messages pending for their senders are accounted for. The nonce of the messages
+    /// marked as flexible is not checked.
+    async fn check_message_set(
+        &self,
+        msgs: &[UnsignedMessage],
+        interned: bool,
+        flexible_nonces: &[bool],
+    ) -> Result<Vec<Vec<MessageCheckStatus>>, Error> {
+        let cur_ts = self.cur_tipset.read().await.clone();
+        let epoch = cur_ts.epoch() + 1;
+        let (base_fee, upgrades) = {
+            let api = self.api.read().await;
+            let base_fee = api.chain_compute_base_fee(&cur_ts)?;
+            (base_fee, api.upgrade_schedule())
+        };
+        let ctx = CheckContext {
+            network_version: upgrades.network_version(epoch),
+            price_list: interpreter::price_list_by_epoch(epoch, &upgrades),
+            base_fee,
+            cur_ts,
+        };
+
+        let mut senders = HashMap::new();
+        let mut result = Vec::with_capacity(msgs.len());
+        for (i, m) in msgs.iter().enumerate() {
+            let flexible_nonce = flexible_nonces.get(i).copied().unwrap_or(false);
+            let checks = self
+                .run_checks(&ctx, &mut senders, m, interned, flexible_nonce)
+                .await;
+            let cid = m.cid()?;
+            result.push(
+                checks
+                    .into_iter()
+                    .map(|status| MessageCheckStatus { cid, status })
+                    .collect(),
+            );
+        }
+        Ok(result)
+    }
+
+    /// Runs the checks of a message, stopping early when the remaining checks can't be done.
+    async fn run_checks(
+        &self,
+        ctx: &CheckContext,
+        senders: &mut HashMap<Address, SenderState>,
+        m: &UnsignedMessage,
+        interned: bool,
+        flexible_nonce: bool,
+    ) -> Vec<CheckStatus> {
+        let mut checks = Vec::new();
+
+        // pre-check: sender nonce
+        let mut status = CheckStatus::new(CheckStatusCode::MessageGetStateNonce);
+        if !senders.contains_key(m.from()) {
+            let pending_state = if interned {
+                None
+            } else {
+                self.pending
+                    .read()
+                    .await
+                    .get(m.from())
+                    .map(|mset| SenderState {
+                        next_sequence: mset.next_sequence,
+                        required_funds: mset
+                            .msgs
+                            .values()
+                            .fold(BigInt::default(), |acc, sm| acc + sm.required_funds()),
+                        balance: None,
+                    })
+            };
+            let state = match pending_state {
+                Some(state) => Some(state),
+                None => match self.api.read().await.get_actor_after(m.from(), &ctx.cur_ts) {
+                    Ok(actor) => Some(SenderState {
+                        next_sequence: actor.sequence,
+                        required_funds: BigInt::default(),
+                        balance: Some(actor.balance),
+                    }),
+                    Err(e) => {
+                        status = status.fail(format!("error retrieving state nonce: {}", e));
+                        None
+                    }
+                },
+            };
+            if let Some(state) = state {
+                status = status.hint("nonce", state.next_sequence);
+                senders.insert(*m.from(), state);
+            }
+        }
+        let ok = status.ok;
+        checks.push(status);
+        if !ok {
+            return checks;
+        }
+        let state = senders
+            .get_mut(m.from())
+            .expect("sender state was inserted above");
+
+        // pre-check: sender balance
+        let mut status = CheckStatus::new(CheckStatusCode::MessageGetStateBalance);
+        if state.balance.is_none() {
+            match self.api.read().await.get_actor_after(m.from(), &ctx.cur_ts) {
+                Ok(actor) => state.balance = Some(actor.balance),
+                Err(e) => status = status.fail(format!("error retrieving state balance: {}", e)),
+            }
+        }
+        let balance = match &state.balance {
+            Some(balance) => balance.clone(),
+            None => {
+                checks.push(status);
+                return checks;
+            }
+        };
+        checks.push(status.hint("balance", &balance));
+
+        // 1. Serialization
+        let status = CheckStatus::new(CheckStatusCode::MessageSerialize);
+        let size = match m.marshal_cbor() {
+            Ok(bz) => {
+                checks.push(status);
+                bz.len()
+            }
+            Err(e) => {
+                checks.push(status.fail(e.to_string()));
+                return checks;
+            }
+        };
+
+        // 2. Message size
+        let mut status = CheckStatus::new(CheckStatusCode::MessageSize);
+        if size > MAX_UNSIGNED_MESSAGE_SIZE {
+            status = status.fail("message too big");
+        }
+        checks.push(status);
+
+        // 3. Syntactic validation, also checking the gas limit against the block gas limit
+        let status = CheckStatus::new(CheckStatusCode::MessageValidity);
+        if let Err(e) = m.valid_for_block_inclusion(0, ctx.network_version) {
+            checks.push(status.fail(format!("syntactically invalid message: {}", e)));
+            return checks;
+        }
+        checks.push(status);
+
+        // 4. Min gas
+        let min_gas = ctx.price_list.on_chain_message(size).total();
+        let mut status = CheckStatus::new(CheckStatusCode::MessageMinGas).hint("minGas", min_gas);
+        if m.gas_limit() < min_gas {
+            status = status.fail("GasLimit less than epoch minimum gas");
+        }
+        checks.push(status);
+
+        // 5. Min base fee
+        let mut status = CheckStatus::new(CheckStatusCode::MessageMinBaseFee);
+        if m.gas_fee_cap() < &MINIMUM_BASE_FEE {
+            status = status.fail("GasFeeCap less than minimum base fee");
+        }
+        let ok = status.ok;
+        checks.push(status);
+
+        if ok {
+            // 6. Base fee
+            let mut status =
+                CheckStatus::new(CheckStatusCode::MessageBaseFee).hint("baseFee", &ctx.base_fee);
+            if m.gas_fee_cap() < &ctx.base_fee {
+                status = status.fail("GasFeeCap less than current base fee");
+            }
+            checks.push(status);
+
+            // 7. Base fee lower bound
+            let lower_bound = get_base_fee_lower_bound(&ctx.base_fee, BASE_FEE_LOWER_BOUND_FACTOR);
+            let mut status = CheckStatus::new(CheckStatusCode::MessageBaseFeeLowerBound)
+                .hint("baseFeeLowerBound", &lower_bound)
+                .hint("baseFee", &ctx.base_fee);
+            if m.gas_fee_cap() < &lower_bound {
+                status = status.fail(
+                    "GasFeeCap less than base fee lower bound for inclusion in next 20 epochs",
+                );
+            }
+            checks.push(status);
+
+            // 8. Base fee upper bound
+            let upper_bound = &ctx.base_fee * BASE_FEE_UPPER_BOUND_FACTOR;
+            let mut status = CheckStatus::new(CheckStatusCode::MessageBaseFeeUpperBound)
+                .hint("baseFeeUpperBound", &upper_bound)
+                .hint("baseFee", &ctx.base_fee);
+            if m.gas_fee_cap() < &upper_bound {
+                status = status.fail(
+                    "GasFeeCap less than base fee upper bound for inclusion in next 20 epochs",
+                );
+            }
+            checks.push(status);
+        }
+
+        // 9. Nonce
+        let mut status =
+            CheckStatus::new(CheckStatusCode::MessageNonce).hint("nextNonce", state.next_sequence);
+        if !flexible_nonce && state.next_sequence != m.sequence() {
+            status = status.fail(format!(
+                "message nonce doesn't match next nonce ({})",
+                state.next_sequence
+            ));
+        } else {
+            state.next_sequence += 1;
+        }
+        checks.push(status);
+
+        // 10. Balance, including the messages checked before and the pending ones
+        state.required_funds += m.required_funds();
+        let mut status = CheckStatus::new(CheckStatusCode::MessageBalance)
+            .hint("requiredFunds", &state.required_funds);
+        if balance < state.required_funds {
+            status = status.fail("insufficient balance");
+        }
+        checks.push(status);
+
+        checks
+    }
+}
@@ -1,17 +1,22 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod check;
 pub(crate) mod msg_pool;
 pub(crate) mod provider;
+pub(crate) mod republish;
 mod selection;
 pub mod test_provider;
 pub(crate) mod utils;
 
 use super::errors::Error;
+use crate::config::MpoolConfig;
+use crate::metrics;
 use crate::msg_chain::{create_message_chains, Chains};
 use crate::msg_pool::MsgSet;
 use crate::msg_pool::{add_helper, remove};
 use crate::provider::Provider;
+use crate::republish::RepublishSchedule;
 use address::Address;
 use async_std::channel::Sender;
 use async_std::sync::{Arc, RwLock};
@@ -24,13 +29,15 @@ use log::error;
 use lru::LruCache;
 use message::{Message, SignedMessage};
 use networks::BLOCK_DELAY_SECS;
+use num_bigint::{BigInt, Integer};
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use std::{borrow::BorrowMut, cmp::Ordering};
 use tokio::sync::broadcast::{Receiver as Subscriber, Sender as Publisher};
 use utils::{get_base_fee_lower_bound, recover_sig};
 
-const REPLACE_BY_FEE_RATIO: f32 = 1.25;
-const RBF_NUM: u64 = ((REPLACE_BY_FEE_RATIO - 1f32) * 256f32) as u64;
+pub use self::check::{CheckStatus, CheckStatusCode, MessageCheckStatus, MessagePrototype};
+
 const RBF_DENOM: u64 = 256;
 const BASE_FEE_LOWER_BOUND_FACTOR_CONSERVATIVE: i64 = 100;
 const BASE_FEE_LOWER_BOUND_FACTOR: i64 = 10;
@@ -40,6 +47,13 @@ const REPUBLISH_INTERVAL: u64 = 10 * BLOCK_DELAY_SECS + PROPAGATION_DELAY_SECS;
 // TODO: Implement guess gas module
 const MIN_GAS: i64 = 1298450;
 
+/// Returns the minimum gas premium a message replacing a pending message of the given premium
+/// needs, for the replace by fee ratio of the message pool config.
+fn rbf_min_premium(premium: &BigInt, replace_by_fee_ratio: f64) -> BigInt {
+    let rbf_num = BigInt::from(((replace_by_fee_ratio - 1.0) * RBF_DENOM as f64) as u64);
+    premium + (premium * rbf_num).div_floor(&BigInt::from(RBF_DENOM)) + 2u8
+}
+
 /// Get the state of the base_sequence for a given address in the current Tipset
 async fn get_state_sequence<T>(
     api: &RwLock<T>,
@@ -55,6 +69,9 @@ where
     Ok(base_sequence)
 }
 
+/// Republishes the local messages which are still pending and due to be republished according
+/// to the republish schedule. The local messages which are not pending anymore are forgotten.
+#[allow(clippy::too_many_arguments)]
 async fn republish_pending_messages<T>(
     api: &RwLock<T>,
     network_sender: &Sender<NetworkMessage>,
@@ -63,6 +80,8 @@ async fn republish_pending_messages<T>(
     cur_tipset: &RwLock<Arc<Tipset>>,
     republished: &RwLock<HashSet<Cid>>,
     local_addrs: &RwLock<Vec<Address>>,
+    local_msgs: &RwLock<HashSet<SignedMessage>>,
+    schedule: &RwLock<RepublishSchedule>,
 ) -> Result<(), Error>
 where
     T: Provider,
@@ -73,20 +92,31 @@ where
     let mut pending_map: HashMap<Address, HashMap<u64, SignedMessage>> = HashMap::new();
 
     republished.write().await.clear();
-    let local_addrs = local_addrs.read().await;
-    for actor in local_addrs.iter() {
-        if let Some(mset) = pending.read().await.get(actor) {
-            if mset.msgs.is_empty() {
-                continue;
-            }
-            let mut pend: HashMap<u64, SignedMessage> = HashMap::with_capacity(mset.msgs.len());
-            for (nonce, m) in mset.msgs.clone().into_iter() {
-                pend.insert(nonce, m);
+    let mut local_pending = HashSet::new();
+    let mut local_addrs = local_addrs.write().await;
+    {
+        let pending = pending.read().await;
+        local_addrs.retain(|actor| pending.contains_key(actor));
+        for actor in local_addrs.iter() {
+            if let Some(mset) = pending.get(actor) {
+                if mset.msgs.is_empty() {
+                    continue;
+                }
+                let mut pend: HashMap<u64, SignedMessage> = HashMap::with_capacity(mset.msgs.len());
+                for (nonce, m) in mset.msgs.clone().into_iter() {
+                    local_pending.insert(m.cid()?);
+                    pend.insert(nonce, m);
+                }
+                pending_map.insert(*actor, pend);
             }
-            pending_map.insert(*actor, pend);
         }
     }
     drop(local_addrs);
+    local_msgs
+        .write()
+        .await
+        .retain(|m| m.cid().map_or(false, |cid| local_pending.contains(&cid)));
+    schedule.write().await.retain(&local_pending);
 
     if pending_map.is_empty() {
         return Ok(());
@@ -154,6 +184,14 @@ where
         }
     }
     drop(ts);
+
+    let now = Instant::now();
+    let mut schedule = schedule.write().await;
+    let selected = msgs.len();
+    msgs.retain(|m| m.cid().map_or(false, |cid| schedule.due(cid, now)));
+    drop(schedule);
+    metrics::MPOOL_REPUBLISH_DEFERRED_MESSAGE_TOTAL.inc_by((selected - msgs.len()) as u64);
+
     for m in msgs.iter() {
         let mb = m.marshal_cbor()?;
         network_sender
@@ -163,6 +201,7 @@ where
             })
             .await
             .map_err(|_| Error::Other("Network receiver dropped".to_string()))?;
+        metrics::MPOOL_REPUBLISHED_MESSAGE_TOTAL.inc();
     }
 
     let mut republished_t = HashSet::new();
@@ -184,6 +223,7 @@ pub async fn head_change<T>(
     republished: &RwLock<HashSet<Cid>>,
     pending: &RwLock<HashMap<Address, MsgSet>>,
     cur_tipset: &RwLock<Arc<Tipset>>,
+    config: &RwLock<MpoolConfig>,
     revert: Vec<Tipset>,
     apply: Vec<Tipset>,
 ) -> Result<(), Error>
@@ -239,11 +279,13 @@ where
             .await
             .map_err(|_| Error::Other("Republish receiver dropped".to_string()))?;
     }
+    let rbf_ratio = config.read().await.replace_by_fee_ratio;
     for (_, hm) in rmsgs {
         for (_, msg) in hm {
             let sequence =
                 get_state_sequence(api, &msg.from(), &cur_tipset.read().await.clone()).await?;
-            if let Err(e) = add_helper(api, bls_sig_cache, pending, msg, sequence).await {
+            if let Err(e) = add_helper(api, bls_sig_cache, pending, msg, sequence, rbf_ratio).await
+            {
                 error!("Failed to readd message from reorg to mpool: {}", e);
             }
         }
@@ -296,6 +338,7 @@ pub mod tests {
     use async_std::task;
     use blocks::Tipset;
     use crypto::SignatureType;
+    use db::MemoryDB;
     use key_management::{KeyStore, KeyStoreConfig, Wallet};
     use message::{SignedMessage, UnsignedMessage};
     use num_bigint::BigInt;
@@ -370,6 +413,7 @@ pub mod tests {
                 republished.as_ref(),
                 pending.as_ref(),
                 cur_tipset.as_ref(),
+                mpool.config.as_ref(),
                 Vec::new(),
                 vec![Tipset::new(vec![a]).unwrap()],
             )
@@ -432,6 +476,7 @@ pub mod tests {
                 republished.as_ref(),
                 pending.as_ref(),
                 cur_tipset.as_ref(),
+                mpool.config.as_ref(),
                 Vec::new(),
                 vec![Tipset::new(vec![a]).unwrap()],
             )
@@ -454,6 +499,7 @@ pub mod tests {
                 republished.as_ref(),
                 pending.as_ref(),
                 cur_tipset.as_ref(),
+                mpool.config.as_ref(),
                 Vec::new(),
                 vec![Tipset::new(vec![b.clone()]).unwrap()],
             )
@@ -471,6 +517,7 @@ pub mod tests {
                 republished.as_ref(),
                 pending.as_ref(),
                 cur_tipset.as_ref(),
+                mpool.config.as_ref(),
                 vec![Tipset::new(vec![b]).unwrap()],
                 Vec::new(),
             )
@@ -533,6 +580,178 @@ pub mod tests {
         })
     }
 
+    #[async_std::test]
+    async fn test_push_untrusted() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+
+        let mut tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let (tx, _rx) = bounded(50);
+        let mpool = MessagePool::new(tma, "mptest".to_string(), tx, Default::default())
+            .await
+            .unwrap();
+
+        let gapped = create_smsg(&target, &sender, wallet.borrow_mut(), 5, 1000000, 1);
+        assert_eq!(
+            mpool.push_untrusted(gapped).await,
+            Err(Error::NonceGapTooLarge)
+        );
+
+        for i in 0..10 {
+            let msg = create_smsg(&target, &sender, wallet.borrow_mut(), i, 1000000, 1);
+            mpool.push_untrusted(msg).await.unwrap();
+        }
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 10, 1000000, 1);
+        assert_eq!(
+            mpool.push_untrusted(msg.clone()).await,
+            Err(Error::TooManyPendingMessages)
+        );
+        // Trusted pushes are not limited
+        mpool.push(msg).await.unwrap();
+
+        mpool.clear(true).await;
+        assert!(mpool.pending().await.unwrap().0.is_empty());
+    }
+
+    #[async_std::test]
+    async fn test_sender_pending_limits() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let remote = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let local = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+
+        let (tx, _rx) = bounded(50);
+        let mpool = MessagePool::new(
+            TestApi::default(),
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        let mut cfg = mpool.get_config().await;
+        cfg.max_local_sender_pending = 3;
+        cfg.max_remote_sender_pending = 2;
+        mpool.set_config(&MemoryDB::default(), cfg).await.unwrap();
+
+        for i in 0..2 {
+            let msg = create_smsg(&target, &remote, wallet.borrow_mut(), i, 1000000, 1);
+            mpool.add(msg).await.unwrap();
+        }
+        let msg = create_smsg(&target, &remote, wallet.borrow_mut(), 2, 1000000, 1);
+        assert_eq!(mpool.add(msg).await, Err(Error::TooManyPendingMessages));
+
+        for i in 0..3 {
+            let msg = create_smsg(&target, &local, wallet.borrow_mut(), i, 1000000, 1);
+            mpool.push(msg).await.unwrap();
+        }
+        let msg = create_smsg(&target, &local, wallet.borrow_mut(), 3, 1000000, 1);
+        assert_eq!(mpool.push(msg).await, Err(Error::TooManyPendingMessages));
+    }
+
+    #[async_std::test]
+    async fn test_replace_by_fee_ratio() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+
+        let (tx, _rx) = bounded(50);
+        let mpool = MessagePool::new(
+            TestApi::default(),
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        let mut cfg = mpool.get_config().await;
+        cfg.replace_by_fee_ratio = 2.0;
+        mpool.set_config(&MemoryDB::default(), cfg).await.unwrap();
+
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 100);
+        mpool.add(msg).await.unwrap();
+
+        // Enough for the default ratio, not for the configured one
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 150);
+        assert_eq!(mpool.add(msg).await, Err(Error::GasPriceTooLow));
+
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 202);
+        mpool.add(msg).await.unwrap();
+    }
+
+    #[async_std::test]
+    async fn test_check_messages() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+
+        let mut tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        tma.set_state_balance_raw(&sender, BigInt::from(150_000_000));
+        // Low enough for the fee caps of the messages to be above the base fee upper bound
+        tma.set_base_fee(BigInt::from(10));
+        let (tx, _rx) = bounded(50);
+        let mpool = MessagePool::new(tma, "mptest".to_string(), tx, Default::default())
+            .await
+            .unwrap();
+
+        let protos = (0..2)
+            .map(|i| MessagePrototype {
+                message: create_smsg(&target, &sender, wallet.borrow_mut(), i * 2, 1000000, 1)
+                    .into_message(),
+                valid_nonce: true,
+            })
+            .collect();
+        let result = mpool.check_messages(protos).await.unwrap();
+        let failed = |checks: &[MessageCheckStatus]| -> Vec<CheckStatusCode> {
+            checks
+                .iter()
+                .filter(|c| !c.status.ok)
+                .map(|c| c.status.code)
+                .collect()
+        };
+        assert!(failed(&result[0]).is_empty());
+        assert_eq!(
+            failed(&result[1]),
+            vec![
+                CheckStatusCode::MessageNonce,
+                CheckStatusCode::MessageBalance
+            ]
+        );
+
+        // The message pushed to the pool is accounted for
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 1);
+        mpool.add(msg.clone()).await.unwrap();
+        let proto = MessagePrototype {
+            message: create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 1)
+                .into_message(),
+            valid_nonce: false,
+        };
+        let result = mpool.check_messages(vec![proto]).await.unwrap();
+        assert_eq!(failed(&result[0]), vec![CheckStatusCode::MessageBalance]);
+
+        let result = mpool.check_pending_messages(&sender).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert!(failed(&result[0]).is_empty());
+
+        let mut replacement = msg.into_message();
+        replacement.gas_premium = BigInt::from(2);
+        let result = mpool
+            .check_replace_messages(vec![replacement])
+            .await
+            .unwrap();
+        assert_eq!(
+            failed(&result[0]),
+            vec![CheckStatusCode::MessageReplaceByFee]
+        );
+    }
+
     #[async_std::test]
     async fn test_msg_chains() {
         let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
@@ -9,12 +9,14 @@
 use crate::config::MpoolConfig;
 use crate::errors::Error;
 use crate::head_change;
+use crate::metrics;
+use crate::msgpool::rbf_min_premium;
 use crate::msgpool::recover_sig;
 use crate::msgpool::republish_pending_messages;
 use crate::msgpool::BASE_FEE_LOWER_BOUND_FACTOR_CONSERVATIVE;
 use crate::msgpool::REPUBLISH_INTERVAL;
-use crate::msgpool::{RBF_DENOM, RBF_NUM};
 use crate::provider::Provider;
+use crate::republish::RepublishSchedule;
 use crate::utils::get_base_fee_lower_bound;
 use address::{Address, Protocol};
 use async_std::channel::{bounded, Sender};
@@ -28,13 +30,15 @@ use crypto::{Signature, SignatureType};
 use db::Store;
 use encoding::Cbor;
 use forest_libp2p::{NetworkMessage, Topic, PUBSUB_MSG_STR};
-use futures::{future::select, StreamExt};
+use futures::{
+    future::{select, Either},
+    StreamExt,
+};
 use log::warn;
 use lru::LruCache;
 use message::{ChainMessage, Message, SignedMessage};
-use networks::NEWEST_NETWORK_VERSION;
+use networks::UpgradeSchedule;
 use num_bigint::BigInt;
-use num_bigint::Integer;
 use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 use tokio::sync::broadcast::error::RecvError;
@@ -43,13 +47,18 @@ use types::verifier::ProofVerifier;
 // LruCache sizes have been taken from the lotus implementation
 const BLS_SIG_CACHE_SIZE: usize = 40000;
 const SIG_VAL_CACHE_SIZE: usize = 32000;
+/// Maximum distance between the sequence of an untrusted message and the next expected sequence
+/// of its sender.
+const MAX_NONCE_GAP: u64 = 4;
+/// Maximum number of pending messages of a sender accepted from untrusted sources.
+const MAX_UNTRUSTED_ACTOR_PENDING_MESSAGES: usize = 10;
 
 /// Simple struct that contains a hashmap of messages where k: a message from address, v: a message
 /// which corresponds to that address.
 #[derive(Clone, Default, Debug)]
 pub struct MsgSet {
     pub(crate) msgs: HashMap<u64, SignedMessage>,
-    next_sequence: u64,
+    pub(crate) next_sequence: u64,
     required_funds: BigInt,
 }
 
@@ -64,17 +73,16 @@ impl MsgSet {
     }
 
     /// Add a signed message to the MsgSet. Increase next_sequence if the message has a
-    /// sequence greater than any existing message sequence.
-    pub fn add(&mut self, m: SignedMessage) -> Result<(), Error> {
+    /// sequence greater than any existing message sequence. A message replacing a pending one
+    /// needs a gas premium raised by the given replace by fee ratio.
+    pub fn add(&mut self, m: SignedMessage, rbf_ratio: f64) -> Result<(), Error> {
         if self.msgs.is_empty() || m.sequence() >= self.next_sequence {
             self.next_sequence = m.sequence() + 1;
         }
         if let Some(exms) = self.msgs.get(&m.sequence()) {
             if m.cid()? != exms.cid()? {
-                let premium = exms.message().gas_premium();
-                let rbf_denom = BigInt::from(RBF_DENOM);
-                let min_price = premium + ((premium * RBF_NUM).div_floor(&rbf_denom)) + 1u8;
-                if m.message().gas_premium() <= &min_price {
+                let min_premium = rbf_min_premium(exms.message().gas_premium(), rbf_ratio);
+                if m.message().gas_premium() < &min_premium {
                     return Err(Error::GasPriceTooLow);
                 }
             } else {
@@ -152,10 +160,10 @@ pub struct MessagePool<T> {
     pub republished: Arc<RwLock<HashSet<Cid>>>,
     /// Acts as a signal to republish messages from the republished set of messages
     pub repub_trigger: Sender<()>,
-    /// TODO look into adding a cap to local_msgs
+    /// Messages pushed by this node, forgotten once they are not pending anymore
     local_msgs: Arc<RwLock<HashSet<SignedMessage>>>,
     /// Configurable parameters of the message pool
-    pub config: MpoolConfig,
+    pub config: Arc<RwLock<MpoolConfig>>,
 }
 
 impl<T> MessagePool<T>
@@ -196,7 +204,7 @@ where
             sig_val_cache,
             local_msgs,
             republished,
-            config,
+            config: Arc::new(RwLock::new(config)),
             network_sender,
             repub_trigger,
         };
@@ -211,6 +219,7 @@ where
         let republished = mp.republished.clone();
 
         let cur_tipset = mp.cur_tipset.clone();
+        let config = mp.config.clone();
         let repub_trigger = Arc::new(mp.repub_trigger.clone());
 
         // Reacts to new HeadChanges
@@ -238,6 +247,7 @@ where
                             republished.as_ref(),
                             pending.as_ref(),
                             &cur.as_ref(),
+                            config.as_ref(),
                             rev,
                             app,
                         )
@@ -259,13 +269,19 @@ where
         let cur_tipset = mp.cur_tipset.clone();
         let republished = mp.republished.clone();
         let local_addrs = mp.local_addrs.clone();
+        let local_msgs = mp.local_msgs.clone();
         let network_sender = Arc::new(mp.network_sender.clone());
         let network_name = mp.network_name.clone();
         // Reacts to republishing requests
         task::spawn(async move {
-            let mut interval = interval(Duration::from_millis(REPUBLISH_INTERVAL));
+            let republish_interval = Duration::from_secs(REPUBLISH_INTERVAL);
+            let schedule = RwLock::new(RepublishSchedule::new(republish_interval));
+            let mut interval = interval(republish_interval);
             loop {
-                select(interval.next(), repub_trigger_rx.next()).await;
+                // Messages are republished right away, when they were reverted by a head change
+                if let Either::Right(_) = select(interval.next(), repub_trigger_rx.next()).await {
+                    schedule.write().await.reset();
+                }
                 if let Err(e) = republish_pending_messages(
                     api.as_ref(),
                     network_sender.as_ref(),
@@ -274,9 +290,12 @@ where
                     cur_tipset.as_ref(),
                     republished.as_ref(),
                     local_addrs.as_ref(),
+                    local_msgs.as_ref(),
+                    &schedule,
                 )
                 .await
                 {
+                    metrics::MPOOL_REPUBLISH_FAILURE_TOTAL.inc();
                     warn!("Failed to republish pending messages: {}", e.to_string());
                 }
             }
@@ -286,7 +305,11 @@ where
 
     /// Add a signed message to the pool and its address.
     async fn add_local(&self, m: SignedMessage) -> Result<(), Error> {
-        self.local_addrs.write().await.push(*m.from());
+        let mut local_addrs = self.local_addrs.write().await;
+        if !local_addrs.contains(m.from()) {
+            local_addrs.push(*m.from());
+        }
+        drop(local_addrs);
         self.local_msgs.write().await.insert(m);
         Ok(())
     }
@@ -311,14 +334,49 @@ where
         Ok(cid)
     }
 
+    /// Push a signed message received from an untrusted source, such as the RPC, to the
+    /// MessagePool. On top of the checks done by [MessagePool::push], this limits the nonce gap
+    /// and the number of pending messages of the sender, and checks that the sender can pay for
+    /// the message together with its other pending messages.
+    pub async fn push_untrusted(&self, msg: SignedMessage) -> Result<Cid, Error> {
+        self.check_untrusted(&msg).await?;
+        self.push(msg).await
+    }
+
+    /// Stricter checks applied to messages pushed by untrusted sources.
+    async fn check_untrusted(&self, msg: &SignedMessage) -> Result<(), Error> {
+        let next_sequence = self.get_sequence(msg.from()).await?;
+        if msg.sequence() > next_sequence + MAX_NONCE_GAP {
+            return Err(Error::NonceGapTooLarge);
+        }
+
+        if let Some(mset) = self.pending.read().await.get(msg.from()) {
+            if mset.msgs.len() >= MAX_UNTRUSTED_ACTOR_PENDING_MESSAGES
+                && !mset.msgs.contains_key(&msg.sequence())
+            {
+                return Err(Error::TooManyPendingMessages);
+            }
+        }
+
+        let cur_ts = self.cur_tipset.read().await.clone();
+        self.check_balance(msg, &cur_ts).await
+    }
+
     /// Basic checks on the validity of a message.
     async fn check_message(&self, msg: &SignedMessage) -> Result<(), Error> {
         if msg.marshal_cbor()?.len() > 32 * 1024 {
             return Err(Error::MessageTooBig);
         }
+        let epoch = self.cur_tipset.read().await.epoch();
+        let version = self
+            .api
+            .read()
+            .await
+            .upgrade_schedule()
+            .network_version(epoch);
         msg.message()
-            .valid_for_block_inclusion(0, NEWEST_NETWORK_VERSION)
-            .map_err(Error::Other)?;
+            .valid_for_block_inclusion(0, version)
+            .map_err(Error::InvalidMessage)?;
         if msg.value() > &types::TOTAL_FILECOIN {
             return Err(Error::MessageValueTooHigh);
         }
@@ -353,7 +411,7 @@ where
             return Ok(());
         }
 
-        msg.verify().map_err(Error::Other)?;
+        msg.verify().map_err(Error::InvalidSignature)?;
 
         self.sig_val_cache.write().await.put(cid, ());
 
@@ -374,7 +432,9 @@ where
             return Err(Error::SequenceTooLow);
         }
 
-        let publish = verify_msg_before_add(&msg, &cur_ts, local)?;
+        let upgrades = self.api.read().await.upgrade_schedule();
+        let publish = verify_msg_before_add(&msg, &cur_ts, local, &upgrades)?;
+        self.check_sender_limit(&msg, local).await?;
 
         let balance = self.get_state_balance(msg.from(), cur_ts).await?;
 
@@ -386,18 +446,42 @@ where
         Ok(publish)
     }
 
+    /// Checks that the sender of a message does not have too many pending messages already,
+    /// unless the message replaces a pending one. Senders whose messages are pushed by this node
+    /// have their own limit.
+    async fn check_sender_limit(&self, msg: &SignedMessage, local: bool) -> Result<(), Error> {
+        let local = local || self.local_addrs.read().await.contains(msg.from());
+        let limit = self.config.read().await.max_sender_pending(local);
+        if let Some(mset) = self.pending.read().await.get(msg.from()) {
+            if mset.msgs.len() as u64 >= limit && !mset.msgs.contains_key(&msg.sequence()) {
+                let sender = if local {
+                    metrics::values::LOCAL
+                } else {
+                    metrics::values::REMOTE
+                };
+                metrics::MPOOL_SENDER_LIMIT_REJECTED_TOTAL
+                    .with_label_values(&[sender])
+                    .inc();
+                return Err(Error::TooManyPendingMessages);
+            }
+        }
+        Ok(())
+    }
+
     /// Finish verifying signed message before adding it to the pending mset hashmap. If an entry
     /// in the hashmap does not yet exist, create a new mset that will correspond to the from
     /// message and push it to the pending hashmap.
     async fn add_helper(&self, msg: SignedMessage) -> Result<(), Error> {
         let from = *msg.from();
         let cur_ts = self.cur_tipset.read().await.clone();
+        let rbf_ratio = self.config.read().await.replace_by_fee_ratio;
         add_helper(
             self.api.as_ref(),
             self.bls_sig_cache.as_ref(),
             self.pending.as_ref(),
             msg,
             self.get_state_sequence(&from, &cur_ts).await?,
+            rbf_ratio,
         )
         .await
     }
@@ -464,7 +548,9 @@ where
             return Err(Error::TryAgain);
         }
 
-        let publish = verify_msg_before_add(&msg, &cur_ts, true)?;
+        let upgrades = self.api.read().await.upgrade_schedule();
+        let publish = verify_msg_before_add(&msg, &cur_ts, true, &upgrades)?;
+        self.check_sender_limit(&msg, true).await?;
         self.check_balance(&msg, &cur_ts).await?;
         self.add_helper(msg.clone()).await?;
         self.add_local(msg.clone()).await?;
@@ -531,6 +617,19 @@ where
         Ok((out, cur_ts))
     }
 
+    /// Returns the cids under which the pending messages are stored, both the signed messages
+    /// and their unsigned messages.
+    pub async fn pending_cids(&self) -> Result<HashSet<Cid>, Error> {
+        let mut cids = HashSet::new();
+        for mset in self.pending.read().await.values() {
+            for msg in mset.msgs.values() {
+                cids.insert(msg.cid()?);
+                cids.insert(msg.message().cid()?);
+            }
+        }
+        Ok(cids)
+    }
+
     /// Return a Vector of signed messages for a given from address. This vector will be sorted by
     /// each messsage's sequence. If no corresponding messages found, return None result type.
     pub async fn pending_for(&self, a: &Address) -> Option<Vec<SignedMessage>> {
@@ -603,7 +702,7 @@ where
     }
     /// If `local = true`, the local messages will be removed as well as pending messages.
     /// If `local = false`, pending messages will be removed while retaining local messages.
-    pub async fn clear(&mut self, local: bool) {
+    pub async fn clear(&self, local: bool) {
         if local {
             let local_addrs = self.local_addrs.read().await;
             for a in local_addrs.iter() {
@@ -623,13 +722,17 @@ where
             pending.retain(|a, _| local_addrs.contains(&a));
         }
     }
-    pub fn get_config(&self) -> &MpoolConfig {
-        &self.config
+
+    /// Returns a copy of the current message pool config.
+    pub async fn get_config(&self) -> MpoolConfig {
+        self.config.read().await.clone()
     }
-    pub fn set_config<DB: Store>(&mut self, db: &DB, cfg: MpoolConfig) -> Result<(), Error> {
+
+    /// Persists the config in the store and applies it to the message pool.
+    pub async fn set_config<DB: Store>(&self, db: &DB, cfg: MpoolConfig) -> Result<(), Error> {
         cfg.save_config(db)
             .map_err(|e| Error::Other(e.to_string()))?;
-        self.config = cfg;
+        *self.config.write().await = cfg;
         Ok(())
     }
 }
@@ -645,6 +748,7 @@ pub(crate) async fn add_helper<T>(
     pending: &RwLock<HashMap<Address, MsgSet>>,
     msg: SignedMessage,
     sequence: u64,
+    rbf_ratio: f64,
 ) -> Result<(), Error>
 where
     T: Provider,
@@ -672,11 +776,11 @@ where
     let mut pending = pending.write().await;
     let msett = pending.get_mut(msg.message().from());
     match msett {
-        Some(mset) => mset.add(msg)?,
+        Some(mset) => mset.add(msg, rbf_ratio)?,
         None => {
             let mut mset = MsgSet::new(sequence);
             let from = *msg.message().from();
-            mset.add(msg)?;
+            mset.add(msg, rbf_ratio)?;
             pending.insert(from, mset);
         }
     }
@@ -684,12 +788,18 @@ where
     Ok(())
 }
 
-fn verify_msg_before_add(m: &SignedMessage, cur_ts: &Tipset, local: bool) -> Result<bool, Error> {
+fn verify_msg_before_add(
+    m: &SignedMessage,
+    cur_ts: &Tipset,
+    local: bool,
+    upgrades: &UpgradeSchedule,
+) -> Result<bool, Error> {
     let epoch = cur_ts.epoch();
-    let min_gas = interpreter::price_list_by_epoch(epoch).on_chain_message(m.marshal_cbor()?.len());
+    let min_gas =
+        interpreter::price_list_by_epoch(epoch, upgrades).on_chain_message(m.marshal_cbor()?.len());
     m.message()
-        .valid_for_block_inclusion(min_gas.total(), NEWEST_NETWORK_VERSION)
-        .map_err(Error::Other)?;
+        .valid_for_block_inclusion(min_gas.total(), upgrades.network_version(epoch))
+        .map_err(Error::InvalidMessage)?;
     if !cur_ts.blocks().is_empty() {
         let base_fee = cur_ts.blocks()[0].parent_base_fee();
         let base_fee_lower_bound =
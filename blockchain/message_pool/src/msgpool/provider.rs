@@ -13,6 +13,7 @@ use chain::HeadChange;
 use cid::Cid;
 use cid::Code::Blake2b256;
 use message::{ChainMessage, SignedMessage, UnsignedMessage};
+use networks::UpgradeSchedule;
 use num_bigint::BigInt;
 use state_manager::StateManager;
 use state_tree::StateTree;
@@ -52,6 +53,8 @@ pub trait Provider {
     async fn load_tipset(&self, tsk: &TipsetKeys) -> Result<Arc<Tipset>, Error>;
     /// Computes the base fee
     fn chain_compute_base_fee(&self, ts: &Tipset) -> Result<BigInt, Error>;
+    /// Returns the hard fork heights of the network
+    fn upgrade_schedule(&self) -> UpgradeSchedule;
 }
 
 /// This is the default Provider implementation that will be used for the mpool RPC.
@@ -119,7 +122,11 @@ where
         Ok(ts)
     }
     fn chain_compute_base_fee(&self, ts: &Tipset) -> Result<BigInt, Error> {
-        chain::compute_base_fee(self.sm.blockstore(), ts).map_err(|err| err.into())
+        let smoke_height = self.sm.network_config().upgrades.smoke;
+        chain::compute_base_fee(self.sm.blockstore(), ts, smoke_height).map_err(|err| err.into())
+    }
+    fn upgrade_schedule(&self) -> UpgradeSchedule {
+        self.sm.network_config().upgrades
     }
     async fn state_account_key<V>(&self, addr: &Address, ts: &Arc<Tipset>) -> Result<Address, Error>
     where
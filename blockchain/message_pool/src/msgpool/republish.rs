@@ -0,0 +1,84 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Cid;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// The delay between two republishes of a message doubles at most this many times.
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
+/// Schedules the republishes of the local messages which are stuck in the message pool.
+///
+/// A message is republished the first time it is selected for republishing, then the delay
+/// before its next republish doubles with each republish, up to 32 times the base delay, so that
+/// messages which can't be included are not rebroadcast at a constant rate forever.
+pub(crate) struct RepublishSchedule {
+    base_delay: Duration,
+    scheduled: HashMap<Cid, Republish>,
+}
+
+struct Republish {
+    count: u32,
+    next: Instant,
+}
+
+impl RepublishSchedule {
+    pub(crate) fn new(base_delay: Duration) -> Self {
+        Self {
+            base_delay,
+            scheduled: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the message is due to be republished, scheduling its next republish.
+    pub(crate) fn due(&mut self, cid: Cid, now: Instant) -> bool {
+        let base_delay = self.base_delay;
+        let republish = self.scheduled.entry(cid).or_insert(Republish {
+            count: 0,
+            next: now,
+        });
+        if now < republish.next {
+            return false;
+        }
+        republish.count += 1;
+        republish.next = now + base_delay * 2u32.pow(republish.count.min(MAX_BACKOFF_EXPONENT));
+        true
+    }
+
+    /// Forgets the messages which are not pending anymore.
+    pub(crate) fn retain(&mut self, pending: &HashSet<Cid>) {
+        self.scheduled.retain(|cid, _| pending.contains(cid));
+    }
+
+    /// Makes all the messages due to be republished, with their backoff reset.
+    pub(crate) fn reset(&mut self) {
+        self.scheduled.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::Code::Identity;
+
+    #[test]
+    fn backoff() {
+        let base = Duration::from_secs(10);
+        let mut schedule = RepublishSchedule::new(base);
+        let cid = cid::new_from_cbor(&[0], Identity);
+        let start = Instant::now();
+
+        assert!(schedule.due(cid, start));
+        assert!(!schedule.due(cid, start + base));
+        assert!(schedule.due(cid, start + base * 2));
+        // The delay doubled after the second republish
+        assert!(!schedule.due(cid, start + base * 5));
+        assert!(schedule.due(cid, start + base * 6));
+
+        schedule.retain(&HashSet::new());
+        assert!(schedule.due(cid, start + base * 6));
+        schedule.reset();
+        assert!(schedule.due(cid, start + base * 6));
+    }
+}
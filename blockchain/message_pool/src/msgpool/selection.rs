@@ -482,12 +482,13 @@ where
         base_fee: &BigInt,
         ts: &Tipset,
     ) -> Result<(Vec<SignedMessage>, i64), Error> {
-        let result = Vec::with_capacity(self.config.size_limit_low() as usize);
+        let config = self.get_config().await;
+        let result = Vec::with_capacity(config.size_limit_low() as usize);
         let gas_limit = types::BLOCK_GAS_LIMIT;
         let min_gas = 1298450;
 
         // 1. Get priority actor chains
-        let priority = self.config.priority_addrs();
+        let priority = config.priority_addrs();
         let mut chains = Chains::new();
         for actor in priority.iter() {
             // remove actor from pending set as we are processing these messages.
@@ -712,6 +713,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b1]).unwrap()],
         )
@@ -775,6 +777,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b2]).unwrap()],
         )
@@ -882,6 +885,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b1]).unwrap()],
         )
@@ -936,7 +940,7 @@ mod test_selection {
     async fn message_selection_priority() {
         let db = MemoryDB::default();
 
-        let mut mpool = make_test_mpool();
+        let mpool = make_test_mpool();
 
         let ks1 = KeyStore::new(KeyStoreConfig::Memory).unwrap();
         let mut w1 = Wallet::new(ks1);
@@ -947,9 +951,9 @@ mod test_selection {
         let a2 = w2.generate_addr(SignatureType::Secp256k1).unwrap();
 
         // set priority addrs to a1
-        let mut mpool_cfg = mpool.get_config().clone();
+        let mut mpool_cfg = mpool.get_config().await;
         mpool_cfg.priority_addrs.push(a1);
-        mpool.set_config(&db, mpool_cfg).unwrap();
+        mpool.set_config(&db, mpool_cfg).await.unwrap();
 
         let b1 = mock_block(1, 1);
         let ts = Tipset::new(vec![b1.clone()]).unwrap();
@@ -966,6 +970,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b1]).unwrap()],
         )
@@ -1063,6 +1068,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b1]).unwrap()],
         )
@@ -1147,6 +1153,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![b1]).unwrap()],
         )
@@ -1269,6 +1276,7 @@ mod test_selection {
             republished.as_ref(),
             pending.as_ref(),
             cur_tipset.as_ref(),
+            mpool.config.as_ref(),
             Vec::new(),
             vec![Tipset::new(vec![block]).unwrap()],
         )
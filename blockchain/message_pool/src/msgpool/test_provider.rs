@@ -17,6 +17,7 @@ use crypto::VRFProof;
 use message::ChainMessage;
 use message::Message;
 use message::{SignedMessage, UnsignedMessage};
+use networks::{NetworkConfig, UpgradeSchedule};
 use num_bigint::BigInt;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -30,6 +31,8 @@ pub struct TestApi {
     balances: HashMap<Address, BigInt>,
     tipsets: Vec<Tipset>,
     publisher: Publisher<HeadChange>,
+    upgrades: UpgradeSchedule,
+    base_fee: BigInt,
 }
 
 impl Default for TestApi {
@@ -42,6 +45,8 @@ impl Default for TestApi {
             balances: HashMap::new(),
             tipsets: Vec::new(),
             publisher,
+            upgrades: NetworkConfig::mainnet().upgrades,
+            base_fee: BigInt::from(100),
         }
     }
 }
@@ -57,6 +62,11 @@ impl TestApi {
         self.balances.insert(*addr, bal);
     }
 
+    /// Set the base fee computed for any tipset for TestApi
+    pub fn set_base_fee(&mut self, base_fee: BigInt) {
+        self.base_fee = base_fee;
+    }
+
     /// Set the block messages for TestApi
     pub fn set_block_messages(&mut self, h: &BlockHeader, msgs: Vec<SignedMessage>) {
         self.bmsgs.insert(*h.cid(), msgs);
@@ -172,7 +182,11 @@ impl Provider for TestApi {
     }
 
     fn chain_compute_base_fee(&self, _ts: &Tipset) -> Result<BigInt, Error> {
-        Ok(100.into())
+        Ok(self.base_fee.clone())
+    }
+
+    fn upgrade_schedule(&self) -> UpgradeSchedule {
+        self.upgrades
     }
 }
 
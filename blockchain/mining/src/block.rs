@@ -0,0 +1,104 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::Error;
+use address::Address;
+use async_std::sync::RwLock;
+use beacon::BeaconEntry;
+use blocks::{BlockHeader, ElectionProof, GossipBlock, Ticket, TipsetKeys};
+use clock::ChainEpoch;
+use fil_types::verifier::ProofVerifier;
+use fil_types::PoStProof;
+use ipld_blockstore::BlockStore;
+use message::SignedMessage;
+use state_manager::StateManager;
+use std::sync::Arc;
+use wallet::{Key, KeyStore};
+
+/// Contents of a block to be created on top of a tipset, as decided by its miner.
+#[derive(Clone, Debug)]
+pub struct BlockTemplate {
+    pub miner: Address,
+    pub parents: TipsetKeys,
+    pub ticket: Ticket,
+    pub eproof: ElectionProof,
+    pub beacon_values: Vec<BeaconEntry>,
+    pub messages: Vec<SignedMessage>,
+    pub epoch: ChainEpoch,
+    pub timestamp: u64,
+    pub winning_post_proof: Vec<PoStProof>,
+}
+
+/// Creates the block of a template, signed with the worker key of its miner, which has to be in
+/// the keystore. The messages of the block are persisted, but not its header.
+pub async fn create_block<DB, V>(
+    state_manager: &Arc<StateManager<DB>>,
+    keystore: &RwLock<KeyStore>,
+    template: BlockTemplate,
+) -> Result<GossipBlock, Error>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let chain_store = state_manager.chain_store();
+    let pts = chain_store.tipset_from_keys(&template.parents).await?;
+    let (st, recpts) = state_manager
+        .tipset_state::<V>(&pts)
+        .await
+        .map_err(|e| Error::Other(e.to_string()))?;
+    let (_, lbst) = state_manager
+        .get_lookback_tipset_for_round::<V>(pts.clone(), template.epoch)
+        .await?;
+    let worker = state_manager.get_miner_worker_raw(&lbst, &template.miner)?;
+
+    let (mmcid, bls_aggregate, bls_cids, secp_cids) =
+        chain::persist_block_messages(chain_store.blockstore(), template.messages)?;
+
+    let pweight = chain::weight(chain_store.blockstore(), &pts).map_err(Error::Other)?;
+    let smoke_height = state_manager.network_config().upgrades.smoke;
+    let base_fee = chain::compute_base_fee(chain_store.blockstore(), &pts, smoke_height)?;
+
+    let mut next = BlockHeader::builder()
+        .messages(mmcid)
+        .bls_aggregate(Some(bls_aggregate))
+        .miner_address(template.miner)
+        .weight(pweight)
+        .parent_base_fee(base_fee)
+        .parents(template.parents)
+        .ticket(Some(template.ticket))
+        .election_proof(Some(template.eproof))
+        .beacon_entries(template.beacon_values)
+        .epoch(template.epoch)
+        .timestamp(template.timestamp)
+        .winning_post_proof(template.winning_post_proof)
+        .state_root(st)
+        .message_receipts(recpts)
+        .signature(None)
+        .build()
+        .map_err(Error::Other)?;
+
+    let key = find_worker_key(&worker, &*keystore.read().await)?;
+    let sig = wallet::sign(
+        *key.key_info.key_type(),
+        key.key_info.private_key(),
+        &next.to_signing_bytes(),
+    )
+    .map_err(|e| Error::Other(e.to_string()))?;
+    next.signature = Some(sig);
+
+    Ok(GossipBlock {
+        header: next,
+        bls_messages: bls_cids,
+        secpk_messages: secp_cids,
+    })
+}
+
+/// Looks up a worker key in the keystore. Only a key missing from the keystore fails with
+/// [Error::WorkerKey], other keystore errors may not last.
+pub(crate) fn find_worker_key(worker: &Address, keystore: &KeyStore) -> Result<Key, Error> {
+    match wallet::find_key(worker, keystore) {
+        Ok(key) => Ok(key),
+        Err(wallet::Error::KeyInfo) => Err(Error::WorkerKey(*worker)),
+        Err(e) => Err(Error::Other(e.to_string())),
+    }
+}
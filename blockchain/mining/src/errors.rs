@@ -0,0 +1,33 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use address::Address;
+use blocks::Error as BlockError;
+use chain::Error as ChainError;
+use encoding::Error as EncodeError;
+use message_pool::Error as MpoolError;
+use state_manager::Error as StateManagerError;
+use thiserror::Error;
+
+/// Block production error.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Mined block is invalid: {0}")]
+    InvalidBlock(String),
+    #[error("Generating the winning PoSt failed: {0}")]
+    WinningPoSt(String),
+    #[error("Selecting messages failed: {0}")]
+    MessageSelection(#[from] MpoolError),
+    #[error("Worker key {0} is not in the keystore")]
+    WorkerKey(Address),
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+    #[error(transparent)]
+    StateManager(#[from] StateManagerError),
+    #[error(transparent)]
+    Block(#[from] BlockError),
+    #[error(transparent)]
+    Encoding(#[from] EncodeError),
+    #[error("{0}")]
+    Other(String),
+}
@@ -0,0 +1,13 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+mod block;
+mod errors;
+mod metrics;
+mod producer;
+mod prover;
+
+pub use self::block::*;
+pub use self::errors::*;
+pub use self::producer::BlockProducer;
+pub use self::prover::*;
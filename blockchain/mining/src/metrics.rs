@@ -0,0 +1,48 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use lazy_static::lazy_static;
+use prometheus::core::{AtomicU64, GenericCounterVec, Opts};
+
+lazy_static! {
+    pub static ref BLOCKS_WON_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
+        let blocks_won_total = Box::new(
+            GenericCounterVec::<AtomicU64>::new(
+                Opts::new(
+                    "mining_blocks_won_total",
+                    "Total number of blocks produced and submitted by each miner",
+                ),
+                &[labels::MINER],
+            )
+            .expect("Defining the mining_blocks_won_total metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(blocks_won_total.clone())
+            .expect(
+                "Registering the mining_blocks_won_total metric with the metrics registry must succeed",
+            );
+        blocks_won_total
+    };
+    pub static ref ROUNDS_MISSED_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
+        let rounds_missed_total = Box::new(
+            GenericCounterVec::<AtomicU64>::new(
+                Opts::new(
+                    "mining_rounds_missed_total",
+                    "Total number of rounds in which a miner failed to produce or submit its block",
+                ),
+                &[labels::MINER],
+            )
+            .expect("Defining the mining_rounds_missed_total metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(rounds_missed_total.clone())
+            .expect(
+                "Registering the mining_rounds_missed_total metric with the metrics registry must succeed",
+            );
+        rounds_missed_total
+    };
+}
+
+pub mod labels {
+    pub const MINER: &str = "miner";
+}
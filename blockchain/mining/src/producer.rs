@@ -0,0 +1,382 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::block::find_worker_key;
+use crate::metrics;
+use crate::{create_block, BlockTemplate, Error, WinningPoStProver};
+use address::Address;
+use async_std::channel::Sender;
+use async_std::sync::RwLock;
+use async_std::task;
+use beacon::{Beacon, BeaconSchedule};
+use blocks::{Block, ElectionProof, GossipBlock, Ticket, Tipset};
+use clock::ChainEpoch;
+use crypto::{DomainSeparationTag, VRFProof};
+use encoding::Cbor;
+use fil_types::verifier::ProofVerifier;
+use fil_types::{Randomness, TICKET_RANDOMNESS_LOOKBACK};
+use forest_libp2p::{NetworkMessage, Topic, PUBSUB_BLOCK_STR};
+use ipld_blockstore::BlockStore;
+use log::{debug, info, warn};
+use message_pool::{MessagePool, Provider};
+use networks::BLOCK_DELAY_SECS;
+use state_manager::StateManager;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wallet::{Key, KeyStore};
+
+/// Seconds after the start of an epoch during which its blocks are expected to arrive. Mining
+/// the next epoch only starts after that, on top of the heaviest tipset received by then.
+const PROPAGATION_DELAY_SECS: u64 = 6;
+
+/// Produces the blocks of a set of miners whose worker keys are in the keystore. At each epoch,
+/// the miners run the election on top of the head. The winners select messages from the message
+/// pool and generate their winning PoSt, then their blocks are validated and submitted to the
+/// chain muxer and the network.
+pub struct BlockProducer<DB, TBeacon, V, M> {
+    state_manager: Arc<StateManager<DB>>,
+    beacon: Arc<BeaconSchedule<TBeacon>>,
+    mpool: Arc<MessagePool<M>>,
+    keystore: Arc<RwLock<KeyStore>>,
+    prover: Arc<dyn WinningPoStProver>,
+    /// Sends the mined blocks to the chain muxer, like those submitted through the RPC.
+    tipset_sink: Sender<Arc<Tipset>>,
+    network_send: Sender<NetworkMessage>,
+    network_name: String,
+    miners: Vec<Address>,
+    verifier: PhantomData<V>,
+}
+
+impl<DB, TBeacon, V, M> BlockProducer<DB, TBeacon, V, M>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    TBeacon: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+    M: Provider + Send + Sync + 'static,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        state_manager: Arc<StateManager<DB>>,
+        beacon: Arc<BeaconSchedule<TBeacon>>,
+        mpool: Arc<MessagePool<M>>,
+        keystore: Arc<RwLock<KeyStore>>,
+        prover: Arc<dyn WinningPoStProver>,
+        tipset_sink: Sender<Arc<Tipset>>,
+        network_send: Sender<NetworkMessage>,
+        network_name: String,
+        miners: Vec<Address>,
+    ) -> Self {
+        Self {
+            state_manager,
+            beacon,
+            mpool,
+            keystore,
+            prover,
+            tipset_sink,
+            network_send,
+            network_name,
+            miners,
+            verifier: Default::default(),
+        }
+    }
+
+    /// Mines every epoch until the task is cancelled. Miners whose worker key is not in the
+    /// keystore are dropped the first time they are eligible to mine.
+    pub async fn run(self) {
+        info!("Producing blocks for miners {:?}", self.miners);
+        let mut miners = self.miners.clone();
+        let mut last_round: ChainEpoch = -1;
+        while !miners.is_empty() {
+            let base = match self.state_manager.chain_store().heaviest_tipset().await {
+                Some(base) => base,
+                None => {
+                    task::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+            let now = unix_now();
+            let (round, timestamp) = next_round(&base, now);
+
+            // Wait for the blocks of the previous epoch, then look at the head again
+            let start = timestamp - BLOCK_DELAY_SECS + PROPAGATION_DELAY_SECS;
+            if now < start {
+                task::sleep(Duration::from_secs(start - now)).await;
+                continue;
+            }
+            // Mining the same round twice, even on another base, would be a consensus fault
+            if round <= last_round {
+                let next_start = timestamp + PROPAGATION_DELAY_SECS;
+                task::sleep(Duration::from_secs(next_start.saturating_sub(now).max(1))).await;
+                continue;
+            }
+            last_round = round;
+
+            let mut blocks = Vec::new();
+            let mut without_key = Vec::new();
+            for miner in &miners {
+                match self.mine_one(*miner, &base, round, timestamp).await {
+                    Ok(Some(block)) => blocks.push(block),
+                    Ok(None) => debug!("Miner {} didn't win at epoch {}", miner, round),
+                    Err(Error::WorkerKey(worker)) => {
+                        warn!(
+                            "Not producing blocks for {}, its worker key {} is not in the keystore",
+                            miner, worker
+                        );
+                        without_key.push(*miner);
+                    }
+                    Err(e) => {
+                        warn!("Failed to mine for {} at epoch {}: {}", miner, round, e);
+                        record_miss(miner);
+                    }
+                }
+            }
+            miners.retain(|miner| !without_key.contains(miner));
+            if blocks.is_empty() {
+                continue;
+            }
+
+            // Blocks from the future are refused, so they are only submitted once their time
+            // has come
+            let now = unix_now();
+            if timestamp > now {
+                task::sleep(Duration::from_secs(timestamp - now)).await;
+            }
+            for block in blocks {
+                let miner = *block.header.miner_address();
+                match self.submit(block).await {
+                    Ok(()) => {
+                        info!("Submitted the block of {} at epoch {}", miner, round);
+                        metrics::BLOCKS_WON_TOTAL
+                            .with_label_values(&[&miner.to_string()])
+                            .inc();
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to submit the block of {} at epoch {}: {}",
+                            miner, round, e
+                        );
+                        record_miss(&miner);
+                    }
+                }
+            }
+        }
+        warn!("Stopped producing blocks, no miner has its worker key in the keystore");
+    }
+
+    /// Runs the election of a miner for a round on top of a base tipset, and creates its block
+    /// if it won. Returns `None` if the miner isn't elected. The election draws on the VRF of
+    /// the worker key, whose absence fails with [Error::WorkerKey] before the election.
+    async fn mine_one(
+        &self,
+        miner: Address,
+        base: &Arc<Tipset>,
+        round: ChainEpoch,
+        timestamp: u64,
+    ) -> Result<Option<GossipBlock>, Error> {
+        let info = match self
+            .state_manager
+            .miner_get_base_info::<V, TBeacon>(&self.beacon, base.key(), round, miner)
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?
+        {
+            Some(info) => info,
+            None => {
+                debug!("Miner {} has no sectors to prove at epoch {}", miner, round);
+                return Ok(None);
+            }
+        };
+        if !info.eligible_for_mining {
+            debug!("Miner {} isn't eligible to mine at epoch {}", miner, round);
+            return Ok(None);
+        }
+        let rbase = info
+            .beacon_entries
+            .last()
+            .unwrap_or(&info.prev_beacon_entry)
+            .data()
+            .to_vec();
+        let key = find_worker_key(&info.worker_key, &*self.keystore.read().await)?;
+        let miner_buf = miner.marshal_cbor()?;
+
+        let election_rand = draw_randomness(
+            &rbase,
+            DomainSeparationTag::ElectionProofProduction,
+            round,
+            &miner_buf,
+        )?;
+        let mut eproof = ElectionProof {
+            win_count: 0,
+            vrfproof: compute_vrf(&key, &election_rand)?,
+        };
+        eproof.win_count = eproof.compute_win_count(
+            &info.miner_power.unwrap_or_default(),
+            &info.network_power.unwrap_or_default(),
+        );
+        if eproof.win_count < 1 {
+            return Ok(None);
+        }
+        info!(
+            "Miner {} won {} times at epoch {}",
+            miner, eproof.win_count, round
+        );
+
+        let mut ticket_buf = miner_buf.clone();
+        if round > self.state_manager.network_config().upgrades.smoke {
+            let min_ticket = base
+                .min_ticket()
+                .ok_or_else(|| Error::Other("Base tipset has no ticket".to_owned()))?;
+            ticket_buf.extend_from_slice(min_ticket.vrfproof.as_bytes());
+        }
+        let ticket_rand = draw_randomness(
+            &rbase,
+            DomainSeparationTag::TicketProduction,
+            round - TICKET_RANDOMNESS_LOOKBACK,
+            &ticket_buf,
+        )?;
+        let ticket = Ticket::new(compute_vrf(&key, &ticket_rand)?);
+
+        let post_rand = draw_randomness(
+            &rbase,
+            DomainSeparationTag::WinningPoStChallengeSeed,
+            round,
+            &miner_buf,
+        )?;
+        let prover = Arc::clone(&self.prover);
+        let sectors = info.sectors;
+        let winning_post_proof = task::spawn_blocking(move || {
+            prover.generate_winning_post(miner, &sectors, Randomness(post_rand.to_vec()))
+        })
+        .await
+        .map_err(Error::WinningPoSt)?;
+
+        let messages = self.mpool.select_messages(base, ticket.quality()).await?;
+        let block = create_block::<_, V>(
+            &self.state_manager,
+            &self.keystore,
+            BlockTemplate {
+                miner,
+                parents: base.key().clone(),
+                ticket,
+                eproof,
+                beacon_values: info.beacon_entries,
+                messages,
+                epoch: round,
+                timestamp,
+                winning_post_proof,
+            },
+        )
+        .await?;
+        Ok(Some(block))
+    }
+
+    /// Fully validates a mined block, then sends it to the chain muxer and gossips it.
+    async fn submit(&self, block: GossipBlock) -> Result<(), Error> {
+        let store = self.state_manager.blockstore();
+        let bls_messages = chain::messages_from_cids(store, &block.bls_messages)?;
+        let secp_messages = chain::messages_from_cids(store, &block.secpk_messages)?;
+        chain_sync::validate_mined_block::<_, _, V>(
+            Arc::clone(&self.state_manager),
+            Arc::clone(&self.beacon),
+            Block {
+                header: block.header.clone(),
+                bls_messages,
+                secp_messages,
+            },
+        )
+        .await
+        .map_err(|e| Error::InvalidBlock(e.to_string()))?;
+
+        let tipset = Arc::new(Tipset::new(vec![block.header.clone()])?);
+        self.tipset_sink
+            .send(tipset)
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+        self.network_send
+            .send(NetworkMessage::PubsubMessage {
+                topic: Topic::new(format!("{}/{}", PUBSUB_BLOCK_STR, self.network_name)),
+                message: block.marshal_cbor()?,
+            })
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Returns the next round to mine on top of a base tipset at the given time, along with the
+/// timestamp of its blocks. The rounds which already passed without a block are null rounds.
+fn next_round(base: &Tipset, now: u64) -> (ChainEpoch, u64) {
+    let null_rounds = now.saturating_sub(base.min_timestamp()) / BLOCK_DELAY_SECS;
+    let round = base.epoch() + null_rounds as ChainEpoch + 1;
+    let timestamp = base.min_timestamp() + BLOCK_DELAY_SECS * (null_rounds + 1);
+    (round, timestamp)
+}
+
+fn draw_randomness(
+    rbase: &[u8],
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> Result<[u8; 32], Error> {
+    chain::draw_randomness(rbase, pers, round, entropy).map_err(|e| Error::Other(e.to_string()))
+}
+
+/// Computes a VRF output, which is the BLS signature of the input by the worker key.
+fn compute_vrf(worker: &Key, input: &[u8]) -> Result<VRFProof, Error> {
+    let sig = wallet::sign(
+        *worker.key_info.key_type(),
+        worker.key_info.private_key(),
+        input,
+    )
+    .map_err(|e| Error::Other(e.to_string()))?;
+    Ok(VRFProof::new(sig.bytes().to_vec()))
+}
+
+fn record_miss(miner: &Address) {
+    metrics::ROUNDS_MISSED_TOTAL
+        .with_label_values(&[&miner.to_string()])
+        .inc();
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blocks::BlockHeader;
+    use cid::Cid;
+
+    fn tipset_at(epoch: ChainEpoch, timestamp: u64) -> Tipset {
+        let header = BlockHeader::builder()
+            .epoch(epoch)
+            .timestamp(timestamp)
+            .messages(Cid::default())
+            .message_receipts(Cid::default())
+            .state_root(Cid::default())
+            .build()
+            .unwrap();
+        Tipset::new(vec![header]).unwrap()
+    }
+
+    #[test]
+    fn next_round_counts_null_rounds() {
+        let base = tipset_at(10, 1000);
+        assert_eq!(next_round(&base, 1000), (11, 1000 + BLOCK_DELAY_SECS));
+        assert_eq!(
+            next_round(&base, 1000 + BLOCK_DELAY_SECS - 1),
+            (11, 1000 + BLOCK_DELAY_SECS)
+        );
+        // Once the time of the next round has passed without a block, mine the one after
+        assert_eq!(
+            next_round(&base, 1000 + BLOCK_DELAY_SECS),
+            (12, 1000 + 2 * BLOCK_DELAY_SECS)
+        );
+        // A clock behind the base doesn't go back in rounds
+        assert_eq!(next_round(&base, 900), (11, 1000 + BLOCK_DELAY_SECS));
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use address::Address;
+use fil_types::{PoStProof, Randomness, SectorInfo};
+
+/// Proof bytes of the winning PoSts made by the [MockProver], which are the ones accepted by
+/// nodes built with the `insecure_post` feature.
+pub const MOCK_WINNING_POST_PROOF: &[u8] = b"valid_proof";
+
+/// Generates the winning PoSt of a miner elected to produce a block, over the sectors
+/// challenged for the round.
+pub trait WinningPoStProver: Send + Sync {
+    fn generate_winning_post(
+        &self,
+        miner: Address,
+        sectors: &[SectorInfo],
+        randomness: Randomness,
+    ) -> Result<Vec<PoStProof>, String>;
+}
+
+/// Prover of devnets, which doesn't read the sectors. Its proofs are only valid for nodes built
+/// with the `insecure_post` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MockProver;
+
+impl WinningPoStProver for MockProver {
+    fn generate_winning_post(
+        &self,
+        miner: Address,
+        sectors: &[SectorInfo],
+        _randomness: Randomness,
+    ) -> Result<Vec<PoStProof>, String> {
+        let sector = sectors
+            .first()
+            .ok_or_else(|| format!("No sector challenged for miner {}", miner))?;
+        Ok(vec![PoStProof {
+            post_proof: sector.proof.registered_winning_post_proof()?,
+            proof_bytes: MOCK_WINNING_POST_PROOF.to_vec(),
+        }])
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use db::Error as DbError;
+use encoding::Error as EncodeError;
+use state_manager::Error as StateManagerError;
+use std::error::Error as StdError;
+use thiserror::Error;
+use vm::TokenAmount;
+
+/// Payment channel manager error.
+#[derive(Debug, PartialEq, Error)]
+pub enum Error {
+    #[error("payment channel {0} is not tracked")]
+    ChannelNotTracked(String),
+    #[error("voucher is invalid: {0}")]
+    InvalidVoucher(String),
+    #[error("voucher nonce {0} is too low for lane {1}")]
+    NonceTooLow(u64, u64),
+    #[error("not enough funds in channel to cover voucher, missing {0}")]
+    InsufficientFunds(TokenAmount),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<DbError> for Error {
+    fn from(e: DbError) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(e: EncodeError) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+impl From<StateManagerError> for Error {
+    fn from(e: StateManagerError) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+impl From<Box<dyn StdError>> for Error {
+    fn from(e: Box<dyn StdError>) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(e: String) -> Self {
+        Error::Other(e)
+    }
+}
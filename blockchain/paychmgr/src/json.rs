@@ -0,0 +1,184 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use actor::paych::{Merge, ModVerifyParams, SignedVoucher};
+use address::Address;
+use clock::ChainEpoch;
+use crypto::signature::json::opt as signature_opt;
+use crypto::Signature;
+use num_bigint::{bigint_ser, BigInt};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use vm::{MethodNum, Serialized};
+
+/// Wrapper for serializing and deserializing a SignedVoucher from JSON.
+#[derive(Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SignedVoucherJson(#[serde(with = "self")] pub SignedVoucher);
+
+/// Wrapper for serializing a SignedVoucher reference to JSON.
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct SignedVoucherJsonRef<'a>(#[serde(with = "self")] pub &'a SignedVoucher);
+
+impl From<SignedVoucherJson> for SignedVoucher {
+    fn from(wrapper: SignedVoucherJson) -> Self {
+        wrapper.0
+    }
+}
+
+impl From<SignedVoucher> for SignedVoucherJson {
+    fn from(sv: SignedVoucher) -> Self {
+        SignedVoucherJson(sv)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ModVerifyParamsJson {
+    #[serde(with = "address::json")]
+    actor: Address,
+    method: MethodNum,
+    data: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MergeJson {
+    lane: usize,
+    nonce: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JsonHelper {
+    #[serde(with = "address::json")]
+    channel_addr: Address,
+    time_lock_min: ChainEpoch,
+    time_lock_max: ChainEpoch,
+    #[serde(rename = "SecretPreimage")]
+    secret_pre_image: String,
+    extra: Option<ModVerifyParamsJson>,
+    lane: usize,
+    nonce: u64,
+    #[serde(with = "bigint_ser::json")]
+    amount: BigInt,
+    min_settle_height: ChainEpoch,
+    merges: Vec<MergeJson>,
+    #[serde(with = "signature_opt")]
+    signature: Option<Signature>,
+}
+
+pub fn serialize<S>(sv: &SignedVoucher, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    JsonHelper {
+        channel_addr: sv.channel_addr,
+        time_lock_min: sv.time_lock_min,
+        time_lock_max: sv.time_lock_max,
+        secret_pre_image: base64::encode(&sv.secret_pre_image),
+        extra: sv.extra.as_ref().map(|e| ModVerifyParamsJson {
+            actor: e.actor,
+            method: e.method,
+            data: base64::encode(e.data.bytes()),
+        }),
+        lane: sv.lane,
+        nonce: sv.nonce,
+        amount: sv.amount.clone(),
+        min_settle_height: sv.min_settle_height,
+        merges: sv
+            .merges
+            .iter()
+            .map(|m| MergeJson {
+                lane: m.lane,
+                nonce: m.nonce,
+            })
+            .collect(),
+        signature: sv.signature.clone(),
+    }
+    .serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<SignedVoucher, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let sv: JsonHelper = Deserialize::deserialize(deserializer)?;
+    let extra = match sv.extra {
+        Some(e) => Some(ModVerifyParams {
+            actor: e.actor,
+            method: e.method,
+            data: Serialized::new(base64::decode(&e.data).map_err(de::Error::custom)?),
+        }),
+        None => None,
+    };
+    Ok(SignedVoucher {
+        channel_addr: sv.channel_addr,
+        time_lock_min: sv.time_lock_min,
+        time_lock_max: sv.time_lock_max,
+        secret_pre_image: base64::decode(&sv.secret_pre_image).map_err(de::Error::custom)?,
+        extra,
+        lane: sv.lane,
+        nonce: sv.nonce,
+        amount: sv.amount,
+        min_settle_height: sv.min_settle_height,
+        merges: sv
+            .merges
+            .into_iter()
+            .map(|m| Merge {
+                lane: m.lane,
+                nonce: m.nonce,
+            })
+            .collect(),
+        signature: sv.signature,
+    })
+}
+
+pub mod opt {
+    use super::{SignedVoucher, SignedVoucherJson, SignedVoucherJsonRef};
+    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(v: &Option<SignedVoucher>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        v.as_ref().map(SignedVoucherJsonRef).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SignedVoucher>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v: Option<SignedVoucherJson> = Deserialize::deserialize(deserializer)?;
+        Ok(v.map(|v| v.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_voucher_round_trip() {
+        let sv = SignedVoucher {
+            channel_addr: Address::new_id(1),
+            time_lock_min: 1,
+            time_lock_max: 2,
+            secret_pre_image: vec![1, 2, 3],
+            extra: Some(ModVerifyParams {
+                actor: Address::new_id(2),
+                method: 3,
+                data: Serialized::new(vec![4, 5]),
+            }),
+            lane: 4,
+            nonce: 5,
+            amount: BigInt::from(6),
+            min_settle_height: 7,
+            merges: vec![Merge { lane: 1, nonce: 2 }],
+            signature: Some(Signature::new_secp256k1(vec![8; 65])),
+        };
+        let json = serde_json::to_string(&SignedVoucherJsonRef(&sv)).unwrap();
+        let SignedVoucherJson(decoded) = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, sv);
+    }
+}
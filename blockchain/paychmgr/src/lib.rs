@@ -0,0 +1,11 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+mod errors;
+pub mod json;
+mod manager;
+mod store;
+
+pub use self::errors::*;
+pub use self::manager::*;
+pub use self::store::*;
@@ -0,0 +1,711 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{ChannelInfo, Direction, Error, PaychStore, VoucherInfo};
+use actor::paych::{self, LaneState, SignedVoucher, UpdateChannelStateParams};
+use actor::{init, ActorVersion};
+use address::Address;
+use async_std::sync::{Arc, Mutex};
+use blockstore::BlockStore;
+use cid::Cid;
+use crypto::Signature;
+use fil_types::verifier::ProofVerifier;
+use log::debug;
+use message::UnsignedMessage;
+use num_traits::Zero;
+use state_manager::StateManager;
+use std::collections::HashMap;
+use vm::{ActorState, ExitCode, Serialized, TokenAmount, METHOD_SEND};
+
+/// Result of creating a voucher. If the channel does not have enough funds to cover the
+/// voucher, no voucher is created and the shortfall is the amount missing from the channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoucherCreateResult {
+    pub voucher: Option<SignedVoucher>,
+    pub shortfall: TokenAmount,
+}
+
+/// Tracks the payment channels of the node and the vouchers sent or received through them.
+///
+/// The manager only builds the messages which create, fund, settle and collect channels, the
+/// caller is responsible for signing and pushing them to the message pool, and for reporting
+/// the cid of the pushed message back to the manager.
+pub struct Manager<DB> {
+    sm: Arc<StateManager<DB>>,
+    store: PaychStore<DB>,
+    /// Serializes the read-modify-write cycles on the store.
+    lock: Mutex<()>,
+    /// Per (from, to) locks serializing the lookup and funding of outbound channels.
+    accessors: Mutex<HashMap<(Address, Address), Arc<Mutex<()>>>>,
+}
+
+impl<DB> Manager<DB>
+where
+    DB: BlockStore + Send + Sync + 'static,
+{
+    pub fn new(sm: Arc<StateManager<DB>>) -> Self {
+        let store = PaychStore::new(sm.blockstore_cloned());
+        Self {
+            sm,
+            store,
+            lock: Mutex::new(()),
+            accessors: Default::default(),
+        }
+    }
+
+    /// Returns the lock of the outbound channels from `from` to `to`. It must be held from
+    /// the lookup of the channel with [Manager::outbound_channel] until its create or add
+    /// funds message is tracked, so that concurrent requests do not create several channels.
+    pub async fn accessor(&self, from: &Address, to: &Address) -> Arc<Mutex<()>> {
+        self.accessors
+            .lock()
+            .await
+            .entry((*from, *to))
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the addresses of all channels whose actor has been created.
+    pub fn list_channels(&self) -> Result<Vec<Address>, Error> {
+        Ok(self
+            .store
+            .list_channels()?
+            .into_iter()
+            .filter_map(|ci| ci.channel)
+            .collect())
+    }
+
+    /// Returns the tracked channel with the given actor address.
+    pub fn channel_info(&self, ch: &Address) -> Result<ChannelInfo, Error> {
+        self.store.by_address(ch)
+    }
+
+    /// Returns the tracked channel with the given store identifier.
+    pub fn channel_info_by_id(&self, id: &str) -> Result<ChannelInfo, Error> {
+        self.store
+            .get_channel(id)?
+            .ok_or_else(|| Error::ChannelNotTracked(id.to_owned()))
+    }
+
+    /// Returns the active outbound channel from `from` to `to`, if any. A channel which
+    /// started settling on chain is marked as settling and is not returned.
+    pub async fn outbound_channel(
+        &self,
+        from: &Address,
+        to: &Address,
+    ) -> Result<Option<ChannelInfo>, Error> {
+        let _guard = self.lock.lock().await;
+        let mut ci = match self.store.outbound_active_by_from_to(from, to)? {
+            Some(ci) => ci,
+            None => return Ok(None),
+        };
+        if let Some(ch) = ci.channel {
+            let (_, state) = self.channel_state(&ch).await?;
+            if state.settling_at() != 0 {
+                debug!("payment channel {} is settling, not reusing it", ch);
+                ci.settling = true;
+                self.store.put_channel(&ci)?;
+                return Ok(None);
+            }
+        }
+        Ok(Some(ci))
+    }
+
+    /// Builds the init actor message which creates a channel from `from` to `to` funded
+    /// with `amount`.
+    pub async fn create_channel_message(
+        &self,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<UnsignedMessage, Error> {
+        let head = self.head().await?;
+        let version = ActorVersion::from(self.sm.get_network_version(head.epoch()));
+        let params = paych::exec_params(version, from, to)?;
+        Ok(UnsignedMessage::builder()
+            .to(**init::ADDRESS)
+            .from(from)
+            .value(amount)
+            .method_num(init::Method::Exec as u64)
+            .params(params)
+            .build()?)
+    }
+
+    /// Builds the message which adds `amount` to the channel.
+    pub fn add_funds_message(
+        &self,
+        ci: &ChannelInfo,
+        amount: TokenAmount,
+    ) -> Result<UnsignedMessage, Error> {
+        let ch = ci
+            .channel
+            .ok_or_else(|| Error::Other("channel has not been created yet".to_owned()))?;
+        Ok(UnsignedMessage::builder()
+            .to(ch)
+            .from(ci.control)
+            .value(amount)
+            .method_num(METHOD_SEND)
+            .build()?)
+    }
+
+    /// Starts tracking an outbound channel whose create message has been pushed.
+    pub async fn track_create(
+        &self,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+        msg: Cid,
+    ) -> Result<ChannelInfo, Error> {
+        let _guard = self.lock.lock().await;
+        let ci = ChannelInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            channel: None,
+            control: from,
+            target: to,
+            direction: Direction::Outbound,
+            vouchers: Vec::new(),
+            next_lane: 0,
+            amount: TokenAmount::zero(),
+            pending_amount: amount,
+            create_msg: Some(msg),
+            add_funds_msg: None,
+            settling: false,
+        };
+        self.store.put_channel(&ci)?;
+        Ok(ci)
+    }
+
+    /// Records a pushed message adding `amount` to the channel.
+    pub async fn track_add_funds(
+        &self,
+        id: &str,
+        amount: TokenAmount,
+        msg: Cid,
+    ) -> Result<ChannelInfo, Error> {
+        let _guard = self.lock.lock().await;
+        let mut ci = self.channel_info_by_id(id)?;
+        ci.pending_amount += amount;
+        ci.add_funds_msg = Some(msg);
+        self.store.put_channel(&ci)?;
+        Ok(ci)
+    }
+
+    /// Waits for the message with the given cid, which creates or adds funds to a channel, to
+    /// be executed and returns the address of the channel.
+    pub async fn wait_ready(&self, sentinel: Cid) -> Result<Address, Error> {
+        let ci = self
+            .store
+            .by_message(&sentinel)?
+            .ok_or_else(|| Error::ChannelNotTracked(format!("for message {}", sentinel)))?;
+        if ci.create_msg == Some(sentinel) {
+            return self.wait_for_create(&ci.id).await;
+        }
+        self.wait_for_add_funds(&ci.id).await?;
+        ci.channel
+            .ok_or_else(|| Error::Other(format!("channel {} has not been created yet", ci.id)))
+    }
+
+    /// Waits for the create message of the channel to be executed and records the address
+    /// of the created actor.
+    pub async fn wait_for_create(&self, id: &str) -> Result<Address, Error> {
+        let msg = self
+            .channel_info_by_id(id)?
+            .create_msg
+            .ok_or_else(|| Error::Other(format!("channel {} has no create message", id)))?;
+        let ret = self.wait_for_success(msg).await?;
+        let ret: init::ExecReturn = ret.deserialize()?;
+
+        let _guard = self.lock.lock().await;
+        let mut ci = self.channel_info_by_id(id)?;
+        if let Some(ch) = ci.channel {
+            return Ok(ch);
+        }
+        debug!("payment channel {} created at {}", id, ret.robust_address);
+        ci.channel = Some(ret.robust_address);
+        ci.amount += std::mem::take(&mut ci.pending_amount);
+        self.store.put_channel(&ci)?;
+        Ok(ret.robust_address)
+    }
+
+    /// Waits for the last add funds message of the channel to be executed.
+    pub async fn wait_for_add_funds(&self, id: &str) -> Result<(), Error> {
+        let msg = self
+            .channel_info_by_id(id)?
+            .add_funds_msg
+            .ok_or_else(|| Error::Other(format!("channel {} has no add funds message", id)))?;
+        self.wait_for_success(msg).await?;
+
+        let _guard = self.lock.lock().await;
+        let mut ci = self.channel_info_by_id(id)?;
+        ci.amount += std::mem::take(&mut ci.pending_amount);
+        self.store.put_channel(&ci)
+    }
+
+    /// Allocates a new lane of an outbound channel, above the lanes already used on chain.
+    pub async fn allocate_lane(&self, ch: &Address) -> Result<u64, Error> {
+        let (_, state) = self.channel_state(ch).await?;
+        let _guard = self.lock.lock().await;
+        let mut ci = self.store.by_address(ch)?;
+        let on_chain = state
+            .lane_states(self.sm.blockstore())?
+            .into_iter()
+            .map(|(lane, _)| lane + 1)
+            .max()
+            .unwrap_or(0);
+        let lane = ci.next_lane.max(on_chain);
+        ci.next_lane = lane + 1;
+        self.store.put_channel(&ci)?;
+        Ok(lane)
+    }
+
+    /// Returns the vouchers tracked for the channel.
+    pub fn list_vouchers(&self, ch: &Address) -> Result<Vec<VoucherInfo>, Error> {
+        Ok(self.store.by_address(ch)?.vouchers)
+    }
+
+    /// Creates a voucher redeeming `amount` on `lane` of an outbound channel. The signing bytes
+    /// of the voucher are signed through `sign`, with the key of the control address.
+    pub async fn create_voucher<V, F>(
+        &self,
+        ch: &Address,
+        amount: TokenAmount,
+        lane: u64,
+        sign: F,
+    ) -> Result<VoucherCreateResult, Error>
+    where
+        V: ProofVerifier,
+        F: FnOnce(&[u8]) -> Result<Signature, Error>,
+    {
+        let (_, state) = self.channel_state(ch).await?;
+        let _guard = self.lock.lock().await;
+        let mut ci = self.store.by_address(ch)?;
+        if ci.direction != Direction::Outbound {
+            return Err(Error::Other(format!(
+                "cannot create a voucher on inbound channel {}",
+                ch
+            )));
+        }
+        if ci.settling || state.settling_at() != 0 {
+            return Err(Error::Other(format!(
+                "cannot create a voucher on settling channel {}",
+                ch
+            )));
+        }
+        if lane >= ci.next_lane {
+            return Err(Error::Other(format!(
+                "lane {} of channel {} has not been allocated",
+                lane, ch
+            )));
+        }
+
+        // The nonce follows both the vouchers submitted on chain and the ones tracked locally
+        let nonce = self
+            .lane_states(&ci, &state)?
+            .get(&lane)
+            .map(|ls| ls.nonce)
+            .unwrap_or(0)
+            + 1;
+        let mut sv = SignedVoucher {
+            channel_addr: *ch,
+            time_lock_min: 0,
+            time_lock_max: 0,
+            secret_pre_image: Vec::new(),
+            extra: None,
+            lane: lane as usize,
+            nonce,
+            amount,
+            min_settle_height: 0,
+            merges: Vec::new(),
+            signature: None,
+        };
+        let bz = sv.signing_bytes()?;
+        sv.signature = Some(sign(&bz)?);
+
+        match self.validate_voucher::<V>(&ci, ch, &sv).await {
+            Ok(_) => {}
+            Err(Error::InsufficientFunds(shortfall)) => {
+                return Ok(VoucherCreateResult {
+                    voucher: None,
+                    shortfall,
+                })
+            }
+            Err(e) => return Err(e),
+        }
+
+        ci.vouchers.push(VoucherInfo {
+            voucher: sv.clone(),
+            proof: Vec::new(),
+            submitted: false,
+        });
+        self.store.put_channel(&ci)?;
+        Ok(VoucherCreateResult {
+            voucher: Some(sv),
+            shortfall: TokenAmount::zero(),
+        })
+    }
+
+    /// Checks that a voucher is valid for the channel: signed by the payer, with a nonce
+    /// higher than the one of its lane and covered by the balance of the channel.
+    pub async fn check_voucher_valid<V: ProofVerifier>(
+        &self,
+        ch: &Address,
+        sv: &SignedVoucher,
+    ) -> Result<(), Error> {
+        let ci = self.channel_or_inbound(ch).await?;
+        self.validate_voucher::<V>(&ci, ch, sv).await?;
+        Ok(())
+    }
+
+    /// Adds a voucher received for the channel, starting to track the channel if it is
+    /// unknown. Returns the amount the voucher adds to its lane, which must be at least
+    /// `min_delta`.
+    pub async fn add_voucher<V: ProofVerifier>(
+        &self,
+        ch: &Address,
+        sv: SignedVoucher,
+        proof: Vec<u8>,
+        min_delta: TokenAmount,
+    ) -> Result<TokenAmount, Error> {
+        let _guard = self.lock.lock().await;
+        let mut ci = self.channel_or_inbound(ch).await?;
+
+        if let Some(vi) = ci.voucher_info_mut(&sv) {
+            // The voucher is already tracked, only record a proof that was missing
+            if !proof.is_empty() && vi.proof != proof {
+                vi.proof = proof;
+                self.store.put_channel(&ci)?;
+            }
+            return Ok(TokenAmount::zero());
+        }
+
+        let lanes = self.validate_voucher::<V>(&ci, ch, &sv).await?;
+        let redeemed = lanes
+            .get(&(sv.lane as u64))
+            .map(|ls| ls.redeemed.clone())
+            .unwrap_or_default();
+        let delta = &sv.amount - redeemed;
+        if delta < min_delta {
+            return Err(Error::InvalidVoucher(format!(
+                "voucher adds {} to its lane, less than the minimum of {}",
+                delta, min_delta
+            )));
+        }
+
+        if ci.next_lane <= sv.lane as u64 {
+            ci.next_lane = sv.lane as u64 + 1;
+        }
+        ci.vouchers.push(VoucherInfo {
+            voucher: sv,
+            proof,
+            submitted: false,
+        });
+        self.store.put_channel(&ci)?;
+        Ok(delta)
+    }
+
+    /// Checks whether the voucher can be redeemed by the recipient of the channel, by
+    /// applying the update channel state message on the chain head.
+    pub async fn check_voucher_spendable<V: ProofVerifier>(
+        &self,
+        ch: &Address,
+        sv: &SignedVoucher,
+        secret: Vec<u8>,
+    ) -> Result<bool, Error> {
+        let ci = self.store.by_address(ch)?;
+        if ci.voucher_info(sv).map_or(false, |vi| vi.submitted) {
+            return Ok(false);
+        }
+
+        let (_, state) = self.channel_state(ch).await?;
+        let mut msg = self.update_channel_state_message(ch, state.to(), sv, secret)?;
+        let res = self.sm.call::<V>(&mut msg, None).await?;
+        Ok(res
+            .msg_rct
+            .map_or(false, |rct| rct.exit_code == ExitCode::Ok))
+    }
+
+    /// Builds the message which submits a voucher of an inbound channel to the chain.
+    pub fn submit_voucher_message(
+        &self,
+        ch: &Address,
+        sv: &SignedVoucher,
+        secret: Vec<u8>,
+    ) -> Result<UnsignedMessage, Error> {
+        let ci = self.store.by_address(ch)?;
+        if ci.voucher_info(sv).map_or(false, |vi| vi.submitted) {
+            return Err(Error::InvalidVoucher(
+                "voucher already submitted".to_owned(),
+            ));
+        }
+        self.update_channel_state_message(ch, ci.control, sv, secret)
+    }
+
+    /// Marks a voucher as submitted, tracking it if it was not known.
+    pub async fn voucher_submitted(&self, ch: &Address, sv: SignedVoucher) -> Result<(), Error> {
+        let _guard = self.lock.lock().await;
+        let mut ci = self.store.by_address(ch)?;
+        match ci.voucher_info_mut(&sv) {
+            Some(vi) => vi.submitted = true,
+            None => ci.vouchers.push(VoucherInfo {
+                voucher: sv,
+                proof: Vec::new(),
+                submitted: true,
+            }),
+        }
+        self.store.put_channel(&ci)
+    }
+
+    /// Builds the message which starts settling the channel.
+    pub fn settle_message(&self, ch: &Address) -> Result<UnsignedMessage, Error> {
+        let ci = self.store.by_address(ch)?;
+        self.channel_method_message(ch, ci.control, paych::Method::Settle as u64)
+    }
+
+    /// Marks the channel as settling, so that it is not used for new payments.
+    pub async fn mark_settling(&self, ch: &Address) -> Result<(), Error> {
+        let _guard = self.lock.lock().await;
+        let mut ci = self.store.by_address(ch)?;
+        ci.settling = true;
+        self.store.put_channel(&ci)
+    }
+
+    /// Builds the message which collects the funds of a settled channel.
+    pub fn collect_message(&self, ch: &Address) -> Result<UnsignedMessage, Error> {
+        let ci = self.store.by_address(ch)?;
+        self.channel_method_message(ch, ci.control, paych::Method::Collect as u64)
+    }
+
+    /// Validates a voucher against the channel, returns the lane states of the channel
+    /// the voucher was checked against.
+    async fn validate_voucher<V: ProofVerifier>(
+        &self,
+        ci: &ChannelInfo,
+        ch: &Address,
+        sv: &SignedVoucher,
+    ) -> Result<HashMap<u64, LaneState>, Error> {
+        if &sv.channel_addr != ch {
+            return Err(Error::InvalidVoucher(
+                "voucher channel address doesn't match channel".to_owned(),
+            ));
+        }
+
+        let (actor, state) = self.channel_state(ch).await?;
+        let head = self.head().await?;
+        let from = self
+            .sm
+            .resolve_to_key_addr::<V>(&state.from(), &head)
+            .await?;
+        let sig = sv
+            .signature
+            .as_ref()
+            .ok_or_else(|| Error::InvalidVoucher("voucher is not signed".to_owned()))?;
+        sig.verify(&sv.signing_bytes()?, &from)
+            .map_err(Error::InvalidVoucher)?;
+
+        if !sv.merges.is_empty() {
+            return Err(Error::InvalidVoucher(
+                "lane merges are not supported".to_owned(),
+            ));
+        }
+
+        let lanes = self.lane_states(ci, &state)?;
+        let lane = sv.lane as u64;
+        if let Some(ls) = lanes.get(&lane) {
+            if sv.nonce <= ls.nonce {
+                return Err(Error::NonceTooLow(sv.nonce, lane));
+            }
+        }
+
+        let total = total_redeemed_with_voucher(&lanes, sv);
+        if actor.balance < total {
+            return Err(Error::InsufficientFunds(total - actor.balance));
+        }
+        Ok(lanes)
+    }
+
+    /// Returns the lane states of the channel on chain, updated with the vouchers tracked
+    /// for the channel.
+    fn lane_states(
+        &self,
+        ci: &ChannelInfo,
+        state: &paych::State,
+    ) -> Result<HashMap<u64, LaneState>, Error> {
+        let mut lanes: HashMap<u64, LaneState> = state
+            .lane_states(self.sm.blockstore())?
+            .into_iter()
+            .collect();
+        for vi in &ci.vouchers {
+            let sv = &vi.voucher;
+            let lane = lanes.entry(sv.lane as u64).or_insert(LaneState {
+                redeemed: TokenAmount::zero(),
+                nonce: 0,
+            });
+            if sv.nonce >= lane.nonce {
+                lane.redeemed = sv.amount.clone();
+                lane.nonce = sv.nonce;
+            }
+        }
+        Ok(lanes)
+    }
+
+    /// Returns the tracked channel, or starts tracking it as an inbound channel from its
+    /// on chain state.
+    async fn channel_or_inbound(&self, ch: &Address) -> Result<ChannelInfo, Error> {
+        match self.store.by_address(ch) {
+            Err(Error::ChannelNotTracked(_)) => {}
+            res => return res,
+        }
+        let (actor, state) = self.channel_state(ch).await?;
+        let ci = ChannelInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            channel: Some(*ch),
+            control: state.to(),
+            target: state.from(),
+            direction: Direction::Inbound,
+            vouchers: Vec::new(),
+            next_lane: 0,
+            amount: actor.balance,
+            pending_amount: TokenAmount::zero(),
+            create_msg: None,
+            add_funds_msg: None,
+            settling: state.settling_at() != 0,
+        };
+        self.store.put_channel(&ci)?;
+        Ok(ci)
+    }
+
+    /// Loads the actor and state of the channel at the chain head.
+    async fn channel_state(&self, ch: &Address) -> Result<(ActorState, paych::State), Error> {
+        let head = self.head().await?;
+        let actor = self
+            .sm
+            .get_actor(ch, head.parent_state())?
+            .ok_or_else(|| Error::Other(format!("payment channel actor {} not found", ch)))?;
+        let state = paych::State::load(self.sm.blockstore(), &actor)?;
+        Ok((actor, state))
+    }
+
+    async fn head(&self) -> Result<Arc<blocks::Tipset>, Error> {
+        self.sm
+            .chain_store()
+            .heaviest_tipset()
+            .await
+            .ok_or_else(|| Error::Other("could not get heaviest tipset".to_owned()))
+    }
+
+    /// Waits for a message to be executed successfully, returns its return value.
+    async fn wait_for_success(&self, msg: Cid) -> Result<Serialized, Error> {
+        let (_, receipt) = self.sm.wait_for_message(msg, 1).await?;
+        let receipt =
+            receipt.ok_or_else(|| Error::Other(format!("no receipt found for message {}", msg)))?;
+        if receipt.exit_code != ExitCode::Ok {
+            return Err(Error::Other(format!(
+                "message {} failed with exit code {:?}",
+                msg, receipt.exit_code
+            )));
+        }
+        Ok(receipt.return_data)
+    }
+
+    fn update_channel_state_message(
+        &self,
+        ch: &Address,
+        from: Address,
+        sv: &SignedVoucher,
+        secret: Vec<u8>,
+    ) -> Result<UnsignedMessage, Error> {
+        let params = Serialized::serialize(UpdateChannelStateParams {
+            sv: sv.clone(),
+            secret,
+        })?;
+        Ok(UnsignedMessage::builder()
+            .to(*ch)
+            .from(from)
+            .method_num(paych::Method::UpdateChannelState as u64)
+            .params(params)
+            .build()?)
+    }
+
+    fn channel_method_message(
+        &self,
+        ch: &Address,
+        from: Address,
+        method: u64,
+    ) -> Result<UnsignedMessage, Error> {
+        Ok(UnsignedMessage::builder()
+            .to(*ch)
+            .from(from)
+            .method_num(method)
+            .build()?)
+    }
+}
+
+/// Returns the total amount redeemed by the channel once the voucher is applied.
+fn total_redeemed_with_voucher(lanes: &HashMap<u64, LaneState>, sv: &SignedVoucher) -> TokenAmount {
+    let mut total: TokenAmount = lanes.values().map(|ls| &ls.redeemed).sum();
+    match lanes.get(&(sv.lane as u64)) {
+        Some(ls) => {
+            if sv.nonce > ls.nonce {
+                total += &sv.amount - &ls.redeemed;
+            }
+        }
+        None => total += &sv.amount,
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voucher(lane: usize, nonce: u64, amount: u64) -> SignedVoucher {
+        SignedVoucher {
+            channel_addr: Address::new_id(1),
+            time_lock_min: 0,
+            time_lock_max: 0,
+            secret_pre_image: Vec::new(),
+            extra: None,
+            lane,
+            nonce,
+            amount: TokenAmount::from(amount),
+            min_settle_height: 0,
+            merges: Vec::new(),
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn total_redeemed() {
+        let mut lanes = HashMap::new();
+        lanes.insert(
+            0,
+            LaneState {
+                redeemed: TokenAmount::from(10),
+                nonce: 2,
+            },
+        );
+        lanes.insert(
+            1,
+            LaneState {
+                redeemed: TokenAmount::from(5),
+                nonce: 1,
+            },
+        );
+
+        // Voucher on an existing lane only adds its delta
+        assert_eq!(
+            total_redeemed_with_voucher(&lanes, &voucher(0, 3, 12)),
+            TokenAmount::from(17)
+        );
+        // Voucher with a stale nonce adds nothing
+        assert_eq!(
+            total_redeemed_with_voucher(&lanes, &voucher(0, 2, 12)),
+            TokenAmount::from(15)
+        );
+        // Voucher on a new lane adds its full amount
+        assert_eq!(
+            total_redeemed_with_voucher(&lanes, &voucher(2, 1, 4)),
+            TokenAmount::from(19)
+        );
+    }
+}
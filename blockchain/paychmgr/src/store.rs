@@ -0,0 +1,282 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::Error;
+use actor::paych::SignedVoucher;
+use address::Address;
+use cid::Cid;
+use db::{Column, Store};
+use encoding::{from_slice, serde_bytes, to_vec};
+use num_bigint::bigint_ser;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use vm::TokenAmount;
+
+const CHANNEL_INDEX_KEY: &[u8] = b"/paych/channels";
+const CHANNEL_KEY_PREFIX: &str = "/paych/channel/";
+const ADDRESS_KEY_PREFIX: &str = "/paych/address/";
+const FROM_TO_KEY_PREFIX: &str = "/paych/from-to/";
+const MESSAGE_KEY_PREFIX: &str = "/paych/message/";
+
+/// Direction of the funds of a payment channel, relative to this node.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Direction {
+    /// Channel which pays this node.
+    Inbound,
+    /// Channel funded by this node.
+    Outbound,
+}
+
+/// Voucher tracked by the manager.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoucherInfo {
+    pub voucher: SignedVoucher,
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+    pub submitted: bool,
+}
+
+/// Payment channel tracked by the manager.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    /// Identifier of the channel in the store, assigned before the channel address is known.
+    pub id: String,
+    /// Address of the channel actor, unset until the create message is executed.
+    pub channel: Option<Address>,
+    /// Address of the party this node signs with, the payer for outbound channels.
+    pub control: Address,
+    /// Address of the counterparty.
+    pub target: Address,
+    pub direction: Direction,
+    pub vouchers: Vec<VoucherInfo>,
+    /// Next lane which will be allocated.
+    pub next_lane: u64,
+    /// Amount confirmed on chain to have been added to the channel.
+    #[serde(with = "bigint_ser")]
+    pub amount: TokenAmount,
+    /// Amount added to the channel by messages which are not yet executed.
+    #[serde(with = "bigint_ser")]
+    pub pending_amount: TokenAmount,
+    /// Message which created the channel, unset for inbound channels.
+    pub create_msg: Option<Cid>,
+    /// Last message which added funds to the channel.
+    pub add_funds_msg: Option<Cid>,
+    pub settling: bool,
+}
+
+impl ChannelInfo {
+    /// Returns the address of the payer of the channel.
+    pub fn from(&self) -> Address {
+        match self.direction {
+            Direction::Outbound => self.control,
+            Direction::Inbound => self.target,
+        }
+    }
+
+    /// Returns the address of the recipient of the channel.
+    pub fn to(&self) -> Address {
+        match self.direction {
+            Direction::Outbound => self.target,
+            Direction::Inbound => self.control,
+        }
+    }
+
+    /// Returns the tracked voucher which is equal to `sv`, if any.
+    pub fn voucher_info(&self, sv: &SignedVoucher) -> Option<&VoucherInfo> {
+        self.vouchers.iter().find(|vi| &vi.voucher == sv)
+    }
+
+    /// Returns the tracked voucher which is equal to `sv` mutably, if any.
+    pub fn voucher_info_mut(&mut self, sv: &SignedVoucher) -> Option<&mut VoucherInfo> {
+        self.vouchers.iter_mut().find(|vi| &vi.voucher == sv)
+    }
+}
+
+/// Persists the payment channels tracked by the manager in the node's store.
+pub struct PaychStore<DB> {
+    db: Arc<DB>,
+}
+
+impl<DB> PaychStore<DB>
+where
+    DB: Store,
+{
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Returns all tracked channels.
+    pub fn list_channels(&self) -> Result<Vec<ChannelInfo>, Error> {
+        self.channel_ids()?
+            .iter()
+            .map(|id| {
+                self.get_channel(id)?
+                    .ok_or_else(|| Error::ChannelNotTracked(id.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the channel with the given store identifier.
+    pub fn get_channel(&self, id: &str) -> Result<Option<ChannelInfo>, Error> {
+        match self.db.read_column(Column::Metadata, channel_key(id))? {
+            Some(bz) => Ok(Some(from_slice(&bz)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the channel with the given actor address.
+    pub fn by_address(&self, ch: &Address) -> Result<ChannelInfo, Error> {
+        self.indexed_channel(format!("{}{}", ADDRESS_KEY_PREFIX, ch))?
+            .ok_or_else(|| Error::ChannelNotTracked(ch.to_string()))
+    }
+
+    /// Returns the channel created or last funded by the message with the given cid, if any.
+    pub fn by_message(&self, msg: &Cid) -> Result<Option<ChannelInfo>, Error> {
+        self.indexed_channel(format!("{}{}", MESSAGE_KEY_PREFIX, msg))
+    }
+
+    /// Returns the outbound channel from `from` to `to` which is not settling, if any.
+    pub fn outbound_active_by_from_to(
+        &self,
+        from: &Address,
+        to: &Address,
+    ) -> Result<Option<ChannelInfo>, Error> {
+        for id in self.from_to_ids(from, to)? {
+            if let Some(ci) = self.get_channel(&id)? {
+                if !ci.settling {
+                    return Ok(Some(ci));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Inserts or updates a channel, and the indices to look it up by address, by the
+    /// addresses of its parties and by its create and add funds messages.
+    pub fn put_channel(&self, ci: &ChannelInfo) -> Result<(), Error> {
+        let mut ids = self.channel_ids()?;
+        if !ids.contains(&ci.id) {
+            ids.push(ci.id.clone());
+            self.db
+                .write_column(Column::Metadata, CHANNEL_INDEX_KEY, to_vec(&ids)?)?;
+        }
+        if ci.direction == Direction::Outbound {
+            let mut ids = self.from_to_ids(&ci.control, &ci.target)?;
+            if !ids.contains(&ci.id) {
+                ids.push(ci.id.clone());
+                self.db.write_column(
+                    Column::Metadata,
+                    from_to_key(&ci.control, &ci.target),
+                    to_vec(&ids)?,
+                )?;
+            }
+        }
+        let mut index_keys = Vec::new();
+        if let Some(ch) = &ci.channel {
+            index_keys.push(format!("{}{}", ADDRESS_KEY_PREFIX, ch));
+        }
+        for msg in ci.create_msg.iter().chain(ci.add_funds_msg.iter()) {
+            index_keys.push(format!("{}{}", MESSAGE_KEY_PREFIX, msg));
+        }
+        for key in index_keys {
+            self.db
+                .write_column(Column::Metadata, key, ci.id.as_bytes())?;
+        }
+        self.db
+            .write_column(Column::Metadata, channel_key(&ci.id), to_vec(ci)?)?;
+        Ok(())
+    }
+
+    fn indexed_channel(&self, key: String) -> Result<Option<ChannelInfo>, Error> {
+        match self.db.read_column(Column::Metadata, key)? {
+            Some(id) => self.get_channel(&String::from_utf8_lossy(&id)),
+            None => Ok(None),
+        }
+    }
+
+    fn from_to_ids(&self, from: &Address, to: &Address) -> Result<Vec<String>, Error> {
+        match self
+            .db
+            .read_column(Column::Metadata, from_to_key(from, to))?
+        {
+            Some(bz) => Ok(from_slice(&bz)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn channel_ids(&self) -> Result<Vec<String>, Error> {
+        match self.db.read_column(Column::Metadata, CHANNEL_INDEX_KEY)? {
+            Some(bz) => Ok(from_slice(&bz)?),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+fn channel_key(id: &str) -> Vec<u8> {
+    format!("{}{}", CHANNEL_KEY_PREFIX, id).into_bytes()
+}
+
+fn from_to_key(from: &Address, to: &Address) -> Vec<u8> {
+    format!("{}{}/{}", FROM_TO_KEY_PREFIX, from, to).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+
+    fn channel(id: &str, control: u64, target: u64) -> ChannelInfo {
+        ChannelInfo {
+            id: id.to_owned(),
+            channel: None,
+            control: Address::new_id(control),
+            target: Address::new_id(target),
+            direction: Direction::Outbound,
+            vouchers: Vec::new(),
+            next_lane: 0,
+            amount: TokenAmount::from(0),
+            pending_amount: TokenAmount::from(0),
+            create_msg: None,
+            add_funds_msg: None,
+            settling: false,
+        }
+    }
+
+    #[test]
+    fn put_and_find_channels() {
+        let store = PaychStore::new(Arc::new(MemoryDB::default()));
+        let mut first = channel("a", 1, 2);
+        store.put_channel(&first).unwrap();
+        store.put_channel(&channel("b", 1, 3)).unwrap();
+
+        first.channel = Some(Address::new_id(100));
+        first.create_msg = Some(cid::new_from_cbor(&[1], cid::Code::Blake2b256));
+        store.put_channel(&first).unwrap();
+
+        assert_eq!(store.list_channels().unwrap().len(), 2);
+        assert_eq!(store.by_address(&Address::new_id(100)).unwrap(), first);
+        assert!(store.by_address(&Address::new_id(101)).is_err());
+        assert_eq!(
+            store.by_message(&first.create_msg.unwrap()).unwrap(),
+            Some(first.clone())
+        );
+
+        // A settling channel is not returned, a new channel can be created for the same parties
+        first.settling = true;
+        store.put_channel(&first).unwrap();
+        assert!(store
+            .outbound_active_by_from_to(&Address::new_id(1), &Address::new_id(2))
+            .unwrap()
+            .is_none());
+
+        let found = store
+            .outbound_active_by_from_to(&Address::new_id(1), &Address::new_id(3))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, "b");
+        assert!(store
+            .outbound_active_by_from_to(&Address::new_id(2), &Address::new_id(1))
+            .unwrap()
+            .is_none());
+    }
+}
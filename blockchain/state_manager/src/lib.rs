@@ -26,20 +26,21 @@ use forest_blocks::{BlockHeader, Tipset, TipsetKeys};
 use forest_crypto::DomainSeparationTag;
 use futures::{channel::oneshot, select, FutureExt};
 use interpreter::{
-    resolve_to_key_addr, ApplyRet, BlockMessages, CircSupplyCalc, LookbackStateGetter, Rand, VM,
+    resolve_to_key_addr, trace, ApplyRet, BlockMessages, CircSupplyCalc, ExecutionTrace,
+    LookbackStateGetter, Rand, VM,
 };
 use ipld_amt::Amt;
 use log::{debug, info, trace, warn};
 use message::{
     message_receipt, unsigned_message, ChainMessage, Message, MessageReceipt, UnsignedMessage,
 };
-use networks::get_network_version_default;
+use networks::NetworkConfig;
 use num_bigint::{bigint_ser, BigInt};
 use num_traits::identities::Zero;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use state_tree::StateTree;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error as StdError;
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -59,6 +60,20 @@ pub struct InvocResult {
     #[serde(with = "message_receipt::json::opt")]
     pub msg_rct: Option<MessageReceipt>,
     pub error: Option<String>,
+    #[serde(with = "trace::json::opt")]
+    pub execution_trace: Option<ExecutionTrace>,
+}
+
+impl InvocResult {
+    /// Builds the result of applying `msg` from the value returned by the VM.
+    pub fn new(msg: UnsignedMessage, ret: ApplyRet) -> Self {
+        Self {
+            msg,
+            msg_rct: Some(ret.msg_receipt),
+            error: ret.act_error.map(|e| e.to_string()),
+            execution_trace: ret.exec_trace,
+        }
+    }
 }
 
 /// An alias Result that represents an InvocResult and an Error.
@@ -87,34 +102,49 @@ pub struct StateManager<DB> {
     cache: RwLock<HashMap<TipsetKeys, Arc<RwLock<Option<CidPair>>>>>,
     publisher: Option<Publisher<HeadChange>>,
     genesis_info: GenesisInfo,
+    network: Arc<NetworkConfig>,
 }
 
 impl<DB> StateManager<DB>
 where
     DB: BlockStore + Send + Sync + 'static,
 {
+    /// Creates a state manager for mainnet.
     pub fn new(cs: Arc<ChainStore<DB>>) -> Self {
+        Self::new_with_config(cs, Arc::new(NetworkConfig::mainnet()))
+    }
+
+    /// Creates a state manager for the given network.
+    pub fn new_with_config(cs: Arc<ChainStore<DB>>, network: Arc<NetworkConfig>) -> Self {
         Self {
             cs,
             cache: RwLock::new(HashMap::new()),
             publisher: None,
-            genesis_info: GenesisInfo::default(),
+            genesis_info: GenesisInfo::new(network.upgrades),
+            network,
         }
     }
 
-    /// Creates a constructor that passes in a HeadChange publisher.
-    pub fn new_with_publisher(cs: Arc<ChainStore<DB>>, chain_subs: Publisher<HeadChange>) -> Self {
+    /// Creates a state manager for the given network, which passes in a HeadChange publisher.
+    pub fn new_with_publisher(
+        cs: Arc<ChainStore<DB>>,
+        network: Arc<NetworkConfig>,
+        chain_subs: Publisher<HeadChange>,
+    ) -> Self {
         Self {
-            cs,
-            cache: RwLock::new(HashMap::new()),
             publisher: Some(chain_subs),
-            genesis_info: GenesisInfo::default(),
+            ..Self::new_with_config(cs, network)
         }
     }
 
+    /// Returns the parameters of the network the state manager runs on.
+    pub fn network_config(&self) -> &Arc<NetworkConfig> {
+        &self.network
+    }
+
     /// Returns network version for the given epoch.
     pub fn get_network_version(&self, epoch: ChainEpoch) -> NetworkVersion {
-        get_network_version_default(epoch)
+        self.network.network_version(epoch)
     }
 
     /// Gets actor from given [Cid], if it exists.
@@ -245,10 +275,15 @@ where
             epoch,
             rand,
             base_fee,
-            get_network_version_default,
+            |epoch| self.get_network_version(epoch),
+            &self.network.upgrades,
             &self.genesis_info,
             &lb_wrapper,
         )?;
+        if callback.is_some() {
+            // Traces are only useful to callers inspecting the results of the messages
+            vm.enable_tracing();
+        }
 
         // Apply tipset messages
         let receipts =
@@ -267,6 +302,21 @@ where
         Ok((state_root, rect_root))
     }
 
+    /// Returns the state roots and receipt roots computed for the cached tipsets. The computed
+    /// state of the head is only referenced by its children, so garbage collection keeps these.
+    /// Entries which are being computed are waited for.
+    pub async fn cached_state_roots(&self) -> HashSet<Cid> {
+        let entries: Vec<_> = self.cache.read().await.values().cloned().collect();
+        let mut roots = HashSet::new();
+        for entry in entries {
+            if let Some((state_root, receipts_root)) = *entry.read().await {
+                roots.insert(state_root);
+                roots.insert(receipts_root);
+            }
+        }
+        roots
+    }
+
     /// Returns the pair of (parent state root, message receipt root). This will either be cached
     /// or will be calculated and fill the cache. Tipset state for a given tipset is guaranteed
     /// not to be computed twice.
@@ -360,7 +410,8 @@ where
                 bheight,
                 rand,
                 0.into(),
-                get_network_version_default,
+                |epoch| self.get_network_version(epoch),
+                &self.network.upgrades,
                 &self.genesis_info,
                 &lb_wrapper,
             )?;
@@ -373,6 +424,7 @@ where
                 .get_actor(msg.from(), bstate)?
                 .ok_or_else(|| Error::Other("Could not get actor".to_string()))?;
             msg.set_sequence(actor.sequence);
+            vm.enable_tracing();
             let apply_ret = vm.apply_implicit_message(msg);
             trace!(
                 "gas limit {:},gas premium{:?},value {:?}",
@@ -384,11 +436,7 @@ where
                 warn!("chain call failed: {:?}", err);
             }
 
-            Ok(InvocResult {
-                msg: msg.clone(),
-                msg_rct: Some(apply_ret.msg_receipt.clone()),
-                error: apply_ret.act_error.map(|e| e.to_string()),
-            })
+            Ok(InvocResult::new(msg.clone(), apply_ret))
         })
     }
 
@@ -452,7 +500,8 @@ where
             ts.epoch() + 1,
             &chain_rand,
             ts.blocks()[0].parent_base_fee().clone(),
-            get_network_version_default,
+            |epoch| self.get_network_version(epoch),
+            &self.network.upgrades,
             &self.genesis_info,
             &lb_wrapper,
         )?;
@@ -469,11 +518,7 @@ where
 
         let ret = vm.apply_message(&message)?;
 
-        Ok(InvocResult {
-            msg: message.message().clone(),
-            msg_rct: Some(ret.msg_receipt.clone()),
-            error: ret.act_error.map(|e| e.to_string()),
-        })
+        Ok(InvocResult::new(message.message().clone(), ret))
     }
 
     /// Replays the given message and returns the result of executing the indicated message,
@@ -521,6 +566,114 @@ where
         Ok((out_mes, out_ret))
     }
 
+    /// Executes the tipset, then applies `msgs` on top of the resulting state at `height`.
+    /// Returns the resulting state root, along with the results of the tipset messages followed
+    /// by the results of `msgs`, all with their execution traces. The cron of the null rounds
+    /// between the tipset and `height` is run, along with the scheduled state migrations,
+    /// before applying `msgs`.
+    pub async fn compute_state<V>(
+        self: &Arc<Self>,
+        height: ChainEpoch,
+        msgs: Vec<UnsignedMessage>,
+        ts: &Arc<Tipset>,
+    ) -> Result<(Cid, Vec<InvocResult>), Error>
+    where
+        V: ProofVerifier,
+    {
+        if height < ts.epoch() {
+            return Err(Error::Other(format!(
+                "cannot compute state at height {} before tipset at height {}",
+                height,
+                ts.epoch()
+            )));
+        }
+
+        let results: Arc<std::sync::Mutex<Vec<InvocResult>>> = Default::default();
+        let results_cb = results.clone();
+        let callback = move |_: &Cid, msg: &ChainMessage, ret: &ApplyRet| {
+            results_cb
+                .lock()
+                .map_err(|e| e.to_string())?
+                .push(InvocResult::new(msg.message().clone(), ret.clone()));
+            Ok(())
+        };
+        let (base, _) = self
+            .compute_tipset_state::<V, _>(ts, Some(callback))
+            .await?;
+        let mut results =
+            std::mem::take(&mut *results.lock().map_err(|e| Error::Other(e.to_string()))?);
+
+        let sm = self.clone();
+        let ts = ts.clone();
+        let (root, msg_results) = task::spawn_blocking(move || {
+            sm.apply_messages_traced::<V>(&base, height, &msgs, &ts)
+                .map_err(|e| Error::Other(e.to_string()))
+        })
+        .await?;
+        results.extend(msg_results);
+        Ok((root, results))
+    }
+
+    /// Applies messages with tracing enabled on the given state of the tipset, after running
+    /// the null rounds up to `epoch`, and persists the resulting state.
+    fn apply_messages_traced<V>(
+        self: &Arc<Self>,
+        state: &Cid,
+        epoch: ChainEpoch,
+        msgs: &[UnsignedMessage],
+        tipset: &Arc<Tipset>,
+    ) -> Result<(Cid, Vec<InvocResult>), Box<dyn StdError>>
+    where
+        V: ProofVerifier,
+    {
+        let db = self.blockstore_cloned();
+        let mut buf_store = Arc::new(BufferedBlockStore::new(db.as_ref()));
+        let store = buf_store.as_ref();
+        let lb_wrapper = SMLookbackWrapper {
+            sm: self,
+            store,
+            tipset,
+            verifier: PhantomData::<V>::default(),
+        };
+        let chain_rand = ChainRand::new(tipset.key().to_owned(), self.cs.clone());
+
+        let mut vm = VM::<_, _, _, _, _, V>::new(
+            state,
+            store,
+            epoch,
+            &chain_rand,
+            tipset.blocks()[0].parent_base_fee().clone(),
+            |epoch| self.get_network_version(epoch),
+            &self.network.upgrades,
+            &self.genesis_info,
+            &lb_wrapper,
+        )?;
+        vm.enable_tracing();
+
+        let mut results = Vec::with_capacity(msgs.len());
+        let mut callback = |_: &Cid, msg: &ChainMessage, ret: &ApplyRet| -> Result<(), String> {
+            results.push(InvocResult::new(msg.message().clone(), ret.clone()));
+            Ok(())
+        };
+        vm.run_null_rounds(
+            tipset.epoch(),
+            epoch,
+            buf_store.clone(),
+            Some(&mut callback),
+        )?;
+        for msg in msgs {
+            let ret = vm.apply_message(&ChainMessage::Unsigned(msg.clone()))?;
+            results.push(InvocResult::new(msg.clone(), ret));
+        }
+
+        let root = vm.flush()?;
+        Arc::get_mut(&mut buf_store)
+            .expect("failed getting store reference")
+            .flush(&root)
+            .map_err(|e| e.to_string())?;
+        Ok((root, results))
+    }
+
     /// Gets lookback tipset for block validations.
     pub async fn get_lookback_tipset_for_round<V>(
         self: &Arc<Self>,
@@ -531,7 +684,7 @@ where
         V: ProofVerifier,
     {
         let mut lbr: ChainEpoch = ChainEpoch::from(0);
-        let version = get_network_version_default(round);
+        let version = self.get_network_version(round);
         let lb = if version <= NetworkVersion::V3 {
             ChainEpoch::from(10)
         } else {
@@ -582,7 +735,7 @@ where
         lookback_tipset: &Tipset,
     ) -> Result<bool, Error> {
         let hmp = self.miner_has_min_power(address, lookback_tipset)?;
-        let version = get_network_version_default(base_tipset.epoch());
+        let version = self.get_network_version(base_tipset.epoch());
 
         if version <= NetworkVersion::V3 {
             return Ok(hmp);
@@ -670,7 +823,7 @@ where
             &buf,
         )?;
 
-        let nv = get_network_version_default(tipset.epoch());
+        let nv = self.get_network_version(tipset.epoch());
         let sectors = self.get_sectors_for_winning_post::<V>(
             &lbst,
             nv,
@@ -908,6 +1061,87 @@ where
             };
         }
     }
+    /// Looks up the message in the message index, returns the tipset in which it was executed
+    /// and its receipt if the indexed tipset is part of the chain of `head`. The indexed tipset
+    /// or its receipts may have been pruned since, a failed lookup returns `None` so callers
+    /// fall back to searching the chain.
+    async fn indexed_message(
+        &self,
+        head: &Arc<Tipset>,
+        msg_cid: &Cid,
+    ) -> Option<(Arc<Tipset>, MessageReceipt)> {
+        match self.lookup_indexed_message(head, msg_cid).await {
+            Ok(res) => res,
+            Err(e) => {
+                debug!(
+                    "failed to load indexed message {}, searching the chain: {}",
+                    msg_cid, e
+                );
+                None
+            }
+        }
+    }
+
+    async fn lookup_indexed_message(
+        &self,
+        head: &Arc<Tipset>,
+        msg_cid: &Cid,
+    ) -> Result<Option<(Arc<Tipset>, MessageReceipt)>, Error> {
+        let location = match self
+            .cs
+            .get_msg_location(msg_cid)
+            .map_err(|e| Error::Other(e.to_string()))?
+        {
+            Some(location) if location.epoch <= head.epoch() => location,
+            _ => return Ok(None),
+        };
+        let ts = self
+            .cs
+            .tipset_by_height(location.epoch, head.clone(), false)
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+        if ts.key() != &location.tipset {
+            // The indexed tipset has been reverted.
+            return Ok(None);
+        }
+        let receipt = chain::get_parent_reciept(
+            self.blockstore(),
+            ts.blocks().first().unwrap(),
+            location.index as usize,
+        )
+        .map_err(|e| Error::Other(e.to_string()))?;
+        Ok(receipt.map(|r| (ts, r)))
+    }
+
+    /// Searches the chain of the heaviest tipset for the message, returns the tipset in which it
+    /// was executed and its receipt. The message index is checked first, the chain is only
+    /// walked back for messages which are not indexed.
+    pub async fn search_for_message(
+        &self,
+        msg_cid: &Cid,
+    ) -> Result<Option<(Arc<Tipset>, MessageReceipt)>, Error> {
+        let head = self
+            .cs
+            .heaviest_tipset()
+            .await
+            .ok_or_else(|| Error::Other("could not get heaviest tipset".to_owned()))?;
+        if let Some(res) = self.indexed_message(&head, msg_cid).await {
+            return Ok(Some(res));
+        }
+
+        let message = chain::get_chain_message(self.blockstore(), msg_cid)
+            .map_err(|err| Error::Other(format!("failed to load message {:}", err)))?;
+        let message_var = (message.from(), &message.sequence());
+        if let Some(r) = self
+            .tipset_executed_message(&head, msg_cid, message_var)
+            .await?
+        {
+            return Ok(Some((head, r)));
+        }
+        self.search_back_for_message(&head, (message.from(), msg_cid, &message.sequence()))
+            .await
+    }
+
     /// Returns a message receipt from a given tipset and message cid.
     pub async fn get_receipt(&self, tipset: &Tipset, msg: &Cid) -> Result<MessageReceipt, Error> {
         let m = chain::get_chain_message(self.blockstore(), msg)
@@ -951,6 +1185,11 @@ where
 
         let message_var = (message.from(), &message.sequence());
         let current_tipset = self.cs.heaviest_tipset().await.unwrap();
+        if let Some((ts, r)) = self.indexed_message(&current_tipset, &msg_cid).await {
+            if current_tipset.epoch() >= ts.epoch() + confidence {
+                return Ok((Some(ts), Some(r)));
+            }
+        }
         let maybe_message_reciept = self
             .tipset_executed_message(&current_tipset, &msg_cid, message_var)
             .await?;
@@ -1307,3 +1546,70 @@ where
         StateTree::new_from_root(self.store, &st)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::Code::Blake2b256;
+    use db::MemoryDB;
+    use forest_blocks::TxMeta;
+    use vm::{ExitCode, Serialized};
+
+    fn header(epoch: ChainEpoch, parents: TipsetKeys, messages: Cid, receipts: Cid) -> BlockHeader {
+        BlockHeader::builder()
+            .epoch(epoch)
+            .parents(parents)
+            .messages(messages)
+            .message_receipts(receipts)
+            .state_root(receipts)
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn search_for_message_with_stale_index() {
+        let db = Arc::new(MemoryDB::default());
+        let cs = Arc::new(ChainStore::new(db.clone()));
+
+        let msg = UnsignedMessage::builder()
+            .to(Address::new_id(1))
+            .from(Address::new_id(2))
+            .build()
+            .unwrap();
+        let msg_cid = db.put(&msg, Blake2b256).unwrap();
+        let meta = TxMeta {
+            bls_message_root: Amt::new_from_iter(db.as_ref(), vec![msg_cid]).unwrap(),
+            secp_message_root: Amt::new_from_iter(db.as_ref(), Vec::<Cid>::new()).unwrap(),
+        };
+        let messages = db.put(&meta, Blake2b256).unwrap();
+        let receipt = MessageReceipt {
+            exit_code: ExitCode::Ok,
+            return_data: Serialized::default(),
+            gas_used: 10,
+        };
+        let receipts = Amt::new_from_iter(db.as_ref(), vec![receipt.clone()]).unwrap();
+        // The receipts of the indexed tipset have been pruned
+        let pruned = cid::new_from_cbor(&[1], Blake2b256);
+
+        // The message is included by both the genesis and the indexed tipset, the index points
+        // at the genesis inclusion while the receipt is only found for the second one.
+        let genesis = header(0, TipsetKeys::default(), messages, receipts);
+        let genesis_ts = Tipset::new(vec![genesis.clone()]).unwrap();
+        let indexed = header(1, genesis_ts.key().clone(), messages, pruned);
+        let indexed_ts = Tipset::new(vec![indexed.clone()]).unwrap();
+        let head = header(2, indexed_ts.key().clone(), messages, receipts);
+        let head_ts = Arc::new(Tipset::new(vec![head.clone()]).unwrap());
+        chain::persist_objects(db.as_ref(), &[genesis.clone(), indexed, head]).unwrap();
+        cs.set_genesis(&genesis).unwrap();
+        task::block_on(cs.set_heaviest_tipset(head_ts.clone())).unwrap();
+        assert!(task::block_on(cs.index_tipset_messages(&indexed_ts)).unwrap());
+
+        let sm = StateManager::new(cs);
+        let (ts, found) = task::block_on(sm.search_for_message(&msg_cid))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ts.key(), head_ts.key());
+        assert_eq!(found, receipt);
+    }
+}
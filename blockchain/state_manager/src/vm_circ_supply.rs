@@ -10,10 +10,7 @@ use cid::Cid;
 use clock::ChainEpoch;
 use fil_types::{FILECOIN_PRECISION, FIL_RESERVED};
 use interpreter::CircSupplyCalc;
-use networks::{
-    UPGRADE_ACTORS_V2_HEIGHT, UPGRADE_CALICO_HEIGHT, UPGRADE_IGNITION_HEIGHT,
-    UPGRADE_LIFTOFF_HEIGHT,
-};
+use networks::UpgradeSchedule;
 use num_bigint::BigInt;
 use once_cell::sync::OnceCell;
 use state_tree::StateTree;
@@ -47,8 +44,8 @@ lazy_static! {
 }
 
 /// Genesis information used when calculating circulating supply.
-#[derive(Default)]
 pub(crate) struct GenesisInfo {
+    upgrades: UpgradeSchedule,
     vesting: GenesisInfoVesting,
 
     /// info about the Accounts in the genesis state
@@ -57,6 +54,15 @@ pub(crate) struct GenesisInfo {
 }
 
 impl GenesisInfo {
+    pub(crate) fn new(upgrades: UpgradeSchedule) -> Self {
+        Self {
+            upgrades,
+            vesting: Default::default(),
+            genesis_pledge: Default::default(),
+            genesis_market_funds: Default::default(),
+        }
+    }
+
     fn init<DB: BlockStore>(&self, bs: &DB) -> Result<(), Box<dyn StdError>> {
         let genesis_block =
             genesis(bs)?.ok_or_else(|| "Genesis Block doesn't exist".to_string())?;
@@ -102,13 +108,14 @@ impl CircSupplyCalc for GenesisInfo {
                 Ok(setup_genesis_vesting_schedule())
             })?;
 
+        let liftoff = self.upgrades.liftoff;
         self.vesting
             .ignition
-            .get_or_init(setup_ignition_vesting_schedule);
+            .get_or_init(|| setup_ignition_vesting_schedule(liftoff));
 
         self.vesting
             .calico
-            .get_or_init(setup_calico_vesting_schedule);
+            .get_or_init(|| setup_calico_vesting_schedule(liftoff));
 
         get_circulating_supply(&self, height, state_tree)
     }
@@ -124,6 +131,7 @@ fn get_actor_state<DB: BlockStore>(
 }
 
 fn get_fil_vested(genesis_info: &GenesisInfo, height: ChainEpoch) -> TokenAmount {
+    let upgrades = &genesis_info.upgrades;
     let mut return_value = TokenAmount::default();
 
     let pre_ignition = genesis_info
@@ -142,11 +150,11 @@ fn get_fil_vested(genesis_info: &GenesisInfo, height: ChainEpoch) -> TokenAmount
         .get()
         .expect("calico vesting should be initialized");
 
-    if height <= UPGRADE_IGNITION_HEIGHT {
+    if height <= upgrades.ignition {
         for actor in pre_ignition {
             return_value += &actor.initial_balance - actor.amount_locked(height);
         }
-    } else if height <= UPGRADE_CALICO_HEIGHT {
+    } else if height <= upgrades.calico {
         for actor in post_ignition {
             return_value +=
                 &actor.initial_balance - actor.amount_locked(height - actor.start_epoch);
@@ -158,7 +166,7 @@ fn get_fil_vested(genesis_info: &GenesisInfo, height: ChainEpoch) -> TokenAmount
         }
     }
 
-    if height <= UPGRADE_ACTORS_V2_HEIGHT {
+    if height <= upgrades.actors_v2 {
         return_value += genesis_info
             .genesis_pledge
             .get()
@@ -239,7 +247,7 @@ fn get_circulating_supply<'a, DB: BlockStore>(
     let fil_mined = get_fil_mined(&state_tree)?;
     let fil_burnt = get_fil_burnt(&state_tree)?;
     let fil_locked = get_fil_locked(&state_tree)?;
-    let fil_reserve_distributed = if height > UPGRADE_ACTORS_V2_HEIGHT {
+    let fil_reserve_distributed = if height > genesis_info.upgrades.actors_v2 {
         get_fil_reserve_disbursed(&state_tree)?
     } else {
         TokenAmount::default()
@@ -270,7 +278,7 @@ fn setup_genesis_vesting_schedule() -> Vec<msig0::State> {
         .collect()
 }
 
-fn setup_ignition_vesting_schedule() -> Vec<msig0::State> {
+fn setup_ignition_vesting_schedule(liftoff: ChainEpoch) -> Vec<msig0::State> {
     PRE_CALICO_VESTING
         .iter()
         .map(|(unlock_duration, initial_balance)| {
@@ -285,7 +293,7 @@ fn setup_ignition_vesting_schedule() -> Vec<msig0::State> {
 
                 // In the pre-ignition logic, the start epoch was 0. This changes in the fork logic
                 // of the Ignition upgrade itself.
-                start_epoch: UPGRADE_LIFTOFF_HEIGHT,
+                start_epoch: liftoff,
 
                 unlock_duration: *unlock_duration,
                 // Default Cid is ok here because this field is never read
@@ -295,7 +303,7 @@ fn setup_ignition_vesting_schedule() -> Vec<msig0::State> {
         .collect()
 }
 
-fn setup_calico_vesting_schedule() -> Vec<msig0::State> {
+fn setup_calico_vesting_schedule(liftoff: ChainEpoch) -> Vec<msig0::State> {
     CALICO_VESTING
         .iter()
         .map(|(unlock_duration, initial_balance)| {
@@ -304,7 +312,7 @@ fn setup_calico_vesting_schedule() -> Vec<msig0::State> {
                 num_approvals_threshold: 0,
                 next_tx_id: msig0::TxnID(0),
                 initial_balance: initial_balance * FILECOIN_PRECISION,
-                start_epoch: UPGRADE_LIFTOFF_HEIGHT,
+                start_epoch: liftoff,
                 unlock_duration: *unlock_duration,
                 // Default Cid is ok here because this field is never read
                 pending_txs: Cid::default(),
@@ -1,10 +1,9 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::{handle_rpc_err, print_rpc_res_bytes, Config};
-use forest_libp2p::{Multiaddr, Protocol};
+use super::{handle_rpc_err, print_rpc_res_bytes};
 use jsonrpc_v2::Error as JsonRpcError;
-use rpc_client::{auth_new, DEFAULT_HOST};
+use rpc_client::{api_info, auth_new};
 use structopt::StructOpt;
 
 use auth::*;
@@ -43,7 +42,7 @@ fn process_perms(perm: String) -> Result<Vec<String>, JsonRpcError> {
 }
 
 impl AuthCommands {
-    pub async fn run(&self, cfg: Config) {
+    pub async fn run(&self) {
         match self {
             Self::CreateToken { perm } => {
                 let perm: String = perm.parse().unwrap();
@@ -55,10 +54,12 @@ impl AuthCommands {
                 let perms = process_perms(perm).map_err(handle_rpc_err).unwrap();
                 match auth_new((perms,)).await {
                     Ok(token) => {
-                        let mut addr = Multiaddr::empty();
-                        addr.push(Protocol::Ip4(DEFAULT_HOST.parse().unwrap()));
-                        addr.push(Protocol::Tcp(cfg.rpc_port.parse().unwrap()));
-                        addr.push(Protocol::Http);
+                        // The address of the daemon the token was created by
+                        let addr = api_info()
+                            .await
+                            .map_err(|e| handle_rpc_err(e.into()))
+                            .unwrap()
+                            .multiaddr;
                         println!(
                             "FULLNODE_API_INFO=\"{}:{}\"",
                             String::from_utf8(token)
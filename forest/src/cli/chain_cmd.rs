@@ -3,8 +3,10 @@
 
 use structopt::StructOpt;
 
-use super::{print_rpc_res, print_rpc_res_cids, print_rpc_res_pretty};
+use super::{handle_rpc_err, print_rpc_res, print_rpc_res_cids, print_rpc_res_pretty};
+use blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
 use cid::{json::CidJson, Cid};
+use clock::ChainEpoch;
 use rpc_client::chain_ops::*;
 
 #[derive(Debug, StructOpt)]
@@ -39,6 +41,52 @@ pub enum ChainCommands {
         #[structopt(short, help = "Input a valid CID")]
         cid: String,
     },
+
+    /// Exports a snapshot of the chain, starting at the given epoch, to a CAR file
+    /// written by the daemon
+    #[structopt(about = "Export a chain snapshot to a CAR file on the daemon host")]
+    Export {
+        #[structopt(
+            short,
+            long,
+            help = "Epoch of the tipset to export from (defaults to the chain head)"
+        )]
+        epoch: Option<ChainEpoch>,
+        #[structopt(
+            long,
+            default_value = "2000",
+            help = "Number of recent state roots to include in the snapshot"
+        )]
+        recent_stateroots: ChainEpoch,
+        #[structopt(
+            long,
+            help = "Do not include messages older than the recent state roots"
+        )]
+        skip_old_msgs: bool,
+        #[structopt(short, long, help = "Path of the snapshot file on the daemon host")]
+        output_path: String,
+    },
+
+    /// Deletes all blocks which are not reachable from the chain head within the given
+    /// number of recent state roots
+    #[structopt(about = "Garbage collect unreachable chain data from the daemon's blockstore")]
+    Gc {
+        #[structopt(
+            long,
+            default_value = "2000",
+            help = "Number of recent state roots to keep"
+        )]
+        recent_stateroots: ChainEpoch,
+        #[structopt(long, help = "Delete messages older than the recent state roots")]
+        skip_old_msgs: bool,
+    },
+
+    /// Forcibly sets the chain head to a stored tipset, which must include the checkpoint
+    #[structopt(about = "Set the chain head to a tipset by its block CIDs")]
+    SetHead {
+        #[structopt(help = "CIDs of the blocks of the tipset")]
+        cids: Vec<String>,
+    },
 }
 
 impl ChainCommands {
@@ -62,6 +110,41 @@ impl ChainCommands {
                 let cid: Cid = cid.parse().unwrap();
                 print_rpc_res(chain_read_obj((CidJson(cid),)).await);
             }
+            Self::Export {
+                epoch,
+                recent_stateroots,
+                skip_old_msgs,
+                output_path,
+            } => {
+                let head = chain_head().await.map_err(handle_rpc_err).unwrap();
+                let epoch = epoch.unwrap_or_else(|| head.0.epoch());
+                let params = (
+                    epoch,
+                    *recent_stateroots,
+                    *skip_old_msgs,
+                    output_path.clone(),
+                    TipsetKeysJson(head.0.key().clone()),
+                );
+                match chain_export(params).await {
+                    Ok(path) => println!("Export completed. Snapshot located at {}", path),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
+            Self::Gc {
+                recent_stateroots,
+                skip_old_msgs,
+            } => {
+                print_rpc_res_pretty(
+                    chain_garbage_collect((*recent_stateroots, *skip_old_msgs)).await,
+                );
+            }
+            Self::SetHead { cids } => {
+                let cids: Vec<Cid> = cids.iter().map(|cid| cid.parse().unwrap()).collect();
+                match chain_set_head((TipsetKeysJson(TipsetKeys::new(cids)),)).await {
+                    Ok(()) => println!("OK"),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
         }
     }
 }
@@ -1,8 +1,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use chain::GcConfig;
 use chain_sync::SyncConfig;
 use forest_libp2p::Libp2pConfig;
+use networks::DEFAULT_NETWORK;
 use rpc_client::DEFAULT_PORT;
 use serde::Deserialize;
 use utils::get_home_dir;
@@ -11,6 +13,8 @@ use utils::get_home_dir;
 #[serde(default)]
 pub struct Config {
     pub network: Libp2pConfig,
+    /// Name of a built-in network, or path to the toml file of a custom network.
+    pub chain: String,
     pub data_dir: String,
     pub genesis_file: Option<String>,
     pub enable_rpc: bool,
@@ -24,14 +28,18 @@ pub struct Config {
     /// Will use the cids in the header of the file to index the chain.
     pub skip_load: bool,
     pub sync: SyncConfig,
+    pub gc: GcConfig,
     pub encrypt_keystore: bool,
     pub metrics_port: u16,
+    pub dev: DevConfig,
+    pub mining: MiningConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             network: Libp2pConfig::default(),
+            chain: DEFAULT_NETWORK.to_owned(),
             data_dir: get_home_dir() + "/.forest",
             genesis_file: None,
             enable_rpc: true,
@@ -41,8 +49,50 @@ impl Default for Config {
             snapshot: false,
             skip_load: false,
             sync: SyncConfig::default(),
+            gc: GcConfig::default(),
             encrypt_keystore: true,
             metrics_port: 6116,
+            dev: DevConfig::default(),
+            mining: MiningConfig::default(),
         }
     }
 }
+
+/// Settings of the dev mode, in which the node runs a local chain of its own instead of joining
+/// a network.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DevConfig {
+    /// Run a local dev chain.
+    pub enabled: bool,
+    /// Seconds between two tipsets of the dev chain. When zero, a tipset is sealed whenever a
+    /// message is pushed to the message pool instead.
+    pub block_time: u64,
+    /// Number of pre-funded accounts created at genesis.
+    pub accounts: usize,
+    /// File the keys of the pre-funded accounts are written to, `dev_keys.json` in the data
+    /// directory by default.
+    pub keys_file: Option<String>,
+}
+
+impl Default for DevConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            block_time: 0,
+            accounts: 4,
+            keys_file: None,
+        }
+    }
+}
+
+/// Settings of the block producer, which mines blocks in the daemon. The producer generates its
+/// winning PoSts with the mock prover, so it is only available on local devnets, with a build
+/// of the `insecure_post` feature.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MiningConfig {
+    /// Addresses of the miners to produce blocks for, none by default. The worker keys of the
+    /// miners have to be in the keystore.
+    pub miners: Vec<String>,
+}
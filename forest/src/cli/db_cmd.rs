@@ -0,0 +1,39 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use structopt::StructOpt;
+
+use super::print_rpc_res_pretty;
+use clock::ChainEpoch;
+use rpc_client::chain_ops::*;
+
+#[derive(Debug, StructOpt)]
+pub enum DbCommands {
+    /// Verifies the headers, messages, receipts and state trees of the chain from the head
+    /// back to the given depth, and reports missing or corrupt blocks
+    #[structopt(about = "Check the integrity of the daemon's blockstore")]
+    Check {
+        #[structopt(
+            short,
+            long,
+            default_value = "2000",
+            help = "Number of epochs behind the chain head to check"
+        )]
+        depth: ChainEpoch,
+        #[structopt(
+            long,
+            help = "Refetch damaged headers and messages from peers over chain exchange"
+        )]
+        repair: bool,
+    },
+}
+
+impl DbCommands {
+    pub async fn run(&self) {
+        match self {
+            Self::Check { depth, repair } => {
+                print_rpc_res_pretty(chain_check_blockstore((*depth, *repair)).await);
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use async_std::fs::File as AsyncFile;
 use log::{info, warn};
 use num_bigint::BigInt;
 use std::collections::HashMap;
@@ -10,9 +11,13 @@ use std::str::FromStr;
 use structopt::StructOpt;
 use uuid::Uuid;
 
+use super::{cli_error_and_die, Config};
 use address::Address;
 use fil_types::genesis::{Actor, ActorType, Miner, Template as GenesisTemplate};
+use fil_types::verifier::FullVerifier;
 use fil_types::FILECOIN_PRECISION;
+use genesis::write_genesis_car;
+use networks::NetworkConfig;
 
 const ACCOUNT_START: u64 = 1000;
 #[derive(Debug, StructOpt)]
@@ -39,10 +44,20 @@ pub enum GenesisCommands {
         #[structopt(short, help = "Preseal filepath")]
         preseal_path: String,
     },
+    /// Builds the genesis CAR of the network selected with `--chain` from a template.
+    #[structopt(
+        about = "Build the genesis CAR of the network selected with --chain from a genesis template. Ex.: 'forest --chain localnet.toml genesis build -t genesis.json'"
+    )]
+    Build {
+        #[structopt(short, help = "Genesis template filepath")]
+        template_path: String,
+        #[structopt(short, default_value = "genesis.car", help = "Output CAR filepath")]
+        out: String,
+    },
 }
 
 impl GenesisCommands {
-    pub async fn run(&self) {
+    pub async fn run(&self, config: &Config) {
         match self {
             Self::NewTemplate {
                 network_name,
@@ -72,6 +87,11 @@ impl GenesisCommands {
                     warn!("Cannot add miner(s), error: {}", err);
                 };
             }
+            Self::Build { template_path, out } => {
+                if let Err(err) = build(template_path, out, &config.chain).await {
+                    cli_error_and_die(&format!("Cannot build genesis, error: {}", err), 1);
+                }
+            }
         }
     }
 }
@@ -123,3 +143,23 @@ fn add_miner(genesis_path: String, preseal_path: String) -> Result<(), Box<dyn s
 
     Ok(())
 }
+
+async fn build(
+    template_path: &str,
+    out: &str,
+    chain: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let network = NetworkConfig::from_chain(chain)?;
+    let template: GenesisTemplate = serde_json::from_reader(File::open(template_path)?)?;
+
+    let header =
+        write_genesis_car::<FullVerifier, _>(&template, &network, AsyncFile::create(out).await?)
+            .await?;
+    info!(
+        "Wrote genesis {} of network {} to {}",
+        header.cid(),
+        template.network_name,
+        out
+    );
+    Ok(())
+}
@@ -4,8 +4,11 @@
 mod auth_cmd;
 mod chain_cmd;
 mod config;
+mod db_cmd;
 mod fetch_params_cmd;
 mod genesis_cmd;
+mod mpool_cmd;
+mod msig_cmd;
 mod net_cmd;
 mod state_cmd;
 mod sync_cmd;
@@ -14,8 +17,11 @@ mod wallet_cmd;
 pub(super) use self::auth_cmd::AuthCommands;
 pub(super) use self::chain_cmd::ChainCommands;
 pub use self::config::Config;
+pub(super) use self::db_cmd::DbCommands;
 pub(super) use self::fetch_params_cmd::FetchCommands;
 pub(super) use self::genesis_cmd::GenesisCommands;
+pub(super) use self::mpool_cmd::MpoolCommands;
+pub(super) use self::msig_cmd::MsigCommands;
 pub(super) use self::net_cmd::NetCommands;
 pub(super) use self::state_cmd::StateCommands;
 pub(super) use self::sync_cmd::SyncCommands;
@@ -79,10 +85,17 @@ pub enum Subcommand {
 
     #[structopt(name = "wallet", about = "Manage wallet")]
     Wallet(WalletCommands),
+
+    #[structopt(name = "msig", about = "Interact with a multisig wallet")]
+    Msig(MsigCommands),
+    #[structopt(name = "mpool", about = "Manage the message pool")]
+    Mpool(MpoolCommands),
     #[structopt(name = "sync", about = "Inspect or interact with the chain syncer")]
     Sync(SyncCommands),
     #[structopt(name = "state", about = "Interact with and query filecoin chain state")]
     State(StateCommands),
+    #[structopt(name = "db", about = "Inspect the daemon's blockstore")]
+    Db(DbCommands),
 }
 
 /// CLI options
@@ -90,6 +103,11 @@ pub enum Subcommand {
 pub struct CLIOpts {
     #[structopt(short, long, help = "A toml file containing relevant configurations")]
     pub config: Option<String>,
+    #[structopt(
+        long,
+        help = "Network to join, either mainnet, calibnet, interopnet, devnet or a network toml file (default = mainnet)"
+    )]
+    pub chain: Option<String>,
     #[structopt(short, long, help = "The genesis CAR file")]
     pub genesis: Option<String>,
     #[structopt(short, long, help = "Allow rpc to be active or not (default = true)")]
@@ -135,6 +153,21 @@ pub struct CLIOpts {
     pub target_peer_count: Option<u32>,
     #[structopt(long, help = "Encrypt the keystore (default = true)")]
     pub encrypt_keystore: Option<bool>,
+    #[structopt(
+        long,
+        help = "Run a local dev chain with pre-funded accounts instead of joining a network"
+    )]
+    pub dev: bool,
+    #[structopt(
+        long,
+        help = "Seconds between the tipsets of the dev chain, or 0 to seal a tipset for each pushed message (default = 0)"
+    )]
+    pub dev_block_time: Option<u64>,
+    #[structopt(
+        long,
+        help = "File the keys of the dev accounts are written to (default = <data dir>/dev_keys.json)"
+    )]
+    pub dev_keys: Option<String>,
 }
 
 impl CLIOpts {
@@ -158,6 +191,9 @@ impl CLIOpts {
                 }
             }
         };
+        if let Some(chain) = &self.chain {
+            cfg.chain = chain.to_owned();
+        }
         if let Some(genesis_file) = &self.genesis {
             cfg.genesis_file = Some(genesis_file.to_owned());
         }
@@ -165,7 +201,7 @@ impl CLIOpts {
             cfg.enable_rpc = true;
             cfg.rpc_port = self.port.to_owned().unwrap_or(cfg.rpc_port);
 
-            if cfg.rpc_token.is_some() {
+            if self.token.is_some() {
                 cfg.rpc_token = self.token.to_owned();
             }
         } else {
@@ -207,6 +243,15 @@ impl CLIOpts {
         if let Some(encrypt_keystore) = self.encrypt_keystore {
             cfg.encrypt_keystore = encrypt_keystore;
         }
+        if self.dev {
+            cfg.dev.enabled = true;
+        }
+        if let Some(block_time) = self.dev_block_time {
+            cfg.dev.block_time = block_time;
+        }
+        if let Some(keys_file) = &self.dev_keys {
+            cfg.dev.keys_file = Some(keys_file.to_owned());
+        }
 
         Ok(cfg)
     }
@@ -0,0 +1,247 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use actor::actorv3::ActorState;
+use address::{json::AddressJson, Address};
+use blocks::{tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson};
+use cid::json::vec::CidJsonVec;
+use message::{signed_message::json::SignedMessageJson, Message, SignedMessage};
+use rpc_client::{chain_head, mpool_ops::*, state_get_actor};
+use structopt::StructOpt;
+
+use crate::cli::cli_error_and_die;
+
+use super::{handle_rpc_err, print_rpc_res_pretty};
+
+#[derive(Debug, StructOpt)]
+pub enum MpoolCommands {
+    /// Prints the pending messages of the message pool
+    #[structopt(about = "Print pending messages")]
+    Pending {
+        #[structopt(long, about = "Only print the messages sent by this address")]
+        from: Option<String>,
+    },
+    /// Gets or sets the message pool config
+    #[structopt(about = "Manage the message pool config")]
+    Config(MpoolConfigCommands),
+    /// Removes the pending messages from the message pool
+    #[structopt(about = "Clear all pending messages from the message pool")]
+    Clear {
+        #[structopt(long, about = "Also clear the messages pushed by this node")]
+        local: bool,
+        #[structopt(long, about = "Must be given to clear the message pool")]
+        really_do_it: bool,
+    },
+    /// Prints the number of pending messages per sender, split by whether their sequence is
+    /// already used, ready to be included or ahead of the next sequence of the sender
+    #[structopt(about = "Print message pool stats")]
+    Stat,
+    /// Checks the pending messages of an address, printing the failed checks
+    #[structopt(about = "Check the pending messages of an address")]
+    Check {
+        #[structopt(about = "Address of the sender")]
+        address: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum MpoolConfigCommands {
+    /// Prints the message pool config
+    #[structopt(about = "Print the message pool config")]
+    Get,
+    /// Updates the given fields of the message pool config
+    #[structopt(about = "Update the message pool config")]
+    Set {
+        #[structopt(long, about = "Addresses whose messages are selected first")]
+        priority_addrs: Option<Vec<String>>,
+        #[structopt(long, about = "Number of messages above which the pool is pruned")]
+        size_limit_high: Option<i64>,
+        #[structopt(long, about = "Number of messages the pool is pruned down to")]
+        size_limit_low: Option<i64>,
+        #[structopt(long, about = "Minimum ratio to replace a message by fee")]
+        replace_by_fee_ratio: Option<f64>,
+        #[structopt(long, about = "Minimum time between two prunes, in seconds")]
+        prune_cooldown: Option<u64>,
+        #[structopt(long, about = "Factor applied to estimated gas limits")]
+        gas_limit_overestimation: Option<f64>,
+        #[structopt(
+            long,
+            about = "Maximum number of pending messages of a sender whose messages are pushed by this node"
+        )]
+        max_local_sender_pending: Option<u64>,
+        #[structopt(long, about = "Maximum number of pending messages of any other sender")]
+        max_remote_sender_pending: Option<u64>,
+    },
+}
+
+impl MpoolCommands {
+    pub async fn run(&self) {
+        match self {
+            Self::Pending { from } => {
+                let from = from.as_ref().map(|from| {
+                    Address::from_str(from)
+                        .unwrap_or_else(|_| panic!("Failed to create address from {}", from))
+                });
+                let messages = pending_messages().await;
+                let messages: Vec<SignedMessageJson> = messages
+                    .into_iter()
+                    .filter(|m| from.map_or(true, |from| m.from() == &from))
+                    .map(SignedMessageJson)
+                    .collect();
+                print_rpc_res_pretty(Ok(messages));
+            }
+            Self::Config(cmd) => cmd.run().await,
+            Self::Clear {
+                local,
+                really_do_it,
+            } => {
+                if !really_do_it {
+                    cli_error_and_die("--really-do-it must be given to clear the mpool", 1);
+                    return;
+                }
+                match mpool_clear((*local,)).await {
+                    Ok(()) => println!("OK"),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
+            Self::Stat => {
+                let TipsetJson(head) = chain_head().await.map_err(handle_rpc_err).unwrap();
+                let tsk = TipsetKeysJson(head.key().clone());
+
+                let mut by_sender: BTreeMap<String, (Address, Vec<u64>)> = BTreeMap::new();
+                for m in pending_messages().await {
+                    by_sender
+                        .entry(m.from().to_string())
+                        .or_insert_with(|| (*m.from(), Vec::new()))
+                        .1
+                        .push(m.sequence());
+                }
+
+                let (mut total_past, mut total_cur, mut total_future) = (0, 0, 0);
+                for (addr_str, (addr, mut sequences)) in by_sender {
+                    let actor = state_get_actor((AddressJson(addr), tsk.clone()))
+                        .await
+                        .map_err(handle_rpc_err)
+                        .unwrap();
+                    let mut next = match actor {
+                        Some(actor) => ActorState::from(actor).sequence,
+                        None => 0,
+                    };
+
+                    sequences.sort_unstable();
+                    let (mut past, mut cur, mut future) = (0, 0, 0);
+                    for sequence in sequences {
+                        if sequence < next {
+                            past += 1;
+                        } else if sequence == next {
+                            cur += 1;
+                            next += 1;
+                        } else {
+                            future += 1;
+                        }
+                    }
+                    println!(
+                        "{}: Nonce past: {}, cur: {}, future: {}",
+                        addr_str, past, cur, future
+                    );
+                    total_past += past;
+                    total_cur += cur;
+                    total_future += future;
+                }
+                println!("-----");
+                println!(
+                    "total: Nonce past: {}, cur: {}, future: {}",
+                    total_past, total_cur, total_future
+                );
+            }
+            Self::Check { address } => {
+                let address = Address::from_str(address)
+                    .unwrap_or_else(|_| panic!("Failed to create address from {}", address));
+                let result = mpool_check_pending_messages((AddressJson(address),))
+                    .await
+                    .map_err(handle_rpc_err)
+                    .unwrap();
+                for checks in result {
+                    let cid = match checks.first() {
+                        Some(check) => check.cid,
+                        None => continue,
+                    };
+                    let failed: Vec<_> = checks.iter().filter(|check| !check.ok).collect();
+                    if failed.is_empty() {
+                        println!("{}: OK", cid);
+                    }
+                    for check in failed {
+                        println!("{}: check {} failed: {}", cid, check.code, check.err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MpoolConfigCommands {
+    pub async fn run(&self) {
+        match self {
+            Self::Get => print_rpc_res_pretty(mpool_get_config(()).await),
+            Self::Set {
+                priority_addrs,
+                size_limit_high,
+                size_limit_low,
+                replace_by_fee_ratio,
+                prune_cooldown,
+                gas_limit_overestimation,
+                max_local_sender_pending,
+                max_remote_sender_pending,
+            } => {
+                let mut cfg = mpool_get_config(()).await.map_err(handle_rpc_err).unwrap();
+                if let Some(addrs) = priority_addrs {
+                    cfg.priority_addrs = addrs
+                        .iter()
+                        .map(|addr| {
+                            Address::from_str(addr).unwrap_or_else(|_| {
+                                panic!("Failed to create address from {}", addr)
+                            })
+                        })
+                        .collect();
+                }
+                if let Some(size_limit_high) = size_limit_high {
+                    cfg.size_limit_high = *size_limit_high;
+                }
+                if let Some(size_limit_low) = size_limit_low {
+                    cfg.size_limit_low = *size_limit_low;
+                }
+                if let Some(ratio) = replace_by_fee_ratio {
+                    cfg.replace_by_fee_ratio = *ratio;
+                }
+                if let Some(secs) = prune_cooldown {
+                    cfg.prune_cooldown = secs * 1_000_000_000;
+                }
+                if let Some(overestimation) = gas_limit_overestimation {
+                    cfg.gas_limit_overestimation = *overestimation;
+                }
+                if let Some(limit) = max_local_sender_pending {
+                    cfg.max_local_sender_pending = *limit;
+                }
+                if let Some(limit) = max_remote_sender_pending {
+                    cfg.max_remote_sender_pending = *limit;
+                }
+                match mpool_set_config((cfg,)).await {
+                    Ok(()) => println!("OK"),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Returns the pending messages of the message pool at the current head.
+async fn pending_messages() -> Vec<SignedMessage> {
+    let TipsetJson(head) = chain_head().await.map_err(handle_rpc_err).unwrap();
+    mpool_pending((CidJsonVec(head.cids().to_vec()),))
+        .await
+        .map_err(handle_rpc_err)
+        .unwrap()
+}
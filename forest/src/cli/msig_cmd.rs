@@ -0,0 +1,374 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::str::FromStr;
+use structopt::StructOpt;
+
+use super::{cli_error_and_die, handle_rpc_err, print_rpc_res, print_rpc_res_pretty};
+use address::{json::AddressJson, Address};
+use blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
+use cid::json::CidJson;
+use clock::ChainEpoch;
+use jsonrpc_v2::Error as JsonRpcError;
+use rpc_client::{chain_ops::*, msig_ops::*, wallet_ops::*};
+
+#[derive(Debug, StructOpt)]
+pub enum MsigCommands {
+    /// Creates a multisig wallet through the init actor
+    #[structopt(about = "Create a new multisig wallet")]
+    Create {
+        #[structopt(required = true, help = "Addresses of the signers")]
+        signers: Vec<String>,
+        #[structopt(
+            long,
+            help = "Number of approvals required (defaults to the number of signers)"
+        )]
+        required: Option<u64>,
+        #[structopt(long, default_value = "0", help = "Initial balance, in attoFIL")]
+        value: String,
+        #[structopt(
+            long,
+            default_value = "0",
+            help = "Number of epochs over which the initial balance vests"
+        )]
+        duration: ChainEpoch,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    /// Proposes a transaction which is executed once enough signers approve it
+    #[structopt(about = "Propose a multisig transaction")]
+    Propose {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Recipient of the transaction")]
+        to: String,
+        #[structopt(help = "Amount to send, in attoFIL")]
+        value: String,
+        #[structopt(long, default_value = "0", help = "Method to call on the recipient")]
+        method: u64,
+        #[structopt(long, default_value = "", help = "Base64 encoded method parameters")]
+        params: String,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    /// Approves a pending transaction. If the proposal details are given, the approval
+    /// only applies to a transaction matching them
+    #[structopt(about = "Approve a pending multisig transaction")]
+    Approve {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Id of the pending transaction")]
+        txn_id: i64,
+        #[structopt(long, help = "Proposer of the transaction")]
+        proposer: Option<String>,
+        #[structopt(long, help = "Recipient of the transaction")]
+        to: Option<String>,
+        #[structopt(long, help = "Amount of the transaction, in attoFIL")]
+        value: Option<String>,
+        #[structopt(long, default_value = "0", help = "Method of the transaction")]
+        method: u64,
+        #[structopt(long, default_value = "", help = "Base64 encoded method parameters")]
+        params: String,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    /// Cancels a pending transaction, only the proposer can cancel it
+    #[structopt(about = "Cancel a pending multisig transaction")]
+    Cancel {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Id of the pending transaction")]
+        txn_id: i64,
+        #[structopt(help = "Recipient of the transaction")]
+        to: String,
+        #[structopt(help = "Amount of the transaction, in attoFIL")]
+        value: String,
+        #[structopt(long, default_value = "0", help = "Method of the transaction")]
+        method: u64,
+        #[structopt(long, default_value = "", help = "Base64 encoded method parameters")]
+        params: String,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    #[structopt(about = "Propose adding a signer to a multisig")]
+    AddPropose {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Signer to add")]
+        signer: String,
+        #[structopt(long, help = "Increase the number of required approvals")]
+        increase_threshold: bool,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    #[structopt(about = "Propose swapping a signer of a multisig")]
+    SwapPropose {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Signer to remove")]
+        old_signer: String,
+        #[structopt(help = "Signer to add")]
+        new_signer: String,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    #[structopt(about = "Propose removing a signer from a multisig")]
+    ProposeRemove {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(help = "Signer to remove")]
+        signer: String,
+        #[structopt(long, help = "Decrease the number of required approvals")]
+        decrease_threshold: bool,
+        #[structopt(long, help = "Account to send from (defaults to the wallet default)")]
+        from: Option<String>,
+    },
+
+    /// Prints the spendable balance, vesting schedule and pending transactions at the
+    /// chain head
+    #[structopt(about = "Inspect a multisig wallet")]
+    Inspect {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+    },
+
+    #[structopt(about = "Print the amount vested by a multisig between two epochs")]
+    Vested {
+        #[structopt(help = "Address of the multisig")]
+        multisig: String,
+        #[structopt(long, default_value = "0", help = "Start epoch")]
+        start_epoch: ChainEpoch,
+        #[structopt(long, help = "End epoch (defaults to the chain head)")]
+        end_epoch: Option<ChainEpoch>,
+    },
+}
+
+impl MsigCommands {
+    pub async fn run(&self) {
+        match self {
+            Self::Create {
+                signers,
+                required,
+                value,
+                duration,
+                from,
+            } => {
+                let signers: Vec<AddressJson> = signers
+                    .iter()
+                    .map(|s| AddressJson(parse_address(s)))
+                    .collect();
+                let required = required.unwrap_or(signers.len() as u64);
+                let from = sender(from).await;
+                let params = (
+                    required,
+                    signers,
+                    *duration,
+                    value.clone(),
+                    AddressJson(from),
+                    "0".to_owned(),
+                );
+                print_msg_cid(msig_create(params).await);
+            }
+            Self::Propose {
+                multisig,
+                to,
+                value,
+                method,
+                params,
+                from,
+            } => {
+                let from = sender(from).await;
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    AddressJson(parse_address(to)),
+                    value.clone(),
+                    AddressJson(from),
+                    *method,
+                    params.clone(),
+                );
+                print_msg_cid(msig_propose(params).await);
+            }
+            Self::Approve {
+                multisig,
+                txn_id,
+                proposer,
+                to,
+                value,
+                method,
+                params,
+                from,
+            } => {
+                let multisig = AddressJson(parse_address(multisig));
+                let from = AddressJson(sender(from).await);
+                let res = match (proposer, to, value) {
+                    (None, None, None) => msig_approve((multisig, *txn_id, from)).await,
+                    (Some(proposer), Some(to), Some(value)) => {
+                        let params = (
+                            multisig,
+                            *txn_id,
+                            AddressJson(parse_address(proposer)),
+                            AddressJson(parse_address(to)),
+                            value.clone(),
+                            from,
+                            *method,
+                            params.clone(),
+                        );
+                        msig_approve_txn_hash(params).await
+                    }
+                    _ => {
+                        return cli_error_and_die(
+                            "Proposer, recipient and value must be given together",
+                            1,
+                        )
+                    }
+                };
+                print_msg_cid(res);
+            }
+            Self::Cancel {
+                multisig,
+                txn_id,
+                to,
+                value,
+                method,
+                params,
+                from,
+            } => {
+                let from = sender(from).await;
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    *txn_id,
+                    AddressJson(parse_address(to)),
+                    value.clone(),
+                    AddressJson(from),
+                    *method,
+                    params.clone(),
+                );
+                print_msg_cid(msig_cancel(params).await);
+            }
+            Self::AddPropose {
+                multisig,
+                signer,
+                increase_threshold,
+                from,
+            } => {
+                let from = sender(from).await;
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    AddressJson(from),
+                    AddressJson(parse_address(signer)),
+                    *increase_threshold,
+                );
+                print_msg_cid(msig_add_propose(params).await);
+            }
+            Self::SwapPropose {
+                multisig,
+                old_signer,
+                new_signer,
+                from,
+            } => {
+                let from = sender(from).await;
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    AddressJson(from),
+                    AddressJson(parse_address(old_signer)),
+                    AddressJson(parse_address(new_signer)),
+                );
+                print_msg_cid(msig_swap_propose(params).await);
+            }
+            Self::ProposeRemove {
+                multisig,
+                signer,
+                decrease_threshold,
+                from,
+            } => {
+                let from = sender(from).await;
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    AddressJson(from),
+                    AddressJson(parse_address(signer)),
+                    *decrease_threshold,
+                );
+                print_msg_cid(msig_remove_signer(params).await);
+            }
+            Self::Inspect { multisig } => {
+                let multisig = parse_address(multisig);
+                let balance = msig_get_available_balance((AddressJson(multisig), head_key()))
+                    .await
+                    .map_err(handle_rpc_err)
+                    .unwrap();
+                println!("Available balance: {}", balance);
+                println!("Vesting schedule:");
+                print_rpc_res_pretty(
+                    msig_get_vesting_schedule((AddressJson(multisig), head_key())).await,
+                );
+                println!("Pending transactions:");
+                print_rpc_res_pretty(msig_get_pending((AddressJson(multisig), head_key())).await);
+            }
+            Self::Vested {
+                multisig,
+                start_epoch,
+                end_epoch,
+            } => {
+                let head = chain_head().await.map_err(handle_rpc_err).unwrap();
+                let start = chain_get_tipset_by_height((*start_epoch, head.0.key().clone()))
+                    .await
+                    .map_err(handle_rpc_err)
+                    .unwrap();
+                let end = match end_epoch {
+                    Some(epoch) => chain_get_tipset_by_height((*epoch, head.0.key().clone()))
+                        .await
+                        .map_err(handle_rpc_err)
+                        .unwrap(),
+                    None => head,
+                };
+                let params = (
+                    AddressJson(parse_address(multisig)),
+                    TipsetKeysJson(start.0.key().clone()),
+                    TipsetKeysJson(end.0.key().clone()),
+                );
+                print_rpc_res(msig_get_vested(params).await);
+            }
+        }
+    }
+}
+
+/// Parses an address, exits with an error if it is invalid.
+fn parse_address(s: &str) -> Address {
+    Address::from_str(s).unwrap_or_else(|_| {
+        cli_error_and_die(&format!("Invalid address {}", s), 1);
+        unreachable!()
+    })
+}
+
+/// Returns the given sender, or the default wallet address if none was given.
+async fn sender(from: &Option<String>) -> Address {
+    match from {
+        Some(from) => parse_address(from),
+        None => {
+            let default = wallet_default_address()
+                .await
+                .map_err(handle_rpc_err)
+                .unwrap();
+            parse_address(&default)
+        }
+    }
+}
+
+/// Empty tipset keys resolve to the chain head.
+fn head_key() -> TipsetKeysJson {
+    TipsetKeysJson(TipsetKeys::new(Vec::new()))
+}
+
+/// Prints the cid of a pushed message from a HTTP JSON-RPC response result
+fn print_msg_cid(res: Result<CidJson, JsonRpcError>) {
+    match res {
+        Ok(cid) => println!("Message sent: {}", cid.0),
+        Err(e) => handle_rpc_err(e),
+    }
+}
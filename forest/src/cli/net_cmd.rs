@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use forest_libp2p::{Multiaddr, Protocol};
-use rpc_api::data_types::AddrInfo;
+use rpc_api::data_types::{AddrInfo, NetBlockList};
 use std::collections::HashSet;
 use structopt::StructOpt;
 
 use crate::cli::cli_error_and_die;
 
-use super::{handle_rpc_err, print_stdout};
+use super::{handle_rpc_err, print_rpc_res_pretty, print_stdout};
 use rpc_client::net_ops::*;
 
 #[derive(Debug, StructOpt)]
@@ -31,6 +31,52 @@ pub enum NetCommands {
         #[structopt(about = "Peer ID to disconnect from")]
         id: String,
     },
+    /// Prints the addresses, request stats and ban state of a peer
+    #[structopt(about = "Print what the node knows about a peer")]
+    PeerInfo {
+        #[structopt(about = "Peer ID")]
+        id: String,
+    },
+    /// Manages the peers blocked by the node
+    #[structopt(about = "Manage the peers blocked by the node")]
+    Block(NetBlockCommands),
+    /// Protects peers from being disconnected by the connection manager
+    #[structopt(about = "Protect peers from the connection manager")]
+    Protect {
+        #[structopt(about = "Peer IDs to protect")]
+        ids: Vec<String>,
+    },
+    /// Removes the protection of peers from the connection manager
+    #[structopt(about = "Unprotect peers from the connection manager")]
+    Unprotect {
+        #[structopt(about = "Peer IDs to unprotect")]
+        ids: Vec<String>,
+    },
+    /// Lists the peers protected from the connection manager
+    #[structopt(about = "List protected peers")]
+    ListProtected,
+    /// Prints the gossipsub scores of the known peers
+    #[structopt(about = "Print gossipsub peer scores")]
+    Scores,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum NetBlockCommands {
+    /// Blocks peers, disconnecting from them and refusing their connections
+    #[structopt(about = "Block peers by their peer IDs")]
+    Add {
+        #[structopt(about = "Peer IDs to block")]
+        ids: Vec<String>,
+    },
+    /// Removes peers from the block list
+    #[structopt(about = "Unblock peers by their peer IDs")]
+    Remove {
+        #[structopt(about = "Peer IDs to unblock")]
+        ids: Vec<String>,
+    },
+    /// Lists the blocked peers
+    #[structopt(about = "List blocked peers")]
+    List,
 }
 
 impl NetCommands {
@@ -115,10 +161,59 @@ impl NetCommands {
             }
             Self::Disconnect { id } => match net_disconnect((id.to_owned(),)).await {
                 Ok(_) => {
-                    todo!();
+                    println!("disconnect {}: success", id);
                 }
                 Err(e) => handle_rpc_err(e),
             },
+            Self::PeerInfo { id } => print_rpc_res_pretty(net_peer_info((id.to_owned(),)).await),
+            Self::Block(cmd) => cmd.run().await,
+            Self::Protect { ids } => match net_protect_add((ids.clone(),)).await {
+                Ok(_) => println!("protected {} peers", ids.len()),
+                Err(e) => handle_rpc_err(e),
+            },
+            Self::Unprotect { ids } => match net_protect_remove((ids.clone(),)).await {
+                Ok(_) => println!("unprotected {} peers", ids.len()),
+                Err(e) => handle_rpc_err(e),
+            },
+            Self::ListProtected => match net_protect_list(()).await {
+                Ok(peers) => print_stdout(peers.join("\n")),
+                Err(e) => handle_rpc_err(e),
+            },
+            Self::Scores => match net_pubsub_scores(()).await {
+                Ok(scores) => {
+                    let output: Vec<String> = scores
+                        .iter()
+                        .map(|s| format!("{}, {}", s.id, s.score))
+                        .collect();
+                    print_stdout(output.join("\n"));
+                }
+                Err(e) => handle_rpc_err(e),
+            },
+        }
+    }
+}
+
+impl NetBlockCommands {
+    pub async fn run(&self) {
+        match self {
+            Self::Add { ids } => {
+                let block_list = NetBlockList { peers: ids.clone() };
+                match net_block_add((block_list,)).await {
+                    Ok(_) => println!("blocked {} peers", ids.len()),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
+            Self::Remove { ids } => {
+                let block_list = NetBlockList { peers: ids.clone() };
+                match net_block_remove((block_list,)).await {
+                    Ok(_) => println!("unblocked {} peers", ids.len()),
+                    Err(e) => handle_rpc_err(e),
+                }
+            }
+            Self::List => match net_block_list(()).await {
+                Ok(block_list) => print_stdout(block_list.peers.join("\n")),
+                Err(e) => handle_rpc_err(e),
+            },
         }
     }
 }
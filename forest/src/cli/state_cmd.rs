@@ -6,15 +6,16 @@ use std::str::FromStr;
 use actor::{actorv3::ActorState, is_miner_actor};
 use address::{json::AddressJson, Address};
 use blocks::{tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson};
+use cid::{json::CidJson, Cid};
 use rpc_client::{
-    chain_head, state_account_key, state_get_actor, state_list_actors, state_lookup,
-    state_miner_power,
+    chain_head, state_account_key, state_changed_actors, state_get_actor, state_list_actors,
+    state_lookup, state_miner_power,
 };
 use structopt::StructOpt;
 
 use crate::cli::{balance_to_fil, cli_error_and_die, to_size_string};
 
-use super::handle_rpc_err;
+use super::{handle_rpc_err, print_rpc_res_pretty};
 
 #[derive(Debug, StructOpt)]
 pub enum StateCommands {
@@ -37,6 +38,13 @@ pub enum StateCommands {
         #[structopt(about = "address")]
         address: String,
     },
+    #[structopt(about = "Print the actors changed between two state roots, as JSON")]
+    Diff {
+        #[structopt(about = "State root to diff from")]
+        old_root: String,
+        #[structopt(about = "State root to diff to")]
+        new_root: String,
+    },
 }
 
 impl StateCommands {
@@ -169,6 +177,19 @@ impl StateCommands {
                     };
                 }
             }
+            Self::Diff { old_root, new_root } => {
+                let parse_root = |root: &str| -> Cid {
+                    root.parse().unwrap_or_else(|e| {
+                        cli_error_and_die(&format!("Invalid state root {}: {}", root, e), 1);
+                        unreachable!()
+                    })
+                };
+                let old_root = parse_root(&old_root);
+                let new_root = parse_root(&new_root);
+                print_rpc_res_pretty(
+                    state_changed_actors((CidJson(old_root), CidJson(new_root))).await,
+                );
+            }
         }
     }
 }
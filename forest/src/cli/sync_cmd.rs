@@ -6,13 +6,14 @@ use std::{
     time::Duration,
 };
 
+use blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
 use chain_sync::SyncStage;
 use cid::{json::CidJson, Cid};
 use rpc_client::*;
 use structopt::StructOpt;
 use ticker::Ticker;
 
-use crate::cli::{format_vec_pretty, handle_rpc_err};
+use crate::cli::{cli_error_and_die, format_vec_pretty, handle_rpc_err};
 
 #[derive(Debug, StructOpt)]
 pub enum SyncCommands {
@@ -33,6 +34,23 @@ pub enum SyncCommands {
         #[structopt(short, about = "the block CID to mark as a bad block")]
         cid: String,
     },
+    #[structopt(about = "Unmark a given block as bad, or all the bad blocks")]
+    UnmarkBad {
+        #[structopt(short, about = "the block CID to unmark as a bad block")]
+        cid: Option<String>,
+        #[structopt(long, about = "unmark all the bad blocks")]
+        all: bool,
+    },
+    #[structopt(about = "Checkpoint a tipset, refusing to sync forks which do not include it")]
+    Checkpoint {
+        #[structopt(about = "the CIDs of the blocks of the tipset")]
+        cids: Vec<String>,
+    },
+    #[structopt(about = "Validate a tipset, without marking its invalid blocks as bad")]
+    Validate {
+        #[structopt(about = "the CIDs of the blocks of the tipset")]
+        cids: Vec<String>,
+    },
 }
 
 #[allow(unused_must_use)]
@@ -130,7 +148,7 @@ impl SyncCommands {
                 if response.is_empty() {
                     println!("Block \"{}\" is not marked as a bad block", cid);
                 } else {
-                    println!("response");
+                    println!("{}", response);
                 }
             }
             Self::MarkBad { cid } => {
@@ -140,6 +158,37 @@ impl SyncCommands {
                     Err(error) => handle_rpc_err(error),
                 }
             }
+            Self::UnmarkBad { cid, all } => {
+                let response = match (cid, all) {
+                    (None, true) => sync_unmark_all_bad(()).await,
+                    (Some(cid), false) => {
+                        let cid: Cid = cid.parse().unwrap();
+                        sync_unmark_bad((CidJson(cid),)).await
+                    }
+                    _ => {
+                        cli_error_and_die("Either a block CID or --all must be given", 1);
+                        return;
+                    }
+                };
+                match response {
+                    Ok(()) => println!("OK"),
+                    Err(error) => handle_rpc_err(error),
+                }
+            }
+            Self::Checkpoint { cids } => {
+                let cids: Vec<Cid> = cids.iter().map(|cid| cid.parse().unwrap()).collect();
+                match sync_checkpoint((TipsetKeysJson(TipsetKeys::new(cids)),)).await {
+                    Ok(()) => println!("OK"),
+                    Err(error) => handle_rpc_err(error),
+                }
+            }
+            Self::Validate { cids } => {
+                let cids: Vec<Cid> = cids.iter().map(|cid| cid.parse().unwrap()).collect();
+                match sync_validate_tipset((TipsetKeysJson(TipsetKeys::new(cids)),)).await {
+                    Ok(_) => println!("OK"),
+                    Err(error) => handle_rpc_err(error),
+                }
+            }
         }
     }
 }
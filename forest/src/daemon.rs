@@ -1,15 +1,20 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::cli::{block_until_sigint, Config};
+use super::cli::{block_until_sigint, cli_error_and_die, Config};
 use auth::{create_token, generate_priv_key, ADMIN, JWT_IDENTIFIER};
+use beacon::IGNORE_DRAND_VAR;
 use chain::ChainStore;
 use chain_sync::ChainMuxer;
+use clock::ChainEpoch;
 use fil_types::verifier::FullVerifier;
 use forest_libp2p::{get_keypair, Libp2pService};
 use genesis::{import_chain, initialize_genesis};
 use message_pool::{MessagePool, MpoolConfig, MpoolRpcProvider};
+use mining::{BlockProducer, MockProver};
+use networks::{NetworkConfig, NetworkPolicy, BLOCK_DELAY_SECS};
 use paramfetch::{get_params_default, SectorSizeOpt};
+use paychmgr::Manager as PaychManager;
 use rpc::start_rpc;
 use rpc_api::data_types::RPCState;
 use state_manager::StateManager;
@@ -19,30 +24,83 @@ use wallet::{KeyStore, KeyStoreConfig};
 
 use async_std::{channel::bounded, sync::RwLock, task};
 use libp2p::identity::{ed25519, Keypair};
+use libp2p::Multiaddr;
 use log::{debug, info, trace, warn};
 use rpassword::read_password;
 
 use std::io::prelude::*;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds after the start of an epoch at which the beacon entries of the next one are
+/// prefetched, leaving time for the drand round published at the start of the epoch to reach
+/// the drand servers.
+const BEACON_PREFETCH_DELAY_SECS: u64 = 3;
+/// Seconds between the attempts to prefetch beacon entries which aren't published yet.
+const BEACON_PREFETCH_RETRY_SECS: u64 = 2;
 
 /// Starts daemon process
-pub(super) async fn start(config: Config) {
-    // Set the Address network prefix
-    #[cfg(feature = "testnet")]
-    address::NETWORK_DEFAULT
-        .set(address::Network::Testnet)
-        .unwrap();
-    #[cfg(not(feature = "testnet"))]
-    address::NETWORK_DEFAULT
-        .set(address::Network::Mainnet)
-        .unwrap();
+pub(super) async fn start(mut config: Config) {
+    let network = Arc::new(
+        NetworkConfig::from_chain(&config.chain).unwrap_or_else(|e| {
+            cli_error_and_die(&format!("Failed to load the network config: {}", e), 1);
+            unreachable!()
+        }),
+    );
+
+    // Set the address prefix and the actor policy of the network
+    if let Err(e) = network.set_process_defaults() {
+        cli_error_and_die(
+            &format!("Failed to set up the {} network: {}", network.name, e),
+            1,
+        );
+    }
+
+    let miners = config
+        .mining
+        .miners
+        .iter()
+        .map(|miner| miner.parse::<address::Address>())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            cli_error_and_die(&format!("Invalid miner address in the config: {}", e), 1);
+            unreachable!()
+        });
+    // Block production is for local devnets only: without a prover reading the sectors, the
+    // blocks would be refused by the validation of the block producer, as by any other node
+    if !miners.is_empty() {
+        if !cfg!(feature = "insecure_post") {
+            cli_error_and_die(
+                "Producing blocks needs a build with the insecure_post feature, only the mock winning PoSt prover is available",
+                1,
+            );
+        }
+        // Only devnets run the devnet policy, the networks of real sectors don't
+        if network.policy != NetworkPolicy::DEVNET {
+            cli_error_and_die(
+                &format!(
+                    "Producing blocks is only supported on local devnets, the {} network doesn't have the devnet policy",
+                    network.name
+                ),
+                1,
+            );
+        }
+    }
 
     info!(
-        "Starting Forest daemon, version {}",
+        "Starting Forest daemon on {}, version {}",
+        network.name,
         option_env!("FOREST_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"))
     );
 
+    for peer in &network.bootstrap_peers {
+        match peer.parse::<Multiaddr>() {
+            Ok(addr) => config.network.bootstrap_peers.push(addr),
+            Err(e) => warn!("Invalid bootstrap peer {}: {}", peer, e),
+        }
+    }
+
     let path: PathBuf = [&config.data_dir, "libp2p"].iter().collect();
     let net_keypair = get_keypair(&path.join("keypair")).unwrap_or_else(|| {
         // Keypair not found, generate and save generated keypair
@@ -126,7 +184,10 @@ pub(super) async fn start(config: Config) {
 
     // Initialize StateManager
     let chain_store = Arc::new(ChainStore::new(Arc::clone(&db)));
-    let state_manager = Arc::new(StateManager::new(Arc::clone(&chain_store)));
+    let state_manager = Arc::new(StateManager::new_with_config(
+        Arc::clone(&chain_store),
+        Arc::clone(&network),
+    ));
 
     let publisher = chain_store.publisher();
 
@@ -144,6 +205,10 @@ pub(super) async fn start(config: Config) {
             .unwrap();
     }
 
+    // Index the messages of the chain which were not indexed on head changes, at startup and
+    // whenever the head jumps past the tipsets indexed on head changes
+    let backfill_task = task::spawn(Arc::clone(&chain_store).run_msg_indexer());
+
     // Fetch and ensure verification keys are downloaded
     get_params_default(SectorSizeOpt::Keys, false)
         .await
@@ -172,12 +237,58 @@ pub(super) async fn start(config: Config) {
         .unwrap(),
     );
 
+    // Initialize payment channel manager
+    let paych_manager = Arc::new(PaychManager::new(Arc::clone(&state_manager)));
+
     let beacon = Arc::new(
-        networks::beacon_schedule_default(genesis.min_timestamp())
+        network
+            .beacon_schedule(genesis.min_timestamp(), Arc::clone(&db))
             .await
             .unwrap(),
     );
 
+    // Fetch the beacon entries of the next epoch ahead of time, so that validating its blocks
+    // doesn't wait on the drand servers. The newest round of the next epoch is published around
+    // the start of the current one, so the fetch starts a few seconds later and is retried until
+    // the round is out.
+    let prefetch_task = if std::env::var(IGNORE_DRAND_VAR) != Ok("1".to_owned()) {
+        let beacon = Arc::clone(&beacon);
+        let genesis_ts = genesis.min_timestamp();
+        let since_genesis = move || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .saturating_sub(genesis_ts)
+        };
+        Some(task::spawn(async move {
+            loop {
+                let epoch = since_genesis() / BLOCK_DELAY_SECS;
+                let next_epoch = (epoch + 1) as ChainEpoch;
+                while let Err(e) = beacon.prefetch(next_epoch).await {
+                    if since_genesis() + BEACON_PREFETCH_RETRY_SECS
+                        >= (epoch + 1) * BLOCK_DELAY_SECS
+                    {
+                        warn!(
+                            "Failed to prefetch the beacon entries of epoch {}: {}",
+                            next_epoch, e
+                        );
+                        break;
+                    }
+                    debug!(
+                        "Retrying to prefetch the beacon entries of epoch {}: {}",
+                        next_epoch, e
+                    );
+                    task::sleep(Duration::from_secs(BEACON_PREFETCH_RETRY_SECS)).await;
+                }
+                let wake_at = (epoch + 1) * BLOCK_DELAY_SECS + BEACON_PREFETCH_DELAY_SECS;
+                task::sleep(Duration::from_secs(wake_at.saturating_sub(since_genesis()))).await;
+            }
+        }))
+    } else {
+        None
+    };
+
     // Initialize ChainMuxer
     let (tipset_sink, tipset_stream) = bounded(20);
     let chain_muxer_tipset_sink = tipset_sink.clone();
@@ -197,6 +308,54 @@ pub(super) async fn start(config: Config) {
     let sync_state = chain_muxer.sync_state_cloned();
     let sync_task = task::spawn(chain_muxer);
 
+    // Start producing the blocks of the configured miners
+    let mining_task = if miners.is_empty() {
+        None
+    } else {
+        let producer = BlockProducer::<_, _, FullVerifier, _>::new(
+            Arc::clone(&state_manager),
+            Arc::clone(&beacon),
+            Arc::clone(&mpool),
+            Arc::clone(&keystore),
+            Arc::new(MockProver),
+            tipset_sink.clone(),
+            network_send.clone(),
+            network_name.clone(),
+            miners,
+        );
+        Some(task::spawn(producer.run()))
+    };
+
+    // Start scheduled chain garbage collection
+    let gc_task = if config.gc.enabled {
+        let chain_store = Arc::clone(&chain_store);
+        let state_manager = Arc::clone(&state_manager);
+        let mpool = Arc::clone(&mpool);
+        let gc_config = config.gc.clone();
+        Some(task::spawn(async move {
+            loop {
+                task::sleep(Duration::from_secs(gc_config.interval)).await;
+                // Pending messages and computed states are not referenced by the chain yet.
+                let keep = || async {
+                    let mut keep = mpool
+                        .pending_cids()
+                        .await
+                        .map_err(|e| chain::Error::Other(e.to_string()))?;
+                    keep.extend(state_manager.cached_state_roots().await);
+                    Ok::<_, chain::Error>(keep)
+                };
+                if let Err(e) = chain_store
+                    .collect_garbage(gc_config.recent_roots, gc_config.skip_old_msgs, keep)
+                    .await
+                {
+                    warn!("Chain garbage collection failed: {}", e);
+                }
+            }
+        }))
+    } else {
+        None
+    };
+
     // Start services
     let p2p_task = task::spawn(async {
         p2p_service.run().await;
@@ -218,6 +377,7 @@ pub(super) async fn start(config: Config) {
                     beacon,
                     chain_store,
                     new_mined_block_tx: tipset_sink,
+                    paych_manager,
                 }),
                 &rpc_listen,
             )
@@ -245,7 +405,17 @@ pub(super) async fn start(config: Config) {
 
     // Cancel all async services
     prometheus_server_task.cancel().await;
+    if let Some(task) = mining_task {
+        task.cancel().await;
+    }
     sync_task.cancel().await;
+    backfill_task.cancel().await;
+    if let Some(task) = gc_task {
+        task.cancel().await;
+    }
+    if let Some(task) = prefetch_task {
+        task.cancel().await;
+    }
     p2p_task.cancel().await;
     if let Some(task) = rpc_task {
         task.cancel().await;
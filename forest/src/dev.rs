@@ -0,0 +1,482 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::cli::{block_until_sigint, cli_error_and_die, Config};
+use address::Address;
+use auth::{create_token, generate_priv_key, ADMIN, JWT_IDENTIFIER};
+use beacon::{Beacon, BeaconPoint, BeaconSchedule, MockBeacon};
+use blocks::{BlockHeader, ElectionProof, Ticket, Tipset};
+use chain::ChainStore;
+use chain_sync::{BadBlockCache, SyncState};
+use db::MemoryDB;
+use encoding::blake2b_256;
+use fil_types::genesis::{Actor, ActorType, Miner, Template};
+use fil_types::verifier::MockVerifier;
+use fil_types::{SectorSize, FILECOIN_PRECISION};
+use forest_crypto::{SignatureType, VRFProof};
+use forest_libp2p::NetworkMessage;
+use genesis::{make_genesis_block, MINER_START};
+use ipld_blockstore::BlockStore;
+use libp2p::PeerId;
+use message_pool::{MessagePool, MpoolConfig, MpoolRpcProvider};
+use networks::{NetworkConfig, UpgradeSchedule, BLOCK_DELAY_SECS};
+use num_bigint::BigInt;
+use paychmgr::Manager as PaychManager;
+use rpc::start_rpc;
+use rpc_api::data_types::RPCState;
+use serde::Serialize;
+use state_manager::StateManager;
+use utils::write_to_file;
+use wallet::json::KeyInfoJsonRef;
+use wallet::{Key, KeyStore, KeyStoreConfig};
+
+use async_std::{channel::unbounded, sync::RwLock, task};
+use log::{debug, info, warn};
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the dev network, set in its genesis.
+const DEV_NETWORK_NAME: &str = "dev";
+/// Balance of each pre-funded account of the dev chain, in FIL.
+const DEV_ACCOUNT_BALANCE: u64 = 1_000_000;
+/// Default name of the file the keys of the dev accounts are written to.
+const DEV_KEYS_FILE: &str = "dev_keys.json";
+
+/// Pre-funded account of the dev chain, as written to the keys file.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DevAccount<'a> {
+    #[serde(with = "address::json")]
+    address: Address,
+    key_info: KeyInfoJsonRef<'a>,
+}
+
+/// Starts the node on a local dev chain. The chain starts from a genesis generated on startup,
+/// which funds a few accounts and creates a miner owned by the first one. Tipsets are sealed by
+/// the node itself, without election or proofs, either on an interval or whenever a message is
+/// pushed to the message pool. Nothing is persisted, the chain is gone once the node stops.
+pub(super) async fn start(config: Config) {
+    if config.dev.accounts == 0 {
+        cli_error_and_die(
+            "The dev chain needs at least one account to own its miner",
+            1,
+        );
+    }
+    let network = Arc::new(dev_network());
+    if let Err(e) = network.set_process_defaults() {
+        cli_error_and_die(&format!("Failed to set up the dev network: {}", e), 1);
+    }
+
+    info!(
+        "Starting Forest dev chain, version {}",
+        option_env!("FOREST_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"))
+    );
+
+    let mut ks = KeyStore::new(KeyStoreConfig::Memory).expect("Error initializing keystore");
+    ks.put(JWT_IDENTIFIER.to_owned(), generate_priv_key())
+        .unwrap();
+    let ki = ks.get(JWT_IDENTIFIER).unwrap();
+    let token = create_token(ADMIN.to_owned(), ki.private_key()).unwrap();
+    println!("Admin token: {}", token);
+
+    // The first account owns the miner, whose worker has to be a BLS key
+    let keys = (0..config.dev.accounts)
+        .map(|i| {
+            let sig_type = if i % 2 == 0 {
+                SignatureType::BLS
+            } else {
+                SignatureType::Secp256k1
+            };
+            wallet::generate_key(sig_type)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            cli_error_and_die(&format!("Failed to generate the dev accounts: {}", e), 1);
+            unreachable!()
+        });
+    for key in &keys {
+        wallet::import(key.key_info.clone(), &mut ks).unwrap();
+    }
+    ks.put("default".to_owned(), keys[0].key_info.clone())
+        .unwrap();
+    let keys_file = config
+        .dev
+        .keys_file
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&config.data_dir).join(DEV_KEYS_FILE));
+    if let Err(e) = write_keys(&keys, &keys_file) {
+        cli_error_and_die(
+            &format!(
+                "Failed to write the dev keys to {}: {}",
+                keys_file.display(),
+                e
+            ),
+            1,
+        );
+    }
+    info!(
+        "Wrote the keys of {} dev accounts to {}",
+        keys.len(),
+        keys_file.display()
+    );
+    let keystore = Arc::new(RwLock::new(ks));
+
+    let db = Arc::new(MemoryDB::default());
+    let chain_store = Arc::new(ChainStore::new(Arc::clone(&db)));
+    let state_manager = Arc::new(StateManager::new_with_config(
+        Arc::clone(&chain_store),
+        Arc::clone(&network),
+    ));
+
+    let template = dev_template(&keys);
+    let genesis = make_genesis_block::<_, MockVerifier>(db.as_ref(), &template, &network)
+        .and_then(|header| Ok(Tipset::new(vec![header])?))
+        .unwrap_or_else(|e| {
+            cli_error_and_die(&format!("Failed to build the dev genesis: {}", e), 1);
+            unreachable!()
+        });
+    chain_store.set_genesis(&genesis.blocks()[0]).unwrap();
+    chain_store
+        .set_heaviest_tipset(Arc::new(genesis.clone()))
+        .await
+        .unwrap();
+    info!("Initialized dev genesis: {:?}", genesis.key());
+    let network_name = template.network_name;
+
+    // There are no peers on the dev chain, so whatever is meant for the network is dropped.
+    // The messages published by the message pool are those pushed to it, which trigger a seal.
+    let (network_send, network_rx) = unbounded();
+    let provider =
+        MpoolRpcProvider::new(chain_store.publisher().clone(), Arc::clone(&state_manager));
+    let mpool = Arc::new(
+        MessagePool::new(
+            provider,
+            network_name.clone(),
+            network_send.clone(),
+            MpoolConfig::load_config(db.as_ref()).unwrap(),
+        )
+        .await
+        .unwrap(),
+    );
+
+    let mock_beacon = Arc::new(MockBeacon::new(Duration::from_secs(BLOCK_DELAY_SECS)));
+    let beacon = Arc::new(BeaconSchedule(vec![BeaconPoint {
+        height: 0,
+        beacon: Arc::clone(&mock_beacon),
+    }]));
+
+    let sealer = Sealer {
+        state_manager: Arc::clone(&state_manager),
+        mpool: Arc::clone(&mpool),
+        keystore: Arc::clone(&keystore),
+        beacon: mock_beacon,
+        miner: Address::new_id(MINER_START),
+        worker: keys[0].address,
+    };
+    let block_time = config.dev.block_time;
+    let sealer_task = task::spawn(async move {
+        loop {
+            if block_time > 0 {
+                task::sleep(Duration::from_secs(block_time)).await;
+                while network_rx.try_recv().is_ok() {}
+            } else {
+                match network_rx.recv().await {
+                    Ok(NetworkMessage::PubsubMessage { .. }) => (),
+                    Ok(_) => continue,
+                    Err(_) => break,
+                }
+            }
+            match sealer.seal().await {
+                Ok(ts) => info!("Sealed dev tipset at epoch {}", ts.epoch()),
+                Err(e) => warn!("Failed to seal a dev tipset: {}", e),
+            }
+        }
+    });
+
+    let rpc_task = if config.enable_rpc {
+        // Submitted blocks are refused, since only the sealer extends the dev chain
+        let (new_mined_block_tx, _) = unbounded();
+        let rpc_listen = format!("127.0.0.1:{}", &config.rpc_port);
+        Some(task::spawn(async move {
+            info!("JSON RPC Endpoint at {}", &rpc_listen);
+            start_rpc::<_, _, MockVerifier>(
+                Arc::new(RPCState {
+                    state_manager: Arc::clone(&state_manager),
+                    keystore,
+                    mpool,
+                    bad_blocks: Arc::new(BadBlockCache::new(db)),
+                    sync_state: Arc::new(RwLock::new(SyncState::default())),
+                    network_send,
+                    network_name,
+                    beacon,
+                    chain_store,
+                    new_mined_block_tx,
+                    paych_manager: Arc::new(PaychManager::new(state_manager)),
+                }),
+                &rpc_listen,
+            )
+            .await
+        }))
+    } else {
+        debug!("RPC disabled");
+        None
+    };
+
+    // Block until ctrl-c is hit
+    block_until_sigint().await;
+
+    sealer_task.cancel().await;
+    if let Some(task) = rpc_task {
+        task.cancel().await;
+    }
+
+    info!("Forest finish shutdown");
+}
+
+/// Returns the config of the dev network, which runs the newest actors from genesis.
+fn dev_network() -> NetworkConfig {
+    NetworkConfig {
+        name: DEV_NETWORK_NAME.to_owned(),
+        upgrades: UpgradeSchedule::ALL_AT_GENESIS,
+        ..NetworkConfig::devnet()
+    }
+}
+
+/// Returns the genesis template of the dev chain, which funds the given accounts and creates a
+/// miner without sectors for the sealer to attribute blocks to.
+fn dev_template(keys: &[Key]) -> Template {
+    let mut template = Template::new(DEV_NETWORK_NAME.to_owned());
+    template.accounts = keys
+        .iter()
+        .map(|key| Actor {
+            actor_type: ActorType::Account,
+            balance: BigInt::from(DEV_ACCOUNT_BALANCE) * FILECOIN_PRECISION,
+            owner: key.address,
+        })
+        .collect();
+    template.miners.push(Miner {
+        owner: keys[0].address,
+        worker: keys[0].address,
+        peer_id: PeerId::random().to_string(),
+        market_balance: Default::default(),
+        power_balance: Default::default(),
+        sector_size: SectorSize::_2KiB,
+        sectors: Vec::new(),
+    });
+    template
+}
+
+/// Writes the addresses and keys of the dev accounts to a JSON file.
+fn write_keys(keys: &[Key], path: &Path) -> Result<(), Box<dyn StdError>> {
+    let accounts: Vec<_> = keys
+        .iter()
+        .map(|key| DevAccount {
+            address: key.address,
+            key_info: KeyInfoJsonRef(&key.key_info),
+        })
+        .collect();
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{} isn't a file path", path.display()))?;
+    let file = write_to_file(
+        &serde_json::to_vec_pretty(&accounts)?,
+        dir,
+        &file_name.to_string_lossy(),
+    )?;
+    // Restrict permissions on files containing private keys
+    #[cfg(unix)]
+    utils::set_user_perm(&file)?;
+    Ok(())
+}
+
+/// Seals the tipsets of the dev chain. The sealer is trusted, its blocks have no election or
+/// winning PoSt and become the head without being validated.
+struct Sealer<DB> {
+    state_manager: Arc<StateManager<DB>>,
+    mpool: Arc<MessagePool<MpoolRpcProvider<DB>>>,
+    keystore: Arc<RwLock<KeyStore>>,
+    beacon: Arc<MockBeacon>,
+    miner: Address,
+    worker: Address,
+}
+
+impl<DB> Sealer<DB>
+where
+    DB: BlockStore + Send + Sync + 'static,
+{
+    /// Seals a tipset of a single block on top of the head, with the messages selected from the
+    /// message pool, and sets it as the new head.
+    async fn seal(&self) -> Result<Arc<Tipset>, Box<dyn StdError + Send + Sync>> {
+        let chain_store = self.state_manager.chain_store();
+        // The sealed blocks are only reachable once the tipset is set as the head
+        let _gc_pause = chain_store.pause_gc().await;
+        let parent = chain_store
+            .heaviest_tipset()
+            .await
+            .ok_or("the dev chain has no head")?;
+        let (state_root, receipts) = self
+            .state_manager
+            .tipset_state::<MockVerifier>(&parent)
+            .await
+            .map_err(|e| e.to_string())?;
+        let base_fee = chain::compute_base_fee(
+            chain_store.blockstore(),
+            &parent,
+            self.state_manager.network_config().upgrades.smoke,
+        )?;
+
+        let messages = self.mpool.select_messages(&parent, 1.0).await?;
+        let message_count = messages.len();
+        let (messages, bls_aggregate, _, _) =
+            chain::persist_block_messages(chain_store.blockstore(), messages)?;
+
+        let epoch = parent.epoch() + 1;
+        let vrf_proof = VRFProof::new(blake2b_256(&epoch.to_be_bytes()).to_vec());
+        let beacon_entry = self
+            .beacon
+            .entry(epoch as u64)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut header = BlockHeader::builder()
+            .miner_address(self.miner)
+            .parents(parent.key().clone())
+            // The power of the dev miner doesn't count, each tipset adds the same weight
+            .weight(parent.weight() + BigInt::from(1))
+            .epoch(epoch)
+            .ticket(Some(Ticket::new(vrf_proof.clone())))
+            .election_proof(Some(ElectionProof {
+                win_count: 1,
+                vrfproof: vrf_proof,
+            }))
+            .beacon_entries(vec![beacon_entry])
+            .messages(messages)
+            .bls_aggregate(Some(bls_aggregate))
+            .state_root(state_root)
+            .message_receipts(receipts)
+            .parent_base_fee(base_fee)
+            .timestamp(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            )
+            .build()?;
+        let key = wallet::find_key(&self.worker, &*self.keystore.read().await)?;
+        header.signature = Some(wallet::sign(
+            *key.key_info.key_type(),
+            key.key_info.private_key(),
+            &header.to_signing_bytes(),
+        )?);
+        chain::persist_objects(chain_store.blockstore(), std::slice::from_ref(&header))?;
+
+        // The messages of the tipset are applied before it becomes the head, so that its state
+        // can be queried right away
+        let tipset = Arc::new(Tipset::new(vec![header])?);
+        self.state_manager
+            .tipset_state::<MockVerifier>(&tipset)
+            .await
+            .map_err(|e| e.to_string())?;
+        chain_store.set_heaviest_tipset(Arc::clone(&tipset)).await?;
+        debug!(
+            "Included {} messages in the dev tipset at epoch {}",
+            message_count, epoch
+        );
+        Ok(tipset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encoding::Cbor;
+    use message::{SignedMessage, UnsignedMessage};
+
+    #[async_std::test]
+    async fn seal_pushed_message() {
+        let keys: Vec<_> = [SignatureType::BLS, SignatureType::Secp256k1]
+            .iter()
+            .map(|sig_type| wallet::generate_key(*sig_type).unwrap())
+            .collect();
+        let mut ks = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        for key in &keys {
+            wallet::import(key.key_info.clone(), &mut ks).unwrap();
+        }
+
+        let network = Arc::new(dev_network());
+        let db = Arc::new(MemoryDB::default());
+        let chain_store = Arc::new(ChainStore::new(Arc::clone(&db)));
+        let state_manager = Arc::new(StateManager::new_with_config(
+            Arc::clone(&chain_store),
+            Arc::clone(&network),
+        ));
+        let genesis =
+            make_genesis_block::<_, MockVerifier>(db.as_ref(), &dev_template(&keys), &network)
+                .unwrap();
+        chain_store.set_genesis(&genesis).unwrap();
+        chain_store
+            .set_heaviest_tipset(Arc::new(Tipset::new(vec![genesis]).unwrap()))
+            .await
+            .unwrap();
+
+        let (network_send, _network_rx) = unbounded();
+        let provider =
+            MpoolRpcProvider::new(chain_store.publisher().clone(), Arc::clone(&state_manager));
+        let mpool = Arc::new(
+            MessagePool::new(
+                provider,
+                DEV_NETWORK_NAME.to_owned(),
+                network_send,
+                MpoolConfig::default(),
+            )
+            .await
+            .unwrap(),
+        );
+        let sealer = Sealer {
+            state_manager: Arc::clone(&state_manager),
+            mpool: Arc::clone(&mpool),
+            keystore: Arc::new(RwLock::new(ks)),
+            beacon: Arc::new(MockBeacon::new(Duration::from_secs(1))),
+            miner: Address::new_id(MINER_START),
+            worker: keys[0].address,
+        };
+
+        let msg = UnsignedMessage::builder()
+            .from(keys[1].address)
+            .to(keys[0].address)
+            .value(BigInt::from(1000))
+            .gas_limit(10_000_000)
+            .gas_fee_cap(BigInt::from(1_000_000))
+            .gas_premium(BigInt::from(1000))
+            .build()
+            .unwrap();
+        let sig = wallet::sign(
+            SignatureType::Secp256k1,
+            keys[1].key_info.private_key(),
+            &msg.cid().unwrap().to_bytes(),
+        )
+        .unwrap();
+        let smsg = SignedMessage::new_from_parts(msg, sig).unwrap();
+        mpool.push(smsg.clone()).await.unwrap();
+
+        let first = sealer.seal().await.unwrap();
+        assert_eq!(first.epoch(), 1);
+        let (_, secp_msgs) = chain::block_messages(db.as_ref(), &first.blocks()[0]).unwrap();
+        assert_eq!(secp_msgs, vec![smsg]);
+
+        // The transfer shows in the state of the next tipset
+        let second = sealer.seal().await.unwrap();
+        assert_eq!(second.epoch(), 2);
+        assert_eq!(chain_store.heaviest_tipset().await.unwrap(), second);
+        let balance = state_manager
+            .get_balance(&keys[0].address, second.parent_state())
+            .unwrap();
+        assert_eq!(
+            balance,
+            BigInt::from(DEV_ACCOUNT_BALANCE) * FILECOIN_PRECISION + 1000
+        );
+    }
+}
@@ -3,6 +3,7 @@
 
 mod cli;
 mod daemon;
+mod dev;
 mod logger;
 mod subcommand;
 
@@ -19,6 +20,7 @@ async fn main() {
     match opts.to_config() {
         Ok(cfg) => match cmd {
             Some(command) => subcommand::process(command, cfg).await,
+            None if cfg.dev.enabled => dev::start(cfg).await,
             None => daemon::start(cfg).await,
         },
         Err(e) => {
@@ -3,61 +3,30 @@
 
 use auth::{create_token, ADMIN, JWT_IDENTIFIER};
 use rpassword::read_password;
-use rpc_client::API_INFO;
+use rpc_client::{ApiInfo, API_INFO};
 use std::path::PathBuf;
+use std::process;
 use wallet::{KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME};
 
 use super::cli::{Config, Subcommand};
 
 /// Process CLI subcommand
 pub(super) async fn process(command: Subcommand, config: Config) {
-    let token = match config.rpc_token.to_owned() {
-        Some(token) => token,
-        // If no token argument is passed or configured, attempt to load it from the local keystore
-        None => {
-            let keystore = if config.encrypt_keystore {
-                loop {
-                    println!("Enter the keystore passphrase: ");
-
-                    let passphrase = read_password().expect("Error reading passphrase");
-
-                    let mut data_dir = PathBuf::from(&config.data_dir);
-                    data_dir.push(ENCRYPTED_KEYSTORE_NAME);
-
-                    if !data_dir.exists() {
-                        println!("The keystore cannot be found from defaults, the environment, or provided arguments");
-                    }
-
-                    let key_store_init_result = KeyStore::new(KeyStoreConfig::Encrypted(
-                        PathBuf::from(&config.data_dir),
-                        passphrase,
-                    ));
-
-                    match key_store_init_result {
-                        Ok(ks) => break ks,
-                        Err(_) => {
-                            log::error!("Incorrect passphrase entered.")
-                        }
-                    };
-                }
-            } else {
-                KeyStore::new(KeyStoreConfig::Persistent(PathBuf::from(&config.data_dir)))
-                    .expect("Error finding keystore")
-            };
-
-            let key_info = keystore
-                .get(JWT_IDENTIFIER)
-                .expect("Keystore initialized with a JWT private key");
-
-            create_token(ADMIN.to_owned(), key_info.private_key())
-                .expect("JWT private key parsed into a JWT")
-        }
+    // The FULLNODE_API_INFO environment variable targets a remote daemon, otherwise the daemon
+    // of the local configuration is used
+    let mut api_info = match ApiInfo::from_env() {
+        Ok(Some(api_info)) => api_info,
+        Ok(None) => ApiInfo::local(&config.rpc_port).unwrap_or_else(exit_with_error),
+        Err(e) => exit_with_error(e),
     };
-
-    {
-        let mut api_info = API_INFO.write().await;
-        api_info.token = Some(token);
+    if api_info.token.is_none() {
+        api_info.token = Some(match config.rpc_token.to_owned() {
+            Some(token) => token,
+            // If no token argument is passed or configured, attempt to load it from the local keystore
+            None => local_token(&config),
+        });
     }
+    *API_INFO.write().await = Ok(api_info);
 
     // Run command
     match command {
@@ -68,10 +37,10 @@ pub(super) async fn process(command: Subcommand, config: Config) {
             cmd.run().await;
         }
         Subcommand::Auth(cmd) => {
-            cmd.run(config).await;
+            cmd.run().await;
         }
         Subcommand::Genesis(cmd) => {
-            cmd.run().await;
+            cmd.run(&config).await;
         }
         Subcommand::Net(cmd) => {
             cmd.run().await;
@@ -79,11 +48,65 @@ pub(super) async fn process(command: Subcommand, config: Config) {
         Subcommand::Wallet(cmd) => {
             cmd.run().await;
         }
+        Subcommand::Msig(cmd) => {
+            cmd.run().await;
+        }
+        Subcommand::Mpool(cmd) => {
+            cmd.run().await;
+        }
         Subcommand::Sync(cmd) => {
             cmd.run().await;
         }
         Subcommand::State(cmd) => {
             cmd.run().await;
         }
+        Subcommand::Db(cmd) => {
+            cmd.run().await;
+        }
     }
 }
+
+/// Creates an admin token with the JWT key of the local keystore.
+fn local_token(config: &Config) -> String {
+    let keystore = if config.encrypt_keystore {
+        loop {
+            println!("Enter the keystore passphrase: ");
+
+            let passphrase = read_password().expect("Error reading passphrase");
+
+            let mut data_dir = PathBuf::from(&config.data_dir);
+            data_dir.push(ENCRYPTED_KEYSTORE_NAME);
+
+            if !data_dir.exists() {
+                println!("The keystore cannot be found from defaults, the environment, or provided arguments");
+            }
+
+            let key_store_init_result = KeyStore::new(KeyStoreConfig::Encrypted(
+                PathBuf::from(&config.data_dir),
+                passphrase,
+            ));
+
+            match key_store_init_result {
+                Ok(ks) => break ks,
+                Err(_) => {
+                    log::error!("Incorrect passphrase entered.")
+                }
+            };
+        }
+    } else {
+        KeyStore::new(KeyStoreConfig::Persistent(PathBuf::from(&config.data_dir)))
+            .expect("Error finding keystore")
+    };
+
+    let key_info = keystore
+        .get(JWT_IDENTIFIER)
+        .expect("Keystore initialized with a JWT private key");
+
+    create_token(ADMIN.to_owned(), key_info.private_key())
+        .expect("JWT private key parsed into a JWT")
+}
+
+fn exit_with_error(e: String) -> ! {
+    eprintln!("{}", e);
+    process::exit(1)
+}
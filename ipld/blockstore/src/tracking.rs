@@ -5,7 +5,7 @@
 
 use super::BlockStore;
 use cid::{Cid, Code};
-use db::{Error, Store};
+use db::{Column, Error, Store};
 use std::cell::RefCell;
 use std::error::Error as StdError;
 
@@ -113,6 +113,44 @@ where
     {
         self.base.bulk_delete(keys)
     }
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.base.read_column(column, key)
+    }
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.base.write_column(column, key, value)
+    }
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.base.delete_column(column, key)
+    }
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.base.exists_column(column, key)
+    }
+    fn bulk_write_column<K, V>(&self, column: Column, values: &[(K, V)]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.base.bulk_write_column(column, values)
+    }
+    fn bulk_delete_column<K>(&self, column: Column, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.base.bulk_delete_column(column, keys)
+    }
 }
 
 #[cfg(test)]
@@ -4,12 +4,14 @@
 // TODO evaluate exporting from libp2p mod
 pub mod libp2p;
 mod message;
+mod request_manager;
 mod response_manager;
 
 #[cfg(test)]
 mod test_utils;
 
 pub use self::message::*;
+pub use self::request_manager::RequestResult;
 
 use cid::Cid;
 use serde::{Deserialize, Serialize};
@@ -18,6 +20,10 @@ use std::collections::HashMap;
 /// The maximum combined block size for a single message.
 pub const MAX_BLOCK_SIZE: usize = 512 * 1024;
 
+/// The maximum size of a message sent over the network, leaving room for the responses and
+/// their metadata next to the blocks.
+pub const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
 /// Provides response metadata for a GraphSync request,
 /// see https://github.com/ipld/specs/blob/master/block-layer/graphsync/known_extensions.md
 pub const EXTENSION_METADATA: &str = "graphsync/response-metadata";
@@ -115,6 +121,21 @@ impl ResponseStatusCode {
         }
     }
 
+    /// Returns true if the status code terminates the request, either successfully or with
+    /// an error.
+    pub fn is_terminal(self) -> bool {
+        !matches!(
+            self,
+            Self::RequestAcknowledged
+                | Self::AdditionalPeers
+                | Self::NotEnoughGas
+                | Self::OtherProtocol
+                | Self::PartialResponse
+                | Self::RequestPaused
+                | Self::Other(_)
+        )
+    }
+
     /// Return the status code for a given integer.
     pub fn from_i32(code: i32) -> Self {
         match code {
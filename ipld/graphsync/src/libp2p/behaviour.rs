@@ -3,58 +3,146 @@
 
 use super::config::GraphSyncConfig;
 use super::handler::GraphSyncHandler;
-use crate::{Extensions, GraphSyncMessage};
+use crate::request_manager::{RequestManager, RequestResult};
+use crate::response_manager::{PeerMessageHandler, ResponseManager};
+use crate::{Extensions, GraphSyncMessage, GraphSyncResponse, RequestID};
+use async_trait::async_trait;
 use cid::Cid;
 use forest_ipld::selector::Selector;
+use futures::channel::mpsc;
 use futures::task::Context;
+use futures::StreamExt;
 use futures_util::task::Poll;
+use ipld_blockstore::BlockStore;
 use libp2p::core::connection::ConnectionId;
 use libp2p::swarm::{
-    protocols_handler::ProtocolsHandler, NetworkBehaviour, NetworkBehaviourAction, NotifyHandler,
-    PollParameters,
+    protocols_handler::ProtocolsHandler, DialPeerCondition, NetworkBehaviour,
+    NetworkBehaviourAction, NotifyHandler, PollParameters,
 };
 use libp2p::{Multiaddr, PeerId};
 use log::debug;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 
-/// The GraphSync behaviour that gets consumed by the Swarm.
-#[derive(Default)]
-pub struct GraphSync {
+/// Event emitted by the GraphSync behaviour.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphSyncEvent {
+    /// A request sent by this node terminated. The blocks received for it have been written
+    /// to the blockstore.
+    RequestCompleted(RequestResult),
+}
+
+/// The GraphSync behaviour that gets consumed by the Swarm. It answers the requests of remote
+/// peers from a blockstore, and writes the blocks received for its own requests into it.
+pub struct GraphSync<BS> {
     /// Config options for the service
     config: GraphSyncConfig,
 
     /// Queue of events to processed.
-    events: VecDeque<NetworkBehaviourAction<GraphSyncMessage, ()>>,
+    events: VecDeque<NetworkBehaviourAction<GraphSyncMessage, GraphSyncEvent>>,
 
-    // TODO just temporary, will probably have to attach some data with peers
+    /// Connected peers.
     peers: HashSet<PeerId>,
+
+    /// Messages waiting for the connection to a peer which is being dialed.
+    pending_messages: HashMap<PeerId, Vec<GraphSyncMessage>>,
+
+    /// Tracks the requests sent by this node.
+    request_manager: RequestManager<BS>,
+
+    /// Executes the requests of remote peers.
+    response_manager: ResponseManager<BS>,
+
+    /// Channel through which the responses built by the response manager are sent.
+    response_tx: mpsc::UnboundedSender<(PeerId, GraphSyncMessage)>,
+    response_rx: mpsc::UnboundedReceiver<(PeerId, GraphSyncMessage)>,
 }
 
-impl GraphSync {
+impl<BS> GraphSync<BS>
+where
+    BS: BlockStore + Send + Sync + 'static,
+{
     /// Creates a new GraphSync behaviour
-    pub fn new(config: GraphSyncConfig) -> Self {
+    pub fn new(config: GraphSyncConfig, blockstore: Arc<BS>) -> Self {
+        let (response_tx, response_rx) = mpsc::unbounded();
         Self {
             config,
-            ..Default::default()
+            events: Default::default(),
+            peers: Default::default(),
+            pending_messages: Default::default(),
+            request_manager: RequestManager::new(blockstore.clone()),
+            response_manager: ResponseManager::new(blockstore),
+            response_tx,
+            response_rx,
         }
     }
 
-    /// Initiates GraphSync request to peer given root and selector.
+    /// Initiates GraphSync request to peer given root and selector. The blocks received are
+    /// written to the blockstore, and a [GraphSyncEvent::RequestCompleted] is emitted once the
+    /// request terminates.
     pub fn send_request(
         &mut self,
-        _peer_id: PeerId,
-        _root: Cid,
-        _selector: Selector,
-        _extensions: Extensions,
-    ) {
-        todo!()
+        peer_id: PeerId,
+        root: Cid,
+        selector: Selector,
+        extensions: Extensions,
+    ) -> RequestID {
+        let request = self
+            .request_manager
+            .new_request(peer_id, root, selector, extensions);
+        let id = request.id;
+        let mut message = GraphSyncMessage::default();
+        message.insert_request(request);
+        self.send_message(peer_id, message);
+        id
+    }
+
+    /// Cancels a request sent by this node, no event is emitted for it.
+    pub fn cancel_request(&mut self, id: RequestID) {
+        if let Some((peer_id, request)) = self.request_manager.cancel_request(id) {
+            let mut message = GraphSyncMessage::default();
+            message.insert_request(request);
+            self.send_message(peer_id, message);
+        }
+    }
+
+    /// Sends a message to a peer, dialing it if it is not connected.
+    fn send_message(&mut self, peer_id: PeerId, message: GraphSyncMessage) {
+        if self.peers.contains(&peer_id) {
+            self.events
+                .push_back(NetworkBehaviourAction::NotifyHandler {
+                    peer_id,
+                    event: message,
+                    handler: NotifyHandler::Any,
+                });
+        } else {
+            let pending = self.pending_messages.entry(peer_id).or_default();
+            if pending.is_empty() {
+                self.events.push_back(NetworkBehaviourAction::DialPeer {
+                    peer_id,
+                    condition: DialPeerCondition::Disconnected,
+                });
+            }
+            pending.push(message);
+        }
+    }
+
+    /// Fails the requests sent to a peer which is unreachable.
+    fn fail_requests(&mut self, peer_id: &PeerId) {
+        for result in self.request_manager.peer_disconnected(peer_id) {
+            self.events.push_back(NetworkBehaviourAction::GenerateEvent(
+                GraphSyncEvent::RequestCompleted(result),
+            ));
+        }
     }
 }
 
-impl NetworkBehaviour for GraphSync {
+impl<BS> NetworkBehaviour for GraphSync<BS>
+where
+    BS: BlockStore + Send + Sync + 'static,
+{
     type ProtocolsHandler = GraphSyncHandler;
-    // TODO this will need to be updated to include data emitted from the GS responses
-    type OutEvent = ();
+    type OutEvent = GraphSyncEvent;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
         GraphSyncHandler::new(
@@ -69,31 +157,50 @@ impl NetworkBehaviour for GraphSync {
 
     fn inject_connected(&mut self, peer_id: &PeerId) {
         debug!("New peer connected: {:?}", peer_id);
-        self.peers.insert(peer_id.clone());
+        self.peers.insert(*peer_id);
+        for message in self.pending_messages.remove(peer_id).unwrap_or_default() {
+            self.send_message(*peer_id, message);
+        }
     }
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
         debug!("Peer disconnected: {:?}", peer_id);
         self.peers.remove(peer_id);
+        self.response_manager.peer_disconnected(peer_id);
+        self.fail_requests(peer_id);
+    }
+
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        debug!("Failed to dial peer: {:?}", peer_id);
+        self.pending_messages.remove(peer_id);
+        self.fail_requests(peer_id);
     }
 
     fn inject_event(
         &mut self,
         peer_id: PeerId,
         _connection: ConnectionId,
-        event: GraphSyncMessage,
+        message: GraphSyncMessage,
     ) {
-        self.events
-            .push_back(NetworkBehaviourAction::NotifyHandler {
+        for request in message.requests().values() {
+            self.response_manager.execute_request(
                 peer_id,
-                event,
-                handler: NotifyHandler::Any,
-            });
+                request.clone(),
+                ResponseSender(self.response_tx.clone()),
+            );
+        }
+        if !message.responses().is_empty() {
+            for result in self.request_manager.process_message(&peer_id, &message) {
+                self.events.push_back(NetworkBehaviourAction::GenerateEvent(
+                    GraphSyncEvent::RequestCompleted(result),
+                ));
+            }
+        }
     }
 
     fn poll(
         &mut self,
-        _: &mut Context,
+        cx: &mut Context,
         _: &mut impl PollParameters,
     ) -> Poll<
         NetworkBehaviourAction<
@@ -101,9 +208,44 @@ impl NetworkBehaviour for GraphSync {
             Self::OutEvent,
         >,
     > {
+        while let Poll::Ready(Some((peer_id, message))) = self.response_rx.poll_next_unpin(cx) {
+            if self.peers.contains(&peer_id) {
+                self.send_message(peer_id, message);
+            } else {
+                debug!("Dropping response to disconnected peer {:?}", peer_id);
+            }
+        }
         if let Some(event) = self.events.pop_front() {
             return Poll::Ready(event);
         }
         Poll::Pending
     }
 }
+
+/// Passes the responses built by the response manager back to the behaviour.
+#[derive(Clone)]
+struct ResponseSender(mpsc::UnboundedSender<(PeerId, GraphSyncMessage)>);
+
+#[async_trait]
+impl PeerMessageHandler for ResponseSender {
+    async fn send_response(
+        &mut self,
+        peer: &PeerId,
+        responses: Vec<GraphSyncResponse>,
+        blocks: Vec<(Cid, Vec<u8>)>,
+    ) {
+        let mut message = GraphSyncMessage::default();
+        for response in responses {
+            message.insert_response(response);
+        }
+        for (cid, data) in blocks {
+            message.insert_block(cid, data);
+        }
+        if self.0.unbounded_send((*peer, message)).is_err() {
+            debug!(
+                "GraphSync behaviour dropped, response to {:?} not sent",
+                peer
+            );
+        }
+    }
+}
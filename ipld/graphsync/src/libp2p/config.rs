@@ -1,6 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::MAX_MESSAGE_SIZE;
 use std::borrow::Cow;
 
 /// Configuration parameters for the GraphSync protocol.
@@ -17,7 +18,7 @@ impl Default for GraphSyncConfig {
     fn default() -> Self {
         Self {
             protocol_id: Cow::Borrowed(b"/ipfs/graphsync/1.0.0"),
-            max_transmit_size: 2048,
+            max_transmit_size: MAX_MESSAGE_SIZE,
         }
     }
 }
@@ -4,30 +4,35 @@
 use super::codec::GraphSyncCodec;
 use super::protocol::ProtocolConfig;
 use crate::GraphSyncMessage;
+use futures::{Sink, Stream};
 use futures_codec::Framed;
 use libp2p::swarm::{
     KeepAlive, NegotiatedSubstream, ProtocolsHandler, ProtocolsHandlerEvent,
     ProtocolsHandlerUpgrErr, SubstreamProtocol,
 };
 use libp2p::{InboundUpgrade, OutboundUpgrade};
-use log::trace;
+use log::{debug, trace};
 use smallvec::SmallVec;
 use std::borrow::Cow;
-use std::collections::VecDeque;
 use std::io;
+use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 
 // TODO move this to config option
 const TIMEOUT: u64 = 5;
 
-/// Handler implementation for GraphSync protocol.
+/// Handler implementation for GraphSync protocol. Every message is sent on a new outbound
+/// substream, and every message received on an inbound substream is passed to the behaviour.
 pub struct GraphSyncHandler {
     /// Upgrade configuration for the GraphSync protocol.
-    listen_protocol: SubstreamProtocol<ProtocolConfig>,
+    listen_protocol: SubstreamProtocol<ProtocolConfig, ()>,
 
-    /// Map of current substreams awaiting a response to a GraphSync request.
-    inbound_substreams: VecDeque<InboundSubstreamState>,
+    /// Inbound substreams, waiting for messages from the remote.
+    inbound_substreams: Vec<InboundSubstreamState>,
+
+    /// Outbound substreams, sending a message to the remote.
+    outbound_substreams: Vec<OutboundSubstreamState>,
 
     /// Queue of outbound substreams to open.
     dial_queue: SmallVec<[GraphSyncMessage; 4]>,
@@ -36,7 +41,7 @@ pub struct GraphSyncHandler {
     dial_negotiated: u32,
 
     /// Maximum number of concurrent outbound substreams being opened. Value is never modified.
-    _max_dial_negotiated: u32,
+    max_dial_negotiated: u32,
 
     /// Value to return from `connection_keep_alive`.
     keep_alive: KeepAlive,
@@ -49,7 +54,7 @@ impl GraphSyncHandler {
     /// Constructor for new RPC handler
     pub fn new(id: impl Into<Cow<'static, [u8]>>, max_transmit_size: usize) -> Self {
         Self {
-            listen_protocol: SubstreamProtocol::new(ProtocolConfig::new(id, max_transmit_size)),
+            listen_protocol: SubstreamProtocol::new(ProtocolConfig::new(id, max_transmit_size), ()),
             ..Default::default()
         }
     }
@@ -60,25 +65,145 @@ impl GraphSyncHandler {
         self.keep_alive = KeepAlive::Yes;
         self.dial_queue.push(upgrade);
     }
+
+    /// Lets the connection close after a timeout if there is no work left.
+    fn update_keep_alive(&mut self) {
+        if self.dial_negotiated == 0
+            && self.dial_queue.is_empty()
+            && self.inbound_substreams.is_empty()
+            && self.outbound_substreams.is_empty()
+        {
+            if let KeepAlive::Yes = self.keep_alive {
+                self.keep_alive = KeepAlive::Until(Instant::now() + Duration::from_secs(TIMEOUT));
+            }
+        } else {
+            self.keep_alive = KeepAlive::Yes;
+        }
+    }
+
+    /// Polls the inbound substreams, returns the first message received.
+    fn poll_inbound(&mut self, cx: &mut Context) -> Option<GraphSyncMessage> {
+        for n in (0..self.inbound_substreams.len()).rev() {
+            let mut state = self.inbound_substreams.swap_remove(n);
+            loop {
+                match std::mem::replace(&mut state, InboundSubstreamState::Poisoned) {
+                    InboundSubstreamState::WaitingInput(mut substream) => {
+                        match Pin::new(&mut substream).poll_next(cx) {
+                            Poll::Ready(Some(Ok(message))) => {
+                                self.inbound_substreams
+                                    .push(InboundSubstreamState::WaitingInput(substream));
+                                return Some(message);
+                            }
+                            Poll::Ready(Some(Err(e))) => {
+                                debug!("Failed to read GraphSync message: {}", e);
+                                state = InboundSubstreamState::Closing(substream);
+                            }
+                            Poll::Ready(None) => {
+                                state = InboundSubstreamState::Closing(substream);
+                            }
+                            Poll::Pending => {
+                                self.inbound_substreams
+                                    .push(InboundSubstreamState::WaitingInput(substream));
+                                break;
+                            }
+                        }
+                    }
+                    InboundSubstreamState::Closing(mut substream) => {
+                        match Sink::<GraphSyncMessage>::poll_close(Pin::new(&mut substream), cx) {
+                            Poll::Ready(_) => break,
+                            Poll::Pending => {
+                                self.inbound_substreams
+                                    .push(InboundSubstreamState::Closing(substream));
+                                break;
+                            }
+                        }
+                    }
+                    InboundSubstreamState::Poisoned => break,
+                }
+            }
+        }
+        None
+    }
+
+    /// Drives the outbound substreams until their message is sent and they are closed.
+    fn poll_outbound(&mut self, cx: &mut Context) {
+        for n in (0..self.outbound_substreams.len()).rev() {
+            let mut state = self.outbound_substreams.swap_remove(n);
+            loop {
+                match std::mem::replace(&mut state, OutboundSubstreamState::Poisoned) {
+                    OutboundSubstreamState::PendingSend(mut substream, message) => {
+                        match Sink::poll_ready(Pin::new(&mut substream), cx) {
+                            Poll::Ready(Ok(())) => {
+                                match Sink::start_send(Pin::new(&mut substream), message) {
+                                    Ok(()) => {
+                                        state = OutboundSubstreamState::PendingFlush(substream)
+                                    }
+                                    Err(e) => {
+                                        debug!("Failed to send GraphSync message: {}", e);
+                                        break;
+                                    }
+                                }
+                            }
+                            Poll::Ready(Err(e)) => {
+                                debug!("Failed to send GraphSync message: {}", e);
+                                break;
+                            }
+                            Poll::Pending => {
+                                self.outbound_substreams
+                                    .push(OutboundSubstreamState::PendingSend(substream, message));
+                                break;
+                            }
+                        }
+                    }
+                    OutboundSubstreamState::PendingFlush(mut substream) => {
+                        match Sink::<GraphSyncMessage>::poll_flush(Pin::new(&mut substream), cx) {
+                            Poll::Ready(Ok(())) => {
+                                state = OutboundSubstreamState::Closing(substream)
+                            }
+                            Poll::Ready(Err(e)) => {
+                                debug!("Failed to flush GraphSync message: {}", e);
+                                break;
+                            }
+                            Poll::Pending => {
+                                self.outbound_substreams
+                                    .push(OutboundSubstreamState::PendingFlush(substream));
+                                break;
+                            }
+                        }
+                    }
+                    OutboundSubstreamState::Closing(mut substream) => {
+                        match Sink::<GraphSyncMessage>::poll_close(Pin::new(&mut substream), cx) {
+                            Poll::Ready(_) => break,
+                            Poll::Pending => {
+                                self.outbound_substreams
+                                    .push(OutboundSubstreamState::Closing(substream));
+                                break;
+                            }
+                        }
+                    }
+                    OutboundSubstreamState::Poisoned => break,
+                }
+            }
+        }
+    }
 }
 
 impl Default for GraphSyncHandler {
     fn default() -> Self {
         Self {
-            listen_protocol: SubstreamProtocol::new(ProtocolConfig::default()),
+            listen_protocol: SubstreamProtocol::new(ProtocolConfig::default(), ()),
             inbound_substreams: Default::default(),
+            outbound_substreams: Default::default(),
             dial_queue: Default::default(),
             dial_negotiated: 0,
-            _max_dial_negotiated: 8,
+            max_dial_negotiated: 8,
             keep_alive: KeepAlive::Yes,
             pending_error: None,
         }
     }
 }
 
-// TODO remove allow dead_code on impl
-#[allow(dead_code, clippy::large_enum_variant)]
-/// State of the inbound substream, opened either by us or by the remote.
+/// State of an inbound substream, opened by the remote.
 enum InboundSubstreamState {
     /// Waiting for a message from the remote. The idle state for an inbound substream.
     WaitingInput(Framed<NegotiatedSubstream, GraphSyncCodec>),
@@ -88,41 +213,54 @@ enum InboundSubstreamState {
     Poisoned,
 }
 
+/// State of an outbound substream, opened by us to send a message.
+#[allow(clippy::large_enum_variant)]
+enum OutboundSubstreamState {
+    /// Waiting to send the message.
+    PendingSend(
+        Framed<NegotiatedSubstream, GraphSyncCodec>,
+        GraphSyncMessage,
+    ),
+    /// Waiting for the message to be flushed.
+    PendingFlush(Framed<NegotiatedSubstream, GraphSyncCodec>),
+    /// The substream is being closed.
+    Closing(Framed<NegotiatedSubstream, GraphSyncCodec>),
+    /// An error occurred during processing.
+    Poisoned,
+}
+
 impl ProtocolsHandler for GraphSyncHandler {
     type InEvent = GraphSyncMessage;
     type OutEvent = GraphSyncMessage;
     type Error = io::Error;
     type InboundProtocol = ProtocolConfig;
     type OutboundProtocol = ProtocolConfig;
+    type InboundOpenInfo = ();
     type OutboundOpenInfo = GraphSyncMessage;
 
-    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol> {
+    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
         self.listen_protocol.clone()
     }
 
     fn inject_fully_negotiated_inbound(
         &mut self,
         substream: <Self::InboundProtocol as InboundUpgrade<NegotiatedSubstream>>::Output,
+        _info: Self::InboundOpenInfo,
     ) {
         // new inbound substream. Push to back of inbound queue
         trace!("New inbound substream request");
         self.inbound_substreams
-            .push_back(InboundSubstreamState::WaitingInput(substream));
+            .push(InboundSubstreamState::WaitingInput(substream));
     }
 
     fn inject_fully_negotiated_outbound(
         &mut self,
-        _out: <Self::OutboundProtocol as OutboundUpgrade<NegotiatedSubstream>>::Output,
-        _event: Self::OutboundOpenInfo,
+        substream: <Self::OutboundProtocol as OutboundUpgrade<NegotiatedSubstream>>::Output,
+        message: Self::OutboundOpenInfo,
     ) {
         self.dial_negotiated -= 1;
-
-        if self.dial_negotiated == 0 && self.dial_queue.is_empty() {
-            self.keep_alive = KeepAlive::Until(Instant::now() + Duration::from_secs(TIMEOUT));
-        }
-
-        // TODO handle outbound when events are emitted from service
-        // self.events_out.push(out);
+        self.outbound_substreams
+            .push(OutboundSubstreamState::PendingSend(substream, message));
     }
 
     fn inject_event(&mut self, event: Self::InEvent) {
@@ -134,6 +272,7 @@ impl ProtocolsHandler for GraphSyncHandler {
         _: Self::OutboundOpenInfo,
         error: ProtocolsHandlerUpgrErr<io::Error>,
     ) {
+        self.dial_negotiated -= 1;
         if self.pending_error.is_none() {
             self.pending_error = Some(error);
         }
@@ -146,7 +285,7 @@ impl ProtocolsHandler for GraphSyncHandler {
     #[allow(clippy::type_complexity)]
     fn poll(
         &mut self,
-        _cx: &mut Context,
+        cx: &mut Context,
     ) -> Poll<
         ProtocolsHandlerEvent<
             Self::OutboundProtocol,
@@ -155,6 +294,27 @@ impl ProtocolsHandler for GraphSyncHandler {
             Self::Error,
         >,
     > {
-        todo!()
+        if let Some(error) = self.pending_error.take() {
+            return Poll::Ready(ProtocolsHandlerEvent::Close(io::Error::new(
+                io::ErrorKind::Other,
+                error.to_string(),
+            )));
+        }
+
+        if !self.dial_queue.is_empty() && self.dial_negotiated < self.max_dial_negotiated {
+            self.dial_negotiated += 1;
+            let message = self.dial_queue.remove(0);
+            return Poll::Ready(ProtocolsHandlerEvent::OutboundSubstreamRequest {
+                protocol: self.listen_protocol.clone().map_info(|()| message),
+            });
+        }
+
+        if let Some(message) = self.poll_inbound(cx) {
+            return Poll::Ready(ProtocolsHandlerEvent::Custom(message));
+        }
+        self.poll_outbound(cx);
+        self.update_keep_alive();
+
+        Poll::Pending
     }
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::GraphSyncCodec;
+use crate::MAX_MESSAGE_SIZE;
 use futures::prelude::*;
 use futures::{AsyncRead, AsyncWrite};
 use futures_codec::Framed;
@@ -24,7 +25,7 @@ impl Default for ProtocolConfig {
     fn default() -> Self {
         Self {
             protocol_id: Cow::Borrowed(b"/ipfs/graphsync/1.0.0"),
-            max_transmit_size: 2048,
+            max_transmit_size: MAX_MESSAGE_SIZE,
         }
     }
 }
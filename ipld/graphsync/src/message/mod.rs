@@ -5,8 +5,8 @@ pub mod proto;
 
 use super::*;
 use cid::{Cid, Prefix};
+use encoding::{Cbor, Error as EncodingError};
 use fnv::FnvHashMap;
-use forest_encoding::{Cbor, Error as EncodingError};
 use forest_ipld::selector::Selector;
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -181,7 +181,7 @@ impl TryFrom<GraphSyncMessage> for proto::Message {
             .into_iter()
             .map(|(cid, data)| proto::Message_Block {
                 data,
-                prefix: cid.prefix().to_bytes(),
+                prefix: Prefix::from(cid).to_bytes(),
                 ..Default::default()
             })
             .collect();
@@ -241,7 +241,7 @@ impl TryFrom<proto::Message> for GraphSyncMessage {
             .into_iter()
             .map(|block| {
                 let prefix = Prefix::new_from_bytes(&block.prefix)?;
-                let cid = Cid::new_from_prefix(&prefix, &block.data)?;
+                let cid = cid::new_from_prefix(&prefix, &block.data)?;
                 Ok((cid, block.data))
             })
             .collect::<Result<_, Self::Error>>()?;
@@ -0,0 +1,249 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{
+    Extensions, GraphSyncMessage, GraphSyncRequest, MetadataItem, Priority, RequestID,
+    ResponseStatusCode, EXTENSION_METADATA,
+};
+use cid::Cid;
+use forest_ipld::selector::Selector;
+use ipld_blockstore::BlockStore;
+use libp2p::core::PeerId;
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Default priority of the requests sent by this node.
+const DEFAULT_PRIORITY: Priority = 0;
+
+/// Outcome of a request sent by this node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestResult {
+    pub peer: PeerId,
+    pub id: RequestID,
+    pub root: Cid,
+    pub status: ResponseStatusCode,
+    /// Number of blocks received for the request and written to the blockstore.
+    pub blocks: usize,
+}
+
+struct OutgoingRequest {
+    peer: PeerId,
+    root: Cid,
+    blocks: usize,
+}
+
+/// Tracks the requests sent by this node, and writes the blocks received for them into a
+/// blockstore.
+///
+/// The cid of a received block is computed from its data, so a block always matches its link.
+/// Blocks are only accepted if the responding peer listed their link in the metadata of a
+/// response to one of the requests sent to it, any other block is dropped.
+pub struct RequestManager<BS> {
+    blockstore: Arc<BS>,
+    next_id: RequestID,
+    requests: HashMap<RequestID, OutgoingRequest>,
+}
+
+impl<BS> RequestManager<BS>
+where
+    BS: BlockStore,
+{
+    /// Creates a new request manager which writes received blocks into the given blockstore.
+    pub fn new(blockstore: Arc<BS>) -> Self {
+        Self {
+            blockstore,
+            next_id: 0,
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Creates a new request to the given peer.
+    pub fn new_request(
+        &mut self,
+        peer: PeerId,
+        root: Cid,
+        selector: Selector,
+        extensions: Extensions,
+    ) -> GraphSyncRequest {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.requests.insert(
+            id,
+            OutgoingRequest {
+                peer,
+                root,
+                blocks: 0,
+            },
+        );
+        GraphSyncRequest::new(id, root, selector, DEFAULT_PRIORITY, Some(extensions))
+    }
+
+    /// Stops tracking the request, returns the peer and the message cancelling it.
+    pub fn cancel_request(&mut self, id: RequestID) -> Option<(PeerId, GraphSyncRequest)> {
+        self.requests
+            .remove(&id)
+            .map(|req| (req.peer, GraphSyncRequest::cancel(id)))
+    }
+
+    /// Fails the requests sent to a peer which disconnected.
+    pub fn peer_disconnected(&mut self, peer: &PeerId) -> Vec<RequestResult> {
+        let ids: Vec<RequestID> = self
+            .requests
+            .iter()
+            .filter(|(_, req)| &req.peer == peer)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| self.finish(id, ResponseStatusCode::RequestFailedUnknown))
+            .collect()
+    }
+
+    /// Processes the responses and blocks of a message received from a peer. Returns the
+    /// results of the requests which it terminated.
+    pub fn process_message(
+        &mut self,
+        peer: &PeerId,
+        message: &GraphSyncMessage,
+    ) -> Vec<RequestResult> {
+        let mut links: HashMap<Cid, RequestID> = HashMap::new();
+        let mut missing: HashSet<Cid> = HashSet::new();
+        for (id, response) in message.responses() {
+            match self.requests.get(id) {
+                Some(req) if &req.peer == peer => (),
+                _ => {
+                    debug!("Ignoring response to unknown request {} from {}", id, peer);
+                    continue;
+                }
+            }
+            let metadata = match response.extensions.get(EXTENSION_METADATA) {
+                Some(bz) => match encoding::from_slice::<Vec<MetadataItem>>(bz) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        warn!("Invalid metadata in response from {}: {}", peer, e);
+                        continue;
+                    }
+                },
+                None => continue,
+            };
+            for item in metadata {
+                if item.block_is_present {
+                    links.insert(item.link, *id);
+                } else {
+                    missing.insert(item.link);
+                }
+            }
+        }
+        if !missing.is_empty() {
+            debug!("Peer {} is missing {} blocks", peer, missing.len());
+        }
+
+        for (cid, data) in message.blocks() {
+            let id = match links.get(cid) {
+                Some(id) => *id,
+                None => {
+                    debug!("Dropping unrequested block {} from {}", cid, peer);
+                    continue;
+                }
+            };
+            if let Err(e) = self.blockstore.write(cid.to_bytes(), data) {
+                warn!("Failed to write block {}: {}", cid, e);
+                continue;
+            }
+            if let Some(req) = self.requests.get_mut(&id) {
+                req.blocks += 1;
+            }
+        }
+
+        message
+            .responses()
+            .iter()
+            .filter(|(id, response)| {
+                response.status.is_terminal()
+                    && self.requests.get(id).map_or(false, |req| &req.peer == peer)
+            })
+            .map(|(id, response)| (*id, response.status))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|(id, status)| self.finish(id, status))
+            .collect()
+    }
+
+    fn finish(&mut self, id: RequestID, status: ResponseStatusCode) -> Option<RequestResult> {
+        self.requests.remove(&id).map(|req| RequestResult {
+            peer: req.peer,
+            id,
+            root: req.root,
+            status,
+            blocks: req.blocks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphSyncResponse;
+    use cid::Code::Blake2b256;
+    use db::{MemoryDB, Store};
+
+    fn response(id: RequestID, status: ResponseStatusCode, links: &[Cid]) -> GraphSyncResponse {
+        let metadata: Vec<MetadataItem> = links
+            .iter()
+            .map(|link| MetadataItem {
+                link: *link,
+                block_is_present: true,
+            })
+            .collect();
+        let mut extensions = Extensions::new();
+        extensions.insert(
+            EXTENSION_METADATA.to_owned(),
+            encoding::to_vec(&metadata).unwrap(),
+        );
+        GraphSyncResponse::new(id, status, Some(extensions))
+    }
+
+    #[test]
+    fn stores_listed_blocks() {
+        let db = Arc::new(MemoryDB::default());
+        let mut manager = RequestManager::new(db.clone());
+        let peer = PeerId::random();
+
+        let block = vec![1, 2, 3];
+        let link = cid::new_from_cbor(&block, Blake2b256);
+        let other = vec![4, 5, 6];
+        let other_link = cid::new_from_cbor(&other, Blake2b256);
+
+        let req = manager.new_request(peer, link, Selector::Matcher, Extensions::new());
+
+        let mut message = GraphSyncMessage::default();
+        message.insert_response(response(
+            req.id,
+            ResponseStatusCode::RequestCompletedFull,
+            &[link],
+        ));
+        message.insert_block(link, block.clone());
+        message.insert_block(other_link, other);
+
+        // responses from another peer are ignored
+        assert!(manager
+            .process_message(&PeerId::random(), &message)
+            .is_empty());
+        assert!(!db.exists(link.to_bytes()).unwrap());
+
+        let results = manager.process_message(&peer, &message);
+        assert_eq!(
+            results,
+            vec![RequestResult {
+                peer,
+                id: req.id,
+                root: link,
+                status: ResponseStatusCode::RequestCompletedFull,
+                blocks: 1,
+            }]
+        );
+        assert_eq!(db.read(link.to_bytes()).unwrap(), Some(block));
+        assert!(!db.exists(other_link.to_bytes()).unwrap());
+        assert!(manager.cancel_request(req.id).is_none());
+    }
+}
@@ -50,10 +50,7 @@ impl LinkTracker {
     /// Records that we traversed a link during a request, and whether we had the block when we did it.
     pub fn record_link_traversal(&mut self, id: RequestID, link: Cid, block_is_present: bool) {
         if block_is_present {
-            self.present_blocks
-                .entry(id)
-                .or_default()
-                .push(link.clone());
+            self.present_blocks.entry(id).or_default().push(link);
             *self.in_progress_traversal_counts.entry(link).or_insert(0) += 1;
         } else {
             self.missing_blocks.entry(id).or_default().insert(link);
@@ -105,7 +102,7 @@ mod tests {
             let mut link_tracker = LinkTracker::new();
             for (id, request) in (0..).zip(requests) {
                 for &block_is_present in request.traversals {
-                    link_tracker.record_link_traversal(id, link.clone(), block_is_present);
+                    link_tracker.record_link_traversal(id, link, block_is_present);
                 }
                 if request.is_finished {
                     link_tracker.finish_request(id);
@@ -229,7 +226,7 @@ mod tests {
                 block_is_present,
             } in traversals
             {
-                link_tracker.record_link_traversal(request_id, link.clone(), block_is_present);
+                link_tracker.record_link_traversal(request_id, link, block_is_present);
             }
             link_tracker.finish_request(request_id)
         };
@@ -273,7 +270,7 @@ mod tests {
             let link = test_utils::random_cid();
 
             for &block_is_present in traversals {
-                link_tracker.record_link_traversal(request_id, link.clone(), block_is_present);
+                link_tracker.record_link_traversal(request_id, link, block_is_present);
             }
             link_tracker.is_known_missing_link(request_id, &link)
         };
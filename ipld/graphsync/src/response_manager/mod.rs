@@ -8,166 +8,351 @@ mod peer_response_sender;
 mod response_builder;
 
 use link_tracker::LinkTracker;
-use peer_response_sender::{PeerMessageHandler, PeerResponseSender};
+pub use peer_response_sender::PeerMessageHandler;
+use peer_response_sender::PeerResponseSender;
 use response_builder::ResponseBuilder;
 
 use super::{
     Extensions, GraphSyncRequest, NewRequestPayload, Payload, RequestID, ResponseStatusCode,
+    EXTENSION_DO_NOT_SEND_CIDS,
 };
+use async_std::sync::Mutex;
+use async_std::task;
 use async_trait::async_trait;
-use cid::Cid;
+use cid::{Cid, DAG_CBOR, RAW};
 use forest_ipld::{selector::LinkResolver, Ipld};
 use ipld_blockstore::BlockStore;
 use libp2p::core::PeerId;
-use std::{collections::HashMap, sync::Arc};
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Error returned by the loader to stop the traversal of a cancelled request.
+const REQUEST_CANCELLED: &str = "request cancelled";
 
 /// Handles incoming graphsync requests from the network, initiates selector traversals, and transmits responses.
-pub struct ResponseManager {
-    peer_response_senders: HashMap<PeerId, PeerResponseSender>,
+pub struct ResponseManager<BS> {
+    blockstore: Arc<BS>,
+    peer_response_senders: HashMap<PeerId, Arc<Mutex<PeerResponseSender>>>,
+    /// Requests being executed, with the flag used to cancel them.
+    in_progress: HashMap<(PeerId, RequestID), Arc<AtomicBool>>,
 }
 
-impl ResponseManager {
-    /// Creates a new response manager.
-    pub fn new() -> Self {
+impl<BS> ResponseManager<BS>
+where
+    BS: BlockStore + Send + Sync + 'static,
+{
+    /// Creates a new response manager which answers requests from the given blockstore.
+    pub fn new(blockstore: Arc<BS>) -> Self {
         Self {
+            blockstore,
             peer_response_senders: HashMap::new(),
+            in_progress: HashMap::new(),
         }
     }
 
     /// Returns the response sender associated with the given peer.
-    fn sender_for_peer(&mut self, peer: PeerId) -> &mut PeerResponseSender {
+    fn sender_for_peer(&mut self, peer: PeerId) -> Arc<Mutex<PeerResponseSender>> {
         self.peer_response_senders
-            .entry(peer.clone())
-            .or_insert_with(|| PeerResponseSender::new(peer))
+            .entry(peer)
+            .or_insert_with(|| Arc::new(Mutex::new(PeerResponseSender::new(peer))))
+            .clone()
     }
 
-    /// Executes the given request.
-    pub async fn execute_request<L, H>(
-        &mut self,
-        peer: PeerId,
-        request: GraphSyncRequest,
-        loader: L,
-        handler: &mut H,
-    ) -> Result<(), String>
+    /// Forgets about the response sender of a disconnected peer and cancels its requests.
+    pub fn peer_disconnected(&mut self, peer: &PeerId) {
+        self.peer_response_senders.remove(peer);
+        self.in_progress.retain(|(p, _), cancelled| {
+            if p == peer {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            p != peer
+        });
+    }
+
+    /// Executes the given request. New requests are traversed in a background task, the
+    /// responses are passed to the handler as they are built.
+    pub fn execute_request<H>(&mut self, peer: PeerId, request: GraphSyncRequest, handler: H)
     where
-        L: LinkResolver + Send + Sync,
-        H: PeerMessageHandler,
+        H: PeerMessageHandler + Clone + Send + Sync + 'static,
     {
         match request.payload {
-            Payload::New(payload) => {
-                self.new_request(peer, request.id, payload, loader, handler)
-                    .await
-            }
-            Payload::Update { extensions } => self.update_request(request.id, extensions).await,
-            Payload::Cancel => self.cancel_request(request.id).await,
+            Payload::New(payload) => self.new_request(peer, request.id, payload, handler),
+            Payload::Update { extensions } => self.update_request(peer, request.id, extensions),
+            Payload::Cancel => self.cancel_request(peer, request.id),
         }
     }
 
-    /// Executes a new request.
-    async fn new_request<L, H>(
+    /// Starts the traversal of a new request.
+    fn new_request<H>(
         &mut self,
-        peer_id: PeerId,
+        peer: PeerId,
         request_id: RequestID,
         payload: NewRequestPayload,
-        mut loader: L,
-        handler: &mut H,
-    ) -> Result<(), String>
-    where
-        L: LinkResolver + Send + Sync,
-        H: PeerMessageHandler,
+        handler: H,
+    ) where
+        H: PeerMessageHandler + Clone + Send + Sync + 'static,
     {
-        // TODO: look for the do-not-send-cids extension
-        let NewRequestPayload { root, selector, .. } = payload;
-        let sender = self.sender_for_peer(peer_id);
-
-        let ipld: Ipld = match loader.load_link(&root).await? {
-            Some(ipld) => ipld,
-            None => {
-                sender.finish_request_with_error(
-                    request_id,
-                    ResponseStatusCode::RequestFailedContentNotFound,
+        // Drop the flags of the requests whose traversal has ended
+        self.in_progress
+            .retain(|_, cancelled| Arc::strong_count(cancelled) > 1);
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self
+            .in_progress
+            .insert((peer, request_id), cancelled.clone())
+        {
+            warn!(
+                "Peer {} reused the id {} of an ongoing request, cancelling it",
+                peer, request_id
+            );
+            previous.store(true, Ordering::Relaxed);
+        }
+
+        let sender = self.sender_for_peer(peer);
+        let blockstore = self.blockstore.clone();
+        task::spawn(async move {
+            let res = respond(blockstore, sender, handler, request_id, payload, cancelled).await;
+            if let Err(e) = res {
+                warn!(
+                    "Failed to respond to request {} of {}: {}",
+                    request_id, peer, e
                 );
-                return sender.flush(handler).await;
             }
-        };
-
-        let intercepted = InterceptedLoader::new(loader, |cid, block| {
-            let data = block
-                .map(|ipld| forest_encoding::to_vec(ipld))
-                .transpose()
-                .map_err(|e| e.to_string())?;
-            sender.send_response(request_id, cid.clone(), data);
-            Ok(())
         });
-
-        // we ignore the callback parameters because we're only interested in the
-        // loaded blocks, which the intercepted loader takes care of
-        selector
-            .walk_all(&ipld, Some(intercepted), |_, _, _| Ok(()))
-            .await
-            .map_err(|e| e.to_string())?;
-        sender.flush(handler).await
     }
 
     /// Updates an ongoing request.
-    async fn update_request(
-        &mut self,
-        _id: RequestID,
-        _extensions: Extensions,
-    ) -> Result<(), String> {
-        // we can't implement this yet because requests are currently executed in one go
-        todo!()
+    fn update_request(&mut self, peer: PeerId, id: RequestID, _extensions: Extensions) {
+        // None of the supported extensions can be changed once a request has started
+        debug!("Ignoring update of request {} from {}", id, peer);
     }
 
-    /// Cancels an ongoing request.
-    async fn cancel_request(&mut self, _id: RequestID) -> Result<(), String> {
-        // we can't implement this yet because requests are currently executed in one go
-        todo!()
+    /// Cancels an ongoing request, the traversal stops at the next link it loads.
+    fn cancel_request(&mut self, peer: PeerId, id: RequestID) {
+        if let Some(cancelled) = self.in_progress.remove(&(peer, id)) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
     }
 }
 
-/// A block loader that wraps another loader and calls a callback whenever
-/// a link is loaded with the cid and the corresponding block.
-struct InterceptedLoader<L, F> {
-    loader: L,
-    f: F,
-}
-
-impl<L, F> InterceptedLoader<L, F>
+/// Traverses the selector of the request from its root, sending the blocks of the links it
+/// loads.
+async fn respond<BS, H>(
+    blockstore: Arc<BS>,
+    sender: Arc<Mutex<PeerResponseSender>>,
+    mut handler: H,
+    request_id: RequestID,
+    payload: NewRequestPayload,
+    cancelled: Arc<AtomicBool>,
+) -> Result<(), String>
 where
-    L: LinkResolver + Send + Sync,
-    F: FnMut(&Cid, Option<&Ipld>) -> Result<(), String> + Send + Sync,
+    BS: BlockStore + Send + Sync,
+    H: PeerMessageHandler + Clone + Send + Sync,
 {
-    fn new(loader: L, f: F) -> Self {
-        Self { loader, f }
+    let NewRequestPayload {
+        root,
+        selector,
+        extensions,
+        ..
+    } = payload;
+
+    let do_not_send = match extensions.get(EXTENSION_DO_NOT_SEND_CIDS) {
+        Some(bz) => encoding::from_slice::<Vec<Cid>>(bz)
+            .map_err(|e| format!("invalid do-not-send-cids extension: {}", e))?
+            .into_iter()
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    if !blockstore
+        .exists(root.to_bytes())
+        .map_err(|e| e.to_string())?
+    {
+        let mut sender = sender.lock().await;
+        sender.finish_request_with_error(
+            request_id,
+            ResponseStatusCode::RequestFailedContentNotFound,
+        );
+        return sender.flush(&mut handler).await;
     }
-}
 
-#[async_trait]
-impl<L, F> LinkResolver for InterceptedLoader<L, F>
-where
-    L: LinkResolver + Send + Sync,
-    F: FnMut(&Cid, Option<&Ipld>) -> Result<(), String> + Send + Sync,
-{
-    async fn load_link(&mut self, link: &Cid) -> Result<Option<Ipld>, String> {
-        let ipld = self.loader.load_link(link).await?;
-        (self.f)(link, ipld.as_ref())?;
-        Ok(ipld)
+    let loader = BlockStoreLoader {
+        blockstore,
+        sender: sender.clone(),
+        handler: handler.clone(),
+        request_id,
+        do_not_send,
+        cancelled: cancelled.clone(),
+    };
+    // The loaded blocks are sent by the loader, the nodes visited are of no interest here
+    let res = selector
+        .walk_all(&Ipld::Link(root), Some(loader), |_, _, _| Ok(()))
+        .await;
+
+    let mut sender = sender.lock().await;
+    if cancelled.load(Ordering::Relaxed) {
+        sender.cancel_request(request_id);
+        return Ok(());
+    }
+    match res {
+        Ok(()) => {
+            sender.finish_request(request_id);
+        }
+        Err(e) => {
+            debug!("Traversal of request {} failed: {}", request_id, e);
+            sender.finish_request_with_error(request_id, ResponseStatusCode::RequestFailedUnknown);
+        }
     }
+    sender.flush(&mut handler).await
 }
 
-/// A block loader that loads the blocks from a blockstore.
-// TODO: put this type somewhere else, graphsync doesn't need to know about blockstores
-struct BlockStoreLoader<BS> {
+/// A block loader that loads the blocks from a blockstore and adds them to the responses of a
+/// request as they are traversed.
+struct BlockStoreLoader<BS, H> {
     blockstore: Arc<BS>,
+    sender: Arc<Mutex<PeerResponseSender>>,
+    handler: H,
+    request_id: RequestID,
+    /// Links whose blocks the requester already has.
+    do_not_send: HashSet<Cid>,
+    cancelled: Arc<AtomicBool>,
 }
 
 #[async_trait]
-impl<BS> LinkResolver for BlockStoreLoader<BS>
+impl<BS, H> LinkResolver for BlockStoreLoader<BS, H>
 where
     BS: BlockStore + Send + Sync,
+    H: PeerMessageHandler + Send + Sync,
 {
     async fn load_link(&mut self, link: &Cid) -> Result<Option<Ipld>, String> {
-        self.blockstore.get(link).map_err(|e| e.to_string())
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(REQUEST_CANCELLED.to_owned());
+        }
+        let block = self.blockstore.get_bytes(link).map_err(|e| e.to_string())?;
+
+        let mut sender = self.sender.lock().await;
+        if self.do_not_send.contains(link) && block.is_some() {
+            sender.skip_block(self.request_id, *link);
+        } else {
+            sender.send_response(self.request_id, *link, block.clone());
+        }
+        if sender.has_full_response() {
+            sender.flush(&mut self.handler).await?;
+        }
+        drop(sender);
+
+        block.map(|bz| decode_block(link, bz)).transpose()
+    }
+}
+
+/// Decodes a block into Ipld, according to the codec of its link.
+pub(crate) fn decode_block(link: &Cid, block: Vec<u8>) -> Result<Ipld, String> {
+    match link.codec() {
+        DAG_CBOR => encoding::from_slice(&block).map_err(|e| e.to_string()),
+        RAW => Ok(Ipld::Bytes(block)),
+        codec => Err(format!("unsupported codec {:#x} for link {}", codec, link)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphSyncResponse;
+    use cid::Code::Blake2b256;
+    use db::MemoryDB;
+    use forest_ipld::selector::Selector;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use std::collections::BTreeMap;
+
+    type Message = (Vec<GraphSyncResponse>, Vec<(Cid, Vec<u8>)>);
+
+    #[derive(Clone)]
+    struct Handler(mpsc::UnboundedSender<Message>);
+
+    #[async_trait]
+    impl PeerMessageHandler for Handler {
+        async fn send_response(
+            &mut self,
+            _peer: &PeerId,
+            responses: Vec<GraphSyncResponse>,
+            blocks: Vec<(Cid, Vec<u8>)>,
+        ) {
+            self.0.unbounded_send((responses, blocks)).unwrap();
+        }
+    }
+
+    /// Stores a root block linking to two leaves, returns the root and the leaves.
+    fn store_dag(db: &MemoryDB) -> (Cid, Vec<Cid>) {
+        let leaves: Vec<Cid> = (0..2u8)
+            .map(|i| db.put(&vec![i; 4], Blake2b256).unwrap())
+            .collect();
+        let root: BTreeMap<String, Ipld> = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i.to_string(), Ipld::Link(*c)))
+            .collect();
+        (db.put(&Ipld::Map(root), Blake2b256).unwrap(), leaves)
+    }
+
+    #[async_std::test]
+    async fn respond_with_traversed_blocks() {
+        let db = Arc::new(MemoryDB::default());
+        let (root, leaves) = store_dag(&db);
+        let mut manager = ResponseManager::new(db);
+
+        let (tx, mut rx) = mpsc::unbounded();
+        let mut extensions = Extensions::new();
+        extensions.insert(
+            EXTENSION_DO_NOT_SEND_CIDS.to_owned(),
+            encoding::to_vec(&vec![leaves[1]]).unwrap(),
+        );
+        let selector = Selector::ExploreAll {
+            next: Box::new(Selector::Matcher),
+        };
+        manager.execute_request(
+            PeerId::random(),
+            GraphSyncRequest::new(0, root, selector, 0, Some(extensions)),
+            Handler(tx),
+        );
+
+        let (responses, blocks) = rx.next().await.unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(
+            responses[0].status,
+            ResponseStatusCode::RequestCompletedFull
+        );
+        let mut sent: Vec<Cid> = blocks.into_iter().map(|(c, _)| c).collect();
+        sent.sort();
+        let mut expected = vec![root, leaves[0]];
+        expected.sort();
+        assert_eq!(sent, expected);
+    }
+
+    #[async_std::test]
+    async fn respond_content_not_found() {
+        let mut manager = ResponseManager::new(Arc::new(MemoryDB::default()));
+
+        let (tx, mut rx) = mpsc::unbounded();
+        manager.execute_request(
+            PeerId::random(),
+            GraphSyncRequest::new(
+                0,
+                cid::new_from_cbor(&[1, 2, 3], Blake2b256),
+                Selector::Matcher,
+                0,
+                None,
+            ),
+            Handler(tx),
+        );
+
+        let (responses, blocks) = rx.next().await.unwrap();
+        assert_eq!(
+            responses[0].status,
+            ResponseStatusCode::RequestFailedContentNotFound
+        );
+        assert!(blocks.is_empty());
     }
 }
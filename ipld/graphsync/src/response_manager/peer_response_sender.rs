@@ -35,19 +35,25 @@ impl PeerResponseSender {
         // there's no need to send it again
         let block = data.filter(|_| self.link_tracker.block_ref_count(&link) == 0);
         self.link_tracker
-            .record_link_traversal(id, link.clone(), block_is_present);
+            .record_link_traversal(id, link, block_is_present);
 
         let builder = self.response_builder(block_size);
         builder.add_link(id, link, block_is_present);
 
         if let Some(block) = block {
-            builder.add_block(block);
+            builder.add_block(link, block);
             true
         } else {
             false
         }
     }
 
+    /// Sends a link for which the requester already has the block, as listed in the
+    /// do-not-send-cids extension. The link is reported as present but the block is not sent.
+    pub fn skip_block(&mut self, id: RequestID, link: Cid) {
+        self.response_builder(0).add_link(id, link, true);
+    }
+
     /// Adds the given extension data to to the response.
     pub fn send_extension_data(&mut self, id: RequestID, extension_data: ExtensionData) {
         // we pass 0 as the block size since we're not adding any blocks to the response
@@ -72,6 +78,17 @@ impl PeerResponseSender {
         self.response_builder(0).complete(id, status);
     }
 
+    /// Forgets about a cancelled request, no further response is sent for it.
+    pub fn cancel_request(&mut self, id: RequestID) {
+        self.link_tracker.finish_request(id);
+    }
+
+    /// Returns true if a response is full, in which case it can be flushed without waiting
+    /// for the end of the request.
+    pub fn has_full_response(&self) -> bool {
+        self.response_builders.len() > 1
+    }
+
     /// Marks the given request ID as paused.
     pub fn pause_request(&mut self, id: RequestID) {
         self.response_builder(0)
@@ -113,7 +130,7 @@ pub trait PeerMessageHandler {
         &mut self,
         peer: &PeerId,
         responses: Vec<GraphSyncResponse>,
-        blocks: Vec<Vec<u8>>,
+        blocks: Vec<(Cid, Vec<u8>)>,
     );
 }
 
@@ -122,14 +139,16 @@ mod tests {
     use super::*;
     use crate::test_utils;
 
-    struct Handler(Vec<(Vec<GraphSyncResponse>, Vec<Vec<u8>>)>);
+    type Message = (Vec<GraphSyncResponse>, Vec<(Cid, Vec<u8>)>);
+
+    struct Handler(Vec<Message>);
 
     impl Handler {
         fn new() -> Self {
             Self(Vec::new())
         }
 
-        fn take(&mut self) -> Vec<(Vec<GraphSyncResponse>, Vec<Vec<u8>>)> {
+        fn take(&mut self) -> Vec<Message> {
             std::mem::take(&mut self.0)
         }
     }
@@ -140,7 +159,7 @@ mod tests {
             &mut self,
             _peer: &PeerId,
             responses: Vec<GraphSyncResponse>,
-            blocks: Vec<Vec<u8>>,
+            blocks: Vec<(Cid, Vec<u8>)>,
         ) {
             self.0.push((responses, blocks));
         }
@@ -155,7 +174,7 @@ mod tests {
         let request_ids = [0, 1, 2];
         let (data, links) = test_utils::random_blocks(5, 100);
 
-        let is_sent = sender.send_response(request_ids[0], links[0].clone(), Some(data[0].clone()));
+        let is_sent = sender.send_response(request_ids[0], links[0], Some(data[0].clone()));
         assert!(is_sent);
 
         sender.flush(&mut handler).await.unwrap();
@@ -169,17 +188,17 @@ mod tests {
         assert_eq!(responses[0].status, ResponseStatusCode::PartialResponse);
 
         assert_eq!(blocks.len(), 1);
-        assert_eq!(blocks[0], data[0]);
+        assert_eq!(blocks[0].1, data[0]);
 
         // we traverse the same block as part of a different request while the first request
         // is still in progress, so this one should not be sent
-        let is_sent = sender.send_response(request_ids[1], links[0].clone(), Some(data[0].clone()));
+        let is_sent = sender.send_response(request_ids[1], links[0], Some(data[0].clone()));
         assert!(!is_sent);
 
-        let is_sent = sender.send_response(request_ids[0], links[1].clone(), Some(data[1].clone()));
+        let is_sent = sender.send_response(request_ids[0], links[1], Some(data[1].clone()));
         assert!(is_sent);
 
-        let is_sent = sender.send_response(request_ids[0], links[2].clone(), None);
+        let is_sent = sender.send_response(request_ids[0], links[2], None);
         assert!(!is_sent);
 
         sender.finish_request(request_ids[0]);
@@ -200,12 +219,12 @@ mod tests {
         assert_eq!(responses[1].status, ResponseStatusCode::PartialResponse);
 
         assert_eq!(blocks.len(), 1);
-        assert_eq!(blocks[0], data[1]);
+        assert_eq!(blocks[0].1, data[1]);
 
-        let is_sent = sender.send_response(request_ids[1], links[3].clone(), Some(data[3].clone()));
+        let is_sent = sender.send_response(request_ids[1], links[3], Some(data[3].clone()));
         assert!(is_sent);
 
-        let is_sent = sender.send_response(request_ids[2], links[4].clone(), Some(data[4].clone()));
+        let is_sent = sender.send_response(request_ids[2], links[4], Some(data[4].clone()));
         assert!(is_sent);
 
         sender.finish_request(request_ids[1]);
@@ -226,16 +245,16 @@ mod tests {
         assert_eq!(responses[1].status, ResponseStatusCode::PartialResponse);
 
         assert_eq!(blocks.len(), 2);
-        assert_eq!(blocks[0], data[3]);
-        assert_eq!(blocks[1], data[4]);
+        assert_eq!(blocks[0].1, data[3]);
+        assert_eq!(blocks[1].1, data[4]);
 
         // this block has already been sent to the peer but that request has already
         // been completed
-        let is_sent = sender.send_response(request_ids[2], links[0].clone(), Some(data[0].clone()));
+        let is_sent = sender.send_response(request_ids[2], links[0], Some(data[0].clone()));
         assert!(is_sent);
 
         // this block has already been sent to the peer, as part of the same request
-        let is_sent = sender.send_response(request_ids[2], links[4].clone(), Some(data[4].clone()));
+        let is_sent = sender.send_response(request_ids[2], links[4], Some(data[4].clone()));
         assert!(!is_sent);
 
         sender.flush(&mut handler).await.unwrap();
@@ -249,7 +268,7 @@ mod tests {
         assert_eq!(responses[0].status, ResponseStatusCode::PartialResponse);
 
         assert_eq!(blocks.len(), 1);
-        assert_eq!(blocks[0], data[0]);
+        assert_eq!(blocks[0].1, data[0]);
     }
 
     #[async_std::test]
@@ -262,7 +281,7 @@ mod tests {
         // just below the 512kb maximum block size, so each block is put in a separate message
         let (data, links) = test_utils::random_blocks(5, 500_000);
 
-        sender.send_response(request_id, links[0].clone(), Some(data[0].clone()));
+        sender.send_response(request_id, links[0], Some(data[0].clone()));
         sender.flush(&mut handler).await.unwrap();
 
         let mut messages = handler.take();
@@ -273,7 +292,7 @@ mod tests {
         assert_eq!(responses[0].status, ResponseStatusCode::PartialResponse);
 
         for i in 1..=4 {
-            sender.send_response(request_id, links[i].clone(), Some(data[i].clone()));
+            sender.send_response(request_id, links[i], Some(data[i].clone()));
         }
         sender.finish_request(request_id);
         sender.flush(&mut handler).await.unwrap();
@@ -291,7 +310,7 @@ mod tests {
             assert_eq!(responses[0].status, status);
 
             assert_eq!(blocks.len(), 1);
-            assert_eq!(blocks[0], data[i]);
+            assert_eq!(blocks[0].1, data[i]);
         }
     }
 
@@ -304,7 +323,7 @@ mod tests {
         let request_id = 0;
         let (data, links) = test_utils::random_blocks(2, 100);
 
-        sender.send_response(request_id, links[0].clone(), Some(data[0].clone()));
+        sender.send_response(request_id, links[0], Some(data[0].clone()));
         sender.flush(&mut handler).await.unwrap();
 
         let mut messages = handler.take();
@@ -315,7 +334,7 @@ mod tests {
         assert_eq!(responses[0].status, ResponseStatusCode::PartialResponse);
 
         assert_eq!(blocks.len(), 1);
-        assert_eq!(blocks[0], data[0]);
+        assert_eq!(blocks[0].1, data[0]);
 
         let extension1 = ExtensionData {
             name: "AppleSauce/McGee".to_string(),
@@ -327,7 +346,7 @@ mod tests {
             data: test_utils::random_bytes(100),
         };
 
-        sender.send_response(request_id, links[1].clone(), Some(data[1].clone()));
+        sender.send_response(request_id, links[1], Some(data[1].clone()));
         sender.send_extension_data(request_id, extension1.clone());
         sender.send_extension_data(request_id, extension2.clone());
         sender.flush(&mut handler).await.unwrap();
@@ -13,8 +13,8 @@ use fnv::FnvHashMap;
 /// message components once responses are ready to send.
 #[derive(Default)]
 pub struct ResponseBuilder {
-    /// The actual blocks that will be sent to the peer.
-    blocks: Vec<Vec<u8>>,
+    /// The actual blocks that will be sent to the peer, with their links.
+    blocks: Vec<(Cid, Vec<u8>)>,
 
     /// The combined block size of this message, i.e. the sum of the lengths
     /// of all included blocks.
@@ -46,9 +46,9 @@ impl ResponseBuilder {
     }
 
     /// Adds the given block to the message.
-    pub fn add_block(&mut self, block: Vec<u8>) {
+    pub fn add_block(&mut self, link: Cid, block: Vec<u8>) {
         self.block_size += block.len();
-        self.blocks.push(block);
+        self.blocks.push((link, block));
     }
 
     /// Adds the given link and whether its block is present to the response for
@@ -88,7 +88,8 @@ impl ResponseBuilder {
     }
 
     /// Assembles and encodes response data from the added requests, links, and blocks.
-    pub fn build(self) -> Result<(Vec<GraphSyncResponse>, Vec<Vec<u8>>), String> {
+    #[allow(clippy::type_complexity)]
+    pub fn build(self) -> Result<(Vec<GraphSyncResponse>, Vec<(Cid, Vec<u8>)>), String> {
         let mut extensions = self.extensions;
         let completed_responses = self.completed_responses;
 
@@ -96,7 +97,7 @@ impl ResponseBuilder {
             .outgoing_responses
             .into_iter()
             .map(|(id, metadata)| {
-                let metadata = forest_encoding::to_vec(&metadata).map_err(|e| e.to_string())?;
+                let metadata = encoding::to_vec(&metadata).map_err(|e| e.to_string())?;
                 let mut extensions = extensions.remove(&id).unwrap_or_default();
                 extensions.insert(EXTENSION_METADATA.to_string(), metadata);
                 let status = completed_responses
@@ -128,23 +129,23 @@ mod tests {
         let (data, links) = test_utils::random_blocks(3, 100);
         let request_ids = [0, 1, 2, 3];
 
-        builder.add_link(request_ids[0], links[0].clone(), true);
-        builder.add_link(request_ids[0], links[1].clone(), false);
-        builder.add_link(request_ids[0], links[2].clone(), true);
+        builder.add_link(request_ids[0], links[0], true);
+        builder.add_link(request_ids[0], links[1], false);
+        builder.add_link(request_ids[0], links[2], true);
         builder.complete(request_ids[0], ResponseStatusCode::RequestCompletedPartial);
 
-        builder.add_link(request_ids[1], links[1].clone(), true);
-        builder.add_link(request_ids[1], links[2].clone(), true);
-        builder.add_link(request_ids[1], links[1].clone(), true);
+        builder.add_link(request_ids[1], links[1], true);
+        builder.add_link(request_ids[1], links[2], true);
+        builder.add_link(request_ids[1], links[1], true);
         builder.complete(request_ids[1], ResponseStatusCode::RequestCompletedFull);
 
-        builder.add_link(request_ids[2], links[0].clone(), true);
-        builder.add_link(request_ids[2], links[1].clone(), true);
+        builder.add_link(request_ids[2], links[0], true);
+        builder.add_link(request_ids[2], links[1], true);
 
         builder.complete(request_ids[3], ResponseStatusCode::RequestCompletedFull);
 
-        for block in &data {
-            builder.add_block(block.clone());
+        for (block, link) in data.iter().zip(&links) {
+            builder.add_block(*link, block.clone());
         }
 
         assert_eq!(builder.block_size(), 300);
@@ -163,7 +164,7 @@ mod tests {
         builder.add_extension_data(request_ids[2], extension2.clone());
 
         let (mut responses, blocks) = builder.build().unwrap();
-        assert_eq!(blocks, data);
+        assert_eq!(blocks.into_iter().map(|(_, b)| b).collect::<Vec<_>>(), data);
         assert_eq!(responses.len(), 4);
         responses.sort_by_key(|r| r.id);
 
@@ -184,59 +185,53 @@ mod tests {
         assert_eq!(response4.status, ResponseStatusCode::RequestCompletedFull);
 
         assert_eq!(
-            forest_encoding::from_slice::<Vec<MetadataItem>>(
-                &response1.extensions[EXTENSION_METADATA]
-            )
-            .unwrap(),
+            encoding::from_slice::<Vec<MetadataItem>>(&response1.extensions[EXTENSION_METADATA])
+                .unwrap(),
             &[
                 MetadataItem {
-                    link: links[0].clone(),
+                    link: links[0],
                     block_is_present: true
                 },
                 MetadataItem {
-                    link: links[1].clone(),
+                    link: links[1],
                     block_is_present: false
                 },
                 MetadataItem {
-                    link: links[2].clone(),
+                    link: links[2],
                     block_is_present: true
                 }
             ]
         );
 
         assert_eq!(
-            forest_encoding::from_slice::<Vec<MetadataItem>>(
-                &response2.extensions[EXTENSION_METADATA]
-            )
-            .unwrap(),
+            encoding::from_slice::<Vec<MetadataItem>>(&response2.extensions[EXTENSION_METADATA])
+                .unwrap(),
             &[
                 MetadataItem {
-                    link: links[1].clone(),
+                    link: links[1],
                     block_is_present: true
                 },
                 MetadataItem {
-                    link: links[2].clone(),
+                    link: links[2],
                     block_is_present: true
                 },
                 MetadataItem {
-                    link: links[1].clone(),
+                    link: links[1],
                     block_is_present: true
                 }
             ]
         );
 
         assert_eq!(
-            forest_encoding::from_slice::<Vec<MetadataItem>>(
-                &response3.extensions[EXTENSION_METADATA]
-            )
-            .unwrap(),
+            encoding::from_slice::<Vec<MetadataItem>>(&response3.extensions[EXTENSION_METADATA])
+                .unwrap(),
             &[
                 MetadataItem {
-                    link: links[0].clone(),
+                    link: links[0],
                     block_is_present: true
                 },
                 MetadataItem {
-                    link: links[1].clone(),
+                    link: links[1],
                     block_is_present: true
                 },
             ]
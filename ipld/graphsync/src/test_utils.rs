@@ -1,8 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use cid::Cid;
-use Code::Blake2b256;
+use cid::{Cid, Code::Blake2b256};
 use rand::{thread_rng, Rng};
 use std::iter;
 
@@ -17,11 +16,11 @@ pub fn random_blocks(len: usize, block_size: usize) -> (Vec<Vec<u8>>, Vec<Cid>)
         .collect();
     let links = blocks
         .iter()
-        .map(|block| Cid::new_from_cbor(block, Blake2b256))
+        .map(|block| cid::new_from_cbor(block, Blake2b256))
         .collect();
     (blocks, links)
 }
 
 pub fn random_cid() -> Cid {
-    Cid::new_from_cbor(&random_bytes(16), Blake2b256)
+    cid::new_from_cbor(&random_bytes(16), Blake2b256)
 }
@@ -1,7 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use cid::{Code::Blake2b256, Cid};
+use cid::Code::Blake2b256;
 use forest_ipld::selector::Selector;
 use graphsync::{proto, GraphSyncMessage, GraphSyncRequest, GraphSyncResponse, ResponseStatusCode};
 use protobuf::{parse_from_bytes, Message};
@@ -44,7 +44,7 @@ fn requests_message() {
     ));
     message.insert_request(GraphSyncRequest::new(
         3,
-        Cid::new_from_cbor(&[1, 2, 3], Blake2b256),
+        cid::new_from_cbor(&[1, 2, 3], Blake2b256),
         Selector::Matcher,
         5,
         None,
@@ -79,7 +79,7 @@ fn responses_message() {
 fn blocks_message() {
     let mut message = GraphSyncMessage::default();
     let data = vec![6, 5, 4, 8, 0xff];
-    message.insert_block(Cid::new_from_cbor(&data, Blake2b256), data);
+    message.insert_block(cid::new_from_cbor(&data, Blake2b256), data);
 
     roundtrip_test(message).unwrap();
 }
@@ -90,7 +90,7 @@ fn all_message() {
     // GraphSync message.
     let mut message = GraphSyncMessage::default();
     let data = vec![6, 5, 4, 8, 0xff];
-    message.insert_block(Cid::new_from_cbor(&data, Blake2b256), data);
+    message.insert_block(cid::new_from_cbor(&data, Blake2b256), data);
     message.insert_request(GraphSyncRequest::cancel(2));
     message.insert_response(GraphSyncResponse::new(
         4,
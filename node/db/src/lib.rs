@@ -13,6 +13,44 @@ pub mod sled;
 pub use errors::Error;
 pub use memory::MemoryDB;
 
+/// Namespace of the data store. Stores which support it keep each column in a separate
+/// keyspace, such as a RocksDB column family or a Sled tree, so that the columns can be tuned,
+/// inspected and pruned independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// Ipld blocks keyed by their cid. This is the column used by the plain [Store] methods.
+    Blocks,
+    /// Chain metadata, such as the head and genesis keys, and node configuration.
+    Metadata,
+    /// Indices built from the chain, which can be rebuilt from the blocks.
+    Indices,
+}
+
+impl Column {
+    /// All the columns of a store.
+    pub const ALL: [Column; 3] = [Column::Blocks, Column::Metadata, Column::Indices];
+
+    /// Name of the column, used to name its keyspace in the underlying database.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Blocks => "blocks",
+            Column::Metadata => "metadata",
+            Column::Indices => "indices",
+        }
+    }
+
+    /// Key under which a key of this column is stored by stores without separate keyspaces.
+    fn prefixed_key<K>(self, key: K) -> Vec<u8>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self {
+            Column::Blocks => key.as_ref().to_vec(),
+            _ => [self.name().as_bytes(), b"/", key.as_ref()].concat(),
+        }
+    }
+}
+
 /// Store interface used as a KV store implementation
 pub trait Store {
     /// Read single value from data store and return `None` if key doesn't exist.
@@ -62,4 +100,85 @@ pub trait Store {
     {
         keys.iter().try_for_each(|key| self.delete(key))
     }
+
+    /// Calls `f` with every key in the data store. Iteration stops at the first error returned.
+    fn for_each_key<F>(&self, _f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        Err(Error::Other(
+            "Key iteration is not supported by this store".to_owned(),
+        ))
+    }
+
+    /// Read single value from a column and return `None` if key doesn't exist.
+    ///
+    /// The column methods of stores without separate keyspaces prefix the keys of the columns
+    /// other than [Column::Blocks] with the column name.
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.read(column.prefixed_key(key))
+    }
+
+    /// Write a single value to a column.
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.write(column.prefixed_key(key), value)
+    }
+
+    /// Delete value at key in a column.
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.delete(column.prefixed_key(key))
+    }
+
+    /// Returns `Ok(true)` if key exists in a column.
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.exists(column.prefixed_key(key))
+    }
+
+    /// Write slice of KV pairs to a column.
+    fn bulk_write_column<K, V>(&self, column: Column, values: &[(K, V)]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        values
+            .iter()
+            .try_for_each(|(key, value)| self.write_column(column, key, value))
+    }
+
+    /// Bulk delete keys from a column.
+    fn bulk_delete_column<K>(&self, column: Column, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        keys.iter()
+            .try_for_each(|key| self.delete_column(column, key))
+    }
+
+    /// Calls `f` with every key of a column. Iteration stops at the first error returned.
+    fn for_each_key_column<F>(&self, column: Column, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        if column == Column::Blocks {
+            return self.for_each_key(f);
+        }
+        let prefix = column.prefixed_key(b"");
+        self.for_each_key(|key| match key.strip_prefix(prefix.as_slice()) {
+            Some(key) => f(key),
+            None => Ok(()),
+        })
+    }
 }
@@ -1,26 +1,14 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::{Error, Store};
+use super::{Column, Error, Store};
 use parking_lot::RwLock;
-use std::collections::{hash_map::DefaultHasher, HashMap};
-use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
 
-/// A thread-safe `HashMap` wrapper.
+/// A thread-safe `HashMap` wrapper, with a separate map for each column.
 #[derive(Debug, Default)]
 pub struct MemoryDB {
-    db: RwLock<HashMap<u64, Vec<u8>>>,
-}
-
-impl MemoryDB {
-    fn db_index<K>(key: K) -> u64
-    where
-        K: AsRef<[u8]>,
-    {
-        let mut hasher = DefaultHasher::new();
-        key.as_ref().hash::<DefaultHasher>(&mut hasher);
-        hasher.finish()
-    }
+    db: RwLock<HashMap<Column, HashMap<Vec<u8>, Vec<u8>>>>,
 }
 
 impl Clone for MemoryDB {
@@ -37,31 +25,89 @@ impl Store for MemoryDB {
         K: AsRef<[u8]>,
         V: AsRef<[u8]>,
     {
-        self.db
-            .write()
-            .insert(Self::db_index(key), value.as_ref().to_vec());
-        Ok(())
+        self.write_column(Column::Blocks, key, value)
     }
 
     fn delete<K>(&self, key: K) -> Result<(), Error>
     where
         K: AsRef<[u8]>,
     {
-        self.db.write().remove(&Self::db_index(key));
-        Ok(())
+        self.delete_column(Column::Blocks, key)
     }
 
     fn read<K>(&self, key: K) -> Result<Option<Vec<u8>>, Error>
     where
         K: AsRef<[u8]>,
     {
-        Ok(self.db.read().get(&Self::db_index(key)).cloned())
+        self.read_column(Column::Blocks, key)
     }
 
     fn exists<K>(&self, key: K) -> Result<bool, Error>
     where
         K: AsRef<[u8]>,
     {
-        Ok(self.db.read().contains_key(&Self::db_index(key)))
+        self.exists_column(Column::Blocks, key)
+    }
+
+    fn for_each_key<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        self.for_each_key_column(Column::Blocks, f)
+    }
+
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self
+            .db
+            .read()
+            .get(&column)
+            .and_then(|db| db.get(key.as_ref()).cloned()))
+    }
+
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.db
+            .write()
+            .entry(column)
+            .or_default()
+            .insert(key.as_ref().to_vec(), value.as_ref().to_vec());
+        Ok(())
+    }
+
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        if let Some(db) = self.db.write().get_mut(&column) {
+            db.remove(key.as_ref());
+        }
+        Ok(())
+    }
+
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self
+            .db
+            .read()
+            .get(&column)
+            .map_or(false, |db| db.contains_key(key.as_ref())))
+    }
+
+    fn for_each_key_column<F>(&self, column: Column, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        match self.db.read().get(&column) {
+            Some(db) => db.keys().try_for_each(|key| f(key)),
+            None => Ok(()),
+        }
     }
 }
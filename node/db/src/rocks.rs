@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::errors::Error;
-use super::Store;
-pub use rocksdb::{Options, WriteBatch, DB};
+use super::{Column, Store};
+pub use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, DB};
 use std::path::Path;
 
 /// RocksDB instance this satisfies the [Store] interface.
@@ -25,12 +25,41 @@ impl RocksDb {
     where
         P: AsRef<Path>,
     {
-        let mut db_opts = Options::default();
+        Self::open_with_column_options(path, |_| Options::default())
+    }
+
+    /// Opens the database with the options returned by `column_options` for each column, which
+    /// allows tuning the compaction of each column separately. The blocks are kept in the
+    /// default column family, so databases created before columns were introduced can be read.
+    pub fn open_with_column_options<P, F>(path: P, column_options: F) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        F: Fn(Column) -> Options,
+    {
+        let mut db_opts = column_options(Column::Blocks);
         db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        let cfs = Column::ALL
+            .iter()
+            .filter(|column| **column != Column::Blocks)
+            .map(|column| ColumnFamilyDescriptor::new(column.name(), column_options(*column)));
         Ok(Self {
-            db: DB::open(&db_opts, path)?,
+            db: DB::open_cf_descriptors(&db_opts, path, cfs)?,
         })
     }
+
+    /// Returns the column family of a column, or `None` for the blocks, which are kept in the
+    /// default column family.
+    fn cf(&self, column: Column) -> Result<Option<&ColumnFamily>, Error> {
+        match column {
+            Column::Blocks => Ok(None),
+            _ => self
+                .db
+                .cf_handle(column.name())
+                .map(Some)
+                .ok_or_else(|| Error::Other(format!("missing column family {}", column.name()))),
+        }
+    }
 }
 
 impl Store for RocksDb {
@@ -61,6 +90,17 @@ impl Store for RocksDb {
         Ok(self.db.write(batch)?)
     }
 
+    fn bulk_delete<K>(&self, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let mut batch = WriteBatch::default();
+        for k in keys {
+            batch.delete(k);
+        }
+        Ok(self.db.write(batch)?)
+    }
+
     fn read<K>(&self, key: K) -> Result<Option<Vec<u8>>, Error>
     where
         K: AsRef<[u8]>,
@@ -77,4 +117,98 @@ impl Store for RocksDb {
             .map(|v| v.is_some())
             .map_err(Error::from)
     }
+
+    fn for_each_key<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        self.db
+            .iterator(IteratorMode::Start)
+            .try_for_each(|(key, _)| f(&key))
+    }
+
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self.cf(column)? {
+            Some(cf) => Ok(self.db.get_cf(cf, key)?),
+            None => self.read(key),
+        }
+    }
+
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        match self.cf(column)? {
+            Some(cf) => Ok(self.db.put_cf(cf, key, value)?),
+            None => self.write(key, value),
+        }
+    }
+
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self.cf(column)? {
+            Some(cf) => Ok(self.db.delete_cf(cf, key)?),
+            None => self.delete(key),
+        }
+    }
+
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self.cf(column)? {
+            Some(cf) => Ok(self.db.get_pinned_cf(cf, key)?.is_some()),
+            None => self.exists(key),
+        }
+    }
+
+    fn bulk_write_column<K, V>(&self, column: Column, values: &[(K, V)]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let cf = match self.cf(column)? {
+            Some(cf) => cf,
+            None => return self.bulk_write(values),
+        };
+        let mut batch = WriteBatch::default();
+        for (k, v) in values {
+            batch.put_cf(cf, k, v);
+        }
+        Ok(self.db.write(batch)?)
+    }
+
+    fn bulk_delete_column<K>(&self, column: Column, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let cf = match self.cf(column)? {
+            Some(cf) => cf,
+            None => return self.bulk_delete(keys),
+        };
+        let mut batch = WriteBatch::default();
+        for k in keys {
+            batch.delete_cf(cf, k);
+        }
+        Ok(self.db.write(batch)?)
+    }
+
+    fn for_each_key_column<F>(&self, column: Column, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        match self.cf(column)? {
+            Some(cf) => self
+                .db
+                .iterator_cf(cf, IteratorMode::Start)
+                .try_for_each(|(key, _)| f(&key)),
+            None => self.for_each_key(f),
+        }
+    }
 }
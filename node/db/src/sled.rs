@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::errors::Error;
-use super::Store;
-pub use sled::{Batch, Config, Db, Mode};
+use super::{Column, Store};
+pub use sled::{Batch, Config, Db, Mode, Tree};
 use std::path::Path;
 
 /// Sled instance this satisfies the [Store] interface.
@@ -48,6 +48,14 @@ impl SledDb {
             db: options.open()?,
         })
     }
+
+    /// Returns the tree of a column. The blocks are kept in the default tree.
+    fn tree(&self, column: Column) -> Result<Tree, Error> {
+        match column {
+            Column::Blocks => Ok((*self.db).clone()),
+            _ => Ok(self.db.open_tree(column.name())?),
+        }
+    }
 }
 
 impl Store for SledDb {
@@ -68,6 +76,17 @@ impl Store for SledDb {
         Ok(())
     }
 
+    fn bulk_delete<K>(&self, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let mut batch = Batch::default();
+        for k in keys {
+            batch.remove(k.as_ref());
+        }
+        Ok(self.db.apply_batch(batch)?)
+    }
+
     fn read<K>(&self, key: K) -> Result<Option<Vec<u8>>, Error>
     where
         K: AsRef<[u8]>,
@@ -81,4 +100,66 @@ impl Store for SledDb {
     {
         Ok(self.db.contains_key(key)?)
     }
+
+    fn for_each_key<F>(&self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        for key in self.db.iter().keys() {
+            f(&key?)?;
+        }
+        Ok(())
+    }
+
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.tree(column)?.get(key)?.map(|v| v.as_ref().into()))
+    }
+
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.tree(column)?.insert(key, value.as_ref())?;
+        Ok(())
+    }
+
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.tree(column)?.remove(key)?;
+        Ok(())
+    }
+
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.tree(column)?.contains_key(key)?)
+    }
+
+    fn bulk_delete_column<K>(&self, column: Column, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        let mut batch = Batch::default();
+        for k in keys {
+            batch.remove(k.as_ref());
+        }
+        Ok(self.tree(column)?.apply_batch(batch)?)
+    }
+
+    fn for_each_key_column<F>(&self, column: Column, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        for key in self.tree(column)?.iter().keys() {
+            f(&key?)?;
+        }
+        Ok(())
+    }
 }
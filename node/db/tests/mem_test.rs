@@ -52,3 +52,15 @@ fn mem_db_bulk_delete() {
     let db = MemoryDB::default();
     subtests::bulk_delete(&db);
 }
+
+#[test]
+fn mem_db_for_each_key() {
+    let db = MemoryDB::default();
+    subtests::for_each_key(&db);
+}
+
+#[test]
+fn mem_db_columns() {
+    let db = MemoryDB::default();
+    subtests::columns(&db);
+}
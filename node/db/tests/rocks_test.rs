@@ -64,3 +64,17 @@ fn rocks_db_bulk_delete() {
     let db = RocksDb::open(path.as_ref()).unwrap();
     subtests::bulk_delete(&db);
 }
+
+#[test]
+fn rocks_db_for_each_key() {
+    let path = DBPath::new("for_each_key_rocks_test");
+    let db = RocksDb::open(path.as_ref()).unwrap();
+    subtests::for_each_key(&db);
+}
+
+#[test]
+fn rocks_db_columns() {
+    let path = DBPath::new("columns_rocks_test");
+    let db = RocksDb::open(path.as_ref()).unwrap();
+    subtests::columns(&db);
+}
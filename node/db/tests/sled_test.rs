@@ -54,3 +54,15 @@ fn sled_db_bulk_delete() {
     let db = SledDb::temporary().unwrap();
     subtests::bulk_delete(&db);
 }
+
+#[test]
+fn sled_db_for_each_key() {
+    let db = SledDb::temporary().unwrap();
+    subtests::for_each_key(&db);
+}
+
+#[test]
+fn sled_db_columns() {
+    let db = SledDb::temporary().unwrap();
+    subtests::columns(&db);
+}
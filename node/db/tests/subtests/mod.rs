@@ -1,7 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use forest_db::Store;
+use forest_db::{Column, Store};
 
 pub fn write<DB>(db: &DB)
 where
@@ -100,3 +100,54 @@ where
         assert_eq!(res, false);
     }
 }
+
+pub fn for_each_key<DB>(db: &DB)
+where
+    DB: Store,
+{
+    let keys = [[0], [1], [2]];
+    let values = [[0], [1], [2]];
+    let kvs: Vec<_> = keys.iter().zip(values.iter()).collect();
+    db.bulk_write(&kvs).unwrap();
+    let mut visited = Vec::new();
+    db.for_each_key(|key| {
+        visited.push(key.to_vec());
+        Ok(())
+    })
+    .unwrap();
+    visited.sort();
+    assert_eq!(visited, vec![vec![0], vec![1], vec![2]]);
+}
+
+pub fn columns<DB>(db: &DB)
+where
+    DB: Store,
+{
+    let key = [0];
+    db.write(key, [0]).unwrap();
+    db.write_column(Column::Metadata, key, [1]).unwrap();
+    db.bulk_write_column(Column::Indices, &[(key, [2]), ([1], [3])])
+        .unwrap();
+    assert_eq!(db.read(key).unwrap(), Some(vec![0]));
+    assert_eq!(db.read_column(Column::Blocks, key).unwrap(), Some(vec![0]));
+    assert_eq!(
+        db.read_column(Column::Metadata, key).unwrap(),
+        Some(vec![1])
+    );
+    assert_eq!(db.read_column(Column::Indices, key).unwrap(), Some(vec![2]));
+
+    let mut visited = Vec::new();
+    db.for_each_key_column(Column::Indices, |key| {
+        visited.push(key.to_vec());
+        Ok(())
+    })
+    .unwrap();
+    visited.sort();
+    assert_eq!(visited, vec![vec![0], vec![1]]);
+
+    db.delete_column(Column::Metadata, key).unwrap();
+    assert!(!db.exists_column(Column::Metadata, key).unwrap());
+    assert!(db.exists(key).unwrap());
+    db.bulk_delete_column(Column::Indices, &[key, [1]]).unwrap();
+    assert!(!db.exists_column(Column::Indices, [1]).unwrap());
+}
@@ -5,6 +5,7 @@ use crate::{
     chain_exchange::{
         ChainExchangeCodec, ChainExchangeProtocolName, ChainExchangeRequest, ChainExchangeResponse,
     },
+    conn_manager::{ConnectionManager, ConnectionManagerEvent},
     discovery::DiscoveryOut,
     gossip_params::{build_peer_score_params, build_peer_score_threshold},
     rpc::RequestResponseError,
@@ -20,6 +21,7 @@ use futures::channel::oneshot::{self, Sender as OneShotSender};
 use futures::{prelude::*, stream::FuturesUnordered};
 use git_version::git_version;
 use libp2p::identify::{Identify, IdentifyEvent};
+use libp2p::multiaddr::Protocol;
 use libp2p::ping::{
     handler::{PingFailure, PingSuccess},
     Ping, PingEvent,
@@ -35,8 +37,8 @@ use libp2p::{core::PeerId, gossipsub::GossipsubMessage};
 use libp2p::{
     gossipsub::{
         error::PublishError, error::SubscriptionError, Gossipsub, GossipsubConfigBuilder,
-        GossipsubEvent, IdentTopic as Topic, MessageAuthenticity, MessageId, TopicHash,
-        ValidationMode,
+        GossipsubEvent, IdentTopic as Topic, MessageAcceptance, MessageAuthenticity, MessageId,
+        TopicHash, ValidationMode,
     },
     Multiaddr,
 };
@@ -70,6 +72,7 @@ pub(crate) struct ForestBehaviour {
     hello: RequestResponse<HelloCodec>,
     chain_exchange: RequestResponse<ChainExchangeCodec>,
     bitswap: Bitswap,
+    conn_manager: ConnectionManager,
     #[behaviour(ignore)]
     events: Vec<ForestBehaviourEvent>,
     /// Keeps track of Chain exchange requests to responses
@@ -100,9 +103,12 @@ struct RequestProcessingOutcome {
 pub(crate) enum ForestBehaviourEvent {
     PeerConnected(PeerId),
     PeerDisconnected(PeerId),
+    /// The connection manager asks for the connections to the peer to be closed.
+    DisconnectPeer(PeerId),
     GossipMessage {
         source: PeerId,
         topic: TopicHash,
+        message_id: MessageId,
         message: Vec<u8>,
     },
     BitswapReceivedBlock(PeerId, Cid, Box<[u8]>),
@@ -133,6 +139,16 @@ impl NetworkBehaviourEventProcess<DiscoveryOut> for ForestBehaviour {
     }
 }
 
+impl NetworkBehaviourEventProcess<ConnectionManagerEvent> for ForestBehaviour {
+    fn inject_event(&mut self, event: ConnectionManagerEvent) {
+        match event {
+            ConnectionManagerEvent::Disconnect(peer) => {
+                self.events.push(ForestBehaviourEvent::DisconnectPeer(peer));
+            }
+        }
+    }
+}
+
 impl NetworkBehaviourEventProcess<BitswapEvent> for ForestBehaviour {
     fn inject_event(&mut self, event: BitswapEvent) {
         match event {
@@ -173,12 +189,13 @@ impl NetworkBehaviourEventProcess<GossipsubEvent> for ForestBehaviour {
         if let GossipsubEvent::Message {
             propagation_source,
             message,
-            message_id: _,
+            message_id,
         } = message
         {
             self.events.push(ForestBehaviourEvent::GossipMessage {
                 source: propagation_source,
                 topic: message.topic,
+                message_id,
                 message: message.data,
             })
         }
@@ -426,10 +443,18 @@ impl ForestBehaviour {
         Poll::Pending
     }
 
-    pub fn new(local_key: &Keypair, config: &Libp2pConfig, network_name: &str) -> Self {
+    pub fn new(
+        local_key: &Keypair,
+        config: &Libp2pConfig,
+        network_name: &str,
+        known_peers: &[(PeerId, Vec<Multiaddr>)],
+    ) -> Self {
         let mut gs_config_builder = GossipsubConfigBuilder::default();
         gs_config_builder.max_transmit_size(1 << 20);
         gs_config_builder.validation_mode(ValidationMode::Strict);
+        // Messages are only forwarded once the service reports them valid, which lets the peer
+        // scores penalize peers sending undecodable messages.
+        gs_config_builder.validate_messages();
         gs_config_builder.message_id_fn(|msg: &GossipsubMessage| {
             let s = blake2b_256(&msg.data);
             MessageId::from(s)
@@ -442,12 +467,14 @@ impl ForestBehaviour {
         )
         .unwrap();
 
-        gossipsub
-            .with_peer_score(
-                build_peer_score_params(network_name),
-                build_peer_score_threshold(),
-            )
-            .unwrap();
+        if config.gossip_score.enabled {
+            gossipsub
+                .with_peer_score(
+                    build_peer_score_params(network_name, &config.gossip_score),
+                    build_peer_score_threshold(&config.gossip_score),
+                )
+                .unwrap();
+        }
 
         let bitswap = Bitswap::new();
 
@@ -456,9 +483,34 @@ impl ForestBehaviour {
             .with_mdns(config.mdns)
             .with_kademlia(config.kademlia)
             .with_user_defined(config.bootstrap_peers.clone())
+            .with_known_peers(known_peers.iter().flat_map(|(peer_id, addrs)| {
+                addrs.iter().map(move |addr| (*peer_id, addr.clone()))
+            }))
             // TODO allow configuring this through config.
             .discovery_limit(config.target_peer_count as u64);
 
+        let mut conn_manager = ConnectionManager::new(
+            config.conn_low_water as usize,
+            config.conn_high_water as usize,
+            Duration::from_secs(config.conn_grace_period),
+            config.max_conns_per_ip as usize,
+        );
+        for addr in config.bootstrap_peers.iter() {
+            if let Some(Protocol::P2p(mh)) = addr.iter().last() {
+                if let Ok(peer_id) = PeerId::from_multihash(mh) {
+                    conn_manager.protect(peer_id);
+                }
+            }
+        }
+        for id in config.protected_peers.iter() {
+            match id.parse() {
+                Ok(peer_id) => {
+                    conn_manager.protect(peer_id);
+                }
+                Err(e) => warn!("Invalid protected peer {}: {}", id, e),
+            }
+        }
+
         let hp = std::iter::once((HelloProtocolName, ProtocolSupport::Full));
         let cp = std::iter::once((ChainExchangeProtocolName, ProtocolSupport::Full));
 
@@ -476,6 +528,7 @@ impl ForestBehaviour {
                 local_key.public(),
             ),
             bitswap,
+            conn_manager,
             hello: RequestResponse::new(HelloCodec::default(), hp, req_res_config.clone()),
             chain_exchange: RequestResponse::new(ChainExchangeCodec::default(), cp, req_res_config),
             cx_pending_responses: Default::default(),
@@ -499,6 +552,35 @@ impl ForestBehaviour {
         self.gossipsub.publish(topic, data)
     }
 
+    /// Reports whether a gossip message is valid, which forwards valid messages and penalizes
+    /// the peer that sent an invalid one.
+    pub fn report_message_validation(
+        &mut self,
+        message_id: &MessageId,
+        source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        if let Err(e) = self
+            .gossipsub
+            .report_message_validation_result(message_id, source, acceptance)
+        {
+            debug!("Failed to report gossip message validation: {:?}", e);
+        }
+    }
+
+    /// Returns the gossipsub scores of the known peers, empty if peer scoring is disabled.
+    pub fn peer_scores(&self) -> Vec<(PeerId, f64)> {
+        self.gossipsub
+            .all_peers()
+            .filter_map(|(peer, _)| self.gossipsub.peer_score(peer).map(|score| (*peer, score)))
+            .collect()
+    }
+
+    /// Returns the number of peers in the mesh of a gossip topic.
+    pub fn mesh_peer_count(&self, topic: &TopicHash) -> usize {
+        self.gossipsub.mesh_peers(topic).count()
+    }
+
     /// Subscribe to a gossip topic.
     pub fn subscribe(&mut self, topic: &Topic) -> Result<bool, SubscriptionError> {
         self.gossipsub.subscribe(topic)
@@ -526,6 +608,21 @@ impl ForestBehaviour {
         self.cx_request_table.insert(req_id, response_channel);
     }
 
+    /// Protects a peer from being disconnected by the connection manager.
+    pub fn protect_peer(&mut self, peer_id: PeerId) -> bool {
+        self.conn_manager.protect(peer_id)
+    }
+
+    /// Removes the protection of a peer from the connection manager.
+    pub fn unprotect_peer(&mut self, peer_id: &PeerId) -> bool {
+        self.conn_manager.unprotect(peer_id)
+    }
+
+    /// Returns the peers protected from the connection manager.
+    pub fn protected_peers(&self) -> &HashSet<PeerId> {
+        self.conn_manager.protected()
+    }
+
     /// Returns a set of peer ids
     pub fn peers(&mut self) -> &HashSet<PeerId> {
         self.discovery.peers()
@@ -1,8 +1,8 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::GossipScoreConfig;
 use libp2p::Multiaddr;
-use networks::DEFAULT_BOOTSTRAP;
 use serde::Deserialize;
 
 /// Libp2p config for the Forest node.
@@ -11,7 +11,7 @@ use serde::Deserialize;
 pub struct Libp2pConfig {
     /// Local address.
     pub listening_multiaddr: Multiaddr,
-    /// Bootstrap peer list.
+    /// Bootstrap peers dialed in addition to the ones of the network.
     pub bootstrap_peers: Vec<Multiaddr>,
     /// Mdns discovery enabled.
     pub mdns: bool,
@@ -19,20 +19,35 @@ pub struct Libp2pConfig {
     pub kademlia: bool,
     /// Target peer count.
     pub target_peer_count: u32,
+    /// Number of connected peers the connection manager trims down to.
+    pub conn_low_water: u32,
+    /// Number of connected peers above which the connection manager starts trimming.
+    pub conn_high_water: u32,
+    /// Time, in seconds, new connections are exempt from trimming.
+    pub conn_grace_period: u64,
+    /// Maximum number of inbound connections from a single IP address.
+    pub max_conns_per_ip: u32,
+    /// IDs of peers which are never disconnected by the connection manager, such as our own
+    /// nodes. The bootstrap peers are always protected.
+    pub protected_peers: Vec<String>,
+    /// Gossipsub peer scoring parameters.
+    pub gossip_score: GossipScoreConfig,
 }
 
 impl Default for Libp2pConfig {
     fn default() -> Self {
-        let bootstrap_peers = DEFAULT_BOOTSTRAP
-            .iter()
-            .map(|node| node.parse().unwrap())
-            .collect();
         Self {
             listening_multiaddr: "/ip4/0.0.0.0/tcp/0".parse().unwrap(),
-            bootstrap_peers,
+            bootstrap_peers: Vec::new(),
             mdns: false,
             kademlia: true,
             target_peer_count: 75,
+            conn_low_water: 100,
+            conn_high_water: 150,
+            conn_grace_period: 20,
+            max_conns_per_ip: 8,
+            protected_peers: Vec::new(),
+            gossip_score: Default::default(),
         }
     }
 }
@@ -0,0 +1,314 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use async_std::stream::{self, Interval};
+use futures::prelude::*;
+use libp2p::{
+    core::{connection::ConnectionId, ConnectedPoint, Multiaddr, PeerId},
+    multiaddr::Protocol,
+    swarm::{
+        protocols_handler::DummyProtocolsHandler, IntoProtocolsHandler, NetworkBehaviour,
+        NetworkBehaviourAction, PollParameters, ProtocolsHandler,
+    },
+};
+use log::debug;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Interval at which the connection manager checks if connections need to be trimmed.
+const TRIM_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Event generated by the [ConnectionManager].
+#[derive(Debug)]
+pub enum ConnectionManagerEvent {
+    /// The connections to the peer should be closed.
+    Disconnect(PeerId),
+}
+
+/// Connections of a peer tracked by the [ConnectionManager].
+struct PeerConnections {
+    /// Time the first connection to the peer was established.
+    since: Instant,
+    /// Remote IP addresses of the inbound connections.
+    inbound: HashMap<ConnectionId, IpAddr>,
+}
+
+/// Keeps the number of connected peers between a low and a high watermark, and limits the
+/// number of inbound connections from a single IP address.
+///
+/// Once more than `high_water` peers are connected, the most recently connected peers are
+/// disconnected until `low_water` peers are left. Peers are not trimmed during the grace period
+/// following their connection, and protected peers, such as bootstrap nodes, are never
+/// disconnected.
+pub struct ConnectionManager {
+    low_water: usize,
+    high_water: usize,
+    grace_period: Duration,
+    max_conns_per_ip: usize,
+    protected: HashSet<PeerId>,
+    peers: HashMap<PeerId, PeerConnections>,
+    pending_events: VecDeque<ConnectionManagerEvent>,
+    next_trim: Interval,
+}
+
+impl ConnectionManager {
+    pub fn new(
+        low_water: usize,
+        high_water: usize,
+        grace_period: Duration,
+        max_conns_per_ip: usize,
+    ) -> Self {
+        Self {
+            low_water: low_water.min(high_water),
+            high_water,
+            grace_period,
+            max_conns_per_ip,
+            protected: HashSet::new(),
+            peers: HashMap::new(),
+            pending_events: VecDeque::new(),
+            next_trim: stream::interval(TRIM_INTERVAL),
+        }
+    }
+
+    /// Protects a peer from being disconnected. Returns false if it was already protected.
+    pub fn protect(&mut self, peer_id: PeerId) -> bool {
+        self.protected.insert(peer_id)
+    }
+
+    /// Removes the protection of a peer. Returns false if it was not protected.
+    pub fn unprotect(&mut self, peer_id: &PeerId) -> bool {
+        self.protected.remove(peer_id)
+    }
+
+    /// Returns the protected peers.
+    pub fn protected(&self) -> &HashSet<PeerId> {
+        &self.protected
+    }
+
+    /// Disconnects the most recently connected peers which are neither protected nor in their
+    /// grace period, until `low_water` peers are left.
+    fn trim(&mut self) {
+        if self.peers.len() <= self.high_water {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut candidates: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(peer, conns)| {
+                !self.protected.contains(peer)
+                    && now.duration_since(conns.since) >= self.grace_period
+            })
+            .map(|(peer, conns)| (*peer, conns.since))
+            .collect();
+        // Long lived connections are kept, they are more likely to be useful peers
+        candidates.sort_by(|(_, s1), (_, s2)| s2.cmp(s1));
+
+        let excess = self.peers.len() - self.low_water;
+        debug!(
+            "Trimming {} of {} connected peers",
+            excess.min(candidates.len()),
+            self.peers.len()
+        );
+        for (peer, _) in candidates.into_iter().take(excess) {
+            self.peers.remove(&peer);
+            self.pending_events
+                .push_back(ConnectionManagerEvent::Disconnect(peer));
+        }
+    }
+
+    /// Returns the number of inbound connections from an IP address.
+    fn inbound_from(&self, ip: &IpAddr) -> usize {
+        self.peers
+            .values()
+            .flat_map(|conns| conns.inbound.values())
+            .filter(|inbound| *inbound == ip)
+            .count()
+    }
+}
+
+impl NetworkBehaviour for ConnectionManager {
+    type ProtocolsHandler = DummyProtocolsHandler;
+    type OutEvent = ConnectionManagerEvent;
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        DummyProtocolsHandler::default()
+    }
+
+    fn addresses_of_peer(&mut self, _: &PeerId) -> Vec<Multiaddr> {
+        Vec::new()
+    }
+
+    fn inject_connected(&mut self, _: &PeerId) {}
+
+    fn inject_disconnected(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    fn inject_connection_established(
+        &mut self,
+        peer_id: &PeerId,
+        conn: &ConnectionId,
+        endpoint: &ConnectedPoint,
+    ) {
+        let conns = self
+            .peers
+            .entry(*peer_id)
+            .or_insert_with(|| PeerConnections {
+                since: Instant::now(),
+                inbound: HashMap::new(),
+            });
+
+        if let ConnectedPoint::Listener { send_back_addr, .. } = endpoint {
+            if let Some(ip) = ip_of(send_back_addr) {
+                conns.inbound.insert(*conn, ip);
+                if self.inbound_from(&ip) > self.max_conns_per_ip
+                    && !self.protected.contains(peer_id)
+                {
+                    debug!(
+                        "Too many connections from {}, disconnecting {}",
+                        ip, peer_id
+                    );
+                    self.peers.remove(peer_id);
+                    self.pending_events
+                        .push_back(ConnectionManagerEvent::Disconnect(*peer_id));
+                    return;
+                }
+            }
+        }
+
+        if self.peers.len() > self.high_water {
+            self.trim();
+        }
+    }
+
+    fn inject_connection_closed(
+        &mut self,
+        peer_id: &PeerId,
+        conn: &ConnectionId,
+        _: &ConnectedPoint,
+    ) {
+        if let Some(conns) = self.peers.get_mut(peer_id) {
+            conns.inbound.remove(conn);
+        }
+    }
+
+    fn inject_event(
+        &mut self,
+        _: PeerId,
+        _: ConnectionId,
+        event: <<Self::ProtocolsHandler as IntoProtocolsHandler>::Handler as ProtocolsHandler>::OutEvent,
+    ) {
+        match event {}
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn poll(
+        &mut self,
+        cx: &mut Context,
+        _: &mut impl PollParameters,
+    ) -> Poll<
+        NetworkBehaviourAction<
+            <<Self::ProtocolsHandler as IntoProtocolsHandler>::Handler as ProtocolsHandler>::InEvent,
+            Self::OutEvent,
+        >,
+    >{
+        while self.next_trim.poll_next_unpin(cx).is_ready() {
+            self.trim();
+        }
+
+        if let Some(ev) = self.pending_events.pop_front() {
+            return Poll::Ready(NetworkBehaviourAction::GenerateEvent(ev));
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Returns the IP address of a multiaddress, if it has one.
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener(addr: &str) -> ConnectedPoint {
+        ConnectedPoint::Listener {
+            local_addr: "/ip4/0.0.0.0/tcp/1347".parse().unwrap(),
+            send_back_addr: addr.parse().unwrap(),
+        }
+    }
+
+    fn disconnected(manager: &mut ConnectionManager) -> Vec<PeerId> {
+        manager
+            .pending_events
+            .drain(..)
+            .map(|ConnectionManagerEvent::Disconnect(peer)| peer)
+            .collect()
+    }
+
+    #[test]
+    fn trims_to_low_water() {
+        let mut manager = ConnectionManager::new(2, 3, Duration::from_secs(0), 10);
+        let protected = PeerId::random();
+        manager.protect(protected);
+
+        let peers: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+        let endpoint = ConnectedPoint::Dialer {
+            address: "/ip4/1.2.3.4/tcp/1347".parse().unwrap(),
+        };
+        for (i, peer) in std::iter::once(&protected).chain(&peers).enumerate() {
+            manager.inject_connection_established(peer, &ConnectionId::new(i), &endpoint);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        // The two most recently connected peers are trimmed
+        let trimmed: HashSet<_> = disconnected(&mut manager).into_iter().collect();
+        assert_eq!(trimmed, peers[1..].iter().copied().collect());
+        assert_eq!(manager.peers.len(), 2);
+    }
+
+    #[test]
+    fn grace_period() {
+        let mut manager = ConnectionManager::new(0, 0, Duration::from_secs(60), 10);
+        let endpoint = listener("/ip4/1.2.3.4/tcp/1347");
+        manager.inject_connection_established(&PeerId::random(), &ConnectionId::new(0), &endpoint);
+        assert!(disconnected(&mut manager).is_empty());
+    }
+
+    #[test]
+    fn per_ip_limit() {
+        let mut manager = ConnectionManager::new(10, 10, Duration::from_secs(0), 2);
+        let peers: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+        for (i, peer) in peers.iter().enumerate() {
+            let endpoint = listener(&format!("/ip4/1.2.3.4/tcp/{}", 4000 + i));
+            manager.inject_connection_established(peer, &ConnectionId::new(i), &endpoint);
+        }
+        let other = PeerId::random();
+        manager.inject_connection_established(
+            &other,
+            &ConnectionId::new(3),
+            &listener("/ip4/5.6.7.8/tcp/4000"),
+        );
+
+        assert_eq!(disconnected(&mut manager), vec![peers[2]]);
+
+        // Protected peers are not limited
+        manager.protect(peers[2]);
+        manager.inject_connection_established(
+            &peers[2],
+            &ConnectionId::new(4),
+            &listener("/ip4/1.2.3.4/tcp/4002"),
+        );
+        assert!(disconnected(&mut manager).is_empty());
+    }
+}
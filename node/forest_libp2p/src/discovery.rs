@@ -46,6 +46,7 @@ pub enum DiscoveryOut {
 pub struct DiscoveryConfig<'a> {
     local_peer_id: PeerId,
     user_defined: Vec<Multiaddr>,
+    known_peers: Vec<(PeerId, Multiaddr)>,
     discovery_max: u64,
     enable_mdns: bool,
     enable_kademlia: bool,
@@ -58,6 +59,7 @@ impl<'a> DiscoveryConfig<'a> {
         DiscoveryConfig {
             local_peer_id: local_public_key.into_peer_id(),
             user_defined: Vec::new(),
+            known_peers: Vec::new(),
             discovery_max: std::u64::MAX,
             enable_mdns: false,
             enable_kademlia: true,
@@ -80,6 +82,16 @@ impl<'a> DiscoveryConfig<'a> {
         self
     }
 
+    /// Set peers remembered from previous runs, which are added to the routing table on start
+    /// but may have expired.
+    pub fn with_known_peers<I>(&mut self, known_peers: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (PeerId, Multiaddr)>,
+    {
+        self.known_peers.extend(known_peers);
+        self
+    }
+
     /// Configures if mdns is enabled.
     pub fn with_mdns(&mut self, value: bool) -> &mut Self {
         self.enable_mdns = value;
@@ -97,6 +109,7 @@ impl<'a> DiscoveryConfig<'a> {
         let DiscoveryConfig {
             local_peer_id,
             user_defined,
+            known_peers,
             discovery_max,
             enable_mdns,
             enable_kademlia,
@@ -133,6 +146,9 @@ impl<'a> DiscoveryConfig<'a> {
                 kademlia.add_address(&peer_id, addr.clone());
                 peers.insert(*peer_id);
             }
+            for (peer_id, addr) in known_peers {
+                kademlia.add_address(&peer_id, addr);
+            }
             if let Err(e) = kademlia.bootstrap() {
                 warn!("Kademlia bootstrap failed: {}", e);
             }
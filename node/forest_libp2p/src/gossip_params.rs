@@ -5,88 +5,151 @@ use crate::{PUBSUB_BLOCK_STR, PUBSUB_MSG_STR};
 use libp2p::gossipsub::{
     score_parameter_decay, IdentTopic, PeerScoreParams, PeerScoreThresholds, TopicScoreParams,
 };
+use serde::Deserialize;
 use std::{collections::HashMap, time::Duration};
 
-// All these parameters are copied from what Lotus has set for their Topic scores.
-// They are currently unused because enabling them causes GossipSub blocks to come
-// delayed usually by 1 second compared to when we have these parameters disabled.
-// Leaving these here so that we can enable and fix these parameters when they are needed.
-
-#[allow(dead_code)]
-fn build_msg_topic_config() -> TopicScoreParams {
-    TopicScoreParams {
-        // expected 10 blocks/min
-        topic_weight: 0.1,
-
-        // 1 tick per second, maxes at 1 after 1 hour (1/3600)
-        time_in_mesh_weight: 0.0002778,
-        time_in_mesh_quantum: Duration::from_secs(1),
-        time_in_mesh_cap: 1.0,
-
-        // deliveries decay after 10min, cap at 100 tx
-        first_message_deliveries_weight: 0.5,
-        first_message_deliveries_decay: score_parameter_decay(Duration::from_secs(10 * 60)), // 10mins
-        // 100 blocks in an hour
-        first_message_deliveries_cap: 100.0,
-
-        // Set to 0 because disabled for Filecoin
-        mesh_message_deliveries_weight: 0.0,
-        mesh_message_deliveries_decay: 0.0,
-        mesh_message_deliveries_cap: 0.0,
-        mesh_message_deliveries_threshold: 0.0,
-        mesh_message_deliveries_window: Duration::from_millis(0),
-        mesh_message_deliveries_activation: Duration::from_millis(0),
-        mesh_failure_penalty_weight: 0.0,
-        mesh_failure_penalty_decay: 0.0,
-
-        // invalid messages decay after 1 hour
-        invalid_message_deliveries_weight: -1000.0,
-        invalid_message_deliveries_decay: score_parameter_decay(Duration::from_secs(60 * 60)),
+/// Gossipsub peer scoring configuration. The defaults are the parameters Lotus uses.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GossipScoreConfig {
+    /// Peer scoring enabled.
+    pub enabled: bool,
+    /// Score parameters of the blocks topic.
+    pub blocks: TopicScoreConfig,
+    /// Score parameters of the messages topic.
+    pub messages: TopicScoreConfig,
+    /// Score below which gossip is not emitted to or accepted from a peer.
+    pub gossip_threshold: f64,
+    /// Score below which our messages are not published to a peer.
+    pub publish_threshold: f64,
+    /// Score below which all the messages of a peer are ignored.
+    pub graylist_threshold: f64,
+    /// Score above which peer exchange from a peer is accepted.
+    pub accept_px_threshold: f64,
+    /// Median mesh score below which peers with a higher score are grafted.
+    pub opportunistic_graft_threshold: f64,
+}
+
+impl Default for GossipScoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            blocks: TopicScoreConfig::blocks(),
+            messages: TopicScoreConfig::messages(),
+            gossip_threshold: -500.0,
+            publish_threshold: -1000.0,
+            graylist_threshold: -2500.0,
+            accept_px_threshold: 1000.0,
+            opportunistic_graft_threshold: 3.5,
+        }
     }
 }
 
-#[allow(dead_code)]
-fn build_block_topic_config() -> TopicScoreParams {
-    TopicScoreParams {
-        topic_weight: 0.1,
-
-        // 1 tick per second, maxes at 1 hours (-1/3600)
-        time_in_mesh_weight: 0.00027,
-        time_in_mesh_quantum: Duration::from_secs(1),
-        time_in_mesh_cap: 1.0,
-
-        // deliveries decay after 10min, cap at 100 blocks
-        first_message_deliveries_weight: 5.0,
-        first_message_deliveries_decay: score_parameter_decay(Duration::from_secs(60 * 60)),
-        // 100 blocks in 10 minutes
-        first_message_deliveries_cap: 100.0,
-
-        // Set to 0 because disabled for Filecoin
-        mesh_message_deliveries_weight: 0.0,
-        mesh_message_deliveries_decay: 0.0,
-        mesh_message_deliveries_cap: 0.0,
-        mesh_message_deliveries_threshold: 0.0,
-        mesh_message_deliveries_window: Duration::from_millis(0),
-        mesh_message_deliveries_activation: Duration::from_millis(0),
-        mesh_failure_penalty_weight: 0.0,
-        mesh_failure_penalty_decay: 0.0,
-
-        // invalid messages decay after 1 hour
-        invalid_message_deliveries_weight: -1000.0,
-        invalid_message_deliveries_decay: score_parameter_decay(Duration::from_secs(60 * 60)),
+/// Score parameters of a gossipsub topic. Mesh message delivery scoring is disabled for
+/// Filecoin, so only the parameters of the other counters are configurable.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TopicScoreConfig {
+    pub topic_weight: f64,
+    pub time_in_mesh_weight: f64,
+    /// Time, in milliseconds, of a time in mesh tick.
+    pub time_in_mesh_quantum: u64,
+    pub time_in_mesh_cap: f64,
+    pub first_message_deliveries_weight: f64,
+    /// Time, in seconds, first message deliveries take to decay.
+    pub first_message_deliveries_decay: u64,
+    pub first_message_deliveries_cap: f64,
+    pub invalid_message_deliveries_weight: f64,
+    /// Time, in seconds, invalid message deliveries take to decay.
+    pub invalid_message_deliveries_decay: u64,
+}
+
+impl TopicScoreConfig {
+    /// Parameters of the blocks topic, which expects around 5 blocks per epoch.
+    pub fn blocks() -> Self {
+        Self {
+            topic_weight: 0.1,
+
+            // 1 tick per second, maxes at 1 after 1 hour (1/3600)
+            time_in_mesh_weight: 0.00027,
+            time_in_mesh_quantum: 1000,
+            time_in_mesh_cap: 1.0,
+
+            // deliveries decay after 1 hour, cap at 100 blocks
+            first_message_deliveries_weight: 5.0,
+            first_message_deliveries_decay: 60 * 60,
+            first_message_deliveries_cap: 100.0,
+
+            // invalid messages decay after 1 hour
+            invalid_message_deliveries_weight: -1000.0,
+            invalid_message_deliveries_decay: 60 * 60,
+        }
+    }
+
+    /// Parameters of the messages topic.
+    pub fn messages() -> Self {
+        Self {
+            topic_weight: 0.1,
+
+            // 1 tick per second, maxes at 1 after 1 hour (1/3600)
+            time_in_mesh_weight: 0.0002778,
+            time_in_mesh_quantum: 1000,
+            time_in_mesh_cap: 1.0,
+
+            // deliveries decay after 10min, cap at 100 messages
+            first_message_deliveries_weight: 0.5,
+            first_message_deliveries_decay: 10 * 60,
+            first_message_deliveries_cap: 100.0,
+
+            // invalid messages decay after 1 hour
+            invalid_message_deliveries_weight: -1000.0,
+            invalid_message_deliveries_decay: 60 * 60,
+        }
+    }
+
+    fn to_params(&self) -> TopicScoreParams {
+        TopicScoreParams {
+            topic_weight: self.topic_weight,
+
+            time_in_mesh_weight: self.time_in_mesh_weight,
+            time_in_mesh_quantum: Duration::from_millis(self.time_in_mesh_quantum),
+            time_in_mesh_cap: self.time_in_mesh_cap,
+
+            first_message_deliveries_weight: self.first_message_deliveries_weight,
+            first_message_deliveries_decay: score_parameter_decay(Duration::from_secs(
+                self.first_message_deliveries_decay,
+            )),
+            first_message_deliveries_cap: self.first_message_deliveries_cap,
+
+            // Set to 0 because disabled for Filecoin
+            mesh_message_deliveries_weight: 0.0,
+            mesh_message_deliveries_decay: 0.0,
+            mesh_message_deliveries_cap: 0.0,
+            mesh_message_deliveries_threshold: 0.0,
+            mesh_message_deliveries_window: Duration::from_millis(0),
+            mesh_message_deliveries_activation: Duration::from_millis(0),
+            mesh_failure_penalty_weight: 0.0,
+            mesh_failure_penalty_decay: 0.0,
+
+            invalid_message_deliveries_weight: self.invalid_message_deliveries_weight,
+            invalid_message_deliveries_decay: score_parameter_decay(Duration::from_secs(
+                self.invalid_message_deliveries_decay,
+            )),
+        }
     }
 }
 
-#[allow(dead_code)]
-pub(crate) fn build_peer_score_params(network_name: &str) -> PeerScoreParams {
+pub(crate) fn build_peer_score_params(
+    network_name: &str,
+    config: &GossipScoreConfig,
+) -> PeerScoreParams {
     let mut psp_topics = HashMap::new();
 
     // msg topic
     let msg_topic = IdentTopic::new(format!("{}/{}", PUBSUB_MSG_STR, network_name));
-    psp_topics.insert(msg_topic.hash(), build_msg_topic_config());
+    psp_topics.insert(msg_topic.hash(), config.messages.to_params());
     // block topic
     let block_topic = IdentTopic::new(format!("{}/{}", PUBSUB_BLOCK_STR, network_name));
-    psp_topics.insert(block_topic.hash(), build_block_topic_config());
+    psp_topics.insert(block_topic.hash(), config.blocks.to_params());
 
     PeerScoreParams {
         app_specific_weight: 1.0,
@@ -109,13 +172,55 @@ pub(crate) fn build_peer_score_params(network_name: &str) -> PeerScoreParams {
     }
 }
 
-#[allow(dead_code)]
-pub(crate) fn build_peer_score_threshold() -> PeerScoreThresholds {
+pub(crate) fn build_peer_score_threshold(config: &GossipScoreConfig) -> PeerScoreThresholds {
     PeerScoreThresholds {
-        gossip_threshold: -500.0,
-        publish_threshold: -1000.0,
-        graylist_threshold: -2500.0,
-        accept_px_threshold: 1000.0,
-        opportunistic_graft_threshold: 3.5,
+        gossip_threshold: config.gossip_threshold,
+        publish_threshold: config.publish_threshold,
+        graylist_threshold: config.graylist_threshold,
+        accept_px_threshold: config.accept_px_threshold,
+        opportunistic_graft_threshold: config.opportunistic_graft_threshold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_score_config() {
+        let config: GossipScoreConfig = toml::from_str(
+            r#"
+            graylist_threshold = -5000.0
+
+            [messages]
+            topic_weight = 0.2
+            time_in_mesh_weight = 0.0002778
+            time_in_mesh_quantum = 1000
+            time_in_mesh_cap = 1.0
+            first_message_deliveries_weight = 0.5
+            first_message_deliveries_decay = 600
+            first_message_deliveries_cap = 100.0
+            invalid_message_deliveries_weight = -2000.0
+            invalid_message_deliveries_decay = 3600
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            GossipScoreConfig {
+                messages: TopicScoreConfig {
+                    topic_weight: 0.2,
+                    invalid_message_deliveries_weight: -2000.0,
+                    ..TopicScoreConfig::messages()
+                },
+                graylist_threshold: -5000.0,
+                ..Default::default()
+            }
+        );
+
+        let params = build_peer_score_params("testnetnet", &config);
+        let msg_topic = IdentTopic::new(format!("{}/testnetnet", PUBSUB_MSG_STR));
+        assert!(params.topics.contains_key(&msg_topic.hash()));
+        assert!(params.validate().is_ok());
     }
 }
@@ -9,14 +9,19 @@ extern crate lazy_static;
 mod behaviour;
 pub mod chain_exchange;
 mod config;
+mod conn_manager;
 mod discovery;
 mod gossip_params;
 pub mod hello;
+mod metrics;
+mod peer_store;
 pub mod rpc;
 mod service;
 
 pub(crate) use self::behaviour::*;
 pub use self::config::*;
+pub use self::gossip_params::{GossipScoreConfig, TopicScoreConfig};
+pub use self::peer_store::*;
 pub use self::service::*;
 
 // Re-export some libp2p types
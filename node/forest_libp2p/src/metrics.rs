@@ -0,0 +1,63 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use lazy_static::lazy_static;
+use prometheus::core::{AtomicU64, GenericCounterVec, GenericGauge, GenericGaugeVec, Opts};
+
+lazy_static! {
+    pub static ref GOSSIPSUB_MESH_PEERS: Box<GenericGaugeVec<AtomicU64>> = {
+        let gossipsub_mesh_peers = Box::new(
+            GenericGaugeVec::<AtomicU64>::new(
+                Opts::new(
+                    "gossipsub_mesh_peers",
+                    "Number of peers in the gossipsub mesh by topic",
+                ),
+                &[labels::TOPIC],
+            )
+            .expect("Defining the gossipsub_mesh_peers metric must succeed"),
+        );
+        prometheus::default_registry().register(gossipsub_mesh_peers.clone()).expect(
+            "Registering the gossipsub_mesh_peers metric with the metrics registry must succeed",
+        );
+        gossipsub_mesh_peers
+    };
+    pub static ref GOSSIPSUB_NEGATIVE_SCORE_PEERS: Box<GenericGauge<AtomicU64>> = {
+        let gossipsub_negative_score_peers = Box::new(
+            GenericGauge::<AtomicU64>::new(
+                "gossipsub_negative_score_peers",
+                "Number of gossipsub peers with a negative score",
+            )
+            .expect("Defining the gossipsub_negative_score_peers metric must succeed"),
+        );
+        prometheus::default_registry().register(gossipsub_negative_score_peers.clone()).expect(
+            "Registering the gossipsub_negative_score_peers metric with the metrics registry must succeed",
+        );
+        gossipsub_negative_score_peers
+    };
+    pub static ref GOSSIPSUB_INVALID_MESSAGE_TOTAL: Box<GenericCounterVec<AtomicU64>> = {
+        let gossipsub_invalid_message_total = Box::new(
+            GenericCounterVec::<AtomicU64>::new(
+                Opts::new(
+                    "gossipsub_invalid_message_total",
+                    "Total number of gossipsub messages rejected by topic",
+                ),
+                &[labels::TOPIC],
+            )
+            .expect("Defining the gossipsub_invalid_message_total metric must succeed"),
+        );
+        prometheus::default_registry().register(gossipsub_invalid_message_total.clone()).expect(
+            "Registering the gossipsub_invalid_message_total metric with the metrics registry must succeed",
+        );
+        gossipsub_invalid_message_total
+    };
+}
+
+pub mod labels {
+    pub const TOPIC: &str = "topic";
+}
+
+pub mod values {
+    // gossipsub topics
+    pub const BLOCKS: &str = "blocks";
+    pub const MESSAGES: &str = "messages";
+}
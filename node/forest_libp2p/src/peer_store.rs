@@ -0,0 +1,339 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use db::{Column, Error, Store};
+use forest_encoding::{from_slice, to_vec};
+use libp2p::{core::PeerId, Multiaddr};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PEER_INDEX_KEY: &[u8] = b"/peers/index";
+const BLOCK_LIST_KEY: &[u8] = b"/peers/blocklist";
+const ADDRESSES_KEY_PREFIX: &str = "/peers/addrs/";
+const STATS_KEY_PREFIX: &str = "/peers/stats/";
+
+/// Maximum number of peers whose addresses are remembered across restarts.
+pub const MAX_KNOWN_PEERS: usize = 1000;
+
+/// Time, in seconds, a peer marked bad by the syncer is ignored for.
+pub const BAD_PEER_TIMEOUT: u64 = 60 * 60;
+
+/// Addresses a peer was last reachable on, written by the libp2p service.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerAddresses {
+    pub addrs: Vec<Multiaddr>,
+    /// Unix time, in seconds, the peer was last connected.
+    pub last_seen: u64,
+}
+
+/// Request statistics of a peer, written by the syncer's peer manager.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerStats {
+    /// Number of successful requests.
+    pub successes: u32,
+    /// Number of failed requests.
+    pub failures: u32,
+    /// Average response time for the peer.
+    pub average_time: Duration,
+    /// Unix time, in seconds, the peer was marked bad, unset if it is not.
+    pub bad_since: Option<u64>,
+}
+
+impl PeerStats {
+    /// Returns true if the peer was marked bad less than [BAD_PEER_TIMEOUT] ago.
+    pub fn is_bad(&self) -> bool {
+        matches!(self.bad_since, Some(since) if unix_now().saturating_sub(since) < BAD_PEER_TIMEOUT)
+    }
+
+    /// Cost of a peer based on its latency and failure rate, lower is better. Peers without any
+    /// request have no cost.
+    fn cost(&self) -> Option<f64> {
+        if self.successes + self.failures == 0 {
+            return None;
+        }
+        let fail_rate = f64::from(self.failures) / f64::from(self.successes + self.failures);
+        Some(self.average_time.as_secs_f64() * (1.0 + fail_rate) + fail_rate)
+    }
+}
+
+/// Everything known about a peer, returned by the libp2p service on a `NetPeerInfo` request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerRecord {
+    /// Current addresses if the peer is connected, otherwise the last known ones.
+    pub addresses: Option<PeerAddresses>,
+    pub stats: Option<PeerStats>,
+    pub connected: bool,
+    pub blocked: bool,
+}
+
+/// Persists the addresses, request statistics and ban state of peers in the node's store, so
+/// that a restarted node can dial the peers which served it well first.
+///
+/// The addresses and the block list are only written by the libp2p service and the statistics
+/// only by the syncer, so the two never overwrite each other's records.
+pub struct PeerStore<DB> {
+    db: Arc<DB>,
+}
+
+impl<DB> Clone for PeerStore<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+        }
+    }
+}
+
+impl<DB> PeerStore<DB>
+where
+    DB: Store,
+{
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Returns the ids of the peers with known addresses.
+    pub fn known_peers(&self) -> Result<Vec<PeerId>, Error> {
+        let ids: Vec<String> = self.read(PEER_INDEX_KEY)?.unwrap_or_default();
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// Returns the last known addresses of a peer.
+    pub fn addresses(&self, peer: &PeerId) -> Result<Option<PeerAddresses>, Error> {
+        self.read(peer_key(ADDRESSES_KEY_PREFIX, peer))
+    }
+
+    /// Saves the addresses of a peer, adding it to the known peers.
+    pub fn put_addresses(&self, peer: &PeerId, addresses: &PeerAddresses) -> Result<(), Error> {
+        let mut ids: Vec<String> = self.read(PEER_INDEX_KEY)?.unwrap_or_default();
+        let id = peer.to_base58();
+        if !ids.contains(&id) {
+            ids.push(id);
+            self.write(PEER_INDEX_KEY, &ids)?;
+        }
+        self.write(peer_key(ADDRESSES_KEY_PREFIX, peer), addresses)
+    }
+
+    /// Returns the request statistics of a peer.
+    pub fn stats(&self, peer: &PeerId) -> Result<Option<PeerStats>, Error> {
+        self.read(peer_key(STATS_KEY_PREFIX, peer))
+    }
+
+    /// Saves the request statistics of a peer.
+    pub fn put_stats(&self, peer: &PeerId, stats: &PeerStats) -> Result<(), Error> {
+        self.write(peer_key(STATS_KEY_PREFIX, peer), stats)
+    }
+
+    /// Returns the peers blocked by the operator.
+    pub fn blocked(&self) -> Result<HashSet<PeerId>, Error> {
+        let ids: Vec<String> = self.read(BLOCK_LIST_KEY)?.unwrap_or_default();
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// Adds peers to the block list.
+    pub fn block(&self, peers: &[PeerId]) -> Result<(), Error> {
+        let mut blocked = self.blocked()?;
+        blocked.extend(peers.iter().copied());
+        self.write_block_list(&blocked)
+    }
+
+    /// Removes peers from the block list.
+    pub fn unblock(&self, peers: &[PeerId]) -> Result<(), Error> {
+        let mut blocked = self.blocked()?;
+        for peer in peers {
+            blocked.remove(peer);
+        }
+        self.write_block_list(&blocked)
+    }
+
+    /// Returns up to `limit` known peers which are neither blocked nor bad with their addresses,
+    /// ordered from the best to the worst. Peers with request statistics are ordered by cost,
+    /// followed by the other peers from the most recently seen.
+    pub fn dial_candidates(&self, limit: usize) -> Result<Vec<(PeerId, Vec<Multiaddr>)>, Error> {
+        let blocked = self.blocked()?;
+        let mut candidates = Vec::new();
+        for peer in self.known_peers()? {
+            if blocked.contains(&peer) {
+                continue;
+            }
+            let stats = self.stats(&peer)?.unwrap_or_default();
+            if stats.is_bad() {
+                continue;
+            }
+            if let Some(addresses) = self.addresses(&peer)? {
+                if !addresses.addrs.is_empty() {
+                    candidates.push((peer, addresses, stats.cost()));
+                }
+            }
+        }
+
+        candidates.sort_by(|(_, a1, c1), (_, a2, c2)| match (c1, c2) {
+            (Some(c1), Some(c2)) => c1.partial_cmp(c2).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a2.last_seen.cmp(&a1.last_seen),
+        });
+        Ok(candidates
+            .into_iter()
+            .take(limit)
+            .map(|(peer, addresses, _)| (peer, addresses.addrs))
+            .collect())
+    }
+
+    /// Forgets the least recently seen peers until at most `max` peers are known.
+    pub fn prune(&self, max: usize) -> Result<(), Error> {
+        let peers = self.known_peers()?;
+        if peers.len() <= max {
+            return Ok(());
+        }
+
+        let mut peers = peers
+            .into_iter()
+            .map(|peer| {
+                let last_seen = self.addresses(&peer)?.map(|a| a.last_seen).unwrap_or(0);
+                Ok((peer, last_seen))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        peers.sort_by(|(_, s1), (_, s2)| s2.cmp(s1));
+
+        for (peer, _) in peers.drain(max..) {
+            self.db
+                .delete_column(Column::Metadata, peer_key(ADDRESSES_KEY_PREFIX, &peer))?;
+            self.db
+                .delete_column(Column::Metadata, peer_key(STATS_KEY_PREFIX, &peer))?;
+        }
+        let ids: Vec<String> = peers.iter().map(|(peer, _)| peer.to_base58()).collect();
+        self.write(PEER_INDEX_KEY, &ids)
+    }
+
+    fn write_block_list(&self, blocked: &HashSet<PeerId>) -> Result<(), Error> {
+        let ids: Vec<String> = blocked.iter().map(PeerId::to_base58).collect();
+        self.write(BLOCK_LIST_KEY, &ids)
+    }
+
+    fn read<K, T>(&self, key: K) -> Result<Option<T>, Error>
+    where
+        K: AsRef<[u8]>,
+        T: serde::de::DeserializeOwned,
+    {
+        match self.db.read_column(Column::Metadata, key)? {
+            Some(bz) => Ok(Some(from_slice(&bz)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn write<K, T>(&self, key: K, value: &T) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        T: Serialize,
+    {
+        self.db.write_column(Column::Metadata, key, to_vec(value)?)
+    }
+}
+
+fn peer_key(prefix: &str, peer: &PeerId) -> Vec<u8> {
+    format!("{}{}", prefix, peer.to_base58()).into_bytes()
+}
+
+/// Returns the current unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time before unix epoch")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+
+    fn addresses(last_seen: u64) -> PeerAddresses {
+        PeerAddresses {
+            addrs: vec!["/ip4/127.0.0.1/tcp/1347".parse().unwrap()],
+            last_seen,
+        }
+    }
+
+    fn stats(successes: u32, failures: u32, millis: u64) -> PeerStats {
+        PeerStats {
+            successes,
+            failures,
+            average_time: Duration::from_millis(millis),
+            bad_since: None,
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let store = PeerStore::new(Arc::new(MemoryDB::default()));
+        let peer = PeerId::random();
+        assert_eq!(store.addresses(&peer).unwrap(), None);
+        assert_eq!(store.stats(&peer).unwrap(), None);
+
+        store.put_addresses(&peer, &addresses(10)).unwrap();
+        store.put_stats(&peer, &stats(3, 1, 200)).unwrap();
+        store.block(&[peer]).unwrap();
+        assert_eq!(store.addresses(&peer).unwrap(), Some(addresses(10)));
+        assert_eq!(store.stats(&peer).unwrap(), Some(stats(3, 1, 200)));
+        assert!(store.blocked().unwrap().contains(&peer));
+        assert_eq!(store.known_peers().unwrap(), vec![peer]);
+
+        store.unblock(&[peer]).unwrap();
+        assert!(store.blocked().unwrap().is_empty());
+    }
+
+    #[test]
+    fn dial_candidates_order() {
+        let store = PeerStore::new(Arc::new(MemoryDB::default()));
+        let fast = PeerId::random();
+        let slow = PeerId::random();
+        let unknown_recent = PeerId::random();
+        let unknown_old = PeerId::random();
+        let bad = PeerId::random();
+        let blocked = PeerId::random();
+
+        for peer in &[fast, slow, bad, blocked, unknown_old] {
+            store.put_addresses(peer, &addresses(1)).unwrap();
+        }
+        store.put_addresses(&unknown_recent, &addresses(2)).unwrap();
+        store.put_stats(&fast, &stats(10, 0, 100)).unwrap();
+        store.put_stats(&slow, &stats(5, 5, 100)).unwrap();
+        store
+            .put_stats(
+                &bad,
+                &PeerStats {
+                    bad_since: Some(unix_now()),
+                    ..stats(10, 0, 10)
+                },
+            )
+            .unwrap();
+        store.block(&[blocked]).unwrap();
+
+        let order: Vec<_> = store
+            .dial_candidates(10)
+            .unwrap()
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .collect();
+        assert_eq!(order, vec![fast, slow, unknown_recent, unknown_old]);
+        assert_eq!(store.dial_candidates(1).unwrap()[0].0, fast);
+    }
+
+    #[test]
+    fn prune_least_recently_seen() {
+        let store = PeerStore::new(Arc::new(MemoryDB::default()));
+        let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+        for (i, peer) in peers.iter().enumerate() {
+            store.put_addresses(peer, &addresses(i as u64)).unwrap();
+            store.put_stats(peer, &stats(1, 0, 1)).unwrap();
+        }
+
+        store.prune(2).unwrap();
+        let known: HashSet<_> = store.known_peers().unwrap().into_iter().collect();
+        assert_eq!(known, vec![peers[2], peers[3]].into_iter().collect());
+        assert_eq!(store.stats(&peers[0]).unwrap(), None);
+    }
+}
@@ -4,9 +4,13 @@
 use super::chain_exchange::{
     make_chain_exchange_response, ChainExchangeRequest, ChainExchangeResponse,
 };
-use super::{ForestBehaviour, ForestBehaviourEvent, Libp2pConfig};
+use super::{
+    unix_now, ForestBehaviour, ForestBehaviourEvent, Libp2pConfig, PeerAddresses, PeerRecord,
+    PeerStore, MAX_KNOWN_PEERS,
+};
 use crate::{
     hello::{HelloRequest, HelloResponse},
+    metrics::{self, values},
     rpc::RequestResponseError,
 };
 use async_std::channel::{unbounded, Receiver, Sender};
@@ -22,6 +26,7 @@ use futures_util::stream::StreamExt;
 use ipld_blockstore::BlockStore;
 pub use libp2p::gossipsub::IdentTopic;
 pub use libp2p::gossipsub::Topic;
+pub use libp2p::gossipsub::{MessageAcceptance, MessageId};
 use libp2p::multiaddr::Protocol;
 use libp2p::request_response::ResponseChannel;
 use libp2p::{
@@ -51,8 +56,11 @@ const PUBSUB_TOPICS: [&str; 2] = [PUBSUB_BLOCK_STR, PUBSUB_MSG_STR];
 /// Events emitted by this Service.
 #[derive(Debug)]
 pub enum NetworkEvent {
+    /// Decoded gossip message, whose validation result must be reported back to the service
+    /// with [NetworkMessage::PubsubValidation].
     PubsubMessage {
         source: PeerId,
+        message_id: MessageId,
         message: PubsubMessage,
     },
     HelloRequest {
@@ -86,6 +94,13 @@ pub enum NetworkMessage {
         topic: IdentTopic,
         message: Vec<u8>,
     },
+    /// Result of the validation of a gossip message, which decides whether the message is
+    /// forwarded and how it affects the score of its source.
+    PubsubValidation {
+        message_id: MessageId,
+        source: PeerId,
+        acceptance: MessageAcceptance,
+    },
     ChainExchangeRequest {
         peer_id: PeerId,
         request: ChainExchangeRequest,
@@ -112,12 +127,21 @@ pub enum NetRPCMethods {
     NetPeers(OneShotSender<HashMap<PeerId, Vec<Multiaddr>>>),
     NetConnect(OneShotSender<bool>, PeerId, Vec<Multiaddr>),
     NetDisconnect(OneShotSender<()>, PeerId),
+    NetPeerInfo(OneShotSender<Result<PeerRecord, String>>, PeerId),
+    NetBlockAdd(OneShotSender<Result<(), String>>, Vec<PeerId>),
+    NetBlockRemove(OneShotSender<Result<(), String>>, Vec<PeerId>),
+    NetBlockList(OneShotSender<Result<Vec<PeerId>, String>>),
+    NetProtectAdd(OneShotSender<()>, Vec<PeerId>),
+    NetProtectRemove(OneShotSender<()>, Vec<PeerId>),
+    NetProtectList(OneShotSender<Vec<PeerId>>),
+    NetPubsubScores(OneShotSender<Vec<(PeerId, f64)>>),
 }
 
 /// The Libp2pService listens to events from the Libp2p swarm.
 pub struct Libp2pService<DB> {
     swarm: Swarm<ForestBehaviour>,
     cs: Arc<ChainStore<DB>>,
+    peer_store: PeerStore<DB>,
 
     network_receiver_in: Receiver<NetworkMessage>,
     network_sender_in: Sender<NetworkMessage>,
@@ -139,18 +163,34 @@ where
     ) -> Self {
         let peer_id = PeerId::from(net_keypair.public());
 
+        // Load the peers of previous runs, best first
+        let peer_store = PeerStore::new(cs.blockstore_cloned());
+        if let Err(e) = peer_store.prune(MAX_KNOWN_PEERS) {
+            warn!("Failed to prune the peer store: {}", e);
+        }
+        let known_peers = peer_store
+            .dial_candidates(config.target_peer_count as usize)
+            .unwrap_or_else(|e| {
+                warn!("Failed to load known peers: {}", e);
+                Vec::new()
+            });
+        let blocked = peer_store.blocked().unwrap_or_else(|e| {
+            warn!("Failed to load blocked peers: {}", e);
+            Default::default()
+        });
+
         let transport = build_transport(net_keypair.clone());
 
         let limits = ConnectionLimits::default()
             .with_max_pending_incoming(Some(10))
             .with_max_pending_outgoing(Some(30))
-            .with_max_established_incoming(Some(config.target_peer_count))
-            .with_max_established_outgoing(Some(config.target_peer_count))
+            .with_max_established_incoming(Some(config.conn_high_water))
+            .with_max_established_outgoing(Some(config.conn_high_water))
             .with_max_established_per_peer(Some(5));
 
         let mut swarm = SwarmBuilder::new(
             transport,
-            ForestBehaviour::new(&net_keypair, &config, network_name),
+            ForestBehaviour::new(&net_keypair, &config, network_name, &known_peers),
             peer_id,
         )
         .connection_limits(limits)
@@ -160,6 +200,16 @@ where
 
         Swarm::listen_on(&mut swarm, config.listening_multiaddr).unwrap();
 
+        for peer_id in blocked {
+            Swarm::ban_peer_id(&mut swarm, peer_id);
+        }
+        info!("Dialing {} known peers", known_peers.len());
+        for (peer_id, _) in known_peers.iter() {
+            if let Err(e) = Swarm::dial(&mut swarm, peer_id) {
+                debug!("Failed to dial known peer {}: {}", peer_id, e);
+            }
+        }
+
         // Subscribe to gossipsub topics with the network name suffix
         for topic in PUBSUB_TOPICS.iter() {
             let t = Topic::new(format!("{}/{}", topic, network_name));
@@ -177,6 +227,7 @@ where
         Libp2pService {
             swarm,
             cs,
+            peer_store,
             network_receiver_in,
             network_sender_in,
             network_receiver_out,
@@ -201,45 +252,61 @@ where
                     Some(event) => match event {
                         ForestBehaviourEvent::PeerConnected(peer_id) => {
                             debug!("Peer connected, {:?}", peer_id);
+                            let addrs = swarm_stream.get_mut().peer_addresses().get(&peer_id).cloned().unwrap_or_default();
+                            if !addrs.is_empty() {
+                                let addresses = PeerAddresses { addrs, last_seen: unix_now() };
+                                if let Err(e) = self.peer_store.put_addresses(&peer_id, &addresses) {
+                                    warn!("Failed to save the addresses of peer {}: {}", peer_id, e);
+                                }
+                            }
                             emit_event(&self.network_sender_out,
                                 NetworkEvent::PeerConnected(peer_id)).await;
                         }
                         ForestBehaviourEvent::PeerDisconnected(peer_id) => {
                             emit_event(&self.network_sender_out, NetworkEvent::PeerDisconnected(peer_id)).await;
                         }
+                        ForestBehaviourEvent::DisconnectPeer(peer_id) => {
+                            if Swarm::disconnect_peer_id(swarm_stream.get_mut(), peer_id).is_err() {
+                                debug!("Peer {} to disconnect is not connected", peer_id);
+                            }
+                        }
                         ForestBehaviourEvent::GossipMessage {
                             source,
                             topic,
+                            message_id,
                             message,
                         } => {
                             trace!("Got a Gossip Message from {:?}", source);
                             let topic = topic.as_str();
-                            if topic == pubsub_block_str {
-                                match from_slice::<GossipBlock>(&message) {
-                                    Ok(b) => {
-                                        emit_event(&self.network_sender_out, NetworkEvent::PubsubMessage{
-                                            source,
-                                            message: PubsubMessage::Block(b),
-                                        }).await;
-                                    }
-                                    Err(e) => {
-                                        warn!("Gossip Block from peer {:?} could not be deserialized: {}", source, e);
-                                    }
-                                }
+                            let decoded = if topic == pubsub_block_str {
+                                from_slice::<GossipBlock>(&message)
+                                    .map(PubsubMessage::Block)
+                                    .map_err(|e| (values::BLOCKS, e))
                             } else if topic == pubsub_msg_str {
-                                match from_slice::<SignedMessage>(&message) {
-                                    Ok(m) => {
-                                        emit_event(&self.network_sender_out, NetworkEvent::PubsubMessage{
-                                            source,
-                                            message: PubsubMessage::Message(m),
-                                        }).await;
-                                    }
-                                    Err(e) => {
-                                        warn!("Gossip Message from peer {:?} could not be deserialized: {}", source, e);
-                                    }
-                                }
+                                from_slice::<SignedMessage>(&message)
+                                    .map(PubsubMessage::Message)
+                                    .map_err(|e| (values::MESSAGES, e))
                             } else {
                                 warn!("Getting gossip messages from unknown topic: {}", topic);
+                                swarm_stream.get_mut().report_message_validation(&message_id, &source, MessageAcceptance::Ignore);
+                                continue;
+                            };
+                            match decoded {
+                                Ok(message) => {
+                                    // Accepted or rejected once validated by the receiver
+                                    emit_event(&self.network_sender_out, NetworkEvent::PubsubMessage{
+                                        source,
+                                        message_id,
+                                        message,
+                                    }).await;
+                                }
+                                Err((topic_label, e)) => {
+                                    warn!("Gossip {} from peer {:?} could not be deserialized: {}", topic_label, source, e);
+                                    swarm_stream.get_mut().report_message_validation(&message_id, &source, MessageAcceptance::Reject);
+                                    metrics::GOSSIPSUB_INVALID_MESSAGE_TOTAL
+                                        .with_label_values(&[topic_label])
+                                        .inc();
+                                }
                             }
                         }
                         ForestBehaviourEvent::HelloRequest { request,  peer } => {
@@ -309,6 +376,9 @@ where
                                 warn!("Failed to send gossipsub message: {:?}", e);
                             }
                         }
+                        NetworkMessage::PubsubValidation { message_id, source, acceptance } => {
+                            swarm_stream.get_mut().report_message_validation(&message_id, &source, acceptance);
+                        }
                         NetworkMessage::HelloRequest { peer_id, request, response_channel } => {
                             swarm_stream.get_mut().send_hello_request(&peer_id, request, response_channel);
                         }
@@ -358,13 +428,101 @@ where
                                         warn!("Failed to connect to a peer");
                                     }
                                 }
-                                NetRPCMethods::NetDisconnect(response_channel, _peer_id) => {
-                                    warn!("NetDisconnect API not yet implmeneted"); // TODO: implement NetDisconnect - See #1181
+                                NetRPCMethods::NetDisconnect(response_channel, peer_id) => {
+                                    if Swarm::disconnect_peer_id(swarm_stream.get_mut(), peer_id).is_err() {
+                                        debug!("Peer {} to disconnect is not connected", peer_id);
+                                    }
 
                                     if response_channel.send(()).is_err() {
                                         warn!("Failed to disconnect from a peer");
                                     }
                                 }
+                                NetRPCMethods::NetPeerInfo(response_channel, peer_id) => {
+                                    let swarm = swarm_stream.get_mut();
+                                    let connected = swarm.peers().contains(&peer_id);
+                                    let live_addrs = swarm.peer_addresses().get(&peer_id).cloned();
+                                    let record = || -> Result<_, db::Error> {
+                                        let addresses = match live_addrs {
+                                            Some(addrs) if connected => Some(PeerAddresses { addrs, last_seen: unix_now() }),
+                                            _ => self.peer_store.addresses(&peer_id)?,
+                                        };
+                                        Ok(PeerRecord {
+                                            addresses,
+                                            stats: self.peer_store.stats(&peer_id)?,
+                                            connected,
+                                            blocked: self.peer_store.blocked()?.contains(&peer_id),
+                                        })
+                                    };
+
+                                    if response_channel.send(record().map_err(|e| e.to_string())).is_err() {
+                                        warn!("Failed to get peer info");
+                                    }
+                                }
+                                NetRPCMethods::NetBlockAdd(response_channel, peers) => {
+                                    let res = self.peer_store.block(&peers).map_err(|e| e.to_string());
+                                    if res.is_ok() {
+                                        for peer_id in peers {
+                                            Swarm::ban_peer_id(swarm_stream.get_mut(), peer_id);
+                                        }
+                                    }
+
+                                    if response_channel.send(res).is_err() {
+                                        warn!("Failed to block peers");
+                                    }
+                                }
+                                NetRPCMethods::NetBlockRemove(response_channel, peers) => {
+                                    let res = self.peer_store.unblock(&peers).map_err(|e| e.to_string());
+                                    if res.is_ok() {
+                                        for peer_id in peers {
+                                            Swarm::unban_peer_id(swarm_stream.get_mut(), peer_id);
+                                        }
+                                    }
+
+                                    if response_channel.send(res).is_err() {
+                                        warn!("Failed to unblock peers");
+                                    }
+                                }
+                                NetRPCMethods::NetBlockList(response_channel) => {
+                                    let res = self.peer_store.blocked()
+                                        .map(|blocked| blocked.into_iter().collect())
+                                        .map_err(|e| e.to_string());
+
+                                    if response_channel.send(res).is_err() {
+                                        warn!("Failed to list blocked peers");
+                                    }
+                                }
+                                NetRPCMethods::NetProtectAdd(response_channel, peers) => {
+                                    for peer_id in peers {
+                                        swarm_stream.get_mut().protect_peer(peer_id);
+                                    }
+
+                                    if response_channel.send(()).is_err() {
+                                        warn!("Failed to protect peers");
+                                    }
+                                }
+                                NetRPCMethods::NetProtectRemove(response_channel, peers) => {
+                                    for peer_id in peers.iter() {
+                                        swarm_stream.get_mut().unprotect_peer(peer_id);
+                                    }
+
+                                    if response_channel.send(()).is_err() {
+                                        warn!("Failed to unprotect peers");
+                                    }
+                                }
+                                NetRPCMethods::NetProtectList(response_channel) => {
+                                    let protected = swarm_stream.get_mut().protected_peers().iter().copied().collect();
+
+                                    if response_channel.send(protected).is_err() {
+                                        warn!("Failed to list protected peers");
+                                    }
+                                }
+                                NetRPCMethods::NetPubsubScores(response_channel) => {
+                                    let scores = swarm_stream.get_mut().peer_scores();
+
+                                    if response_channel.send(scores).is_err() {
+                                        warn!("Failed to get gossipsub peer scores");
+                                    }
+                                }
                             }
                         }
                     }
@@ -373,6 +531,16 @@ where
                 interval_event = interval.next() => if interval_event.is_some() {
                     // Print peer count on an interval.
                     info!("Peers connected: {}", swarm_stream.get_mut().peers().len());
+
+                    let swarm = swarm_stream.get_mut();
+                    for (topic, label) in [(&pubsub_block_str, values::BLOCKS), (&pubsub_msg_str, values::MESSAGES)].iter() {
+                        let mesh_peers = swarm.mesh_peer_count(&IdentTopic::new(topic.as_str()).hash());
+                        metrics::GOSSIPSUB_MESH_PEERS
+                            .with_label_values(&[label])
+                            .set(mesh_peers as u64);
+                    }
+                    let negative_peers = swarm.peer_scores().iter().filter(|(_, score)| *score < 0.0).count();
+                    metrics::GOSSIPSUB_NEGATIVE_SCORE_PEERS.set(negative_peers as u64);
                 }
             };
         }
@@ -9,6 +9,8 @@ use jsonrpc_v2::{MapRouter as JsonRpcMapRouter, Server as JsonRpcServer};
 use serde::{Deserialize, Serialize};
 
 use actor::market::{DealProposal, DealState};
+use actor::multisig;
+use actor::paych::SignedVoucher;
 use address::{json::AddressJson, Address};
 use beacon::{json::BeaconEntryJson, Beacon, BeaconSchedule};
 use bitfield::json::BitFieldJson;
@@ -17,7 +19,7 @@ use blocks::{
     tipset_keys_json::TipsetKeysJson, Tipset,
 };
 use blockstore::BlockStore;
-use chain::{headchange_json::SubscriptionHeadChange, ChainStore};
+use chain::{headchange_json::SubscriptionHeadChange, BlockstoreCheck, ChainStore};
 use chain_sync::{BadBlockCache, SyncState};
 use cid::{json::CidJson, Cid};
 use clock::ChainEpoch;
@@ -29,9 +31,15 @@ use message::{
     message_receipt::json::MessageReceiptJson, signed_message,
     signed_message::json::SignedMessageJson, unsigned_message, SignedMessage, UnsignedMessage,
 };
-use message_pool::{MessagePool, MpoolRpcProvider};
+use message_pool::{
+    MessageCheckStatus, MessagePool, MessagePrototype, MpoolConfig, MpoolRpcProvider,
+};
 use num_bigint::{bigint_ser, BigInt};
-use state_manager::{MiningBaseInfo, StateManager};
+use paychmgr::{ChannelInfo, Direction, Manager as PaychManager, VoucherCreateResult};
+use state_manager::{InvocResult, MiningBaseInfo, StateManager};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::time::Duration;
 use vm::{ActorState, TokenAmount};
 use wallet::KeyStore;
 
@@ -53,12 +61,13 @@ where
     pub chain_store: Arc<ChainStore<DB>>,
     pub state_manager: Arc<StateManager<DB>>,
     pub mpool: Arc<MessagePool<MpoolRpcProvider<DB>>>,
-    pub bad_blocks: Arc<BadBlockCache>,
+    pub bad_blocks: Arc<BadBlockCache<DB>>,
     pub sync_state: Arc<RwLock<SyncState>>,
     pub network_send: Sender<NetworkMessage>,
     pub network_name: String,
     pub new_mined_block_tx: Sender<Arc<Tipset>>,
     pub beacon: Arc<BeaconSchedule<B>>,
+    pub paych_manager: Arc<PaychManager<DB>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,11 +89,114 @@ pub struct BlockMessages {
     pub cids: Vec<Cid>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct MessageSendSpec {
+    /// Maximum total fee the sender is willing to pay for the message, or zero to use the default.
     #[serde(with = "bigint_ser::json")]
-    max_fee: TokenAmount,
+    pub max_fee: TokenAmount,
+}
+
+/// JSON representation of the message pool config, with the prune cooldown in nanoseconds.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MpoolConfigJson {
+    #[serde(with = "address::json::vec")]
+    pub priority_addrs: Vec<Address>,
+    pub size_limit_high: i64,
+    pub size_limit_low: i64,
+    pub replace_by_fee_ratio: f64,
+    pub prune_cooldown: u64,
+    pub gas_limit_overestimation: f64,
+    #[serde(default = "MpoolConfigJson::default_max_local_sender_pending")]
+    pub max_local_sender_pending: u64,
+    #[serde(default = "MpoolConfigJson::default_max_remote_sender_pending")]
+    pub max_remote_sender_pending: u64,
+}
+
+impl MpoolConfigJson {
+    fn default_max_local_sender_pending() -> u64 {
+        MpoolConfig::default().max_local_sender_pending
+    }
+
+    fn default_max_remote_sender_pending() -> u64 {
+        MpoolConfig::default().max_remote_sender_pending
+    }
+}
+
+impl From<MpoolConfig> for MpoolConfigJson {
+    fn from(cfg: MpoolConfig) -> Self {
+        Self {
+            priority_addrs: cfg.priority_addrs,
+            size_limit_high: cfg.size_limit_high,
+            size_limit_low: cfg.size_limit_low,
+            replace_by_fee_ratio: cfg.replace_by_fee_ratio,
+            prune_cooldown: cfg.prune_cooldown.as_nanos() as u64,
+            gas_limit_overestimation: cfg.gas_limit_overestimation,
+            max_local_sender_pending: cfg.max_local_sender_pending,
+            max_remote_sender_pending: cfg.max_remote_sender_pending,
+        }
+    }
+}
+
+impl TryFrom<MpoolConfigJson> for MpoolConfig {
+    type Error = String;
+
+    fn try_from(cfg: MpoolConfigJson) -> Result<Self, Self::Error> {
+        MpoolConfig::new(
+            cfg.priority_addrs,
+            cfg.size_limit_high,
+            cfg.size_limit_low,
+            cfg.replace_by_fee_ratio,
+            Duration::from_nanos(cfg.prune_cooldown),
+            cfg.gas_limit_overestimation,
+            cfg.max_local_sender_pending,
+            cfg.max_remote_sender_pending,
+        )
+    }
+}
+
+/// Message proposed to be pushed, checked by `MpoolCheckMessages`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessagePrototypeJson {
+    #[serde(with = "unsigned_message::json")]
+    pub message: UnsignedMessage,
+    pub valid_nonce: bool,
+}
+
+impl From<MessagePrototypeJson> for MessagePrototype {
+    fn from(proto: MessagePrototypeJson) -> Self {
+        Self {
+            message: proto.message,
+            valid_nonce: proto.valid_nonce,
+        }
+    }
+}
+
+/// Result of one of the checks done on a message by the mpool check methods.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageCheckStatusJson {
+    #[serde(with = "cid::json")]
+    pub cid: Cid,
+    pub code: u8,
+    #[serde(rename = "OK")]
+    pub ok: bool,
+    pub err: String,
+    pub hint: BTreeMap<String, String>,
+}
+
+impl From<MessageCheckStatus> for MessageCheckStatusJson {
+    fn from(check: MessageCheckStatus) -> Self {
+        Self {
+            cid: check.cid,
+            code: check.status.code as u8,
+            ok: check.status.ok,
+            err: check.status.err,
+            hint: check.status.hint,
+        }
+    }
 }
 
 // State API
@@ -163,6 +275,44 @@ pub struct MessageLookup {
     pub return_dec: IpldJson,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ComputeStateOutput {
+    pub root: CidJson,
+    pub trace: Vec<InvocResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BlockstoreCheckResult {
+    pub checked: u64,
+    #[serde(with = "cid::json::vec")]
+    pub missing: Vec<Cid>,
+    #[serde(with = "cid::json::vec")]
+    pub corrupt: Vec<Cid>,
+    pub damaged_tipsets: Vec<TipsetKeysJson>,
+    /// Number of damaged tipsets whose headers and messages were refetched from peers.
+    pub repaired: u64,
+    pub duration: u64,
+}
+
+impl From<BlockstoreCheck> for BlockstoreCheckResult {
+    fn from(check: BlockstoreCheck) -> Self {
+        Self {
+            checked: check.checked,
+            missing: check.missing,
+            corrupt: check.corrupt,
+            damaged_tipsets: check
+                .damaged_tipsets
+                .into_iter()
+                .map(TipsetKeysJson)
+                .collect(),
+            repaired: 0,
+            duration: check.duration,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct BlockTemplate {
@@ -217,6 +367,93 @@ impl From<MiningBaseInfo> for MiningBaseInfoJson {
     }
 }
 
+// Msig API
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigTransaction {
+    #[serde(rename = "ID")]
+    pub id: i64,
+    #[serde(with = "address::json")]
+    pub to: Address,
+    #[serde(with = "bigint_ser::json")]
+    pub value: TokenAmount,
+    pub method: u64,
+    /// Base64 encoded method parameters.
+    pub params: String,
+    #[serde(with = "address::json::vec")]
+    pub approved: Vec<Address>,
+}
+
+impl From<multisig::Transaction> for MsigTransaction {
+    fn from(tx: multisig::Transaction) -> Self {
+        Self {
+            id: tx.id,
+            to: tx.to,
+            value: tx.value,
+            method: tx.method,
+            params: base64::encode(tx.params.bytes()),
+            approved: tx.approved,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigVesting {
+    #[serde(with = "bigint_ser::json")]
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+}
+
+// Paych API
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PaychChannelInfo {
+    /// Address of the channel, unset while the channel is being created.
+    pub channel: Option<AddressJson>,
+    /// Message to wait for before the channel can be used, if any.
+    pub wait_sentinel: Option<CidJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct VoucherCreateResultJson {
+    #[serde(with = "paychmgr::json::opt")]
+    pub voucher: Option<SignedVoucher>,
+    #[serde(with = "bigint_ser::json")]
+    pub shortfall: TokenAmount,
+}
+
+impl From<VoucherCreateResult> for VoucherCreateResultJson {
+    fn from(res: VoucherCreateResult) -> Self {
+        Self {
+            voucher: res.voucher,
+            shortfall: res.shortfall,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PaychStatus {
+    pub control_addr: AddressJson,
+    /// Direction of the channel, 1 for inbound and 2 for outbound.
+    pub direction: u8,
+}
+
+impl From<&ChannelInfo> for PaychStatus {
+    fn from(ci: &ChannelInfo) -> Self {
+        Self {
+            control_addr: AddressJson(ci.control),
+            direction: match ci.direction {
+                Direction::Inbound => 1,
+                Direction::Outbound => 2,
+            },
+        }
+    }
+}
+
 // Net API
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -226,6 +463,40 @@ pub struct AddrInfo {
     pub addrs: Vec<Multiaddr>,
 }
 
+/// Addresses, request stats and ban state of a peer known to the node.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExtendedPeerInfo {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub addrs: Vec<Multiaddr>,
+    pub connected: bool,
+    /// Unix time, in seconds, the peer was last connected.
+    pub last_seen: Option<u64>,
+    pub successes: u32,
+    pub failures: u32,
+    /// Average response time of the peer, in milliseconds.
+    pub average_latency: u64,
+    /// Whether the syncer ignores the peer after it failed to serve requests.
+    pub bad: bool,
+    /// Whether the peer was blocked by the operator.
+    pub blocked: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct NetBlockList {
+    pub peers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PubsubScore {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub score: f64,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PeerID {
     pub multihash: Multihash,
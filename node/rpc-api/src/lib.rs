@@ -41,6 +41,10 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(chain_api::CHAIN_GET_TIPSET, Access::Read);
     access.insert(chain_api::CHAIN_GET_RANDOMNESS_FROM_TICKETS, Access::Read);
     access.insert(chain_api::CHAIN_GET_RANDOMNESS_FROM_BEACON, Access::Read);
+    access.insert(chain_api::CHAIN_EXPORT, Access::Admin);
+    access.insert(chain_api::CHAIN_GARBAGE_COLLECT, Access::Admin);
+    access.insert(chain_api::CHAIN_CHECK_BLOCKSTORE, Access::Admin);
+    access.insert(chain_api::CHAIN_SET_HEAD, Access::Admin);
 
     // Message Pool API
     access.insert(mpool_api::MPOOL_ESTIMATE_GAS_PRICE, Access::Read);
@@ -49,10 +53,53 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(mpool_api::MPOOL_PUSH, Access::Write);
     access.insert(mpool_api::MPOOL_PUSH_MESSAGE, Access::Sign);
     access.insert(mpool_api::MPOOL_SELECT, Access::Read);
+    access.insert(mpool_api::MPOOL_GET_CONFIG, Access::Read);
+    access.insert(mpool_api::MPOOL_SET_CONFIG, Access::Admin);
+    access.insert(mpool_api::MPOOL_CLEAR, Access::Write);
+    access.insert(mpool_api::MPOOL_PUSH_UNTRUSTED, Access::Write);
+    access.insert(mpool_api::MPOOL_BATCH_PUSH, Access::Write);
+    access.insert(mpool_api::MPOOL_BATCH_PUSH_UNTRUSTED, Access::Write);
+    access.insert(mpool_api::MPOOL_BATCH_PUSH_MESSAGE, Access::Sign);
+    access.insert(mpool_api::MPOOL_CHECK_MESSAGES, Access::Read);
+    access.insert(mpool_api::MPOOL_CHECK_PENDING_MESSAGES, Access::Read);
+    access.insert(mpool_api::MPOOL_CHECK_REPLACE_MESSAGES, Access::Read);
+
+    // Msig API
+    access.insert(msig_api::MSIG_CREATE, Access::Sign);
+    access.insert(msig_api::MSIG_PROPOSE, Access::Sign);
+    access.insert(msig_api::MSIG_APPROVE, Access::Sign);
+    access.insert(msig_api::MSIG_APPROVE_TXN_HASH, Access::Sign);
+    access.insert(msig_api::MSIG_CANCEL, Access::Sign);
+    access.insert(msig_api::MSIG_ADD_PROPOSE, Access::Sign);
+    access.insert(msig_api::MSIG_SWAP_PROPOSE, Access::Sign);
+    access.insert(msig_api::MSIG_REMOVE_SIGNER, Access::Sign);
+    access.insert(msig_api::MSIG_GET_PENDING, Access::Read);
+    access.insert(msig_api::MSIG_GET_AVAILABLE_BALANCE, Access::Read);
+    access.insert(msig_api::MSIG_GET_VESTED, Access::Read);
+    access.insert(msig_api::MSIG_GET_VESTING_SCHEDULE, Access::Read);
+
+    // Paych API
+    access.insert(paych_api::PAYCH_GET, Access::Sign);
+    access.insert(paych_api::PAYCH_GET_WAIT_READY, Access::Sign);
+    access.insert(paych_api::PAYCH_ALLOCATE_LANE, Access::Sign);
+    access.insert(paych_api::PAYCH_VOUCHER_CREATE, Access::Sign);
+    access.insert(paych_api::PAYCH_VOUCHER_CHECK_VALID, Access::Read);
+    access.insert(paych_api::PAYCH_VOUCHER_CHECK_SPENDABLE, Access::Read);
+    access.insert(paych_api::PAYCH_VOUCHER_ADD, Access::Write);
+    access.insert(paych_api::PAYCH_VOUCHER_LIST, Access::Write);
+    access.insert(paych_api::PAYCH_VOUCHER_SUBMIT, Access::Sign);
+    access.insert(paych_api::PAYCH_SETTLE, Access::Sign);
+    access.insert(paych_api::PAYCH_COLLECT, Access::Sign);
+    access.insert(paych_api::PAYCH_STATUS, Access::Read);
+    access.insert(paych_api::PAYCH_LIST, Access::Read);
 
     // Sync API
+    access.insert(sync_api::SYNC_CHECKPOINT, Access::Admin);
     access.insert(sync_api::SYNC_CHECK_BAD, Access::Read);
     access.insert(sync_api::SYNC_MARK_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_ALL_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_VALIDATE_TIPSET, Access::Read);
     access.insert(sync_api::SYNC_STATE, Access::Read);
     access.insert(sync_api::SYNC_SUBMIT_BLOCK, Access::Write);
 
@@ -91,6 +138,7 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
         Access::Read,
     );
     access.insert(state_api::STATE_REPLAY, Access::Read);
+    access.insert(state_api::STATE_COMPUTE, Access::Read);
     access.insert(state_api::STATE_GET_ACTOR, Access::Read);
     access.insert(state_api::STATE_ACCOUNT_KEY, Access::Read);
     access.insert(state_api::STATE_LOOKUP_ID, Access::Read);
@@ -98,10 +146,12 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(state_api::STATE_MARKET_DEALS, Access::Read);
     access.insert(state_api::STATE_GET_RECEIPT, Access::Read);
     access.insert(state_api::STATE_WAIT_MSG, Access::Read);
+    access.insert(state_api::STATE_SEARCH_MSG, Access::Read);
     access.insert(state_api::STATE_MINER_SECTOR_ALLOCATED, Access::Read);
     access.insert(state_api::STATE_NETWORK_NAME, Access::Read);
     access.insert(state_api::MINER_GET_BASE_INFO, Access::Read);
     access.insert(state_api::STATE_LIST_ACTORS, Access::Read);
+    access.insert(state_api::STATE_CHANGED_ACTORS, Access::Read);
     access.insert(state_api::MINER_CREATE_BLOCK, Access::Write);
     access.insert(state_api::STATE_NETWORK_VERSION, Access::Read);
 
@@ -119,6 +169,14 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(net_api::NET_PEERS, Access::Read);
     access.insert(net_api::NET_CONNECT, Access::Write);
     access.insert(net_api::NET_DISCONNECT, Access::Write);
+    access.insert(net_api::NET_PEER_INFO, Access::Read);
+    access.insert(net_api::NET_BLOCK_ADD, Access::Admin);
+    access.insert(net_api::NET_BLOCK_REMOVE, Access::Admin);
+    access.insert(net_api::NET_BLOCK_LIST, Access::Read);
+    access.insert(net_api::NET_PROTECT_ADD, Access::Admin);
+    access.insert(net_api::NET_PROTECT_REMOVE, Access::Admin);
+    access.insert(net_api::NET_PROTECT_LIST, Access::Read);
+    access.insert(net_api::NET_PUBSUB_SCORES, Access::Read);
 
     access
 });
@@ -162,12 +220,12 @@ pub mod beacon_api {
 
 /// Chain API
 pub mod chain_api {
-    use crate::data_types::BlockMessages;
+    use crate::data_types::{BlockMessages, BlockstoreCheckResult};
     use blocks::{
         header::json::BlockHeaderJson, tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson,
         TipsetKeys,
     };
-    use chain::headchange_json::SubscriptionHeadChange;
+    use chain::{headchange_json::SubscriptionHeadChange, GcStats};
     use cid::json::CidJson;
     use clock::ChainEpoch;
     use message::unsigned_message::json::UnsignedMessageJson;
@@ -228,11 +286,30 @@ pub mod chain_api {
     pub const CHAIN_GET_RANDOMNESS_FROM_BEACON: &str = "Filecoin.ChainGetRandomnessFromBeacon";
     pub type ChainGetRandomnessFromBeaconParams = (TipsetKeysJson, i64, ChainEpoch, Option<String>);
     pub type ChainGetRandomnessFromBeaconResult = [u8; 32];
+
+    pub const CHAIN_EXPORT: &str = "Filecoin.ChainExport";
+    pub type ChainExportParams = (ChainEpoch, ChainEpoch, bool, String, TipsetKeysJson);
+    pub type ChainExportResult = String;
+
+    pub const CHAIN_GARBAGE_COLLECT: &str = "Filecoin.ChainGarbageCollect";
+    pub type ChainGarbageCollectParams = (ChainEpoch, bool);
+    pub type ChainGarbageCollectResult = GcStats;
+
+    pub const CHAIN_CHECK_BLOCKSTORE: &str = "Filecoin.ChainCheckBlockstore";
+    pub type ChainCheckBlockstoreParams = (ChainEpoch, bool);
+    pub type ChainCheckBlockstoreResult = BlockstoreCheckResult;
+
+    pub const CHAIN_SET_HEAD: &str = "Filecoin.ChainSetHead";
+    pub type ChainSetHeadParams = (TipsetKeysJson,);
+    pub type ChainSetHeadResult = ();
 }
 
 /// Message Pool API
 pub mod mpool_api {
-    use crate::data_types::MessageSendSpec;
+    use crate::data_types::{
+        MessageCheckStatusJson, MessagePrototypeJson, MessageSendSpec, MpoolConfigJson,
+    };
+    use address::json::AddressJson;
     use blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
     use cid::json::CidJson;
     use message::{
@@ -265,14 +342,199 @@ pub mod mpool_api {
     pub const MPOOL_SELECT: &str = "Filecoin.MpoolSelect";
     pub type MpoolSelectParams = (TipsetKeysJson, f64);
     pub type MpoolSelectResult = Vec<SignedMessageJson>;
+
+    pub const MPOOL_GET_CONFIG: &str = "Filecoin.MpoolGetConfig";
+    pub type MpoolGetConfigParams = ();
+    pub type MpoolGetConfigResult = MpoolConfigJson;
+
+    pub const MPOOL_SET_CONFIG: &str = "Filecoin.MpoolSetConfig";
+    pub type MpoolSetConfigParams = (MpoolConfigJson,);
+    pub type MpoolSetConfigResult = ();
+
+    pub const MPOOL_CLEAR: &str = "Filecoin.MpoolClear";
+    pub type MpoolClearParams = (bool,);
+    pub type MpoolClearResult = ();
+
+    pub const MPOOL_PUSH_UNTRUSTED: &str = "Filecoin.MpoolPushUntrusted";
+    pub type MpoolPushUntrustedParams = (SignedMessageJson,);
+    pub type MpoolPushUntrustedResult = CidJson;
+
+    pub const MPOOL_BATCH_PUSH: &str = "Filecoin.MpoolBatchPush";
+    pub type MpoolBatchPushParams = (Vec<SignedMessageJson>,);
+    pub type MpoolBatchPushResult = Vec<CidJson>;
+
+    pub const MPOOL_BATCH_PUSH_UNTRUSTED: &str = "Filecoin.MpoolBatchPushUntrusted";
+    pub type MpoolBatchPushUntrustedParams = (Vec<SignedMessageJson>,);
+    pub type MpoolBatchPushUntrustedResult = Vec<CidJson>;
+
+    pub const MPOOL_BATCH_PUSH_MESSAGE: &str = "Filecoin.MpoolBatchPushMessage";
+    pub type MpoolBatchPushMessageParams = (Vec<UnsignedMessageJson>, Option<MessageSendSpec>);
+    pub type MpoolBatchPushMessageResult = Vec<SignedMessageJson>;
+
+    pub const MPOOL_CHECK_MESSAGES: &str = "Filecoin.MpoolCheckMessages";
+    pub type MpoolCheckMessagesParams = (Vec<MessagePrototypeJson>,);
+    pub type MpoolCheckMessagesResult = Vec<Vec<MessageCheckStatusJson>>;
+
+    pub const MPOOL_CHECK_PENDING_MESSAGES: &str = "Filecoin.MpoolCheckPendingMessages";
+    pub type MpoolCheckPendingMessagesParams = (AddressJson,);
+    pub type MpoolCheckPendingMessagesResult = Vec<Vec<MessageCheckStatusJson>>;
+
+    pub const MPOOL_CHECK_REPLACE_MESSAGES: &str = "Filecoin.MpoolCheckReplaceMessages";
+    pub type MpoolCheckReplaceMessagesParams = (Vec<UnsignedMessageJson>,);
+    pub type MpoolCheckReplaceMessagesResult = Vec<Vec<MessageCheckStatusJson>>;
+}
+
+/// Msig API
+pub mod msig_api {
+    use crate::data_types::{MsigTransaction, MsigVesting};
+    use address::json::AddressJson;
+    use blocks::tipset_keys_json::TipsetKeysJson;
+    use cid::json::CidJson;
+    use clock::ChainEpoch;
+
+    pub const MSIG_CREATE: &str = "Filecoin.MsigCreate";
+    pub type MsigCreateParams = (
+        u64,
+        Vec<AddressJson>,
+        ChainEpoch,
+        String,
+        AddressJson,
+        String,
+    );
+    pub type MsigCreateResult = CidJson;
+
+    pub const MSIG_PROPOSE: &str = "Filecoin.MsigPropose";
+    pub type MsigProposeParams = (AddressJson, AddressJson, String, AddressJson, u64, String);
+    pub type MsigProposeResult = CidJson;
+
+    pub const MSIG_APPROVE: &str = "Filecoin.MsigApprove";
+    pub type MsigApproveParams = (AddressJson, i64, AddressJson);
+    pub type MsigApproveResult = CidJson;
+
+    pub const MSIG_APPROVE_TXN_HASH: &str = "Filecoin.MsigApproveTxnHash";
+    pub type MsigApproveTxnHashParams = (
+        AddressJson,
+        i64,
+        AddressJson,
+        AddressJson,
+        String,
+        AddressJson,
+        u64,
+        String,
+    );
+    pub type MsigApproveTxnHashResult = CidJson;
+
+    pub const MSIG_CANCEL: &str = "Filecoin.MsigCancel";
+    pub type MsigCancelParams = (
+        AddressJson,
+        i64,
+        AddressJson,
+        String,
+        AddressJson,
+        u64,
+        String,
+    );
+    pub type MsigCancelResult = CidJson;
+
+    pub const MSIG_ADD_PROPOSE: &str = "Filecoin.MsigAddPropose";
+    pub type MsigAddProposeParams = (AddressJson, AddressJson, AddressJson, bool);
+    pub type MsigAddProposeResult = CidJson;
+
+    pub const MSIG_SWAP_PROPOSE: &str = "Filecoin.MsigSwapPropose";
+    pub type MsigSwapProposeParams = (AddressJson, AddressJson, AddressJson, AddressJson);
+    pub type MsigSwapProposeResult = CidJson;
+
+    pub const MSIG_REMOVE_SIGNER: &str = "Filecoin.MsigRemoveSigner";
+    pub type MsigRemoveSignerParams = (AddressJson, AddressJson, AddressJson, bool);
+    pub type MsigRemoveSignerResult = CidJson;
+
+    pub const MSIG_GET_PENDING: &str = "Filecoin.MsigGetPending";
+    pub type MsigGetPendingParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetPendingResult = Vec<MsigTransaction>;
+
+    pub const MSIG_GET_AVAILABLE_BALANCE: &str = "Filecoin.MsigGetAvailableBalance";
+    pub type MsigGetAvailableBalanceParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetAvailableBalanceResult = String;
+
+    pub const MSIG_GET_VESTED: &str = "Filecoin.MsigGetVested";
+    pub type MsigGetVestedParams = (AddressJson, TipsetKeysJson, TipsetKeysJson);
+    pub type MsigGetVestedResult = String;
+
+    pub const MSIG_GET_VESTING_SCHEDULE: &str = "Filecoin.MsigGetVestingSchedule";
+    pub type MsigGetVestingScheduleParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetVestingScheduleResult = MsigVesting;
+}
+
+/// Paych API
+pub mod paych_api {
+    use crate::data_types::{PaychChannelInfo, PaychStatus, VoucherCreateResultJson};
+    use address::json::AddressJson;
+    use cid::json::CidJson;
+    use paychmgr::json::SignedVoucherJson;
+
+    pub const PAYCH_GET: &str = "Filecoin.PaychGet";
+    pub type PaychGetParams = (AddressJson, AddressJson, String);
+    pub type PaychGetResult = PaychChannelInfo;
+
+    pub const PAYCH_GET_WAIT_READY: &str = "Filecoin.PaychGetWaitReady";
+    pub type PaychGetWaitReadyParams = (CidJson,);
+    pub type PaychGetWaitReadyResult = AddressJson;
+
+    pub const PAYCH_ALLOCATE_LANE: &str = "Filecoin.PaychAllocateLane";
+    pub type PaychAllocateLaneParams = (AddressJson,);
+    pub type PaychAllocateLaneResult = u64;
+
+    pub const PAYCH_VOUCHER_CREATE: &str = "Filecoin.PaychVoucherCreate";
+    pub type PaychVoucherCreateParams = (AddressJson, String, u64);
+    pub type PaychVoucherCreateResult = VoucherCreateResultJson;
+
+    pub const PAYCH_VOUCHER_CHECK_VALID: &str = "Filecoin.PaychVoucherCheckValid";
+    pub type PaychVoucherCheckValidParams = (AddressJson, SignedVoucherJson);
+    pub type PaychVoucherCheckValidResult = ();
+
+    pub const PAYCH_VOUCHER_CHECK_SPENDABLE: &str = "Filecoin.PaychVoucherCheckSpendable";
+    pub type PaychVoucherCheckSpendableParams = (AddressJson, SignedVoucherJson, String, String);
+    pub type PaychVoucherCheckSpendableResult = bool;
+
+    pub const PAYCH_VOUCHER_ADD: &str = "Filecoin.PaychVoucherAdd";
+    pub type PaychVoucherAddParams = (AddressJson, SignedVoucherJson, String, String);
+    pub type PaychVoucherAddResult = String;
+
+    pub const PAYCH_VOUCHER_LIST: &str = "Filecoin.PaychVoucherList";
+    pub type PaychVoucherListParams = (AddressJson,);
+    pub type PaychVoucherListResult = Vec<SignedVoucherJson>;
+
+    pub const PAYCH_VOUCHER_SUBMIT: &str = "Filecoin.PaychVoucherSubmit";
+    pub type PaychVoucherSubmitParams = (AddressJson, SignedVoucherJson, String, String);
+    pub type PaychVoucherSubmitResult = CidJson;
+
+    pub const PAYCH_SETTLE: &str = "Filecoin.PaychSettle";
+    pub type PaychSettleParams = (AddressJson,);
+    pub type PaychSettleResult = CidJson;
+
+    pub const PAYCH_COLLECT: &str = "Filecoin.PaychCollect";
+    pub type PaychCollectParams = (AddressJson,);
+    pub type PaychCollectResult = CidJson;
+
+    pub const PAYCH_STATUS: &str = "Filecoin.PaychStatus";
+    pub type PaychStatusParams = (AddressJson,);
+    pub type PaychStatusResult = PaychStatus;
+
+    pub const PAYCH_LIST: &str = "Filecoin.PaychList";
+    pub type PaychListParams = ();
+    pub type PaychListResult = Vec<AddressJson>;
 }
 
 /// Sync API
 pub mod sync_api {
     use crate::data_types::RPCSyncState;
-    use blocks::gossip_block::json::GossipBlockJson;
+    use blocks::{gossip_block::json::GossipBlockJson, tipset_keys_json::TipsetKeysJson};
     use cid::json::CidJson;
 
+    pub const SYNC_CHECKPOINT: &str = "Filecoin.SyncCheckpoint";
+    pub type SyncCheckpointParams = (TipsetKeysJson,);
+    pub type SyncCheckpointResult = ();
+
     pub const SYNC_CHECK_BAD: &str = "Filecoin.SyncCheckBad";
     pub type SyncCheckBadParams = (CidJson,);
     pub type SyncCheckBadResult = String;
@@ -281,6 +543,18 @@ pub mod sync_api {
     pub type SyncMarkBadParams = (CidJson,);
     pub type SyncMarkBadResult = ();
 
+    pub const SYNC_UNMARK_BAD: &str = "Filecoin.SyncUnmarkBad";
+    pub type SyncUnmarkBadParams = (CidJson,);
+    pub type SyncUnmarkBadResult = ();
+
+    pub const SYNC_UNMARK_ALL_BAD: &str = "Filecoin.SyncUnmarkAllBad";
+    pub type SyncUnmarkAllBadParams = ();
+    pub type SyncUnmarkAllBadResult = ();
+
+    pub const SYNC_VALIDATE_TIPSET: &str = "Filecoin.SyncValidateTipset";
+    pub type SyncValidateTipsetParams = (TipsetKeysJson,);
+    pub type SyncValidateTipsetResult = bool;
+
     pub const SYNC_STATE: &str = "Filecoin.SyncState";
     pub type SyncStateParams = ();
     pub type SyncStateResult = RPCSyncState;
@@ -349,8 +623,8 @@ pub mod state_api {
     use std::collections::HashMap;
 
     use crate::data_types::{
-        ActorStateJson, BlockTemplate, Deadline, Fault, MarketDeal, MessageLookup,
-        MiningBaseInfoJson, Partition,
+        ActorStateJson, BlockTemplate, ComputeStateOutput, Deadline, Fault, MarketDeal,
+        MessageLookup, MiningBaseInfoJson, Partition,
     };
     use actor::miner::{
         MinerInfo, MinerPower, SectorOnChainInfo, SectorPreCommitInfo, SectorPreCommitOnChainInfo,
@@ -367,6 +641,7 @@ pub mod state_api {
         message_receipt::json::MessageReceiptJson, unsigned_message::json::UnsignedMessageJson,
     };
     use state_manager::{InvocResult, MarketBalance};
+    use statediff::StateDiff;
 
     pub const STATE_MINER_SECTORS: &str = "Filecoin.StateMinerSectors";
     pub type StateMinerSectorsParams = (AddressJson, BitFieldJson, TipsetKeysJson);
@@ -416,6 +691,10 @@ pub mod state_api {
     pub type StateReplayParams = (CidJson, TipsetKeysJson);
     pub type StateReplayResult = InvocResult;
 
+    pub const STATE_COMPUTE: &str = "Filecoin.StateCompute";
+    pub type StateComputeParams = (ChainEpoch, Vec<UnsignedMessageJson>, TipsetKeysJson);
+    pub type StateComputeResult = ComputeStateOutput;
+
     pub const STATE_NETWORK_NAME: &str = "Filecoin.StateNetworkName";
     pub type StateNetworkNameParams = ();
     pub type StateNetworkNameResult = String;
@@ -432,6 +711,10 @@ pub mod state_api {
     pub type StateListActorsParams = (TipsetKeysJson,);
     pub type StateListActorsResult = Vec<AddressJson>;
 
+    pub const STATE_CHANGED_ACTORS: &str = "Filecoin.StateChangedActors";
+    pub type StateChangedActorsParams = (CidJson, CidJson);
+    pub type StateChangedActorsResult = StateDiff;
+
     pub const STATE_ACCOUNT_KEY: &str = "Filecoin.StateAccountKey";
     pub type StateAccountKeyParams = (AddressJson, TipsetKeysJson);
     pub type StateAccountKeyResult = Option<AddressJson>;
@@ -456,6 +739,10 @@ pub mod state_api {
     pub type StateWaitMsgParams = (CidJson, i64);
     pub type StateWaitMsgResult = MessageLookup;
 
+    pub const STATE_SEARCH_MSG: &str = "Filecoin.StateSearchMsg";
+    pub type StateSearchMsgParams = (CidJson,);
+    pub type StateSearchMsgResult = Option<MessageLookup>;
+
     pub const MINER_CREATE_BLOCK: &str = "Filecoin.MinerCreateBlock";
     pub type MinerCreateBlockParams = (BlockTemplate,);
     pub type MinerCreateBlockResult = BlockMsgJson;
@@ -521,7 +808,7 @@ pub mod common_api {
 
 /// Net API
 pub mod net_api {
-    use crate::data_types::AddrInfo;
+    use crate::data_types::{AddrInfo, ExtendedPeerInfo, NetBlockList, PubsubScore};
 
     pub const NET_ADDRS_LISTEN: &str = "Filecoin.NetAddrsListen";
     pub type NetAddrsListenParams = ();
@@ -538,4 +825,36 @@ pub mod net_api {
     pub const NET_DISCONNECT: &str = "Filecoin.NetDisconnect";
     pub type NetDisconnectParams = (String,);
     pub type NetDisconnectResult = ();
+
+    pub const NET_PEER_INFO: &str = "Filecoin.NetPeerInfo";
+    pub type NetPeerInfoParams = (String,);
+    pub type NetPeerInfoResult = ExtendedPeerInfo;
+
+    pub const NET_BLOCK_ADD: &str = "Filecoin.NetBlockAdd";
+    pub type NetBlockAddParams = (NetBlockList,);
+    pub type NetBlockAddResult = ();
+
+    pub const NET_BLOCK_REMOVE: &str = "Filecoin.NetBlockRemove";
+    pub type NetBlockRemoveParams = (NetBlockList,);
+    pub type NetBlockRemoveResult = ();
+
+    pub const NET_BLOCK_LIST: &str = "Filecoin.NetBlockList";
+    pub type NetBlockListParams = ();
+    pub type NetBlockListResult = NetBlockList;
+
+    pub const NET_PROTECT_ADD: &str = "Filecoin.NetProtectAdd";
+    pub type NetProtectAddParams = (Vec<String>,);
+    pub type NetProtectAddResult = ();
+
+    pub const NET_PROTECT_REMOVE: &str = "Filecoin.NetProtectRemove";
+    pub type NetProtectRemoveParams = (Vec<String>,);
+    pub type NetProtectRemoveResult = ();
+
+    pub const NET_PROTECT_LIST: &str = "Filecoin.NetProtectList";
+    pub type NetProtectListParams = ();
+    pub type NetProtectListResult = Vec<String>;
+
+    pub const NET_PUBSUB_SCORES: &str = "Filecoin.NetPubsubScores";
+    pub type NetPubsubScoresParams = ();
+    pub type NetPubsubScoresResult = Vec<PubsubScore>;
 }
@@ -24,3 +24,23 @@ pub async fn chain_get_message(cid: ChainGetMessageParams) -> Result<ChainGetMes
 pub async fn chain_read_obj(cid: ChainReadObjParams) -> Result<ChainReadObjResult, Error> {
     call(CHAIN_READ_OBJ, cid).await
 }
+
+pub async fn chain_export(params: ChainExportParams) -> Result<ChainExportResult, Error> {
+    call(CHAIN_EXPORT, params).await
+}
+
+pub async fn chain_garbage_collect(
+    params: ChainGarbageCollectParams,
+) -> Result<ChainGarbageCollectResult, Error> {
+    call(CHAIN_GARBAGE_COLLECT, params).await
+}
+
+pub async fn chain_check_blockstore(
+    params: ChainCheckBlockstoreParams,
+) -> Result<ChainCheckBlockstoreResult, Error> {
+    call(CHAIN_CHECK_BLOCKSTORE, params).await
+}
+
+pub async fn chain_set_head(params: ChainSetHeadParams) -> Result<ChainSetHeadResult, Error> {
+    call(CHAIN_SET_HEAD, params).await
+}
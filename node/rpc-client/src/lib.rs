@@ -4,6 +4,8 @@
 /// Filecoin RPC client interface methods
 pub mod auth_ops;
 pub mod chain_ops;
+pub mod mpool_ops;
+pub mod msig_ops;
 pub mod net_ops;
 pub mod state_ops;
 pub mod sync_ops;
@@ -18,6 +20,7 @@ use once_cell::sync::Lazy;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::str::FromStr;
 
 pub const API_INFO_KEY: &str = "FULLNODE_API_INFO";
 pub const DEFAULT_HOST: &str = "127.0.0.1";
@@ -29,33 +32,81 @@ pub const RPC_ENDPOINT: &str = "rpc/v0";
 
 pub use self::auth_ops::*;
 pub use self::chain_ops::*;
+pub use self::mpool_ops::*;
+pub use self::msig_ops::*;
 pub use self::net_ops::*;
 pub use self::state_ops::*;
 pub use self::sync_ops::*;
 pub use self::wallet_ops::*;
 
+/// Address and token of the JSON-RPC API of a Forest daemon, in the
+/// `token:multiaddr` format of the `FULLNODE_API_INFO` environment variable.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ApiInfo {
     pub multiaddr: Multiaddr,
     pub token: Option<String>,
 }
 
-pub static API_INFO: Lazy<RwLock<ApiInfo>> = Lazy::new(|| {
-    // Get API_INFO environment variable if exists, otherwise, use default multiaddress
-    let api_info = env::var(API_INFO_KEY).unwrap_or_else(|_| DEFAULT_MULTIADDRESS.to_owned());
-
-    let (multiaddr, token) = match api_info.split_once(':') {
-        // Typically this is when a JWT was provided
-        Some((jwt, host)) => (
-            host.parse().expect("Parse multiaddress"),
-            Some(jwt.to_owned()),
-        ),
-        // Use entire API_INFO env var as host string
-        None => (api_info.parse().expect("Parse multiaddress"), None),
-    };
+impl ApiInfo {
+    /// API info of a daemon listening on the given port of the local host.
+    pub fn local(port: &str) -> Result<Self, String> {
+        Ok(Self {
+            multiaddr: format!("/ip4/{}/tcp/{}/http", DEFAULT_HOST, port)
+                .parse()
+                .map_err(|e| format!("Invalid RPC port {}: {}", port, e))?,
+            token: None,
+        })
+    }
+
+    /// Reads the API info from the `FULLNODE_API_INFO` environment variable, returns `None`
+    /// if it is not set.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        match env::var(API_INFO_KEY) {
+            Ok(api_info) => api_info
+                .parse()
+                .map(Some)
+                .map_err(|e| format!("Invalid {}: {}", API_INFO_KEY, e)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Returns the URL of the JSON-RPC endpoint.
+    pub fn url(&self) -> String {
+        multiaddress_to_url(&self.multiaddr)
+    }
+}
+
+impl FromStr for ApiInfo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // Multiaddresses start with a slash, anything before the first colon is a JWT
+        let (token, multiaddr) = match s.split_once(':') {
+            Some((jwt, multiaddr)) if !jwt.starts_with('/') => (Some(jwt.to_owned()), multiaddr),
+            _ => (None, s),
+        };
+        let multiaddr = multiaddr
+            .parse()
+            .map_err(|e| format!("invalid multiaddress {}: {}", multiaddr, e))?;
+        Ok(Self { multiaddr, token })
+    }
+}
 
-    RwLock::new(ApiInfo { multiaddr, token })
+/// API info the RPC calls are sent to. It is read from the `FULLNODE_API_INFO` environment
+/// variable if set, and defaults to the local daemon otherwise. A malformed variable is kept as
+/// an error, which the calls return, until binaries set valid API info.
+pub static API_INFO: Lazy<RwLock<Result<ApiInfo, String>>> = Lazy::new(|| {
+    RwLock::new(ApiInfo::from_env().map(|api_info| {
+        api_info.unwrap_or_else(|| DEFAULT_MULTIADDRESS.parse().expect("Parse multiaddress"))
+    }))
 });
 
+/// Returns the API info the RPC calls are sent to.
+pub async fn api_info() -> Result<ApiInfo, String> {
+    API_INFO.read().await.clone()
+}
+
 /// Error object in a response
 #[derive(Deserialize)]
 pub struct JsonRpcError {
@@ -84,10 +135,11 @@ struct URL {
     host: String,
 }
 
-/// Parses a multiaddress into a URL
-fn multiaddress_to_url(multiaddr: Multiaddr) -> String {
+/// Parses a multiaddress into a URL. Websocket endpoints are called over HTTP, as the daemon
+/// serves both on the same path.
+fn multiaddress_to_url(multiaddr: &Multiaddr) -> String {
     // Fold Multiaddress into a URL struct
-    let addr = multiaddr.into_iter().fold(
+    let addr = multiaddr.iter().fold(
         URL {
             protocol: DEFAULT_PROTOCOL.to_owned(),
             port: DEFAULT_PORT.to_owned(),
@@ -96,7 +148,7 @@ fn multiaddress_to_url(multiaddr: Multiaddr) -> String {
         |mut addr, protocol| {
             match protocol {
                 Protocol::Ip6(ip) => {
-                    addr.host = ip.to_string();
+                    addr.host = format!("[{}]", ip);
                 }
                 Protocol::Ip4(ip) => {
                     addr.host = ip.to_string();
@@ -116,10 +168,10 @@ fn multiaddress_to_url(multiaddr: Multiaddr) -> String {
                 Protocol::Tcp(p) => {
                     addr.port = p.to_string();
                 }
-                Protocol::Http => {
+                Protocol::Http | Protocol::Ws(_) => {
                     addr.protocol = "http".to_string();
                 }
-                Protocol::Https => {
+                Protocol::Https | Protocol::Wss(_) => {
                     addr.protocol = "https".to_string();
                 }
                 _ => {}
@@ -128,13 +180,10 @@ fn multiaddress_to_url(multiaddr: Multiaddr) -> String {
         },
     );
 
-    // Format, print and return the URL
-    let url = format!(
+    format!(
         "{}://{}:{}/{}",
         addr.protocol, addr.host, addr.port, RPC_ENDPOINT
-    );
-
-    url
+    )
 }
 
 /// Utility method for sending RPC requests over HTTP
@@ -148,8 +197,8 @@ where
         .with_params(serde_json::to_value(params)?)
         .finish();
 
-    let api_info = API_INFO.read().await;
-    let api_url = multiaddress_to_url(api_info.multiaddr.to_owned());
+    let api_info = api_info().await?;
+    let api_url = api_info.url();
 
     debug!("Using JSON-RPC v2 HTTP URL: {}", api_url);
 
@@ -199,3 +248,24 @@ where
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_api_info() {
+        let info: ApiInfo = "token:/ip4/10.0.0.1/tcp/2345/http".parse().unwrap();
+        assert_eq!(info.token.as_deref(), Some("token"));
+        assert_eq!(info.url(), "http://10.0.0.1:2345/rpc/v0");
+
+        let info: ApiInfo = "/dns4/node.example.com/tcp/443/wss".parse().unwrap();
+        assert_eq!(info.token, None);
+        assert_eq!(info.url(), "https://node.example.com:443/rpc/v0");
+
+        let info: ApiInfo = "/ip6/::1/tcp/1234/ws".parse().unwrap();
+        assert_eq!(info.url(), "http://[::1]:1234/rpc/v0");
+
+        assert!("token:not-a-multiaddr".parse::<ApiInfo>().is_err());
+    }
+}
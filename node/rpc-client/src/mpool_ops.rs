@@ -0,0 +1,84 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::call;
+use jsonrpc_v2::Error as JsonRpcError;
+use rpc_api::mpool_api::*;
+
+pub async fn mpool_get_nonce(
+    params: MpoolGetNonceParams,
+) -> Result<MpoolGetNonceResult, JsonRpcError> {
+    call(MPOOL_GET_NONCE, params).await
+}
+
+pub async fn mpool_pending(params: MpoolPendingParams) -> Result<MpoolPendingResult, JsonRpcError> {
+    call(MPOOL_PENDING, params).await
+}
+
+pub async fn mpool_push(params: MpoolPushParams) -> Result<MpoolPushResult, JsonRpcError> {
+    call(MPOOL_PUSH, params).await
+}
+
+pub async fn mpool_push_untrusted(
+    params: MpoolPushUntrustedParams,
+) -> Result<MpoolPushUntrustedResult, JsonRpcError> {
+    call(MPOOL_PUSH_UNTRUSTED, params).await
+}
+
+pub async fn mpool_push_message(
+    params: MpoolPushMessageParams,
+) -> Result<MpoolPushMessageResult, JsonRpcError> {
+    call(MPOOL_PUSH_MESSAGE, params).await
+}
+
+pub async fn mpool_batch_push(
+    params: MpoolBatchPushParams,
+) -> Result<MpoolBatchPushResult, JsonRpcError> {
+    call(MPOOL_BATCH_PUSH, params).await
+}
+
+pub async fn mpool_batch_push_untrusted(
+    params: MpoolBatchPushUntrustedParams,
+) -> Result<MpoolBatchPushUntrustedResult, JsonRpcError> {
+    call(MPOOL_BATCH_PUSH_UNTRUSTED, params).await
+}
+
+pub async fn mpool_batch_push_message(
+    params: MpoolBatchPushMessageParams,
+) -> Result<MpoolBatchPushMessageResult, JsonRpcError> {
+    call(MPOOL_BATCH_PUSH_MESSAGE, params).await
+}
+
+pub async fn mpool_get_config(
+    params: MpoolGetConfigParams,
+) -> Result<MpoolGetConfigResult, JsonRpcError> {
+    call(MPOOL_GET_CONFIG, params).await
+}
+
+pub async fn mpool_set_config(
+    params: MpoolSetConfigParams,
+) -> Result<MpoolSetConfigResult, JsonRpcError> {
+    call(MPOOL_SET_CONFIG, params).await
+}
+
+pub async fn mpool_clear(params: MpoolClearParams) -> Result<MpoolClearResult, JsonRpcError> {
+    call(MPOOL_CLEAR, params).await
+}
+
+pub async fn mpool_check_messages(
+    params: MpoolCheckMessagesParams,
+) -> Result<MpoolCheckMessagesResult, JsonRpcError> {
+    call(MPOOL_CHECK_MESSAGES, params).await
+}
+
+pub async fn mpool_check_pending_messages(
+    params: MpoolCheckPendingMessagesParams,
+) -> Result<MpoolCheckPendingMessagesResult, JsonRpcError> {
+    call(MPOOL_CHECK_PENDING_MESSAGES, params).await
+}
+
+pub async fn mpool_check_replace_messages(
+    params: MpoolCheckReplaceMessagesParams,
+) -> Result<MpoolCheckReplaceMessagesResult, JsonRpcError> {
+    call(MPOOL_CHECK_REPLACE_MESSAGES, params).await
+}
@@ -0,0 +1,70 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::call;
+use jsonrpc_v2::Error as JsonRpcError;
+use rpc_api::msig_api::*;
+
+pub async fn msig_create(params: MsigCreateParams) -> Result<MsigCreateResult, JsonRpcError> {
+    call(MSIG_CREATE, params).await
+}
+
+pub async fn msig_propose(params: MsigProposeParams) -> Result<MsigProposeResult, JsonRpcError> {
+    call(MSIG_PROPOSE, params).await
+}
+
+pub async fn msig_approve(params: MsigApproveParams) -> Result<MsigApproveResult, JsonRpcError> {
+    call(MSIG_APPROVE, params).await
+}
+
+pub async fn msig_approve_txn_hash(
+    params: MsigApproveTxnHashParams,
+) -> Result<MsigApproveTxnHashResult, JsonRpcError> {
+    call(MSIG_APPROVE_TXN_HASH, params).await
+}
+
+pub async fn msig_cancel(params: MsigCancelParams) -> Result<MsigCancelResult, JsonRpcError> {
+    call(MSIG_CANCEL, params).await
+}
+
+pub async fn msig_add_propose(
+    params: MsigAddProposeParams,
+) -> Result<MsigAddProposeResult, JsonRpcError> {
+    call(MSIG_ADD_PROPOSE, params).await
+}
+
+pub async fn msig_swap_propose(
+    params: MsigSwapProposeParams,
+) -> Result<MsigSwapProposeResult, JsonRpcError> {
+    call(MSIG_SWAP_PROPOSE, params).await
+}
+
+pub async fn msig_remove_signer(
+    params: MsigRemoveSignerParams,
+) -> Result<MsigRemoveSignerResult, JsonRpcError> {
+    call(MSIG_REMOVE_SIGNER, params).await
+}
+
+pub async fn msig_get_pending(
+    params: MsigGetPendingParams,
+) -> Result<MsigGetPendingResult, JsonRpcError> {
+    call(MSIG_GET_PENDING, params).await
+}
+
+pub async fn msig_get_available_balance(
+    params: MsigGetAvailableBalanceParams,
+) -> Result<MsigGetAvailableBalanceResult, JsonRpcError> {
+    call(MSIG_GET_AVAILABLE_BALANCE, params).await
+}
+
+pub async fn msig_get_vested(
+    params: MsigGetVestedParams,
+) -> Result<MsigGetVestedResult, JsonRpcError> {
+    call(MSIG_GET_VESTED, params).await
+}
+
+pub async fn msig_get_vesting_schedule(
+    params: MsigGetVestingScheduleParams,
+) -> Result<MsigGetVestingScheduleResult, JsonRpcError> {
+    call(MSIG_GET_VESTING_SCHEDULE, params).await
+}
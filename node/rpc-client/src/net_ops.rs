@@ -20,3 +20,39 @@ pub async fn net_connect(params: NetConnectParams) -> Result<NetConnectResult, E
 pub async fn net_disconnect(params: NetDisconnectParams) -> Result<NetDisconnectResult, Error> {
     call(NET_DISCONNECT, params).await
 }
+
+pub async fn net_peer_info(params: NetPeerInfoParams) -> Result<NetPeerInfoResult, Error> {
+    call(NET_PEER_INFO, params).await
+}
+
+pub async fn net_block_add(params: NetBlockAddParams) -> Result<NetBlockAddResult, Error> {
+    call(NET_BLOCK_ADD, params).await
+}
+
+pub async fn net_block_remove(params: NetBlockRemoveParams) -> Result<NetBlockRemoveResult, Error> {
+    call(NET_BLOCK_REMOVE, params).await
+}
+
+pub async fn net_block_list(params: NetBlockListParams) -> Result<NetBlockListResult, Error> {
+    call(NET_BLOCK_LIST, params).await
+}
+
+pub async fn net_protect_add(params: NetProtectAddParams) -> Result<NetProtectAddResult, Error> {
+    call(NET_PROTECT_ADD, params).await
+}
+
+pub async fn net_protect_remove(
+    params: NetProtectRemoveParams,
+) -> Result<NetProtectRemoveResult, Error> {
+    call(NET_PROTECT_REMOVE, params).await
+}
+
+pub async fn net_protect_list(params: NetProtectListParams) -> Result<NetProtectListResult, Error> {
+    call(NET_PROTECT_LIST, params).await
+}
+
+pub async fn net_pubsub_scores(
+    params: NetPubsubScoresParams,
+) -> Result<NetPubsubScoresResult, Error> {
+    call(NET_PUBSUB_SCORES, params).await
+}
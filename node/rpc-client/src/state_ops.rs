@@ -21,6 +21,12 @@ pub async fn state_list_actors(
     call(STATE_LIST_ACTORS, params).await
 }
 
+pub async fn state_changed_actors(
+    params: StateChangedActorsParams,
+) -> Result<StateChangedActorsResult, Error> {
+    call(STATE_CHANGED_ACTORS, params).await
+}
+
 pub async fn state_lookup(params: StateLookupIdParams) -> Result<StateLookupIdResult, Error> {
     call(STATE_LOOKUP_ID, params).await
 }
@@ -5,6 +5,12 @@ use crate::call;
 use jsonrpc_v2::Error as JsonRpcError;
 use rpc_api::sync_api::*;
 
+pub async fn sync_checkpoint(
+    params: SyncCheckpointParams,
+) -> Result<SyncCheckpointResult, JsonRpcError> {
+    call(SYNC_CHECKPOINT, params).await
+}
+
 pub async fn sync_check_bad(
     params: SyncCheckBadParams,
 ) -> Result<SyncCheckBadResult, JsonRpcError> {
@@ -15,6 +21,24 @@ pub async fn sync_mark_bad(params: SyncMarkBadParams) -> Result<SyncMarkBadResul
     call(SYNC_MARK_BAD, params).await
 }
 
+pub async fn sync_unmark_bad(
+    params: SyncUnmarkBadParams,
+) -> Result<SyncUnmarkBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_BAD, params).await
+}
+
+pub async fn sync_unmark_all_bad(
+    params: SyncUnmarkAllBadParams,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_ALL_BAD, params).await
+}
+
+pub async fn sync_validate_tipset(
+    params: SyncValidateTipsetParams,
+) -> Result<SyncValidateTipsetResult, JsonRpcError> {
+    call(SYNC_VALIDATE_TIPSET, params).await
+}
+
 pub async fn sync_status(params: SyncStateParams) -> Result<SyncStateResult, JsonRpcError> {
     call(SYNC_STATE, params).await
 }
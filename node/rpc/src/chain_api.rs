@@ -1,21 +1,32 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use async_std::{fs::File, future, io::BufWriter, task};
+use futures::channel::oneshot;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Id, Params};
-use log::debug;
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::rpc_util::get_error_obj;
+use actor::CHAIN_FINALITY;
 use beacon::Beacon;
 use blocks::{
     header::json::BlockHeaderJson, tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson,
-    BlockHeader, Tipset,
+    BlockHeader, FullTipset, Tipset, TipsetKeys,
 };
 use blockstore::BlockStore;
 use chain::headchange_json::HeadChangeJson;
+use chain_sync::TipsetValidator;
 use cid::{json::CidJson, Cid};
 use crypto::DomainSeparationTag;
+use fil_types::verifier::ProofVerifier;
+use forest_libp2p::{
+    chain_exchange::{ChainExchangeRequest, HEADERS, MESSAGES},
+    NetRPCMethods, NetworkMessage, PeerId,
+};
 use message::{
     unsigned_message::{self, json::UnsignedMessageJson},
     UnsignedMessage,
@@ -23,9 +34,12 @@ use message::{
 use num_traits::FromPrimitive;
 use rpc_api::{
     chain_api::*,
-    data_types::{BlockMessages, RPCState},
+    data_types::{BlockMessages, BlockstoreCheckResult, RPCState},
 };
 
+/// Timeout of a chain exchange request sent to refetch a damaged tipset.
+const REFETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub(crate) struct Message {
@@ -277,7 +291,7 @@ where
             DomainSeparationTag::from_i64(pers).ok_or("invalid DomainSeparationTag")?,
             epoch,
             &base64::decode(entropy)?,
-            epoch <= networks::UPGRADE_HYPERDRIVE_HEIGHT,
+            epoch <= data.state_manager.network_config().upgrades.hyperdrive,
         )
         .await?)
 }
@@ -301,7 +315,205 @@ where
             DomainSeparationTag::from_i64(pers).ok_or("invalid DomainSeparationTag")?,
             epoch,
             &base64::decode(entropy)?,
-            epoch <= networks::UPGRADE_HYPERDRIVE_HEIGHT,
+            epoch <= data.state_manager.network_config().upgrades.hyperdrive,
         )
         .await?)
 }
+
+pub(crate) async fn chain_export<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainExportParams>,
+) -> Result<ChainExportResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (epoch, recent_roots, skip_old_msgs, output_path, TipsetKeysJson(tsk)) = params;
+
+    if recent_roots < CHAIN_FINALITY {
+        return Err(format!(
+            "recent roots must be at least the chain finality ({})",
+            CHAIN_FINALITY
+        )
+        .into());
+    }
+
+    let chain_store = data.state_manager.chain_store();
+    let head = chain_store.tipset_from_keys(&tsk).await?;
+    let start_ts = chain_store.tipset_by_height(epoch, head, true).await?;
+
+    let output_path = PathBuf::from(output_path);
+    let file = File::create(&output_path).await.map_err(|e| {
+        format!(
+            "failed to create export file {}: {}",
+            output_path.display(),
+            e
+        )
+    })?;
+
+    info!(
+        "Exporting chain at epoch {} to {}",
+        start_ts.epoch(),
+        output_path.display()
+    );
+    chain_store
+        .export(&start_ts, recent_roots, skip_old_msgs, BufWriter::new(file))
+        .await?;
+
+    Ok(output_path.display().to_string())
+}
+
+pub(crate) async fn chain_garbage_collect<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainGarbageCollectParams>,
+) -> Result<ChainGarbageCollectResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (recent_roots, skip_old_msgs) = params;
+    // Pending messages and computed states are not referenced by the chain yet.
+    let keep = || async {
+        let mut keep = data
+            .mpool
+            .pending_cids()
+            .await
+            .map_err(|e| chain::Error::Other(e.to_string()))?;
+        keep.extend(data.state_manager.cached_state_roots().await);
+        Ok::<_, chain::Error>(keep)
+    };
+    Ok(data
+        .state_manager
+        .chain_store()
+        .collect_garbage(recent_roots, skip_old_msgs, keep)
+        .await?)
+}
+
+pub(crate) async fn chain_check_blockstore<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainCheckBlockstoreParams>,
+) -> Result<ChainCheckBlockstoreResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (depth, repair) = params;
+    let chain_store = data.state_manager.chain_store().clone();
+    let head = chain_store
+        .heaviest_tipset()
+        .await
+        .ok_or("no heaviest tipset to check from")?;
+    let check = task::spawn_blocking(move || chain_store.check_blockstore(&head, depth)).await?;
+
+    let damaged = check.damaged_tipsets.clone();
+    let mut result = BlockstoreCheckResult::from(check);
+    if repair && !damaged.is_empty() {
+        let (tx, rx) = oneshot::channel();
+        data.network_send
+            .send(NetworkMessage::JSONRPCRequest {
+                method: NetRPCMethods::NetPeers(tx),
+            })
+            .await?;
+        let peers: Vec<PeerId> = rx.await?.keys().copied().collect();
+        for tsk in &damaged {
+            match refetch_tipset(&data, &peers, tsk).await {
+                Ok(()) => result.repaired += 1,
+                Err(e) => warn!("Failed to refetch tipset {:?}: {}", tsk.cids(), e),
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Forcibly sets the head to a stored tipset, computing its state if needed. The blocks above
+/// the new head are unmarked as validated, so that they are validated again if synced.
+pub(crate) async fn chain_set_head<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainSetHeadParams>,
+) -> Result<ChainSetHeadResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (TipsetKeysJson(tsk),) = params;
+    let chain_store = data.state_manager.chain_store();
+    let new_head = chain_store.tipset_from_keys(&tsk).await?;
+
+    let mut current = chain_store
+        .heaviest_tipset()
+        .await
+        .ok_or("can't find heaviest tipset")?;
+    while current.epoch() > new_head.epoch() {
+        for cid in current.cids() {
+            chain_store.unmark_block_as_validated(cid)?;
+        }
+        current = chain_store.tipset_from_keys(current.parents()).await?;
+    }
+
+    data.state_manager.tipset_state::<V>(&new_head).await?;
+    chain_store.set_head(new_head).await?;
+    Ok(())
+}
+
+/// Requests the headers and messages of a tipset over chain exchange from each peer in turn,
+/// and writes the first complete response to the blockstore.
+async fn refetch_tipset<DB, B>(
+    data: &RPCState<DB, B>,
+    peers: &[PeerId],
+    tsk: &TipsetKeys,
+) -> Result<(), String>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    for peer_id in peers {
+        let (tx, rx) = oneshot::channel();
+        data.network_send
+            .send(NetworkMessage::ChainExchangeRequest {
+                peer_id: *peer_id,
+                request: ChainExchangeRequest {
+                    start: tsk.cids().to_vec(),
+                    request_len: 1,
+                    options: HEADERS | MESSAGES,
+                },
+                response_channel: tx,
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        let fts = match future::timeout(REFETCH_TIMEOUT, rx).await {
+            Ok(Ok(Ok(res))) => res.into_result::<FullTipset>(),
+            Ok(Ok(Err(e))) => Err(format!("{:?}", e)),
+            Ok(Err(_)) | Err(_) => Err("request timed out".to_owned()),
+        };
+        match fts {
+            Ok(mut fts) if fts.len() == 1 && fts[0].key() == tsk => {
+                return persist_full_tipset(data.state_manager.blockstore(), &fts.remove(0));
+            }
+            Ok(_) => debug!("Peer {} returned the wrong tipset", peer_id),
+            Err(e) => debug!("Chain exchange request to {} failed: {}", peer_id, e),
+        }
+    }
+    Err("no peer returned the tipset".to_owned())
+}
+
+/// Writes the headers and messages of a tipset, checking that the messages match the headers.
+fn persist_full_tipset<DB>(db: &DB, fts: &FullTipset) -> Result<(), String>
+where
+    DB: BlockStore,
+{
+    for block in fts.blocks() {
+        let msg_root = TipsetValidator::compute_msg_root(db, block.bls_msgs(), block.secp_msgs())
+            .map_err(|e| e.to_string())?;
+        if &msg_root != block.header().messages() {
+            return Err(format!(
+                "messages of block {} do not match its header",
+                block.cid()
+            ));
+        }
+        chain::persist_objects(db, &[block.header()]).map_err(|e| e.to_string())?;
+        chain::persist_objects(db, block.bls_msgs()).map_err(|e| e.to_string())?;
+        chain::persist_objects(db, block.secp_msgs()).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
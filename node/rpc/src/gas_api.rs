@@ -5,22 +5,30 @@ use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use num_traits::{FromPrimitive, Zero};
 use rand_distr::{Distribution, Normal};
 
-use address::json::AddressJson;
+use address::{json::AddressJson, Protocol};
 use beacon::Beacon;
 use blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
 use blockstore::BlockStore;
 use chain::{BASE_FEE_MAX_CHANGE_DENOM, BLOCK_GAS_TARGET, MINIMUM_BASE_FEE};
-use fil_types::{verifier::ProofVerifier, BLOCK_GAS_LIMIT};
-use message::{unsigned_message::json::UnsignedMessageJson, UnsignedMessage};
+use crypto::Signature;
+use fil_types::{verifier::ProofVerifier, BLOCK_GAS_LIMIT, FILECOIN_PRECISION};
+use message::{unsigned_message::json::UnsignedMessageJson, SignedMessage, UnsignedMessage};
 use message::{ChainMessage, Message};
 use num_bigint::BigInt;
 use rpc_api::{
     data_types::{MessageSendSpec, RPCState},
     gas_api::*,
 };
+use vm::TokenAmount;
 
 const MIN_GAS_PREMIUM: f64 = 100000.0;
 
+/// Maximum fee paid for a message whose send spec doesn't set one, 0.07 FIL.
+const DEFAULT_MAX_FEE: i64 = 7 * FILECOIN_PRECISION / 100;
+
+/// Length of a secp256k1 signature, which adds to the on chain size of a message.
+const SECP_SIG_LEN: usize = 65;
+
 /// Estimate the fee cap
 pub(crate) async fn gas_estimate_fee_cap<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -93,11 +101,6 @@ where
         nblocksincl = 1;
     }
 
-    struct GasMeta {
-        pub price: BigInt,
-        pub limit: i64,
-    }
-
     let mut prices: Vec<GasMeta> = Vec::new();
     let mut blocks = 0;
 
@@ -120,38 +123,16 @@ where
         blocks += pts.blocks().len();
         let msgs = chain::messages_for_tipset(data.state_manager.blockstore(), &pts)?;
 
-        prices.append(
-            &mut msgs
-                .iter()
-                .map(|msg| GasMeta {
-                    price: msg.gas_premium().clone(),
-                    limit: msg.gas_limit(),
-                })
-                .collect(),
-        );
+        prices.extend(msgs.iter().map(|msg| GasMeta {
+            price: msg.gas_premium().clone(),
+            limit: msg.gas_limit(),
+        }));
         ts = pts;
     }
 
-    prices.sort_by(|a, b| b.price.cmp(&a.price));
-    // TODO: From lotus, account for how full blocks are
-    let mut at = BLOCK_GAS_TARGET * blocks as i64 / 2;
-    let mut prev = BigInt::zero();
-    let mut premium = BigInt::zero();
+    let mut premium = median_gas_premium(prices, blocks);
 
-    for price in prices {
-        at -= price.limit;
-        if at > 0 {
-            prev = price.price;
-            continue;
-        }
-        if prev == 0.into() {
-            let ret: BigInt = price.price + 1;
-            return Ok(ret);
-        }
-        premium = (&price.price + &prev) / 2 + 1
-    }
-
-    if premium == 0.into() {
+    if premium < BigInt::from_f64(MIN_GAS_PREMIUM).unwrap_or_default() {
         premium = BigInt::from_f64(match nblocksincl {
             1 => MIN_GAS_PREMIUM * 2.0,
             2 => MIN_GAS_PREMIUM * 1.5,
@@ -174,6 +155,40 @@ where
     Ok(premium)
 }
 
+struct GasMeta {
+    price: BigInt,
+    limit: i64,
+}
+
+/// Returns the premium paid by the messages just past half of the gas target of the
+/// given number of blocks, when sorted by decreasing premium, as the average of the premium of
+/// the message crossing that point and of the one before it.
+///
+/// When the blocks are less than half full, this is the average of the two cheapest premiums,
+/// or the only premium when there is a single message.
+fn median_gas_premium(mut prices: Vec<GasMeta>, blocks: usize) -> BigInt {
+    prices.sort_by(|a, b| b.price.cmp(&a.price));
+
+    let mut at = BLOCK_GAS_TARGET * blocks as i64 / 2;
+    at += BLOCK_GAS_TARGET * blocks as i64 / (2 * 20);
+
+    let mut prev1 = BigInt::zero();
+    let mut prev2 = BigInt::zero();
+    for price in prices {
+        prev2 = std::mem::replace(&mut prev1, price.price);
+        at -= price.limit;
+        if at < 0 {
+            break;
+        }
+    }
+
+    if prev2.is_zero() {
+        prev1
+    } else {
+        (prev1 + prev2) / 2
+    }
+}
+
 /// Estimate the gas limit
 pub(crate) async fn gas_estimate_gas_limit<DB, B, V>(
     data: Data<RPCState<DB, B>>,
@@ -214,29 +229,40 @@ where
         .resolve_to_key_addr::<V>(msg.from(), &curr_ts)
         .await?;
 
-    let pending = data.mpool.pending_for(&from_a).await;
+    // Apply the message on top of the pending messages of the sender which precede it
+    let pending = data.mpool.pending_for(&from_a).await.unwrap_or_default();
     let prior_messages: Vec<ChainMessage> = pending
-        .map(|s| s.into_iter().map(ChainMessage::Signed).collect::<Vec<_>>())
-        .unwrap_or_default();
+        .into_iter()
+        .take_while(|m| m.sequence() != msg.sequence())
+        .map(ChainMessage::Signed)
+        .collect();
+
+    // The on chain size of the message, which is charged for, includes the signature
+    let mut chain_msg = match from_a.protocol() {
+        Protocol::Secp256k1 => ChainMessage::Signed(SignedMessage {
+            message: msg,
+            signature: Signature::new_secp256k1(vec![0; SECP_SIG_LEN]),
+        }),
+        _ => ChainMessage::Unsigned(msg),
+    };
 
     let res = data
         .state_manager
         .call_with_gas::<V>(
-            &mut ChainMessage::Unsigned(msg),
+            &mut chain_msg,
             &prior_messages,
             Some(data.mpool.cur_tipset.as_ref().read().await.clone()),
         )
         .await?;
     match res.msg_rct {
-        Some(rct) => {
-            if rct.exit_code as u64 != 0 {
-                return Ok(-1);
-            }
-            // TODO: Figure out why we always under estimate the gas calculation so we dont need to add 200000
-            // https://github.com/ChainSafe/forest/issues/901
-            Ok(rct.gas_used + 200000)
-        }
-        None => Ok(-1),
+        Some(rct) if rct.exit_code as u64 == 0 => Ok(rct.gas_used),
+        Some(rct) => Err(format!(
+            "message execution failed: exit {:?}, reason: {}",
+            rct.exit_code,
+            res.error.unwrap_or_default()
+        )
+        .into()),
+        None => Err("message execution returned no receipt".into()),
     }
 }
 
@@ -259,7 +285,7 @@ where
 pub(crate) async fn estimate_message_gas<DB, B, V>(
     data: &Data<RPCState<DB, B>>,
     msg: UnsignedMessage,
-    _spec: Option<MessageSendSpec>,
+    spec: Option<MessageSendSpec>,
     tsk: TipsetKeys,
 ) -> Result<UnsignedMessage, JsonRpcError>
 where
@@ -270,7 +296,8 @@ where
     let mut msg = msg;
     if msg.gas_limit() == 0 {
         let gl = estimate_gas_limit::<DB, B, V>(&data, msg.clone(), tsk.clone()).await?;
-        msg.gas_limit = gl;
+        let overestimation = data.mpool.get_config().await.gas_limit_overestimation;
+        msg.gas_limit = (gl as f64 * overestimation) as i64;
     }
     if msg.gas_premium().is_zero() {
         let gp = estimate_gas_premium(&data, 10).await?;
@@ -280,6 +307,84 @@ where
         let gfp = estimate_fee_cap(&data, msg.clone(), 20, tsk).await?;
         msg.gas_fee_cap = gfp;
     }
-    // TODO: Cap Gas Fee https://github.com/ChainSafe/forest/issues/901
+    cap_gas_fee(&mut msg, spec.map(|s| s.max_fee));
     Ok(msg)
 }
+
+/// Lowers the fee cap of the message so that it can't cost more than the maximum fee, or
+/// `DEFAULT_MAX_FEE` if it is not given, capping the premium to the fee cap.
+fn cap_gas_fee(msg: &mut UnsignedMessage, max_fee: Option<TokenAmount>) {
+    let max_fee = match max_fee {
+        Some(max_fee) if !max_fee.is_zero() => max_fee,
+        _ => BigInt::from(DEFAULT_MAX_FEE),
+    };
+
+    let gas_limit = BigInt::from(msg.gas_limit());
+    if msg.gas_fee_cap() * &gas_limit <= max_fee {
+        return;
+    }
+    msg.gas_fee_cap = max_fee / gas_limit;
+    if msg.gas_premium > msg.gas_fee_cap {
+        msg.gas_premium = msg.gas_fee_cap.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use address::Address;
+
+    fn gas_meta(price: u64, limit: i64) -> GasMeta {
+        GasMeta {
+            price: price.into(),
+            limit,
+        }
+    }
+
+    #[test]
+    fn median_premium_accounts_for_block_fullness() {
+        // Full block: the premium is taken just past half of the gas target
+        let prices = vec![
+            gas_meta(400, BLOCK_GAS_TARGET / 4),
+            gas_meta(300, BLOCK_GAS_TARGET / 4),
+            gas_meta(200, BLOCK_GAS_TARGET / 4),
+            gas_meta(100, BLOCK_GAS_TARGET / 4),
+        ];
+        assert_eq!(median_gas_premium(prices, 1), BigInt::from(250));
+
+        // Mostly empty block: the premium of the cheapest messages is used
+        let prices = vec![
+            gas_meta(400, BLOCK_GAS_TARGET / 10),
+            gas_meta(100, BLOCK_GAS_TARGET / 10),
+            gas_meta(300, BLOCK_GAS_TARGET / 10),
+        ];
+        assert_eq!(median_gas_premium(prices, 1), BigInt::from(200));
+
+        assert_eq!(median_gas_premium(Vec::new(), 1), BigInt::zero());
+    }
+
+    #[test]
+    fn cap_gas_fee_to_max_fee() {
+        let mut msg = UnsignedMessage::builder()
+            .to(Address::new_id(1))
+            .from(Address::new_id(2))
+            .gas_limit(1000)
+            .gas_fee_cap(500.into())
+            .gas_premium(400.into())
+            .build()
+            .unwrap();
+
+        // Under the maximum fee
+        cap_gas_fee(&mut msg, Some(500_000.into()));
+        assert_eq!(msg.gas_fee_cap(), &BigInt::from(500));
+
+        cap_gas_fee(&mut msg, Some(300_000.into()));
+        assert_eq!(msg.gas_fee_cap(), &BigInt::from(300));
+        assert_eq!(msg.gas_premium(), &BigInt::from(300));
+
+        // A zero maximum fee falls back to the default one
+        msg.gas_fee_cap = BigInt::from(DEFAULT_MAX_FEE);
+        cap_gas_fee(&mut msg, Some(BigInt::zero()));
+        assert_eq!(msg.gas_fee_cap(), &BigInt::from(DEFAULT_MAX_FEE / 1000));
+    }
+}
@@ -7,7 +7,9 @@ mod chain_api;
 mod common_api;
 mod gas_api;
 mod mpool_api;
+mod msig_api;
 mod net_api;
+mod paych_api;
 mod rpc_http_handler;
 mod rpc_util;
 mod rpc_ws_handler;
@@ -30,8 +32,8 @@ use crate::rpc_ws_handler::rpc_ws_handler;
 use crate::{beacon_api::beacon_get_entry, common_api::version, state_api::*};
 
 use rpc_api::{
-    auth_api::*, beacon_api::*, chain_api::*, common_api::*, gas_api::*, mpool_api::*, net_api::*,
-    state_api::*, sync_api::*, wallet_api::*,
+    auth_api::*, beacon_api::*, chain_api::*, common_api::*, gas_api::*, mpool_api::*, msig_api::*,
+    net_api::*, paych_api::*, state_api::*, sync_api::*, wallet_api::*,
 };
 
 pub async fn start_rpc<DB, B, V>(
@@ -47,6 +49,8 @@ where
     use chain_api::*;
     use gas_api::*;
     use mpool_api::*;
+    use msig_api::*;
+    use paych_api::*;
     use sync_api::*;
     use wallet_api::*;
 
@@ -83,6 +87,10 @@ where
                 chain_get_randomness_from_beacon::<DB, B>,
             )
             .with_method(CHAIN_GET_BLOCK, chain_api::chain_get_block::<DB, B>)
+            .with_method(CHAIN_EXPORT, chain_export::<DB, B>)
+            .with_method(CHAIN_GARBAGE_COLLECT, chain_garbage_collect::<DB, B>)
+            .with_method(CHAIN_CHECK_BLOCKSTORE, chain_check_blockstore::<DB, B>)
+            .with_method(CHAIN_SET_HEAD, chain_set_head::<DB, B, V>)
             // Message Pool API
             .with_method(MPOOL_ESTIMATE_GAS_PRICE, estimate_gas_premium::<DB, B>)
             .with_method(MPOOL_GET_NONCE, mpool_get_sequence::<DB, B>)
@@ -90,11 +98,76 @@ where
             .with_method(MPOOL_PUSH, mpool_push::<DB, B>)
             .with_method(MPOOL_PUSH_MESSAGE, mpool_push_message::<DB, B, V>)
             .with_method(MPOOL_SELECT, mpool_select::<DB, B>)
+            .with_method(MPOOL_GET_CONFIG, mpool_get_config::<DB, B>)
+            .with_method(MPOOL_SET_CONFIG, mpool_set_config::<DB, B>)
+            .with_method(MPOOL_CLEAR, mpool_clear::<DB, B>)
+            .with_method(MPOOL_PUSH_UNTRUSTED, mpool_push_untrusted::<DB, B>)
+            .with_method(MPOOL_BATCH_PUSH, mpool_batch_push::<DB, B>)
+            .with_method(
+                MPOOL_BATCH_PUSH_UNTRUSTED,
+                mpool_batch_push_untrusted::<DB, B>,
+            )
+            .with_method(
+                MPOOL_BATCH_PUSH_MESSAGE,
+                mpool_batch_push_message::<DB, B, V>,
+            )
+            .with_method(MPOOL_CHECK_MESSAGES, mpool_check_messages::<DB, B>)
+            .with_method(
+                MPOOL_CHECK_PENDING_MESSAGES,
+                mpool_check_pending_messages::<DB, B>,
+            )
+            .with_method(
+                MPOOL_CHECK_REPLACE_MESSAGES,
+                mpool_check_replace_messages::<DB, B>,
+            )
+            // Msig API
+            .with_method(MSIG_CREATE, msig_create::<DB, B, V>)
+            .with_method(MSIG_PROPOSE, msig_propose::<DB, B, V>)
+            .with_method(MSIG_APPROVE, msig_approve::<DB, B, V>)
+            .with_method(MSIG_APPROVE_TXN_HASH, msig_approve_txn_hash::<DB, B, V>)
+            .with_method(MSIG_CANCEL, msig_cancel::<DB, B, V>)
+            .with_method(MSIG_ADD_PROPOSE, msig_add_propose::<DB, B, V>)
+            .with_method(MSIG_SWAP_PROPOSE, msig_swap_propose::<DB, B, V>)
+            .with_method(MSIG_REMOVE_SIGNER, msig_remove_signer::<DB, B, V>)
+            .with_method(MSIG_GET_PENDING, msig_get_pending::<DB, B>)
+            .with_method(
+                MSIG_GET_AVAILABLE_BALANCE,
+                msig_get_available_balance::<DB, B>,
+            )
+            .with_method(MSIG_GET_VESTED, msig_get_vested::<DB, B>)
+            .with_method(
+                MSIG_GET_VESTING_SCHEDULE,
+                msig_get_vesting_schedule::<DB, B>,
+            )
+            // Paych API
+            .with_method(PAYCH_GET, paych_get::<DB, B, V>)
+            .with_method(PAYCH_GET_WAIT_READY, paych_get_wait_ready::<DB, B>)
+            .with_method(PAYCH_ALLOCATE_LANE, paych_allocate_lane::<DB, B>)
+            .with_method(PAYCH_VOUCHER_CREATE, paych_voucher_create::<DB, B, V>)
+            .with_method(
+                PAYCH_VOUCHER_CHECK_VALID,
+                paych_voucher_check_valid::<DB, B, V>,
+            )
+            .with_method(
+                PAYCH_VOUCHER_CHECK_SPENDABLE,
+                paych_voucher_check_spendable::<DB, B, V>,
+            )
+            .with_method(PAYCH_VOUCHER_ADD, paych_voucher_add::<DB, B, V>)
+            .with_method(PAYCH_VOUCHER_LIST, paych_voucher_list::<DB, B>)
+            .with_method(PAYCH_VOUCHER_SUBMIT, paych_voucher_submit::<DB, B, V>)
+            .with_method(PAYCH_SETTLE, paych_settle::<DB, B, V>)
+            .with_method(PAYCH_COLLECT, paych_collect::<DB, B, V>)
+            .with_method(PAYCH_STATUS, paych_status::<DB, B>)
+            .with_method(PAYCH_LIST, paych_list::<DB, B>)
             // Sync API
+            .with_method(SYNC_CHECKPOINT, sync_checkpoint::<DB, B, V>)
             .with_method(SYNC_CHECK_BAD, sync_check_bad::<DB, B>)
             .with_method(SYNC_MARK_BAD, sync_mark_bad::<DB, B>)
+            .with_method(SYNC_UNMARK_BAD, sync_unmark_bad::<DB, B>)
+            .with_method(SYNC_UNMARK_ALL_BAD, sync_unmark_all_bad::<DB, B>)
+            .with_method(SYNC_VALIDATE_TIPSET, sync_validate_tipset::<DB, B, V>)
             .with_method(SYNC_STATE, sync_state::<DB, B>)
-            .with_method(SYNC_SUBMIT_BLOCK, sync_submit_block::<DB, B>)
+            .with_method(SYNC_SUBMIT_BLOCK, sync_submit_block::<DB, B, V>)
             // Wallet API
             .with_method(WALLET_BALANCE, wallet_balance::<DB, B>)
             .with_method(WALLET_DEFAULT_ADDRESS, wallet_default_address::<DB, B>)
@@ -128,15 +201,17 @@ where
             .with_method(STATE_REPLAY, state_replay::<DB, B>)
             .with_method(STATE_NETWORK_NAME, state_network_name::<DB, B>)
             .with_method(STATE_NETWORK_VERSION, state_get_network_version::<DB, B>)
-            .with_method(STATE_REPLAY, state_replay::<DB, B>)
+            .with_method(STATE_COMPUTE, state_compute::<DB, B, V>)
             .with_method(STATE_GET_ACTOR, state_get_actor::<DB, B, V>)
             .with_method(STATE_LIST_ACTORS, state_list_actors::<DB, B, V>)
+            .with_method(STATE_CHANGED_ACTORS, state_changed_actors::<DB, B>)
             .with_method(STATE_ACCOUNT_KEY, state_account_key::<DB, B, V>)
             .with_method(STATE_LOOKUP_ID, state_lookup_id::<DB, B, V>)
             .with_method(STATE_MARKET_BALANCE, state_market_balance::<DB, B>)
             .with_method(STATE_MARKET_DEALS, state_market_deals::<DB, B>)
             .with_method(STATE_GET_RECEIPT, state_get_receipt::<DB, B>)
             .with_method(STATE_WAIT_MSG, state_wait_msg::<DB, B>)
+            .with_method(STATE_SEARCH_MSG, state_search_msg::<DB, B>)
             .with_method(MINER_CREATE_BLOCK, miner_create_block::<DB, B, V>)
             .with_method(
                 STATE_MINER_SECTOR_ALLOCATED,
@@ -167,6 +242,14 @@ where
             .with_method(NET_PEERS, net_api::net_peers::<DB, B>)
             .with_method(NET_CONNECT, net_api::net_connect::<DB, B>)
             .with_method(NET_DISCONNECT, net_api::net_disconnect::<DB, B>)
+            .with_method(NET_PEER_INFO, net_api::net_peer_info::<DB, B>)
+            .with_method(NET_BLOCK_ADD, net_api::net_block_add::<DB, B>)
+            .with_method(NET_BLOCK_REMOVE, net_api::net_block_remove::<DB, B>)
+            .with_method(NET_BLOCK_LIST, net_api::net_block_list::<DB, B>)
+            .with_method(NET_PROTECT_ADD, net_api::net_protect_add::<DB, B>)
+            .with_method(NET_PROTECT_REMOVE, net_api::net_protect_remove::<DB, B>)
+            .with_method(NET_PROTECT_LIST, net_api::net_protect_list::<DB, B>)
+            .with_method(NET_PUBSUB_SCORES, net_api::net_pubsub_scores::<DB, B>)
             .finish_unwrapped(),
     );
 
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::gas_api::estimate_message_gas;
-use address::{Address, Protocol};
+use address::{json::AddressJson, Address, Protocol};
 use beacon::Beacon;
 use blocks::TipsetKeys;
 use blockstore::BlockStore;
@@ -12,9 +12,11 @@ use fil_types::verifier::{FullVerifier, ProofVerifier};
 use message::Message;
 use message::{
     signed_message::json::SignedMessageJson, unsigned_message::json::UnsignedMessageJson,
-    SignedMessage,
+    SignedMessage, UnsignedMessage,
 };
-use rpc_api::data_types::RPCState;
+use message_pool::{MessageCheckStatus, MpoolConfig};
+use rpc_api::data_types::MessageCheckStatusJson;
+use rpc_api::data_types::{MessageSendSpec, RPCState};
 use rpc_api::mpool_api::*;
 
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
@@ -134,6 +136,62 @@ where
     Ok(CidJson(cid))
 }
 
+/// Add SignedMessage from an untrusted source to mpool, return msg CID
+pub(crate) async fn mpool_push_untrusted<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolPushUntrustedParams>,
+) -> Result<MpoolPushUntrustedResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (SignedMessageJson(smsg),) = params;
+
+    let cid = data.mpool.as_ref().push_untrusted(smsg).await?;
+
+    Ok(CidJson(cid))
+}
+
+/// Add a batch of SignedMessages to mpool, return their CIDs. Stops at the first message that
+/// fails to be added.
+pub(crate) async fn mpool_batch_push<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolBatchPushParams>,
+) -> Result<MpoolBatchPushResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (smsgs,) = params;
+
+    let mut cids = Vec::with_capacity(smsgs.len());
+    for SignedMessageJson(smsg) in smsgs {
+        cids.push(CidJson(data.mpool.as_ref().push(smsg).await?));
+    }
+
+    Ok(cids)
+}
+
+/// Add a batch of SignedMessages from an untrusted source to mpool, return their CIDs. Stops at
+/// the first message that fails to be added.
+pub(crate) async fn mpool_batch_push_untrusted<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolBatchPushUntrustedParams>,
+) -> Result<MpoolBatchPushUntrustedResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (smsgs,) = params;
+
+    let mut cids = Vec::with_capacity(smsgs.len());
+    for SignedMessageJson(smsg) in smsgs {
+        cids.push(CidJson(data.mpool.as_ref().push_untrusted(smsg).await?));
+    }
+
+    Ok(cids)
+}
+
 /// Sign given UnsignedMessage and add it to mpool, return SignedMessage
 pub(crate) async fn mpool_push_message<DB, B, V>(
     data: Data<RPCState<DB, B>>,
@@ -146,6 +204,23 @@ where
 {
     let (UnsignedMessageJson(umsg), spec) = params;
 
+    let smsg = sign_and_push::<DB, B, V>(&data, umsg, spec).await?;
+
+    Ok(SignedMessageJson(smsg))
+}
+
+/// Estimates gas for the message, assigns the next sequence of the sender, signs it with the
+/// sender's key from the keystore and adds it to the mpool.
+pub(crate) async fn sign_and_push<DB, B, V>(
+    data: &Data<RPCState<DB, B>>,
+    umsg: UnsignedMessage,
+    spec: Option<MessageSendSpec>,
+) -> Result<SignedMessage, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
     let from = *umsg.from();
 
     let mut keystore = data.keystore.as_ref().write().await;
@@ -165,7 +240,7 @@ where
             "Expected nonce for MpoolPushMessage is 0, and will be calculated for you.".into(),
         );
     }
-    let mut umsg = estimate_message_gas::<DB, B, V>(&data, umsg, spec, Default::default()).await?;
+    let mut umsg = estimate_message_gas::<DB, B, V>(data, umsg, spec, Default::default()).await?;
     if umsg.gas_premium() > umsg.gas_fee_cap() {
         return Err("After estimation, gas premium is greater than gas fee cap".into());
     }
@@ -186,7 +261,29 @@ where
 
     data.mpool.as_ref().push(smsg.clone()).await?;
 
-    Ok(SignedMessageJson(smsg))
+    Ok(smsg)
+}
+
+/// Sign a batch of UnsignedMessages and add them to mpool, return the SignedMessages. Stops at
+/// the first message that fails to be added.
+pub(crate) async fn mpool_batch_push_message<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolBatchPushMessageParams>,
+) -> Result<MpoolBatchPushMessageResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (umsgs, spec) = params;
+
+    let mut smsgs = Vec::with_capacity(umsgs.len());
+    for UnsignedMessageJson(umsg) in umsgs {
+        let smsg = sign_and_push::<DB, B, V>(&data, umsg, spec.clone()).await?;
+        smsgs.push(SignedMessageJson(smsg));
+    }
+
+    Ok(smsgs)
 }
 
 pub(crate) async fn mpool_select<DB, B>(
@@ -209,3 +306,97 @@ where
         .map(|e| e.into())
         .collect())
 }
+
+/// Return the current mpool config
+pub(crate) async fn mpool_get_config<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<MpoolGetConfigResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    Ok(data.mpool.get_config().await.into())
+}
+
+/// Validate, persist and apply a new mpool config
+pub(crate) async fn mpool_set_config<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolSetConfigParams>,
+) -> Result<MpoolSetConfigResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (cfg,) = params;
+    let cfg = MpoolConfig::try_from(cfg)?;
+    data.mpool
+        .set_config(data.state_manager.blockstore(), cfg)
+        .await?;
+    Ok(())
+}
+
+/// Remove the pending messages from mpool, including the local ones if `local` is set
+pub(crate) async fn mpool_clear<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolClearParams>,
+) -> Result<MpoolClearResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (local,) = params;
+    data.mpool.clear(local).await;
+    Ok(())
+}
+
+/// Check messages proposed to be pushed to mpool, return the result of each check per message
+pub(crate) async fn mpool_check_messages<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolCheckMessagesParams>,
+) -> Result<MpoolCheckMessagesResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (protos,) = params;
+    let protos = protos.into_iter().map(|proto| proto.into()).collect();
+    let result = data.mpool.check_messages(protos).await?;
+    Ok(checks_to_json(result))
+}
+
+/// Check the pending messages of an address, return the result of each check per message
+pub(crate) async fn mpool_check_pending_messages<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolCheckPendingMessagesParams>,
+) -> Result<MpoolCheckPendingMessagesResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(from),) = params;
+    let result = data.mpool.check_pending_messages(&from).await?;
+    Ok(checks_to_json(result))
+}
+
+/// Check the pending messages of the senders of the given messages, as if the given messages
+/// replaced the pending ones, return the result of each check per message
+pub(crate) async fn mpool_check_replace_messages<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MpoolCheckReplaceMessagesParams>,
+) -> Result<MpoolCheckReplaceMessagesResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (msgs,) = params;
+    let msgs = msgs.into_iter().map(|UnsignedMessageJson(m)| m).collect();
+    let result = data.mpool.check_replace_messages(msgs).await?;
+    Ok(checks_to_json(result))
+}
+
+fn checks_to_json(result: Vec<Vec<MessageCheckStatus>>) -> Vec<Vec<MessageCheckStatusJson>> {
+    result
+        .into_iter()
+        .map(|checks| checks.into_iter().map(|check| check.into()).collect())
+        .collect()
+}
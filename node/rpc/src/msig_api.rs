@@ -0,0 +1,537 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::mpool_api::sign_and_push;
+use actor::{
+    init,
+    multisig::{
+        self, AddSignerParams, ProposalHashData, ProposeParams, RemoveSignerParams,
+        SwapSignerParams, TxnID, TxnIDParams,
+    },
+    ActorVersion,
+};
+use address::{json::AddressJson, Address};
+use beacon::Beacon;
+use blocks::{tipset_keys_json::TipsetKeysJson, Tipset};
+use blockstore::BlockStore;
+use cid::{json::CidJson, Cid};
+use encoding::{blake2b_256, to_vec, Cbor};
+use fil_types::verifier::ProofVerifier;
+use message::UnsignedMessage;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use rpc_api::{
+    data_types::{MsigVesting, RPCState},
+    msig_api::*,
+};
+use state_tree::StateTree;
+use vm::{ActorState, MethodNum, Serialized, TokenAmount};
+
+use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Creates a multisig wallet, returns the cid of the message which creates it.
+pub(crate) async fn msig_create<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCreateParams>,
+) -> Result<MsigCreateResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (required, signers, unlock_duration, value, AddressJson(from), gas_premium) = params;
+    let signers: Vec<Address> = signers.into_iter().map(|a| a.0).collect();
+
+    if signers.is_empty() {
+        return Err("Must provide at least one signer".into());
+    }
+    if required == 0 || required as usize > signers.len() {
+        return Err(format!(
+            "Required approvals must be between 1 and the number of signers ({})",
+            signers.len()
+        )
+        .into());
+    }
+
+    let head = data
+        .state_manager
+        .chain_store()
+        .heaviest_tipset()
+        .await
+        .ok_or("Could not get heaviest tipset")?;
+    let version = ActorVersion::from(data.state_manager.get_network_version(head.epoch()));
+    let params = multisig::exec_params(
+        version,
+        signers,
+        required as usize,
+        unlock_duration,
+        head.epoch(),
+    )?;
+
+    let umsg = UnsignedMessage::builder()
+        .to(**init::ADDRESS)
+        .from(from)
+        .value(TokenAmount::from_str(&value)?)
+        .method_num(init::Method::Exec as MethodNum)
+        .params(params)
+        .gas_premium(TokenAmount::from_str(&gas_premium)?)
+        .build()?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Proposes a transaction to a multisig wallet.
+pub(crate) async fn msig_propose<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigProposeParams>,
+) -> Result<MsigProposeResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), AddressJson(to), value, AddressJson(from), method, params) = params;
+
+    let propose = ProposeParams {
+        to,
+        value: TokenAmount::from_str(&value)?,
+        method,
+        params: decode_params(&params)?,
+    };
+    let umsg = msig_message(
+        msig,
+        from,
+        multisig::Method::Propose as MethodNum,
+        Serialized::serialize(propose)?,
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Approves a pending multisig transaction by id.
+pub(crate) async fn msig_approve<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigApproveParams>,
+) -> Result<MsigApproveResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), txn_id, AddressJson(from)) = params;
+
+    let umsg = txn_message(
+        msig,
+        from,
+        multisig::Method::Approve as MethodNum,
+        txn_id,
+        Vec::new(),
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Approves a pending multisig transaction, only if it matches the given proposal.
+pub(crate) async fn msig_approve_txn_hash<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigApproveTxnHashParams>,
+) -> Result<MsigApproveTxnHashResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (
+        AddressJson(msig),
+        txn_id,
+        AddressJson(proposer),
+        AddressJson(to),
+        value,
+        AddressJson(from),
+        method,
+        params,
+    ) = params;
+
+    let head = data
+        .state_manager
+        .chain_store()
+        .heaviest_tipset()
+        .await
+        .ok_or("Could not get heaviest tipset")?;
+    let hash = proposal_hash(
+        data.state_manager.blockstore(),
+        head.parent_state(),
+        &proposer,
+        &to,
+        &TokenAmount::from_str(&value)?,
+        method,
+        &decode_params(&params)?,
+    )?;
+    let umsg = txn_message(
+        msig,
+        from,
+        multisig::Method::Approve as MethodNum,
+        txn_id,
+        hash,
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Cancels a pending multisig transaction, only if it matches the given proposal.
+pub(crate) async fn msig_cancel<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCancelParams>,
+) -> Result<MsigCancelResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), txn_id, AddressJson(to), value, AddressJson(from), method, params) =
+        params;
+
+    let head = data
+        .state_manager
+        .chain_store()
+        .heaviest_tipset()
+        .await
+        .ok_or("Could not get heaviest tipset")?;
+    // Only the proposer can cancel a transaction, so it is also the requester in the hash.
+    let hash = proposal_hash(
+        data.state_manager.blockstore(),
+        head.parent_state(),
+        &from,
+        &to,
+        &TokenAmount::from_str(&value)?,
+        method,
+        &decode_params(&params)?,
+    )?;
+    let umsg = txn_message(
+        msig,
+        from,
+        multisig::Method::Cancel as MethodNum,
+        txn_id,
+        hash,
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Proposes adding a signer to a multisig wallet.
+pub(crate) async fn msig_add_propose<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigAddProposeParams>,
+) -> Result<MsigAddProposeResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), AddressJson(from), AddressJson(signer), increase) = params;
+
+    let params = Serialized::serialize(AddSignerParams { signer, increase })?;
+    let umsg = self_proposal(msig, from, multisig::Method::AddSigner as MethodNum, params)?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Proposes swapping a signer of a multisig wallet for another address.
+pub(crate) async fn msig_swap_propose<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigSwapProposeParams>,
+) -> Result<MsigSwapProposeResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), AddressJson(from), AddressJson(old), AddressJson(new)) = params;
+
+    let params = Serialized::serialize(SwapSignerParams { from: old, to: new })?;
+    let umsg = self_proposal(
+        msig,
+        from,
+        multisig::Method::SwapSigner as MethodNum,
+        params,
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Proposes removing a signer from a multisig wallet.
+pub(crate) async fn msig_remove_signer<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigRemoveSignerParams>,
+) -> Result<MsigRemoveSignerResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(msig), AddressJson(from), AddressJson(signer), decrease) = params;
+
+    let params = Serialized::serialize(RemoveSignerParams { signer, decrease })?;
+    let umsg = self_proposal(
+        msig,
+        from,
+        multisig::Method::RemoveSigner as MethodNum,
+        params,
+    )?;
+
+    push_message::<DB, B, V>(&data, umsg).await
+}
+
+/// Returns the pending transactions of a multisig wallet.
+pub(crate) async fn msig_get_pending<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetPendingParams>,
+) -> Result<MsigGetPendingResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk).await?;
+    let (_, state) = load_msig(&data, &msig, &ts)?;
+
+    Ok(state
+        .pending_transactions(data.state_manager.blockstore())?
+        .into_iter()
+        .map(From::from)
+        .collect())
+}
+
+/// Returns the portion of a multisig wallet's balance which can be spent.
+pub(crate) async fn msig_get_available_balance<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetAvailableBalanceParams>,
+) -> Result<MsigGetAvailableBalanceResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk).await?;
+    let (actor, state) = load_msig(&data, &msig, &ts)?;
+
+    let locked = state.locked_balance(ts.epoch());
+    let available = if actor.balance > locked {
+        actor.balance - locked
+    } else {
+        BigInt::zero()
+    };
+    Ok(available.to_string())
+}
+
+/// Returns the amount vested by a multisig wallet between two tipsets.
+pub(crate) async fn msig_get_vested<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetVestedParams>,
+) -> Result<MsigGetVestedResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(msig), TipsetKeysJson(start), TipsetKeysJson(end)) = params;
+    let start = data.chain_store.tipset_from_keys(&start).await?;
+    let end = data.chain_store.tipset_from_keys(&end).await?;
+
+    if start.epoch() > end.epoch() {
+        return Err(format!(
+            "Start tipset {} is after end tipset {}",
+            start.epoch(),
+            end.epoch()
+        )
+        .into());
+    }
+    if start.epoch() == end.epoch() {
+        return Ok(BigInt::zero().to_string());
+    }
+
+    let (_, state) = load_msig(&data, &msig, &end)?;
+    let vested = state.locked_balance(start.epoch()) - state.locked_balance(end.epoch());
+    Ok(vested.to_string())
+}
+
+/// Returns the vesting schedule of a multisig wallet.
+pub(crate) async fn msig_get_vesting_schedule<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetVestingScheduleParams>,
+) -> Result<MsigGetVestingScheduleResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(msig), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk).await?;
+    let (_, state) = load_msig(&data, &msig, &ts)?;
+
+    Ok(MsigVesting {
+        initial_balance: state.initial_balance(),
+        start_epoch: state.start_epoch(),
+        unlock_duration: state.unlock_duration(),
+    })
+}
+
+/// Loads the actor and multisig state of `msig` at the given tipset.
+fn load_msig<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    msig: &Address,
+    ts: &Arc<Tipset>,
+) -> Result<(ActorState, multisig::State), JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let actor = data
+        .state_manager
+        .get_actor(msig, ts.parent_state())?
+        .ok_or_else(|| format!("Multisig actor {} not found", msig))?;
+    let state = multisig::State::load(data.state_manager.blockstore(), &actor)?;
+    Ok((actor, state))
+}
+
+/// Signs and pushes the message, returns the cid of the signed message.
+async fn push_message<DB, B, V>(
+    data: &Data<RPCState<DB, B>>,
+    umsg: UnsignedMessage,
+) -> Result<CidJson, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let smsg = sign_and_push::<DB, B, V>(data, umsg, None).await?;
+    Ok(CidJson(smsg.cid()?))
+}
+
+/// Builds a message calling `method` on the multisig actor.
+fn msig_message(
+    msig: Address,
+    from: Address,
+    method: MethodNum,
+    params: Serialized,
+) -> Result<UnsignedMessage, JsonRpcError> {
+    Ok(UnsignedMessage::builder()
+        .to(msig)
+        .from(from)
+        .method_num(method)
+        .params(params)
+        .build()?)
+}
+
+/// Builds a proposal of the multisig to call `method` on itself, which is how signers are
+/// managed.
+fn self_proposal(
+    msig: Address,
+    from: Address,
+    method: MethodNum,
+    params: Serialized,
+) -> Result<UnsignedMessage, JsonRpcError> {
+    let propose = ProposeParams {
+        to: msig,
+        value: TokenAmount::zero(),
+        method,
+        params,
+    };
+    msig_message(
+        msig,
+        from,
+        multisig::Method::Propose as MethodNum,
+        Serialized::serialize(propose)?,
+    )
+}
+
+/// Builds an approve or cancel message for a pending transaction.
+fn txn_message(
+    msig: Address,
+    from: Address,
+    method: MethodNum,
+    txn_id: i64,
+    proposal_hash: Vec<u8>,
+) -> Result<UnsignedMessage, JsonRpcError> {
+    let params = TxnIDParams {
+        id: TxnID(txn_id),
+        proposal_hash,
+    };
+    msig_message(msig, from, method, Serialized::serialize(params)?)
+}
+
+/// Computes the hash which references a pending proposal. The multisig actor hashes the ID
+/// address of the requester, so the requester is resolved in the state at `state_root` first.
+fn proposal_hash<BS: BlockStore>(
+    bs: &BS,
+    state_root: &Cid,
+    requester: &Address,
+    to: &Address,
+    value: &TokenAmount,
+    method: MethodNum,
+    params: &Serialized,
+) -> Result<Vec<u8>, JsonRpcError> {
+    let requester = StateTree::new_from_root(bs, state_root)?
+        .lookup_id(requester)?
+        .ok_or_else(|| format!("Requester {} not found in state", requester))?;
+    let data = ProposalHashData {
+        requester: Some(&requester),
+        to,
+        value,
+        method: &method,
+        params,
+    };
+    Ok(blake2b_256(&to_vec(&data)?).to_vec())
+}
+
+/// Decodes base64 encoded method parameters.
+fn decode_params(params: &str) -> Result<Serialized, JsonRpcError> {
+    Ok(Serialized::new(base64::decode(params)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actor::actorv5;
+    use cid::Code::Blake2b256;
+    use db::MemoryDB;
+    use fil_types::StateTreeVersion;
+
+    #[test]
+    fn proposal_hash_resolves_requester_id() {
+        let db = MemoryDB::default();
+        let key = Address::new_secp256k1(&[4; 65]).unwrap();
+        let mut init_st = actorv5::init::State::new(&db, "test".to_owned()).unwrap();
+        let id = init_st.map_address_to_new_id(&db, &key).unwrap();
+        let init_head = db.put(&init_st, Blake2b256).unwrap();
+        let mut tree = StateTree::new(&db, StateTreeVersion::V3).unwrap();
+        tree.set_actor(
+            actor::init::ADDRESS,
+            ActorState::new(*actorv5::INIT_ACTOR_CODE_ID, init_head, 0.into(), 0),
+        )
+        .unwrap();
+        let root = tree.flush().unwrap();
+
+        let to = Address::new_id(1000);
+        let value = TokenAmount::from(10);
+        let params = Serialized::default();
+        let by_key = proposal_hash(&db, &root, &key, &to, &value, 0, &params).unwrap();
+
+        // The actor computes the hash over the ID address of the requester
+        let expected = ProposalHashData {
+            requester: Some(&id),
+            to: &to,
+            value: &value,
+            method: &0,
+            params: &params,
+        };
+        assert_eq!(by_key, blake2b_256(&to_vec(&expected).unwrap()).to_vec());
+        assert_eq!(
+            by_key,
+            proposal_hash(&db, &root, &id, &to, &value, 0, &params).unwrap()
+        );
+
+        let unknown = Address::new_secp256k1(&[5; 65]).unwrap();
+        assert!(proposal_hash(&db, &root, &unknown, &to, &value, 0, &params).is_err());
+    }
+}
@@ -9,7 +9,7 @@ use beacon::Beacon;
 use blockstore::BlockStore;
 use forest_libp2p::{NetRPCMethods, NetworkMessage, PeerId};
 use rpc_api::{
-    data_types::{AddrInfo, RPCState},
+    data_types::{AddrInfo, ExtendedPeerInfo, NetBlockList, PubsubScore, RPCState},
     net_api::*,
 };
 
@@ -93,7 +93,7 @@ pub(crate) async fn net_disconnect<
     Params(params): Params<NetDisconnectParams>,
 ) -> Result<NetDisconnectResult, JsonRpcError> {
     let (id,) = params;
-    let peer_id = PeerId::from_bytes(id.as_bytes())?;
+    let peer_id: PeerId = id.parse()?;
 
     let (tx, rx) = oneshot::channel();
     let req = NetworkMessage::JSONRPCRequest {
@@ -105,3 +105,196 @@ pub(crate) async fn net_disconnect<
 
     Ok(())
 }
+
+pub(crate) async fn net_peer_info<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetPeerInfoParams>,
+) -> Result<NetPeerInfoResult, JsonRpcError> {
+    let (id,) = params;
+    let peer_id: PeerId = id.parse()?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetPeerInfo(tx, peer_id),
+    };
+
+    data.network_send.send(req).await?;
+    let record = rx.await??;
+    let stats = record.stats.unwrap_or_default();
+
+    Ok(ExtendedPeerInfo {
+        id: peer_id.to_string(),
+        addrs: record
+            .addresses
+            .as_ref()
+            .map(|a| a.addrs.clone())
+            .unwrap_or_default(),
+        connected: record.connected,
+        last_seen: record.addresses.map(|a| a.last_seen),
+        successes: stats.successes,
+        failures: stats.failures,
+        average_latency: stats.average_time.as_millis() as u64,
+        bad: stats.is_bad(),
+        blocked: record.blocked,
+    })
+}
+
+pub(crate) async fn net_block_add<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetBlockAddParams>,
+) -> Result<NetBlockAddResult, JsonRpcError> {
+    let (NetBlockList { peers },) = params;
+    let peers = peers
+        .iter()
+        .map(|id| id.parse())
+        .collect::<Result<Vec<PeerId>, _>>()?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBlockAdd(tx, peers),
+    };
+
+    data.network_send.send(req).await?;
+    rx.await??;
+
+    Ok(())
+}
+
+pub(crate) async fn net_block_remove<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetBlockRemoveParams>,
+) -> Result<NetBlockRemoveResult, JsonRpcError> {
+    let (NetBlockList { peers },) = params;
+    let peers = peers
+        .iter()
+        .map(|id| id.parse())
+        .collect::<Result<Vec<PeerId>, _>>()?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBlockRemove(tx, peers),
+    };
+
+    data.network_send.send(req).await?;
+    rx.await??;
+
+    Ok(())
+}
+
+pub(crate) async fn net_block_list<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetBlockListResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBlockList(tx),
+    };
+
+    data.network_send.send(req).await?;
+    let mut peers: Vec<String> = rx.await??.iter().map(PeerId::to_string).collect();
+    peers.sort();
+
+    Ok(NetBlockList { peers })
+}
+
+pub(crate) async fn net_protect_add<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetProtectAddParams>,
+) -> Result<NetProtectAddResult, JsonRpcError> {
+    let (ids,) = params;
+    let peers = ids
+        .iter()
+        .map(|id| id.parse())
+        .collect::<Result<Vec<PeerId>, _>>()?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetProtectAdd(tx, peers),
+    };
+
+    data.network_send.send(req).await?;
+    rx.await?;
+
+    Ok(())
+}
+
+pub(crate) async fn net_protect_remove<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<NetProtectRemoveParams>,
+) -> Result<NetProtectRemoveResult, JsonRpcError> {
+    let (ids,) = params;
+    let peers = ids
+        .iter()
+        .map(|id| id.parse())
+        .collect::<Result<Vec<PeerId>, _>>()?;
+
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetProtectRemove(tx, peers),
+    };
+
+    data.network_send.send(req).await?;
+    rx.await?;
+
+    Ok(())
+}
+
+pub(crate) async fn net_protect_list<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetProtectListResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetProtectList(tx),
+    };
+
+    data.network_send.send(req).await?;
+    let mut peers: Vec<String> = rx.await?.iter().map(PeerId::to_string).collect();
+    peers.sort();
+
+    Ok(peers)
+}
+
+pub(crate) async fn net_pubsub_scores<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetPubsubScoresResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetPubsubScores(tx),
+    };
+
+    data.network_send.send(req).await?;
+    let mut scores: Vec<PubsubScore> = rx
+        .await?
+        .into_iter()
+        .map(|(peer, score)| PubsubScore {
+            id: peer.to_string(),
+            score,
+        })
+        .collect();
+    scores.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(scores)
+}
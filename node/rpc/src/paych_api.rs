@@ -0,0 +1,317 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::mpool_api::sign_and_push;
+use address::json::AddressJson;
+use async_std::task;
+use beacon::Beacon;
+use blockstore::BlockStore;
+use cid::json::CidJson;
+use encoding::Cbor;
+use fil_types::verifier::ProofVerifier;
+use log::warn;
+use message::UnsignedMessage;
+use num_traits::Zero;
+use paychmgr::{json::SignedVoucherJson, Error as PaychError};
+use rpc_api::{
+    data_types::{PaychChannelInfo, PaychStatus, RPCState},
+    paych_api::*,
+};
+use std::convert::TryFrom;
+use vm::TokenAmount;
+
+use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use std::str::FromStr;
+
+/// Returns an outbound channel from `from` to `to` with at least `amount` added to it,
+/// creating the channel if there is none.
+pub(crate) async fn paych_get<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychGetParams>,
+) -> Result<PaychGetResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(from), AddressJson(to), amount) = params;
+    let amount = TokenAmount::from_str(&amount)?;
+    let mgr = data.paych_manager.clone();
+
+    // Held until the create or add funds message is tracked, so that concurrent requests
+    // for the same parties fund a single channel
+    let accessor = mgr.accessor(&from, &to).await;
+    let _guard = accessor.lock().await;
+    match mgr.outbound_channel(&from, &to).await? {
+        Some(ci) => {
+            let ch = ci.channel.ok_or_else(|| {
+                format!(
+                    "Channel from {} to {} is still being created, wait for message {}",
+                    from,
+                    to,
+                    ci.create_msg.map(|c| c.to_string()).unwrap_or_default()
+                )
+            })?;
+            if amount.is_zero() {
+                return Ok(PaychChannelInfo {
+                    channel: Some(AddressJson(ch)),
+                    wait_sentinel: None,
+                });
+            }
+            let umsg = mgr.add_funds_message(&ci, amount.clone())?;
+            let cid = push_message::<DB, B, V>(&data, umsg).await?;
+            mgr.track_add_funds(&ci.id, amount, cid).await?;
+            task::spawn(async move {
+                if let Err(e) = mgr.wait_for_add_funds(&ci.id).await {
+                    warn!("Failed to add funds to payment channel {}: {}", ch, e);
+                }
+            });
+            Ok(PaychChannelInfo {
+                channel: Some(AddressJson(ch)),
+                wait_sentinel: Some(CidJson(cid)),
+            })
+        }
+        None => {
+            let umsg = mgr.create_channel_message(from, to, amount.clone()).await?;
+            let cid = push_message::<DB, B, V>(&data, umsg).await?;
+            let ci = mgr.track_create(from, to, amount, cid).await?;
+            task::spawn(async move {
+                if let Err(e) = mgr.wait_for_create(&ci.id).await {
+                    warn!("Failed to create payment channel {}: {}", ci.id, e);
+                }
+            });
+            Ok(PaychChannelInfo {
+                channel: None,
+                wait_sentinel: Some(CidJson(cid)),
+            })
+        }
+    }
+}
+
+/// Waits for the message returned by `PaychGet` and returns the address of the channel.
+pub(crate) async fn paych_get_wait_ready<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychGetWaitReadyParams>,
+) -> Result<PaychGetWaitReadyResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (CidJson(sentinel),) = params;
+    Ok(AddressJson(data.paych_manager.wait_ready(sentinel).await?))
+}
+
+pub(crate) async fn paych_allocate_lane<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychAllocateLaneParams>,
+) -> Result<PaychAllocateLaneResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(ch),) = params;
+    Ok(data.paych_manager.allocate_lane(&ch).await?)
+}
+
+/// Creates a voucher on an outbound channel, signed with the key of the channel's payer.
+pub(crate) async fn paych_voucher_create<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherCreateParams>,
+) -> Result<PaychVoucherCreateResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch), amount, lane) = params;
+    let amount = TokenAmount::from_str(&amount)?;
+    let mgr = &data.paych_manager;
+
+    let control = mgr.channel_info(&ch)?.control;
+    let heaviest_tipset = data
+        .state_manager
+        .chain_store()
+        .heaviest_tipset()
+        .await
+        .ok_or("Could not get heaviest tipset")?;
+    let key_addr = data
+        .state_manager
+        .resolve_to_key_addr::<V>(&control, &heaviest_tipset)
+        .await?;
+    let key = {
+        let mut keystore = data.keystore.write().await;
+        wallet::Key::try_from(wallet::try_find(&key_addr, &mut *keystore)?)?
+    };
+
+    let res = mgr
+        .create_voucher::<V, _>(&ch, amount, lane, |bz| {
+            wallet::sign(*key.key_info.key_type(), key.key_info.private_key(), bz)
+                .map_err(|e| PaychError::Other(e.to_string()))
+        })
+        .await?;
+    Ok(res.into())
+}
+
+pub(crate) async fn paych_voucher_check_valid<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherCheckValidParams>,
+) -> Result<PaychVoucherCheckValidResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch), SignedVoucherJson(sv)) = params;
+    Ok(data
+        .paych_manager
+        .check_voucher_valid::<V>(&ch, &sv)
+        .await?)
+}
+
+/// Checks whether the voucher can be redeemed by the recipient of the channel. The proof is
+/// ignored, as it is not used by actors v2 and later.
+pub(crate) async fn paych_voucher_check_spendable<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherCheckSpendableParams>,
+) -> Result<PaychVoucherCheckSpendableResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch), SignedVoucherJson(sv), secret, _proof) = params;
+    Ok(data
+        .paych_manager
+        .check_voucher_spendable::<V>(&ch, &sv, base64::decode(secret)?)
+        .await?)
+}
+
+/// Adds a received voucher, returns the amount it adds to its lane.
+pub(crate) async fn paych_voucher_add<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherAddParams>,
+) -> Result<PaychVoucherAddResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch), SignedVoucherJson(sv), proof, min_delta) = params;
+    let delta = data
+        .paych_manager
+        .add_voucher::<V>(
+            &ch,
+            sv,
+            base64::decode(proof)?,
+            TokenAmount::from_str(&min_delta)?,
+        )
+        .await?;
+    Ok(delta.to_string())
+}
+
+pub(crate) async fn paych_voucher_list<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherListParams>,
+) -> Result<PaychVoucherListResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(ch),) = params;
+    Ok(data
+        .paych_manager
+        .list_vouchers(&ch)?
+        .into_iter()
+        .map(|vi| SignedVoucherJson(vi.voucher))
+        .collect())
+}
+
+/// Submits a voucher of an inbound channel to the chain, returns the cid of the message.
+pub(crate) async fn paych_voucher_submit<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychVoucherSubmitParams>,
+) -> Result<PaychVoucherSubmitResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch), SignedVoucherJson(sv), secret, _proof) = params;
+    let mgr = &data.paych_manager;
+    let umsg = mgr.submit_voucher_message(&ch, &sv, base64::decode(secret)?)?;
+    let cid = push_message::<DB, B, V>(&data, umsg).await?;
+    mgr.voucher_submitted(&ch, sv).await?;
+    Ok(CidJson(cid))
+}
+
+pub(crate) async fn paych_settle<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychSettleParams>,
+) -> Result<PaychSettleResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch),) = params;
+    let mgr = &data.paych_manager;
+    let umsg = mgr.settle_message(&ch)?;
+    let cid = push_message::<DB, B, V>(&data, umsg).await?;
+    mgr.mark_settling(&ch).await?;
+    Ok(CidJson(cid))
+}
+
+pub(crate) async fn paych_collect<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychCollectParams>,
+) -> Result<PaychCollectResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (AddressJson(ch),) = params;
+    let umsg = data.paych_manager.collect_message(&ch)?;
+    Ok(CidJson(push_message::<DB, B, V>(&data, umsg).await?))
+}
+
+pub(crate) async fn paych_status<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<PaychStatusParams>,
+) -> Result<PaychStatusResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (AddressJson(ch),) = params;
+    Ok(PaychStatus::from(&data.paych_manager.channel_info(&ch)?))
+}
+
+pub(crate) async fn paych_list<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<PaychListResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    Ok(data
+        .paych_manager
+        .list_channels()?
+        .into_iter()
+        .map(AddressJson)
+        .collect())
+}
+
+/// Signs and pushes the message, returns the cid of the signed message.
+async fn push_message<DB, B, V>(
+    data: &Data<RPCState<DB, B>>,
+    umsg: UnsignedMessage,
+) -> Result<cid::Cid, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let smsg = sign_and_push::<DB, B, V>(data, umsg, None).await?;
+    Ok(smsg.cid()?)
+}
@@ -1,6 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use async_std::task;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -17,26 +18,21 @@ use blocks::{
     election_proof::json::ElectionProofJson, ticket::json::TicketJson,
     tipset_keys_json::TipsetKeysJson,
 };
-use blocks::{
-    gossip_block::json::GossipBlockJson as BlockMsgJson, BlockHeader, GossipBlock as BlockMsg,
-    Tipset, TxMeta,
-};
+use blocks::{gossip_block::json::GossipBlockJson as BlockMsgJson, Tipset};
 use blockstore::BlockStore;
-use bls_signatures::Serialize as SerializeBls;
-use cid::{json::CidJson, Cid, Code::Blake2b256};
-use crypto::SignatureType;
+use cid::{json::CidJson, Cid};
 use fil_types::{
     verifier::{FullVerifier, ProofVerifier},
     PoStProof,
 };
 use ipld::{json::IpldJson, Ipld};
-use ipld_amt::Amt;
-use message::{signed_message::SignedMessage, unsigned_message::UnsignedMessage};
+use message::{signed_message::SignedMessage, unsigned_message::UnsignedMessage, MessageReceipt};
+use mining::BlockTemplate;
 use num_bigint::BigInt;
 use rpc_api::{
     data_types::{
-        ActorStateJson, Deadline, MarketDeal, MessageLookup, MiningBaseInfoJson, Partition,
-        RPCState,
+        ActorStateJson, ComputeStateOutput, Deadline, MarketDeal, MessageLookup,
+        MiningBaseInfoJson, Partition, RPCState,
     },
     state_api::*,
 };
@@ -347,10 +343,33 @@ pub(crate) async fn state_replay<
         .await?;
     let (msg, ret) = state_manager.replay::<FullVerifier>(&tipset, cid).await?;
 
-    Ok(InvocResult {
-        msg,
-        msg_rct: Some(ret.msg_receipt),
-        error: ret.act_error.map(|e| e.to_string()),
+    Ok(InvocResult::new(msg, ret))
+}
+
+/// Applies the given messages on top of the state computed for the tipset, returns the
+/// resulting state root and the execution traces of all the applied messages.
+pub(crate) async fn state_compute<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateComputeParams>,
+) -> Result<StateComputeResult, JsonRpcError> {
+    let (height, msgs, key) = params;
+    let msgs: Vec<UnsignedMessage> = msgs.into_iter().map(|m| m.into()).collect();
+    let tipset = data
+        .state_manager
+        .chain_store()
+        .tipset_from_keys(&key.into())
+        .await?;
+    let (root, trace) = data
+        .state_manager
+        .compute_state::<V>(height, msgs, &tipset)
+        .await?;
+    Ok(ComputeStateOutput {
+        root: CidJson(root),
+        trace,
     })
 }
 
@@ -433,6 +452,24 @@ pub(crate) async fn state_list_actors<
     Ok(addresses_json)
 }
 
+/// returns the actors added, removed and modified between two state roots
+pub(crate) async fn state_changed_actors<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateChangedActorsParams>,
+) -> Result<StateChangedActorsResult, JsonRpcError> {
+    let (CidJson(old_root), CidJson(new_root)) = params;
+    let store = data.state_manager.blockstore_cloned();
+    // Diffing large states walks many nodes, which would hold up the async executor
+    let diff = task::spawn_blocking(move || {
+        statediff::diff_state_roots(store.as_ref(), &old_root, &new_root).map_err(|e| e.to_string())
+    })
+    .await?;
+    Ok(diff)
+}
+
 /// returns the public key address of the given ID address
 pub(crate) async fn state_account_key<
     DB: BlockStore + Send + Sync + 'static,
@@ -574,6 +611,29 @@ pub(crate) async fn state_wait_msg<
     let (tipset, receipt) = state_manager.wait_for_message(cid, confidence).await?;
     let tipset = tipset.ok_or("wait for msg returned empty tuple")?;
     let receipt = receipt.ok_or("wait for msg returned empty receipt")?;
+    message_lookup(cid, &tipset, receipt)
+}
+
+/// Looks up the tipset in which a message was executed, without waiting for it.
+pub(crate) async fn state_search_msg<
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateSearchMsgParams>,
+) -> Result<StateSearchMsgResult, JsonRpcError> {
+    let (CidJson(cid),) = params;
+    match data.state_manager.search_for_message(&cid).await? {
+        Some((tipset, receipt)) => Ok(Some(message_lookup(cid, &tipset, receipt)?)),
+        None => Ok(None),
+    }
+}
+
+fn message_lookup(
+    cid: Cid,
+    tipset: &Tipset,
+    receipt: MessageReceipt,
+) -> Result<MessageLookup, JsonRpcError> {
     let ipld: Ipld = if receipt.return_data.bytes().is_empty() {
         Ipld::Null
     } else {
@@ -611,97 +671,23 @@ pub(crate) async fn miner_create_block<
         .map(|wpp| wpp.0)
         .collect();
 
-    let pts = data.chain_store.tipset_from_keys(&parents).await?;
-    let (st, recpts) = data.state_manager.tipset_state::<V>(&pts).await?;
-    let (_, lbst) = data
-        .state_manager
-        .get_lookback_tipset_for_round::<V>(pts.clone(), epoch)
-        .await?;
-    let worker = data.state_manager.get_miner_worker_raw(&lbst, &miner)?;
-
-    let mut bls_msgs = Vec::new();
-    let mut secp_msgs = Vec::new();
-    let mut bls_cids = Vec::new();
-    let mut secp_cids = Vec::new();
-
-    let mut bls_sigs = Vec::new();
-    for msg in messages {
-        if msg.signature().signature_type() == SignatureType::BLS {
-            let c = data
-                .chain_store
-                .blockstore()
-                .put(&msg.message, Blake2b256)?;
-            bls_sigs.push(msg.signature);
-            bls_msgs.push(msg.message);
-            bls_cids.push(c);
-        } else {
-            let c = data.chain_store.blockstore().put(&msg, Blake2b256)?;
-            secp_cids.push(c);
-            secp_msgs.push(msg);
-        }
-    }
-
-    let bls_msg_root = Amt::new_from_iter(data.chain_store.blockstore(), bls_cids.iter().copied())?;
-    let secp_msg_root =
-        Amt::new_from_iter(data.chain_store.blockstore(), secp_cids.iter().copied())?;
-
-    let mmcid = data.chain_store.blockstore().put(
-        &TxMeta {
-            bls_message_root: bls_msg_root,
-            secp_message_root: secp_msg_root,
+    let block = mining::create_block::<_, V>(
+        &data.state_manager,
+        &data.keystore,
+        BlockTemplate {
+            miner,
+            parents,
+            ticket,
+            eproof,
+            beacon_values,
+            messages,
+            epoch,
+            timestamp,
+            winning_post_proof,
         },
-        Blake2b256,
-    )?;
-
-    let calculated_bls_agg = if bls_sigs.is_empty() {
-        Some(crypto::Signature::new_bls(vec![]))
-    } else {
-        Some(crypto::Signature::new_bls(
-            bls_signatures::aggregate(
-                &bls_sigs
-                    .iter()
-                    .map(|s| s.bytes())
-                    .map(bls_signatures::Signature::from_bytes)
-                    .collect::<Result<Vec<_>, _>>()?,
-            )
-            .unwrap()
-            .as_bytes(),
-        ))
-    };
-    let pweight = chain::weight(data.chain_store.blockstore(), &pts.as_ref())?;
-    let base_fee = chain::compute_base_fee(data.chain_store.blockstore(), &pts.as_ref())?;
-
-    let mut next = BlockHeader::builder()
-        .messages(mmcid)
-        .bls_aggregate(calculated_bls_agg)
-        .miner_address(miner)
-        .weight(pweight)
-        .parent_base_fee(base_fee)
-        .parents(parents)
-        .ticket(Some(ticket))
-        .election_proof(Some(eproof))
-        .beacon_entries(beacon_values)
-        .epoch(epoch)
-        .timestamp(timestamp)
-        .winning_post_proof(winning_post_proof)
-        .state_root(st)
-        .message_receipts(recpts)
-        .signature(None)
-        .build()?;
-
-    let key = wallet::find_key(&worker, &*data.keystore.as_ref().write().await)?;
-    let sig = wallet::sign(
-        *key.key_info.key_type(),
-        key.key_info.private_key(),
-        &next.to_signing_bytes(),
-    )?;
-    next.signature = Some(sig);
-
-    Ok(BlockMsgJson(BlockMsg {
-        header: next,
-        bls_messages: bls_cids,
-        secpk_messages: secp_cids,
-    }))
+    )
+    .await?;
+    Ok(BlockMsgJson(block))
 }
 
 pub(crate) async fn state_miner_sector_allocated<
@@ -3,11 +3,13 @@
 
 use beacon::Beacon;
 use blocks::gossip_block::json::GossipBlockJson;
-use blocks::Tipset;
+use blocks::tipset_keys_json::TipsetKeysJson;
+use blocks::{Block, Tipset};
 use blockstore::BlockStore;
 use chain_sync::SyncState;
 use cid::json::CidJson;
 use encoding::Cbor;
+use fil_types::verifier::ProofVerifier;
 use forest_libp2p::{NetworkMessage, Topic, PUBSUB_BLOCK_STR};
 use message::{SignedMessage, UnsignedMessage};
 use rpc_api::data_types::{RPCState, RPCSyncState};
@@ -17,6 +19,31 @@ use async_std::sync::RwLock;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use std::sync::Arc;
 
+/// Sets a tipset as checkpoint, refusing forks which do not include it from then on. If the
+/// tipset is not in the current chain, its state is computed and it becomes the head.
+pub(crate) async fn sync_checkpoint<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncCheckpointParams>,
+) -> Result<SyncCheckpointResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (TipsetKeysJson(tsk),) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk).await?;
+    let head = data
+        .chain_store
+        .heaviest_tipset()
+        .await
+        .ok_or("can't find heaviest tipset")?;
+    if !data.chain_store.is_ancestor_of(&ts, head).await? {
+        data.state_manager.tipset_state::<V>(&ts).await?;
+    }
+    data.chain_store.set_checkpoint(ts).await?;
+    Ok(())
+}
+
 /// Checks if a given block is marked as bad.
 pub(crate) async fn sync_check_bad<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -42,10 +69,65 @@ where
     let (CidJson(cid),) = params;
     data.bad_blocks
         .put(cid, "Marked bad manually through RPC API".to_string())
-        .await;
+        .await?;
     Ok(())
 }
 
+/// Unmarks a block as bad, allowing it to be synced again.
+pub(crate) async fn sync_unmark_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncUnmarkBadParams>,
+) -> Result<SyncUnmarkBadResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    let (CidJson(cid),) = params;
+    data.bad_blocks.remove(&cid).await?;
+    Ok(())
+}
+
+/// Unmarks all the blocks marked as bad.
+pub(crate) async fn sync_unmark_all_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+{
+    data.bad_blocks.clear().await?;
+    Ok(())
+}
+
+/// Validates a tipset from the store, returning an error with the reason if it is invalid.
+/// Blocks failing validation are not marked as bad.
+pub(crate) async fn sync_validate_tipset<DB, B, V>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncValidateTipsetParams>,
+) -> Result<SyncValidateTipsetResult, JsonRpcError>
+where
+    DB: BlockStore + Send + Sync + 'static,
+    B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
+{
+    let (TipsetKeysJson(tsk),) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk).await?;
+    let genesis = data
+        .chain_store
+        .genesis()?
+        .ok_or("Genesis block not found in the store")?;
+    let genesis = Arc::new(Tipset::new(vec![genesis])?);
+    chain_sync::validate_stored_tipset::<_, _, V>(
+        data.state_manager.clone(),
+        data.beacon.clone(),
+        data.bad_blocks.clone(),
+        &ts,
+        genesis,
+    )
+    .await?;
+    Ok(true)
+}
+
 // TODO SyncIncomingBlocks (requires websockets)
 
 async fn clone_state(state: &RwLock<SyncState>) -> SyncState {
@@ -64,39 +146,33 @@ where
     Ok(RPCSyncState { active_syncs })
 }
 
-/// Submits block to be sent through gossipsub.
-pub(crate) async fn sync_submit_block<DB, B>(
+/// Submits block to be sent through gossipsub, once it is fully validated.
+pub(crate) async fn sync_submit_block<DB, B, V>(
     data: Data<RPCState<DB, B>>,
     Params((GossipBlockJson(blk),)): Params<SyncSubmitBlockParams>,
 ) -> Result<SyncSubmitBlockResult, JsonRpcError>
 where
     DB: BlockStore + Send + Sync + 'static,
     B: Beacon + Send + Sync + 'static,
+    V: ProofVerifier + Send + Sync + 'static,
 {
     let bls_msgs: Vec<UnsignedMessage> =
         chain::messages_from_cids(data.state_manager.blockstore(), &blk.bls_messages)?;
     let secp_msgs: Vec<SignedMessage> =
         chain::messages_from_cids(data.state_manager.blockstore(), &blk.secpk_messages)?;
-    let sm_root = chain_sync::TipsetValidator::compute_msg_root(
-        data.state_manager.blockstore(),
-        &bls_msgs,
-        &secp_msgs,
-    )?;
-    if blk.header.messages() != &sm_root {
-        return Err(format!(
-            "Block message root does not match the computed: Actual: {}, Computed: {}",
-            blk.header.messages(),
-            sm_root,
-        )
-        .into());
-    }
-
-    chain::persist_objects(data.state_manager.blockstore(), &bls_msgs)?;
-    chain::persist_objects(data.state_manager.blockstore(), &secp_msgs)?;
+    chain_sync::validate_mined_block::<_, _, V>(
+        data.state_manager.clone(),
+        data.beacon.clone(),
+        Block {
+            header: blk.header.clone(),
+            bls_messages: bls_msgs,
+            secp_messages: secp_msgs,
+        },
+    )
+    .await?;
 
     let ts = Arc::new(Tipset::new(vec![blk.header.clone()])?);
     data.new_mined_block_tx.send(ts).await?;
-    // TODO validate by constructing full block and validate (cids of messages could be invalid)
     data.network_send
         .send(NetworkMessage::PubsubMessage {
             topic: Topic::new(format!("{}/{}", PUBSUB_BLOCK_STR, data.network_name)),
@@ -115,10 +191,11 @@ mod tests {
     use beacon::{BeaconPoint, BeaconSchedule, MockBeacon};
     use blocks::{BlockHeader, Tipset};
     use chain::ChainStore;
-    use chain_sync::SyncStage;
+    use chain_sync::{BadBlockCache, SyncStage};
     use db::{MemoryDB, Store};
     use forest_libp2p::NetworkMessage;
     use message_pool::{MessagePool, MpoolRpcProvider};
+    use paychmgr::Manager as PaychManager;
     use serde_json::from_str;
     use state_manager::StateManager;
     use std::{sync::Arc, time::Duration};
@@ -168,17 +245,19 @@ mod tests {
             .unwrap()
         });
         let (new_mined_block_tx, _) = bounded(5);
+        let paych_manager = Arc::new(PaychManager::new(state_manager.clone()));
         let state = Arc::new(RPCState {
             state_manager,
             keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory).unwrap())),
             mpool: Arc::new(pool),
-            bad_blocks: Default::default(),
+            bad_blocks: Arc::new(BadBlockCache::new(db)),
             sync_state: Arc::new(RwLock::new(Default::default())),
             network_send,
             network_name: TEST_NET_NAME.to_owned(),
             chain_store: cs_for_chain,
             beacon,
             new_mined_block_tx,
+            paych_manager,
         });
         (state, network_rx)
     }
@@ -199,10 +278,19 @@ mod tests {
         assert!(sync_mark_bad(Data(state.clone()), Params((cid.clone(),)))
             .await
             .is_ok());
-        match sync_check_bad(Data(state), Params((cid,))).await {
+        match sync_check_bad(Data(state.clone()), Params((cid.clone(),))).await {
             Ok(reason) => assert_eq!(reason, "Marked bad manually through RPC API"),
             Err(e) => std::panic::panic_any(e),
         }
+
+        // Unmark the block and check that it is no longer bad
+        assert!(sync_unmark_bad(Data(state.clone()), Params((cid.clone(),)))
+            .await
+            .is_ok());
+        match sync_check_bad(Data(state), Params((cid,))).await {
+            Ok(reason) => assert_eq!(reason, ""),
+            Err(e) => std::panic::panic_any(e),
+        }
     }
 
     #[async_std::test]
@@ -4,7 +4,7 @@
 use super::*;
 use db::MemoryDB;
 use interpreter::{CircSupplyCalc, LookbackStateGetter};
-use networks::get_network_version_default;
+use networks::{get_network_version_default, NetworkConfig};
 use state_tree::StateTree;
 use vm::TokenAmount;
 
@@ -50,6 +50,7 @@ pub fn execute_message(
 ) -> Result<(ApplyRet, Cid), Box<dyn StdError>> {
     let circ_supply = MockCircSupply(params.circ_supply);
     let lb = MockStateLB(bs);
+    let upgrades = NetworkConfig::mainnet().upgrades;
     let mut vm = VM::<_, _, _, _, _>::new(
         params.pre_root,
         bs,
@@ -57,6 +58,7 @@ pub fn execute_message(
         &params.randomness,
         params.basefee,
         get_network_version_default,
+        &upgrades,
         &circ_supply,
         &lb,
     )?;
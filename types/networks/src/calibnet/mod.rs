@@ -0,0 +1,46 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{drand::DRAND_MAINNET, DrandPoint, NetworkConfig, NetworkPolicy, UpgradeSchedule};
+
+const UPGRADES: UpgradeSchedule = UpgradeSchedule {
+    breeze: -1,
+    smoke: -2,
+    ignition: -3,
+    actors_v2: 30,
+    tape: 60,
+    liftoff: -5,
+    kumquat: 90,
+    calico: 120,
+    persian: 240,
+    orange: 300,
+    claus: 270,
+    actors_v3: 600,
+    norwegian: 114000,
+    actors_v4: 193789,
+    hyperdrive: 321519,
+};
+
+/// Bootstrap peer ids
+const BOOTSTRAP_PEERS: &[&str] = &[
+    "/dns4/bootstrap-0.calibration.fildev.network/tcp/1347/p2p/12D3KooWJkikQQkxS58spo76BYzFt4fotaT5NpV2zngvrqm4u5ow",
+    "/dns4/bootstrap-1.calibration.fildev.network/tcp/1347/p2p/12D3KooWLce5FDHR4EX4CrYavphA5xS3uDsX6aoowXh5tzDUxJav",
+    "/dns4/bootstrap-2.calibration.fildev.network/tcp/1347/p2p/12D3KooWA9hFfQG9GjP6bHeuQQbMD3FDtZLdW1NayxKXUT26PQZu",
+    "/dns4/bootstrap-3.calibration.fildev.network/tcp/1347/p2p/12D3KooWMHDtrfKyfWD9pvwWxD5ye8MBdBbu3EiJChABsPQbMNwr",
+];
+
+pub(super) fn config() -> NetworkConfig {
+    NetworkConfig {
+        name: "calibnet".to_owned(),
+        testnet: true,
+        bootstrap_peers: BOOTSTRAP_PEERS.iter().map(|p| p.to_string()).collect(),
+        upgrades: UPGRADES,
+        drand_schedule: vec![DrandPoint {
+            height: 0,
+            config: DRAND_MAINNET.clone(),
+        }],
+        policy: NetworkPolicy::CALIBNET,
+        genesis_file: None,
+        genesis_car: None,
+    }
+}
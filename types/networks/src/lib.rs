@@ -4,131 +4,374 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod calibnet;
+mod devnet;
+mod drand;
+mod interopnet;
+mod mainnet;
+
+pub use actor::NetworkPolicy;
+use actor::NETWORK_POLICY;
+use address::{Network, NETWORK_DEFAULT};
 use beacon::{BeaconPoint, BeaconSchedule, DrandBeacon, DrandConfig};
-use clock::ChainEpoch;
+use clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
+use db::Store;
 use fil_types::NetworkVersion;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
 use std::{error::Error, sync::Arc};
-mod drand;
 
-#[cfg(feature = "mainnet")]
-mod mainnet;
-#[cfg(feature = "mainnet")]
-pub use self::mainnet::*;
-
-#[cfg(all(
-    feature = "interopnet",
-    not(feature = "devnet"),
-    not(feature = "mainnet")
-))]
-mod interopnet;
-#[cfg(all(
-    feature = "interopnet",
-    not(feature = "devnet"),
-    not(feature = "mainnet")
-))]
-pub use self::interopnet::*;
-
-#[cfg(all(
-    feature = "devnet",
-    not(feature = "interopnet"),
-    not(feature = "mainnet")
-))]
-mod devnet;
-#[cfg(all(
-    feature = "devnet",
-    not(feature = "interopnet"),
-    not(feature = "mainnet")
-))]
-pub use self::devnet::*;
-
-/// Defines the different hard fork parameters.
-struct Upgrade {
-    /// When the hard fork will happen
-    height: ChainEpoch,
-    /// The version of the fork
-    network: NetworkVersion,
+/// Time, in seconds, between each block.
+pub const BLOCK_DELAY_SECS: u64 = EPOCH_DURATION_SECONDS as u64;
+
+/// Newest network version supported by this build.
+pub const NEWEST_NETWORK_VERSION: NetworkVersion = NetworkVersion::V12;
+
+/// Name of the network used when none is selected.
+pub const DEFAULT_NETWORK: &str = "mainnet";
+
+/// Names of the networks built into the binary.
+pub const BUILTIN_NETWORKS: &[&str] = &["mainnet", "calibnet", "interopnet", "devnet"];
+
+/// Heights of the hard forks of a network. A fork applies to the epochs strictly after its
+/// height, and a negative height marks a fork which is already active at genesis.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct UpgradeSchedule {
+    /// V1 network upgrade
+    pub breeze: ChainEpoch,
+    /// V2 network upgrade
+    pub smoke: ChainEpoch,
+    /// V3 network upgrade
+    pub ignition: ChainEpoch,
+    /// V4 network upgrade
+    pub actors_v2: ChainEpoch,
+    /// V5 network upgrade
+    pub tape: ChainEpoch,
+    /// Switching to mainnet network name
+    pub liftoff: ChainEpoch,
+    /// V6 network upgrade
+    pub kumquat: ChainEpoch,
+    /// V7 network upgrade
+    pub calico: ChainEpoch,
+    /// V8 network upgrade
+    pub persian: ChainEpoch,
+    /// V9 network upgrade
+    pub orange: ChainEpoch,
+    /// Remove burn on window PoSt fork
+    pub claus: ChainEpoch,
+    /// V10 network upgrade
+    pub actors_v3: ChainEpoch,
+    /// V11 network upgrade
+    pub norwegian: ChainEpoch,
+    /// V12 network upgrade
+    pub actors_v4: ChainEpoch,
+    /// V13 network upgrade
+    pub hyperdrive: ChainEpoch,
 }
 
-struct DrandPoint<'a> {
+impl UpgradeSchedule {
+    /// Schedule of a network which has gone through all upgrades at genesis, like local test
+    /// networks running the newest actors.
+    pub const ALL_AT_GENESIS: UpgradeSchedule = UpgradeSchedule {
+        breeze: -1,
+        smoke: -2,
+        ignition: -3,
+        actors_v2: -4,
+        tape: -5,
+        liftoff: -6,
+        kumquat: -7,
+        calico: -8,
+        persian: -9,
+        orange: -10,
+        claus: -11,
+        actors_v3: -12,
+        norwegian: -13,
+        actors_v4: -14,
+        hyperdrive: -15,
+    };
+
+    /// Returns the network version active at the given epoch.
+    pub fn network_version(&self, epoch: ChainEpoch) -> NetworkVersion {
+        let schedule = [
+            (self.breeze, NetworkVersion::V1),
+            (self.smoke, NetworkVersion::V2),
+            (self.ignition, NetworkVersion::V3),
+            (self.actors_v2, NetworkVersion::V4),
+            (self.tape, NetworkVersion::V5),
+            (self.kumquat, NetworkVersion::V6),
+            (self.calico, NetworkVersion::V7),
+            (self.persian, NetworkVersion::V8),
+            (self.orange, NetworkVersion::V9),
+            (self.actors_v3, NetworkVersion::V10),
+            (self.norwegian, NetworkVersion::V11),
+            (self.actors_v4, NetworkVersion::V12),
+            (self.hyperdrive, NetworkVersion::V13),
+        ];
+        schedule
+            .iter()
+            .rev()
+            .find(|(height, _)| epoch > *height)
+            .map(|(_, version)| *version)
+            .unwrap_or(NetworkVersion::V0)
+    }
+}
+
+/// Drand beacon used by the network from the given height.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DrandPoint {
     pub height: ChainEpoch,
-    pub config: &'a DrandConfig<'a>,
+    pub config: DrandConfig<'static>,
 }
 
-const VERSION_SCHEDULE: [Upgrade; 13] = [
-    Upgrade {
-        height: UPGRADE_BREEZE_HEIGHT,
-        network: NetworkVersion::V1,
-    },
-    Upgrade {
-        height: UPGRADE_SMOKE_HEIGHT,
-        network: NetworkVersion::V2,
-    },
-    Upgrade {
-        height: UPGRADE_IGNITION_HEIGHT,
-        network: NetworkVersion::V3,
-    },
-    Upgrade {
-        height: UPGRADE_ACTORS_V2_HEIGHT,
-        network: NetworkVersion::V4,
-    },
-    Upgrade {
-        height: UPGRADE_TAPE_HEIGHT,
-        network: NetworkVersion::V5,
-    },
-    Upgrade {
-        height: UPGRADE_KUMQUAT_HEIGHT,
-        network: NetworkVersion::V6,
-    },
-    Upgrade {
-        height: UPGRADE_CALICO_HEIGHT,
-        network: NetworkVersion::V7,
-    },
-    Upgrade {
-        height: UPGRADE_PERSIAN_HEIGHT,
-        network: NetworkVersion::V8,
-    },
-    Upgrade {
-        height: UPGRADE_ORANGE_HEIGHT,
-        network: NetworkVersion::V9,
-    },
-    Upgrade {
-        height: UPGRADE_ACTORS_V3_HEIGHT,
-        network: NetworkVersion::V10,
-    },
-    Upgrade {
-        height: UPGRADE_NORWEGIAN_HEIGHT,
-        network: NetworkVersion::V11,
-    },
-    Upgrade {
-        height: UPGRADE_ACTORS_V4_HEIGHT,
-        network: NetworkVersion::V12,
-    },
-    Upgrade {
-        height: UPGRADE_HYPERDRIVE_HEIGHT,
-        network: NetworkVersion::V13,
-    },
-];
+/// Parameters of a Filecoin network, which the node is started with. The built-in networks are
+/// returned by [NetworkConfig::from_name], other networks can be loaded from a toml file with
+/// [NetworkConfig::from_file].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NetworkConfig {
+    /// Name of the network, only used to select and display it.
+    pub name: String,
+    /// Whether addresses of the network are displayed with the testnet prefix.
+    #[serde(default)]
+    pub testnet: bool,
+    /// Multiaddresses of the peers dialed at startup.
+    #[serde(default)]
+    pub bootstrap_peers: Vec<String>,
+    pub upgrades: UpgradeSchedule,
+    pub drand_schedule: Vec<DrandPoint>,
+    /// Actor policy of the network, mainnet's if not given.
+    #[serde(default)]
+    pub policy: NetworkPolicy,
+    /// Path to the genesis car file of the network.
+    #[serde(default)]
+    pub genesis_file: Option<String>,
+    /// Genesis car file embedded in the binary, for built-in networks.
+    #[serde(skip)]
+    pub genesis_car: Option<&'static [u8]>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl NetworkConfig {
+    /// Filecoin mainnet.
+    pub fn mainnet() -> Self {
+        mainnet::config()
+    }
+
+    /// Calibration network, a testnet with the mainnet policy for smaller miners. Its genesis
+    /// isn't built in, so it has to be given on startup.
+    pub fn calibnet() -> Self {
+        calibnet::config()
+    }
+
+    /// Network used for interoperability testing between implementations.
+    pub fn interopnet() -> Self {
+        interopnet::config()
+    }
+
+    /// Local development network. Devnet genesis files are generated for each devnet, and given
+    /// on startup.
+    pub fn devnet() -> Self {
+        devnet::config()
+    }
+
+    /// Returns the built-in network with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mainnet" => Some(Self::mainnet()),
+            "calibnet" => Some(Self::calibnet()),
+            "interopnet" => Some(Self::interopnet()),
+            "devnet" => Some(Self::devnet()),
+            _ => None,
+        }
+    }
+
+    /// Loads a network from a toml file. A relative genesis file path is resolved from the
+    /// directory of the network file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut config: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        if let (Some(genesis), Some(dir)) = (&config.genesis_file, path.parent()) {
+            config.genesis_file = Some(dir.join(genesis).to_string_lossy().into_owned());
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Resolves the network selected with `--chain`, which is either the name of a built-in
+    /// network or the path to a network toml file.
+    pub fn from_chain(chain: &str) -> Result<Self, Box<dyn Error>> {
+        match Self::from_name(chain) {
+            Some(config) => Ok(config),
+            None if Path::new(chain).is_file() => Self::from_file(chain),
+            None => Err(format!(
+                "unknown chain {}, expected one of {} or a network toml file",
+                chain,
+                BUILTIN_NETWORKS.join(", ")
+            )
+            .into()),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self.drand_schedule.first() {
+            Some(point) if point.height == 0 => (),
+            _ => return Err("the drand schedule must start at height 0".to_owned()),
+        }
+        if self
+            .drand_schedule
+            .windows(2)
+            .any(|w| w[0].height >= w[1].height)
+        {
+            return Err("the drand schedule must be sorted by height".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Returns the network version active at the given epoch.
+    pub fn network_version(&self, epoch: ChainEpoch) -> NetworkVersion {
+        self.upgrades.network_version(epoch)
+    }
+
+    /// Sets the address prefix and the actor policy of the network for the process. They can
+    /// only be set once, so this fails if they were already set to other values.
+    pub fn set_process_defaults(&self) -> Result<(), String> {
+        let address_network = if self.testnet {
+            Network::Testnet
+        } else {
+            Network::Mainnet
+        };
+        if *NETWORK_DEFAULT.get_or_init(|| address_network) != address_network {
+            return Err("the address network was already set to another network".to_owned());
+        }
+        if *NETWORK_POLICY.get_or_init(|| self.policy.clone()) != self.policy {
+            return Err("the actor policy was already set to another network's".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Constructs the drand beacon schedule of the network, whose verified entries are persisted
+    /// in the given database.
+    pub async fn beacon_schedule<DB>(
+        &self,
+        genesis_ts: u64,
+        db: Arc<DB>,
+    ) -> Result<BeaconSchedule<DrandBeacon>, Box<dyn Error>>
+    where
+        DB: Store + Send + Sync + 'static,
+    {
+        let mut points = BeaconSchedule(Vec::with_capacity(self.drand_schedule.len()));
+        for dc in self.drand_schedule.iter() {
+            let beacon = DrandBeacon::new(genesis_ts, BLOCK_DELAY_SECS, &dc.config)
+                .await?
+                .with_db(db.clone());
+            points.0.push(BeaconPoint {
+                height: dc.height,
+                beacon: Arc::new(beacon),
+            });
+        }
+        Ok(points)
+    }
+}
 
 /// Gets network version from epoch using default Mainnet schedule.
 pub fn get_network_version_default(epoch: ChainEpoch) -> NetworkVersion {
-    VERSION_SCHEDULE
-        .iter()
-        .rev()
-        .find(|upgrade| epoch > upgrade.height)
-        .map(|upgrade| upgrade.network)
-        .unwrap_or(NetworkVersion::V0)
+    mainnet::UPGRADES.network_version(epoch)
 }
 
-/// Constructs a drand beacon schedule based on the build config.
-pub async fn beacon_schedule_default(
-    genesis_ts: u64,
-) -> Result<BeaconSchedule<DrandBeacon>, Box<dyn Error>> {
-    let mut points = BeaconSchedule(Vec::with_capacity(DRAND_SCHEDULE.len()));
-    for dc in DRAND_SCHEDULE.iter() {
-        points.0.push(BeaconPoint {
-            height: dc.height,
-            beacon: Arc::new(DrandBeacon::new(genesis_ts, BLOCK_DELAY_SECS, dc.config).await?),
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_versions() {
+        let mainnet = NetworkConfig::mainnet();
+        assert_eq!(mainnet.network_version(0), NetworkVersion::V0);
+        assert_eq!(
+            mainnet.network_version(mainnet.upgrades.breeze),
+            NetworkVersion::V0
+        );
+        assert_eq!(
+            mainnet.network_version(mainnet.upgrades.breeze + 1),
+            NetworkVersion::V1
+        );
+        assert_eq!(
+            mainnet.network_version(mainnet.upgrades.hyperdrive + 1),
+            NetworkVersion::V13
+        );
+
+        // Upgrades with negative heights are active at genesis
+        let interopnet = NetworkConfig::interopnet();
+        assert_eq!(interopnet.network_version(0), NetworkVersion::V3);
+
+        let calibnet = NetworkConfig::from_name("calibnet").unwrap();
+        assert_eq!(calibnet.network_version(0), NetworkVersion::V3);
+        assert_eq!(
+            calibnet.network_version(calibnet.upgrades.hyperdrive + 1),
+            NetworkVersion::V13
+        );
+    }
+
+    #[test]
+    fn custom_network_from_toml() {
+        let config: NetworkConfig = toml::from_str(
+            r#"
+            name = "localnet"
+            testnet = true
+            bootstrap_peers = ["/ip4/127.0.0.1/tcp/1347"]
+            genesis_file = "genesis.car"
+
+            [upgrades]
+            breeze = -1
+            smoke = -2
+            ignition = -3
+            actors_v2 = -4
+            tape = -5
+            liftoff = -6
+            kumquat = -7
+            calico = -8
+            persian = -9
+            orange = -10
+            claus = -11
+            actors_v3 = -12
+            norwegian = -13
+            actors_v4 = 100
+            hyperdrive = 200
+
+            [policy]
+            consensus_miner_min_power = 2048
+            minimum_verified_deal_size = 256
+            pre_commit_challenge_delay = 10
+            allow_2kib_sectors = true
+
+            [[drand_schedule]]
+            height = 0
+
+            [drand_schedule.config]
+            servers = ["https://api.drand.sh", "https://api2.drand.sh", "https://api3.drand.sh", "https://drand.cloudflare.com"]
+
+            [drand_schedule.config.chain_info]
+            public_key = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31"
+            period = 30
+            genesis_time = 1595431050
+            hash = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce"
+            groupHash = "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert!(config.testnet);
+        assert_eq!(config.genesis_car, None);
+        assert_eq!(config.policy, NetworkPolicy::DEVNET);
+        assert_eq!(config.network_version(0), NetworkVersion::V11);
+        assert_eq!(config.network_version(101), NetworkVersion::V12);
+        assert_eq!(
+            config.drand_schedule[0].config,
+            drand::DRAND_MAINNET.clone()
+        );
     }
-    Ok(points)
 }
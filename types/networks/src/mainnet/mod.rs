@@ -3,52 +3,33 @@
 
 use super::{
     drand::{DRAND_INCENTINET, DRAND_MAINNET},
-    DrandPoint,
+    DrandPoint, NetworkConfig, UpgradeSchedule,
 };
-use clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
-use fil_types::NetworkVersion;
 
-/// Default genesis car file bytes.
-pub const DEFAULT_GENESIS: &[u8] = include_bytes!("genesis.car");
+/// Genesis car file bytes.
+const GENESIS: &[u8] = include_bytes!("genesis.car");
 
-/// V1 network upgrade
-pub const UPGRADE_BREEZE_HEIGHT: ChainEpoch = 41280;
-/// V2 network upgrade
-pub const UPGRADE_SMOKE_HEIGHT: ChainEpoch = 51000;
-/// V3 network upgrade
-pub const UPGRADE_IGNITION_HEIGHT: ChainEpoch = 94000;
-/// V4 network upgrade
-pub const UPGRADE_ACTORS_V2_HEIGHT: ChainEpoch = 138720;
-/// V5 network upgrade
-pub const UPGRADE_TAPE_HEIGHT: ChainEpoch = 140760;
-/// Switching to mainnet network name
-pub const UPGRADE_LIFTOFF_HEIGHT: i64 = 148888;
-/// V6 network upgrade
-pub const UPGRADE_KUMQUAT_HEIGHT: ChainEpoch = 170000;
-/// V7 network upgrade
-pub const UPGRADE_CALICO_HEIGHT: ChainEpoch = 265200;
-/// V8 network upgrade
-pub const UPGRADE_PERSIAN_HEIGHT: ChainEpoch = 272400;
-/// V9 network upgrade
-pub const UPGRADE_ORANGE_HEIGHT: ChainEpoch = 336458;
-/// Remove burn on window PoSt fork
-pub const UPGRADE_CLAUS_HEIGHT: ChainEpoch = 343200;
-/// V10 network upgrade
-pub const UPGRADE_ACTORS_V3_HEIGHT: ChainEpoch = 550321;
-/// V11 network upgrade
-pub const UPGRADE_NORWEGIAN_HEIGHT: ChainEpoch = 665280;
-/// V12 network upgrade
-pub const UPGRADE_ACTORS_V4_HEIGHT: ChainEpoch = 712320;
-/// V13 network upgrade
-pub const UPGRADE_HYPERDRIVE_HEIGHT: ChainEpoch = 892800;
-
-pub const UPGRADE_PLACEHOLDER_HEIGHT: ChainEpoch = 9999999;
-
-/// Current network version for the network
-pub const NEWEST_NETWORK_VERSION: NetworkVersion = NetworkVersion::V11;
+/// Mainnet hard fork heights.
+pub(super) const UPGRADES: UpgradeSchedule = UpgradeSchedule {
+    breeze: 41280,
+    smoke: 51000,
+    ignition: 94000,
+    actors_v2: 138720,
+    tape: 140760,
+    liftoff: 148888,
+    kumquat: 170000,
+    calico: 265200,
+    persian: 272400,
+    orange: 336458,
+    claus: 343200,
+    actors_v3: 550321,
+    norwegian: 665280,
+    actors_v4: 712320,
+    hyperdrive: 892800,
+};
 
 /// Bootstrap peer ids
-pub const DEFAULT_BOOTSTRAP: &[&str] = &[
+const BOOTSTRAP_PEERS: &[&str] = &[
     "/dns4/bootstrap-0.mainnet.filops.net/tcp/1347/p2p/12D3KooWCVe8MmsEMes2FzgTpt9fXtmCY7wrq91GRiaC8PHSCCBj",
     "/dns4/bootstrap-1.mainnet.filops.net/tcp/1347/p2p/12D3KooWCwevHg1yLCvktf2nvLu7L9894mcrJR4MsBCcm4syShVc",
     "/dns4/bootstrap-2.mainnet.filops.net/tcp/1347/p2p/12D3KooWEWVwHGn2yR36gKLozmb4YjDJGerotAPGxmdWZx2nxMC4",
@@ -66,18 +47,24 @@ pub const DEFAULT_BOOTSTRAP: &[&str] = &[
     "/dns4/bootstrap-1.ipfsmain.cn/tcp/34723/p2p/12D3KooWMKxMkD5DMpSWsW7dBddKxKT7L2GgbNuckz9otxvkvByP",
 ];
 
-lazy_static! {
-    pub(super) static ref DRAND_SCHEDULE: [DrandPoint<'static>; 2] = [
-        DrandPoint {
-            height: 0,
-            config: &*DRAND_INCENTINET,
-        },
-        DrandPoint {
-            height: UPGRADE_SMOKE_HEIGHT,
-            config: &*DRAND_MAINNET,
-        },
-    ];
+pub(super) fn config() -> NetworkConfig {
+    NetworkConfig {
+        name: "mainnet".to_owned(),
+        testnet: false,
+        bootstrap_peers: BOOTSTRAP_PEERS.iter().map(|p| p.to_string()).collect(),
+        upgrades: UPGRADES,
+        drand_schedule: vec![
+            DrandPoint {
+                height: 0,
+                config: DRAND_INCENTINET.clone(),
+            },
+            DrandPoint {
+                height: UPGRADES.smoke,
+                config: DRAND_MAINNET.clone(),
+            },
+        ],
+        policy: NetworkPolicy::MAINNET,
+        genesis_file: None,
+        genesis_car: Some(GENESIS),
+    }
 }
-
-/// Time, in seconds, between each block.
-pub const BLOCK_DELAY_SECS: u64 = EPOCH_DURATION_SECONDS as u64;
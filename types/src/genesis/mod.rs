@@ -1,9 +1,11 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::SectorSize;
+use super::{PaddedPieceSize, RegisteredSealProof, SectorNumber, SectorSize};
 use address::{json as addr_json, Address};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use cid::{json as cid_json, Cid};
+use clock::ChainEpoch;
 use num_bigint::bigint_ser::json as bigint_json;
 use serde::{Deserialize, Serialize};
 use vm::TokenAmount;
@@ -45,6 +47,45 @@ pub struct Miner {
     #[serde(with = "bigint_json")]
     pub power_balance: TokenAmount,
     pub sector_size: SectorSize,
+    #[serde(default)]
+    pub sectors: Vec<PreSeal>,
+}
+
+/// A sector sealed before genesis, committed to the miner's power in the genesis state.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PreSeal {
+    #[serde(with = "cid_json")]
+    pub comm_r: Cid,
+    #[serde(with = "cid_json")]
+    pub comm_d: Cid,
+    #[serde(rename = "SectorID")]
+    pub sector_id: SectorNumber,
+    pub deal: PreSealDeal,
+    pub proof_type: RegisteredSealProof,
+}
+
+/// The storage deal of the data stored in a preseal sector.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PreSealDeal {
+    #[serde(with = "cid_json", rename = "PieceCID")]
+    pub piece_cid: Cid,
+    pub piece_size: PaddedPieceSize,
+    pub verified_deal: bool,
+    #[serde(with = "addr_json")]
+    pub client: Address,
+    #[serde(with = "addr_json")]
+    pub provider: Address,
+    pub label: String,
+    pub start_epoch: ChainEpoch,
+    pub end_epoch: ChainEpoch,
+    #[serde(with = "bigint_json")]
+    pub storage_price_per_epoch: TokenAmount,
+    #[serde(with = "bigint_json")]
+    pub provider_collateral: TokenAmount,
+    #[serde(with = "bigint_json")]
+    pub client_collateral: TokenAmount,
 }
 
 /// Format of genesis file.
@@ -55,6 +96,9 @@ pub struct Template {
     pub miners: Vec<Miner>,
     pub network_name: String,
     timestamp: String,
+    /// Root key of the verified registry. Without it, no verifiers can be added.
+    #[serde(default)]
+    pub verifreg_root_key: Option<Actor>,
 }
 
 impl Template {
@@ -64,6 +108,14 @@ impl Template {
             miners: Vec::new(),
             network_name,
             timestamp: Utc::now().to_rfc3339(),
+            verifreg_root_key: None,
         }
     }
+
+    /// Returns the genesis timestamp, in seconds since the Unix epoch.
+    pub fn timestamp(&self) -> Result<u64, String> {
+        let time = DateTime::parse_from_rfc3339(&self.timestamp)
+            .map_err(|e| format!("invalid genesis timestamp {}: {}", self.timestamp, e))?;
+        Ok(time.timestamp() as u64)
+    }
 }
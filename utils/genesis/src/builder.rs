@@ -0,0 +1,975 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use actor::actorv5::{
+    account, cron, init, make_empty_map, make_map_with_root, market, miner, multisig, power,
+    reward, system, verifreg, DealID, Set, SetMultimap, ACCOUNT_ACTOR_CODE_ID,
+    BURNT_FUNDS_ACTOR_ADDR, CRON_ACTOR_ADDR, CRON_ACTOR_CODE_ID, INIT_ACTOR_ADDR,
+    INIT_ACTOR_CODE_ID, MARKET_ACTOR_CODE_ID, MULTISIG_ACTOR_CODE_ID, POWER_ACTOR_CODE_ID,
+    RESERVE_ADDRESS, REWARD_ACTOR_ADDR, REWARD_ACTOR_CODE_ID, STORAGE_MARKET_ACTOR_ADDR,
+    STORAGE_POWER_ACTOR_ADDR, SYSTEM_ACTOR_ADDR, SYSTEM_ACTOR_CODE_ID,
+    VERIFIED_REGISTRY_ACTOR_ADDR, VERIFREG_ACTOR_CODE_ID,
+};
+use actor::ActorVersion;
+use address::Address;
+use beacon::BeaconEntry;
+use bitfield::BitField;
+use blocks::{BlockHeader, Ticket, Tipset, TxMeta};
+use chain::{persist_objects, ChainStore, INITIAL_BASE_FEE};
+use cid::{Cid, Code::Blake2b256};
+use clock::ChainEpoch;
+use crypto::{DomainSeparationTag, VRFProof};
+use db::MemoryDB;
+use encoding::Cbor;
+use fil_types::genesis::{Actor, ActorType, PreSealDeal, Template};
+use fil_types::verifier::ProofVerifier;
+use fil_types::{
+    RegisteredSealProof, StateTreeVersion, StoragePower, BLOCK_GAS_LIMIT, FILECOIN_PRECISION,
+    HAMT_BIT_WIDTH, TOTAL_FILECOIN,
+};
+use futures::AsyncWrite;
+use interpreter::{CircSupplyCalc, LookbackStateGetter, Rand, VM};
+use ipld_amt::Amt;
+use ipld_blockstore::BlockStore;
+use libp2p::PeerId;
+use message::{Message, UnsignedMessage};
+use networks::NetworkConfig;
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use serde::{de::DeserializeOwned, Serialize};
+use state_tree::StateTree;
+use std::error::Error as StdError;
+use std::str::FromStr;
+use std::sync::Arc;
+use vm::{ActorState, MethodNum, Serialized, TokenAmount, METHOD_SEND};
+
+/// Balance of the reward actor at genesis, in FIL.
+const INITIAL_REWARD_BALANCE: i64 = 1_100_000_000;
+/// ID of the first preseal miner. The IDs below are left to the accounts of the template.
+pub const MINER_START: u64 = 1000;
+/// ID of the root key of the verified registry, which adds verifiers.
+const VERIFREG_ROOT_ID: u64 = 80;
+
+/// Builds the genesis block of a network from a template, and stores the genesis state in the
+/// given store. The genesis state holds the v5 actors, so the network has to run them from
+/// genesis.
+pub fn make_genesis_block<DB, V>(
+    store: &DB,
+    template: &Template,
+    network: &NetworkConfig,
+) -> Result<BlockHeader, Box<dyn StdError>>
+where
+    DB: BlockStore,
+    V: ProofVerifier,
+{
+    if !matches!(
+        ActorVersion::from(network.network_version(0)),
+        ActorVersion::V5
+    ) {
+        return Err(format!(
+            "the {} network must run the v5 actors from genesis, set the heights of its upgrades up to hyperdrive below 0",
+            network.name
+        )
+        .into());
+    }
+
+    let root = make_initial_state(store, template)?;
+    let root = setup_miners::<_, V>(store, root, template, network)?;
+
+    // Like the messages and receipts of any block, those of the genesis are v0 AMTs
+    let empty_amt = Amt::new_from_iter(store, Vec::<Cid>::new())?;
+    let messages = store.put(
+        &TxMeta {
+            bls_message_root: empty_amt,
+            secp_message_root: empty_amt,
+        },
+        Blake2b256,
+    )?;
+    let header = BlockHeader::builder()
+        .miner_address(*SYSTEM_ACTOR_ADDR)
+        .ticket(Some(Ticket::new(VRFProof::new(
+            rand::random::<[u8; 32]>().to_vec(),
+        ))))
+        .beacon_entries(vec![BeaconEntry::new(0, vec![0; 32])])
+        .messages(messages)
+        .message_receipts(empty_amt)
+        .state_root(root)
+        .timestamp(template.timestamp()?)
+        .parent_base_fee(TokenAmount::from(INITIAL_BASE_FEE))
+        .build()?;
+    persist_objects(store, std::slice::from_ref(&header))?;
+    Ok(header)
+}
+
+/// Builds the genesis of a network from a template and writes it to a CAR file, which can be
+/// loaded with [initialize_genesis](crate::initialize_genesis).
+pub async fn write_genesis_car<V, W>(
+    template: &Template,
+    network: &NetworkConfig,
+    writer: W,
+) -> Result<BlockHeader, Box<dyn StdError>>
+where
+    V: ProofVerifier,
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let db = Arc::new(MemoryDB::default());
+    let header = make_genesis_block::<_, V>(db.as_ref(), template, network)?;
+    let tipset = Tipset::new(vec![header.clone()])?;
+    ChainStore::new(db)
+        .export(&tipset, 0, false, writer)
+        .await?;
+    Ok(header)
+}
+
+/// Creates the singleton actors and the accounts of the template, and returns the state root.
+fn make_initial_state<DB: BlockStore>(
+    store: &DB,
+    template: &Template,
+) -> Result<Cid, Box<dyn StdError>> {
+    let mut tree = StateTree::new(store, StateTreeVersion::V4)?;
+    let zero = TokenAmount::zero();
+
+    set_actor(
+        &mut tree,
+        &SYSTEM_ACTOR_ADDR,
+        &SYSTEM_ACTOR_CODE_ID,
+        &system::State::default(),
+        zero.clone(),
+    )?;
+    set_actor(
+        &mut tree,
+        &INIT_ACTOR_ADDR,
+        &INIT_ACTOR_CODE_ID,
+        &init::State::new(store, template.network_name.clone())?,
+        zero.clone(),
+    )?;
+    let reward_balance = BigInt::from(INITIAL_REWARD_BALANCE) * FILECOIN_PRECISION;
+    set_actor(
+        &mut tree,
+        &REWARD_ACTOR_ADDR,
+        &REWARD_ACTOR_CODE_ID,
+        &reward::State::new(StoragePower::zero()),
+        reward_balance.clone(),
+    )?;
+    let cron_state = cron::State {
+        entries: vec![
+            cron::Entry {
+                receiver: *STORAGE_POWER_ACTOR_ADDR,
+                method_num: power::Method::OnEpochTickEnd as MethodNum,
+            },
+            cron::Entry {
+                receiver: *STORAGE_MARKET_ACTOR_ADDR,
+                method_num: market::Method::CronTick as MethodNum,
+            },
+        ],
+    };
+    set_actor(
+        &mut tree,
+        &CRON_ACTOR_ADDR,
+        &CRON_ACTOR_CODE_ID,
+        &cron_state,
+        zero.clone(),
+    )?;
+    set_actor(
+        &mut tree,
+        &STORAGE_POWER_ACTOR_ADDR,
+        &POWER_ACTOR_CODE_ID,
+        &power::State::new(store)?,
+        zero.clone(),
+    )?;
+    set_actor(
+        &mut tree,
+        &STORAGE_MARKET_ACTOR_ADDR,
+        &MARKET_ACTOR_CODE_ID,
+        &market::State::new(store)?,
+        zero.clone(),
+    )?;
+    let verifreg_root = Address::new_id(VERIFREG_ROOT_ID);
+    set_actor(
+        &mut tree,
+        &VERIFIED_REGISTRY_ACTOR_ADDR,
+        &VERIFREG_ACTOR_CODE_ID,
+        &verifreg::State::new(store, verifreg_root)?,
+        zero.clone(),
+    )?;
+    set_actor(
+        &mut tree,
+        &BURNT_FUNDS_ACTOR_ADDR,
+        &ACCOUNT_ACTOR_CODE_ID,
+        &account::State {
+            address: *BURNT_FUNDS_ACTOR_ADDR,
+        },
+        zero,
+    )?;
+
+    let mut allocated = reward_balance;
+    for actor in &template.accounts {
+        let id = match actor.actor_type {
+            ActorType::Account => {
+                if tree.lookup_id(&actor.owner)?.is_some() {
+                    return Err(format!("account {} is allocated twice", actor.owner).into());
+                }
+                tree.register_new_address(&actor.owner)?
+            }
+            ActorType::MultiSig => next_id_address(&mut tree)?,
+        };
+        create_actor(&mut tree, &id, actor)?;
+        allocated += &actor.balance;
+    }
+
+    match &template.verifreg_root_key {
+        Some(actor) => {
+            if let ActorType::Account = actor.actor_type {
+                map_address(&mut tree, &actor.owner, VERIFREG_ROOT_ID)?;
+            }
+            create_actor(&mut tree, &verifreg_root, actor)?;
+            allocated += &actor.balance;
+        }
+        // Without a root key, verifiers can't be added
+        None => set_actor(
+            &mut tree,
+            &verifreg_root,
+            &MULTISIG_ACTOR_CODE_ID,
+            &multisig_state(store, Vec::new())?,
+            TokenAmount::zero(),
+        )?,
+    }
+
+    let remainder = &*TOTAL_FILECOIN - allocated;
+    if remainder.is_negative() {
+        return Err(format!(
+            "the genesis allocates more than the {} attoFIL in existence",
+            *TOTAL_FILECOIN
+        )
+        .into());
+    }
+    set_actor(
+        &mut tree,
+        &RESERVE_ADDRESS,
+        &MULTISIG_ACTOR_CODE_ID,
+        &multisig_state(store, Vec::new())?,
+        remainder,
+    )?;
+
+    // Miners are created by the power actor, with IDs following the accounts
+    let mut init_state: init::State = load_state(&tree, &INIT_ACTOR_ADDR)?;
+    if init_state.next_id > MINER_START {
+        return Err(format!(
+            "the template has too many accounts, their IDs overlap with the miners starting at {}",
+            MINER_START
+        )
+        .into());
+    }
+    init_state.next_id = MINER_START;
+    save_state(&mut tree, &INIT_ACTOR_ADDR, &init_state)?;
+
+    tree.flush()
+}
+
+/// Creates the account or multisig actor of the template at the given ID address.
+fn create_actor<DB: BlockStore>(
+    tree: &mut StateTree<DB>,
+    id: &Address,
+    actor: &Actor,
+) -> Result<(), Box<dyn StdError>> {
+    match actor.actor_type {
+        ActorType::Account => set_actor(
+            tree,
+            id,
+            &ACCOUNT_ACTOR_CODE_ID,
+            &account::State {
+                address: actor.owner,
+            },
+            actor.balance.clone(),
+        ),
+        ActorType::MultiSig => {
+            // Multisig signers are ID addresses, so the owner needs an account
+            let signer = match tree.lookup_id(&actor.owner)? {
+                Some(signer) => signer,
+                None => {
+                    let signer = tree.register_new_address(&actor.owner)?;
+                    set_actor(
+                        tree,
+                        &signer,
+                        &ACCOUNT_ACTOR_CODE_ID,
+                        &account::State {
+                            address: actor.owner,
+                        },
+                        TokenAmount::zero(),
+                    )?;
+                    signer
+                }
+            };
+            let state = multisig_state(tree.store(), vec![signer])?;
+            set_actor(
+                tree,
+                id,
+                &MULTISIG_ACTOR_CODE_ID,
+                &state,
+                actor.balance.clone(),
+            )
+        }
+    }
+}
+
+/// Returns the state of an unlocked multisig, whose signers all have to approve transactions.
+fn multisig_state<DB: BlockStore>(
+    store: &DB,
+    signers: Vec<Address>,
+) -> Result<multisig::State, Box<dyn StdError>> {
+    let pending_txs = make_empty_map::<_, ()>(store, HAMT_BIT_WIDTH).flush()?;
+    Ok(multisig::State {
+        num_approvals_threshold: signers.len(),
+        signers,
+        next_tx_id: Default::default(),
+        initial_balance: TokenAmount::zero(),
+        start_epoch: 0,
+        unlock_duration: 0,
+        pending_txs,
+    })
+}
+
+/// Allocates an ID address which no other address resolves to.
+fn next_id_address<DB: BlockStore>(tree: &mut StateTree<DB>) -> Result<Address, Box<dyn StdError>> {
+    let mut state: init::State = load_state(tree, &INIT_ACTOR_ADDR)?;
+    let id = state.next_id;
+    state.next_id += 1;
+    save_state(tree, &INIT_ACTOR_ADDR, &state)?;
+    Ok(Address::new_id(id))
+}
+
+/// Resolves an address to a fixed ID, for actors which aren't allocated the next ID.
+fn map_address<DB: BlockStore>(
+    tree: &mut StateTree<DB>,
+    addr: &Address,
+    id: u64,
+) -> Result<(), Box<dyn StdError>> {
+    let mut state: init::State = load_state(tree, &INIT_ACTOR_ADDR)?;
+    let mut map = make_map_with_root(&state.address_map, tree.store())?;
+    map.set(addr.to_bytes().into(), id)?;
+    state.address_map = map.flush()?;
+    save_state(tree, &INIT_ACTOR_ADDR, &state)
+}
+
+/// Creates the preseal miners of the template, activates their sectors and deals, and returns
+/// the new state root.
+fn setup_miners<DB, V>(
+    store: &DB,
+    mut root: Cid,
+    template: &Template,
+    network: &NetworkConfig,
+) -> Result<Cid, Box<dyn StdError>>
+where
+    DB: BlockStore,
+    V: ProofVerifier,
+{
+    let network_version = network.network_version(0);
+    let mut miners = Vec::with_capacity(template.miners.len());
+    for (i, m) in template.miners.iter().enumerate() {
+        let seal_proof = RegisteredSealProof::from_sector_size(m.sector_size, network_version);
+        let params = power::CreateMinerParams {
+            owner: m.owner,
+            worker: m.worker,
+            window_post_proof_type: seal_proof.registered_window_post_proof()?,
+            peer: PeerId::from_str(&m.peer_id)?.to_bytes(),
+            multiaddrs: Vec::new(),
+        };
+        let msg = implicit_message(
+            m.owner,
+            *STORAGE_POWER_ACTOR_ADDR,
+            m.power_balance.clone(),
+            power::Method::CreateMiner as MethodNum,
+            Serialized::serialize(params)?,
+        )?;
+        let (new_root, ret) = exec::<_, V>(store, &root, network, &msg)?;
+        root = new_root;
+
+        let ret: power::CreateMinerReturn = ret.deserialize()?;
+        let expected = Address::new_id(MINER_START + i as u64);
+        if ret.id_address != expected {
+            return Err(format!(
+                "miner {} was created as {} instead of {}",
+                i, ret.id_address, expected
+            )
+            .into());
+        }
+
+        if m.market_balance.is_positive() {
+            let msg = implicit_message(
+                m.worker,
+                *STORAGE_MARKET_ACTOR_ADDR,
+                m.market_balance.clone(),
+                market::Method::AddBalance as MethodNum,
+                Serialized::serialize(expected)?,
+            )?;
+            root = exec::<_, V>(store, &root, network, &msg)?.0;
+        }
+        miners.push(expected);
+    }
+
+    // Preseal deals are inserted in the market state as if they had been published, since they
+    // don't have client signatures.
+    let tree = StateTree::new_from_root(store, &root)?;
+    let mut proposals = Vec::with_capacity(miners.len());
+    for (m, maddr) in template.miners.iter().zip(&miners) {
+        proposals.push(
+            m.sectors
+                .iter()
+                .map(|sector| deal_proposal(&tree, &sector.deal, maddr))
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+    }
+    let mut deal_ids: Vec<Vec<DealID>> = Vec::with_capacity(miners.len());
+    root = mutate_state(
+        store,
+        &root,
+        &STORAGE_MARKET_ACTOR_ADDR,
+        |st: &mut market::State| {
+            let mut deals = market::DealArray::load(&st.proposals, store)?;
+            let mut pending = Set::from_root(store, &st.pending_proposals)?;
+            let mut deals_by_epoch = SetMultimap::from_root(store, &st.deal_ops_by_epoch)?;
+            for miner_proposals in proposals {
+                let mut ids = Vec::with_capacity(miner_proposals.len());
+                for proposal in miner_proposals {
+                    let id = st.next_id;
+                    st.next_id += 1;
+                    pending.put(proposal.cid()?.to_bytes().into())?;
+                    deals_by_epoch
+                        .put(market::gen_rand_next_epoch(proposal.start_epoch, id), id)?;
+                    deals.set(id as usize, proposal)?;
+                    ids.push(id);
+                }
+                deal_ids.push(ids);
+            }
+            st.proposals = deals.flush()?;
+            st.pending_proposals = pending.root()?;
+            st.deal_ops_by_epoch = deals_by_epoch.root()?;
+            Ok(())
+        },
+    )?;
+
+    let market_state: market::State = read_state(store, &root, &STORAGE_MARKET_ACTOR_ADDR)?;
+    let deals = market::DealArray::load(&market_state.proposals, store)?;
+    let mut sectors = Vec::with_capacity(miners.len());
+    let mut raw_power = StoragePower::zero();
+    let mut qa_power = StoragePower::zero();
+    for ((m, maddr), ids) in template.miners.iter().zip(&miners).zip(&deal_ids) {
+        let sector_size = StoragePower::from(m.sector_size as u64);
+        let miner_state: miner::State = read_state(store, &root, maddr)?;
+        // Preseal sectors expire as late as sectors committed at genesis can
+        let max_periods = miner::MAX_SECTOR_EXPIRATION_EXTENSION / miner::WPOST_PROVING_PERIOD;
+        let expiration: ChainEpoch =
+            (max_periods - 1) * miner::WPOST_PROVING_PERIOD + miner_state.proving_period_start - 1;
+
+        let mut miner_sectors = Vec::with_capacity(m.sectors.len());
+        for (sector, &deal_id) in m.sectors.iter().zip(ids) {
+            let (deal_weight, verified_deal_weight, _) =
+                market::validate_and_compute_deal_weight(&deals, &[deal_id], maddr, expiration, 0)?;
+            let power = miner::qa_power_for_weight(
+                m.sector_size,
+                expiration,
+                &deal_weight,
+                &verified_deal_weight,
+            );
+            raw_power += &sector_size;
+            qa_power += &power;
+
+            let pre_commit = miner::SectorPreCommitOnChainInfo {
+                info: miner::SectorPreCommitInfo {
+                    seal_proof: sector.proof_type,
+                    sector_number: sector.sector_id,
+                    sealed_cid: sector.comm_r,
+                    seal_rand_epoch: -1,
+                    deal_ids: vec![deal_id],
+                    expiration,
+                    replace_capacity: false,
+                    replace_sector_deadline: 0,
+                    replace_sector_partition: 0,
+                    replace_sector_number: 0,
+                },
+                pre_commit_deposit: TokenAmount::zero(),
+                pre_commit_epoch: 0,
+                deal_weight,
+                verified_deal_weight,
+            };
+            miner_sectors.push((pre_commit, power));
+        }
+        sectors.push(miner_sectors);
+    }
+
+    // The network power accounts for all preseal sectors while they are committed, so that their
+    // pledge doesn't depend on the order they are committed in. The power of each sector is only
+    // claimed once it is committed.
+    root = mutate_state(
+        store,
+        &root,
+        &STORAGE_POWER_ACTOR_ADDR,
+        |st: &mut power::State| {
+            st.total_raw_byte_power = raw_power.clone();
+            st.total_quality_adj_power = qa_power.clone();
+            st.this_epoch_raw_byte_power = raw_power.clone();
+            st.this_epoch_quality_adj_power = qa_power.clone();
+            Ok(())
+        },
+    )?;
+    root = mutate_state(
+        store,
+        &root,
+        &REWARD_ACTOR_ADDR,
+        |st: &mut reward::State| {
+            *st = reward::State::new(qa_power.clone());
+            Ok(())
+        },
+    )?;
+
+    for ((m, maddr), miner_sectors) in template.miners.iter().zip(&miners).zip(sectors) {
+        let sector_size = StoragePower::from(m.sector_size as u64);
+        // Sectors are committed one at a time, their pledge grows too much otherwise
+        for (pre_commit, power) in miner_sectors {
+            let sector_number = pre_commit.info.sector_number;
+            root = mutate_state(store, &root, maddr, |st: &mut miner::State| {
+                let mut sector_numbers = BitField::new();
+                sector_numbers.set(sector_number as usize);
+                st.allocate_sector_numbers(
+                    store,
+                    &sector_numbers,
+                    miner::CollisionPolicy::DenyCollisions,
+                )?;
+                st.put_precommitted_sectors(store, vec![pre_commit])
+            })?;
+            root = mutate_state(
+                store,
+                &root,
+                &STORAGE_POWER_ACTOR_ADDR,
+                |st: &mut power::State| {
+                    st.total_raw_byte_power -= &sector_size;
+                    st.total_quality_adj_power -= &power;
+                    Ok(())
+                },
+            )?;
+
+            let pledge = initial_pledge(store, &root, &power)?;
+            let msg =
+                implicit_message(m.worker, *maddr, pledge, METHOD_SEND, Serialized::default())?;
+            root = exec::<_, V>(store, &root, network, &msg)?.0;
+
+            let msg = implicit_message(
+                *STORAGE_POWER_ACTOR_ADDR,
+                *maddr,
+                TokenAmount::zero(),
+                miner::Method::ConfirmSectorProofsValid as MethodNum,
+                Serialized::serialize(miner::ConfirmSectorProofsParams {
+                    sectors: vec![sector_number],
+                })?,
+            )?;
+            root = exec::<_, V>(store, &root, network, &msg)?.0;
+
+            let msg = implicit_message(
+                *maddr,
+                *STORAGE_POWER_ACTOR_ADDR,
+                TokenAmount::zero(),
+                power::Method::UpdateClaimedPower as MethodNum,
+                Serialized::serialize(power::UpdateClaimedPowerParams {
+                    raw_byte_delta: sector_size.clone(),
+                    quality_adjusted_delta: power,
+                })?,
+            )?;
+            root = exec::<_, V>(store, &root, network, &msg)?.0;
+
+            root = mutate_state(store, &root, maddr, |st: &mut miner::State| {
+                activate_unproven(store, st)
+            })?;
+        }
+    }
+
+    let power_state: power::State = read_state(store, &root, &STORAGE_POWER_ACTOR_ADDR)?;
+    if power_state.total_raw_byte_power != raw_power
+        || power_state.total_quality_adj_power != qa_power
+    {
+        return Err(format!(
+            "the network power ({} raw, {} quality adjusted) doesn't match the preseal sectors ({} raw, {} quality adjusted)",
+            power_state.total_raw_byte_power,
+            power_state.total_quality_adj_power,
+            raw_power,
+            qa_power
+        )
+        .into());
+    }
+
+    Ok(root)
+}
+
+/// Returns the market deal of a preseal sector. Preseal deals have no escrow, so they have to be
+/// free.
+fn deal_proposal<DB: BlockStore>(
+    tree: &StateTree<DB>,
+    deal: &PreSealDeal,
+    miner: &Address,
+) -> Result<market::DealProposal, Box<dyn StdError>> {
+    if tree.lookup_id(&deal.provider)?.as_ref() != Some(miner) {
+        return Err(format!(
+            "deal of piece {} is provided by {} instead of miner {}",
+            deal.piece_cid, deal.provider, miner
+        )
+        .into());
+    }
+    if !deal.storage_price_per_epoch.is_zero()
+        || !deal.provider_collateral.is_zero()
+        || !deal.client_collateral.is_zero()
+    {
+        return Err(format!(
+            "deal of piece {} has a price or collateral, which preseal deals can't have",
+            deal.piece_cid
+        )
+        .into());
+    }
+    let client = tree.lookup_id(&deal.client)?.ok_or_else(|| {
+        format!(
+            "client {} of the deal of piece {} isn't a genesis account",
+            deal.client, deal.piece_cid
+        )
+    })?;
+
+    Ok(market::DealProposal {
+        piece_cid: deal.piece_cid,
+        piece_size: deal.piece_size,
+        verified_deal: deal.verified_deal,
+        client,
+        provider: *miner,
+        label: deal.label.clone(),
+        start_epoch: deal.start_epoch,
+        end_epoch: deal.end_epoch,
+        storage_price_per_epoch: TokenAmount::zero(),
+        provider_collateral: TokenAmount::zero(),
+        client_collateral: TokenAmount::zero(),
+    })
+}
+
+/// Returns the initial pledge the miner actor requires to commit a sector with the given power.
+fn initial_pledge<DB: BlockStore>(
+    store: &DB,
+    root: &Cid,
+    power: &StoragePower,
+) -> Result<TokenAmount, Box<dyn StdError>> {
+    let reward_state: reward::State = read_state(store, root, &REWARD_ACTOR_ADDR)?;
+    let power_state: power::State = read_state(store, root, &STORAGE_POWER_ACTOR_ADDR)?;
+    // Nothing circulates at genesis
+    Ok(miner::initial_pledge_for_power(
+        power,
+        &reward_state.this_epoch_baseline_power,
+        &reward_state.this_epoch_reward_smoothed,
+        &power_state.this_epoch_qa_power_smoothed,
+        &TokenAmount::zero(),
+    ))
+}
+
+/// Activates the unproven sectors of a miner, so that preseal sectors have power without waiting
+/// for their first window PoSt.
+fn activate_unproven<DB: BlockStore>(
+    store: &DB,
+    st: &mut miner::State,
+) -> Result<(), Box<dyn StdError>> {
+    let mut deadlines = st.load_deadlines(store)?;
+    let mut updated = Vec::new();
+    deadlines.for_each(store, |idx, mut deadline| {
+        let mut partitions = deadline.partitions_amt(store)?;
+        partitions.for_each_mut(|_, partition| {
+            partition.activate_unproven();
+            Ok(())
+        })?;
+        deadline.partitions = partitions.flush()?;
+        updated.push((idx, deadline));
+        Ok(())
+    })?;
+    for (idx, deadline) in updated {
+        deadlines.update_deadline(store, idx, &deadline)?;
+    }
+    st.save_deadlines(store, deadlines)
+}
+
+fn implicit_message(
+    from: Address,
+    to: Address,
+    value: TokenAmount,
+    method_num: MethodNum,
+    params: Serialized,
+) -> Result<UnsignedMessage, String> {
+    UnsignedMessage::builder()
+        .from(from)
+        .to(to)
+        .value(value)
+        .method_num(method_num)
+        .params(params)
+        .gas_limit(BLOCK_GAS_LIMIT * 10000)
+        .build()
+}
+
+/// Applies an implicit message on top of the given state, and returns the new state root with
+/// the return value of the message.
+fn exec<DB, V>(
+    store: &DB,
+    root: &Cid,
+    network: &NetworkConfig,
+    msg: &UnsignedMessage,
+) -> Result<(Cid, Serialized), Box<dyn StdError>>
+where
+    DB: BlockStore,
+    V: ProofVerifier,
+{
+    let mut vm = VM::<_, _, _, _, _, V>::new(
+        root,
+        store,
+        0,
+        &GenesisRand,
+        TokenAmount::zero(),
+        |epoch| network.network_version(epoch),
+        &network.upgrades,
+        &GenesisSupply,
+        &NoLookback,
+    )?;
+    let ret = vm.apply_implicit_message(msg);
+    if let Some(err) = ret.act_error {
+        return Err(format!(
+            "failed to apply method {} from {} to {}: {}",
+            msg.method_num(),
+            msg.from(),
+            msg.to(),
+            err
+        )
+        .into());
+    }
+    Ok((vm.flush()?, ret.msg_receipt.return_data))
+}
+
+/// Mutates the state of an actor outside of the VM, and returns the new state root.
+fn mutate_state<DB, S, F>(
+    store: &DB,
+    root: &Cid,
+    addr: &Address,
+    f: F,
+) -> Result<Cid, Box<dyn StdError>>
+where
+    DB: BlockStore,
+    S: Serialize + DeserializeOwned,
+    F: FnOnce(&mut S) -> Result<(), Box<dyn StdError>>,
+{
+    let mut tree = StateTree::new_from_root(store, root)?;
+    let mut state = load_state(&tree, addr)?;
+    f(&mut state)?;
+    save_state(&mut tree, addr, &state)?;
+    tree.flush()
+}
+
+fn read_state<DB, S>(store: &DB, root: &Cid, addr: &Address) -> Result<S, Box<dyn StdError>>
+where
+    DB: BlockStore,
+    S: DeserializeOwned,
+{
+    load_state(&StateTree::new_from_root(store, root)?, addr)
+}
+
+fn load_state<DB, S>(tree: &StateTree<DB>, addr: &Address) -> Result<S, Box<dyn StdError>>
+where
+    DB: BlockStore,
+    S: DeserializeOwned,
+{
+    let actor = tree
+        .get_actor(addr)?
+        .ok_or_else(|| format!("actor {} not found", addr))?;
+    Ok(tree
+        .store()
+        .get(&actor.state)?
+        .ok_or_else(|| format!("state of actor {} not found", addr))?)
+}
+
+fn save_state<DB, S>(
+    tree: &mut StateTree<DB>,
+    addr: &Address,
+    state: &S,
+) -> Result<(), Box<dyn StdError>>
+where
+    DB: BlockStore,
+    S: Serialize,
+{
+    let head = tree.store().put(state, Blake2b256)?;
+    tree.mutate_actor(addr, |actor| {
+        actor.state = head;
+        Ok(())
+    })
+}
+
+fn set_actor<DB, S>(
+    tree: &mut StateTree<DB>,
+    addr: &Address,
+    code: &Cid,
+    state: &S,
+    balance: TokenAmount,
+) -> Result<(), Box<dyn StdError>>
+where
+    DB: BlockStore,
+    S: Serialize,
+{
+    let head = tree.store().put(state, Blake2b256)?;
+    tree.set_actor(addr, ActorState::new(*code, head, balance, 0))
+}
+
+/// Randomness of the genesis VM, which precedes any ticket or beacon entry.
+struct GenesisRand;
+impl Rand for GenesisRand {
+    fn get_chain_randomness(
+        &self,
+        _: DomainSeparationTag,
+        _: ChainEpoch,
+        _: &[u8],
+    ) -> Result<[u8; 32], Box<dyn StdError>> {
+        Ok([0; 32])
+    }
+    fn get_chain_randomness_looking_forward(
+        &self,
+        _: DomainSeparationTag,
+        _: ChainEpoch,
+        _: &[u8],
+    ) -> Result<[u8; 32], Box<dyn StdError>> {
+        Ok([0; 32])
+    }
+    fn get_beacon_randomness(
+        &self,
+        _: DomainSeparationTag,
+        _: ChainEpoch,
+        _: &[u8],
+    ) -> Result<[u8; 32], Box<dyn StdError>> {
+        Ok([0; 32])
+    }
+    fn get_beacon_randomness_looking_forward(
+        &self,
+        _: DomainSeparationTag,
+        _: ChainEpoch,
+        _: &[u8],
+    ) -> Result<[u8; 32], Box<dyn StdError>> {
+        Ok([0; 32])
+    }
+}
+
+struct GenesisSupply;
+impl CircSupplyCalc for GenesisSupply {
+    fn get_supply<DB: BlockStore>(
+        &self,
+        _: ChainEpoch,
+        _: &StateTree<DB>,
+    ) -> Result<TokenAmount, Box<dyn StdError>> {
+        Ok(TokenAmount::zero())
+    }
+}
+
+struct NoLookback;
+impl<'db, DB> LookbackStateGetter<'db, DB> for NoLookback {
+    fn state_lookback(&self, _: ChainEpoch) -> Result<StateTree<'db, DB>, Box<dyn StdError>> {
+        Err("no state precedes genesis".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_types::genesis::{Miner, PreSeal};
+    use fil_types::verifier::MockVerifier;
+    use fil_types::{PaddedPieceSize, SectorSize};
+    use networks::UpgradeSchedule;
+
+    #[test]
+    fn empty_genesis() {
+        let network = NetworkConfig {
+            upgrades: UpgradeSchedule::ALL_AT_GENESIS,
+            ..NetworkConfig::devnet()
+        };
+        network.set_process_defaults().unwrap();
+        let store = MemoryDB::default();
+        let template = Template::new("localnet".to_owned());
+        let header = make_genesis_block::<_, MockVerifier>(&store, &template, &network).unwrap();
+        assert_eq!(header.epoch(), 0);
+
+        let tree = StateTree::new_from_root(&store, header.state_root()).unwrap();
+        let init_state: init::State = load_state(&tree, &INIT_ACTOR_ADDR).unwrap();
+        assert_eq!(init_state.network_name, "localnet");
+        assert_eq!(init_state.next_id, MINER_START);
+        let reserve = tree.get_actor(&RESERVE_ADDRESS).unwrap().unwrap();
+        let reward = tree.get_actor(&REWARD_ACTOR_ADDR).unwrap().unwrap();
+        assert_eq!(reserve.balance + reward.balance, *TOTAL_FILECOIN);
+
+        // The v5 actors can't run before hyperdrive
+        assert!(
+            make_genesis_block::<_, MockVerifier>(&store, &template, &NetworkConfig::devnet())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn genesis_with_preseal_miner() {
+        let network = NetworkConfig {
+            upgrades: UpgradeSchedule::ALL_AT_GENESIS,
+            ..NetworkConfig::devnet()
+        };
+        // 2KiB sectors only have power under the devnet policy
+        network.set_process_defaults().unwrap();
+
+        let owner = Address::new_bls(&[1; 48]).unwrap();
+        let maddr = Address::new_id(MINER_START);
+        let comm_r = cid::new_from_cbor(b"sealed", Blake2b256);
+        let piece_cid = cid::new_from_cbor(b"piece", Blake2b256);
+        let mut template = Template::new("localnet".to_owned());
+        template.accounts.push(Actor {
+            actor_type: ActorType::Account,
+            balance: BigInt::from(1000) * FILECOIN_PRECISION,
+            owner,
+        });
+        template.miners.push(Miner {
+            owner,
+            worker: owner,
+            peer_id: PeerId::random().to_string(),
+            market_balance: TokenAmount::zero(),
+            power_balance: TokenAmount::zero(),
+            sector_size: SectorSize::_2KiB,
+            sectors: vec![PreSeal {
+                comm_r,
+                comm_d: cid::new_from_cbor(b"unsealed", Blake2b256),
+                sector_id: 0,
+                deal: PreSealDeal {
+                    piece_cid,
+                    piece_size: PaddedPieceSize(2048),
+                    verified_deal: false,
+                    client: owner,
+                    provider: maddr,
+                    label: "preseal".to_owned(),
+                    start_epoch: 0,
+                    end_epoch: 100_000,
+                    storage_price_per_epoch: TokenAmount::zero(),
+                    provider_collateral: TokenAmount::zero(),
+                    client_collateral: TokenAmount::zero(),
+                },
+                proof_type: RegisteredSealProof::StackedDRG2KiBV1P1,
+            }],
+        });
+
+        let store = MemoryDB::default();
+        let header = make_genesis_block::<_, MockVerifier>(&store, &template, &network).unwrap();
+        let tree = StateTree::new_from_root(&store, header.state_root()).unwrap();
+
+        let miner_state: miner::State = load_state(&tree, &maddr).unwrap();
+        let sector = miner_state.get_sector(&store, 0).unwrap().unwrap();
+        assert_eq!(sector.sealed_cid, comm_r);
+        assert_eq!(sector.deal_ids, vec![0]);
+
+        let market_state: market::State = load_state(&tree, &STORAGE_MARKET_ACTOR_ADDR).unwrap();
+        let proposals = market::DealArray::load(&market_state.proposals, &store).unwrap();
+        let proposal = proposals.get(0).unwrap().unwrap();
+        assert_eq!(proposal.provider, maddr);
+        assert_eq!(proposal.piece_cid, piece_cid);
+        let states = market::DealMetaArray::load(&market_state.states, &store).unwrap();
+        assert_eq!(states.get(0).unwrap().unwrap().sector_start_epoch, 0);
+
+        let power_state: power::State = load_state(&tree, &STORAGE_POWER_ACTOR_ADDR).unwrap();
+        let claim = power_state.get_claim(&store, &maddr).unwrap().unwrap();
+        assert_eq!(claim.raw_byte_power, StoragePower::from(2048));
+        assert_eq!(power_state.total_raw_byte_power, StoragePower::from(2048));
+    }
+}
@@ -1,6 +1,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod builder;
+
+pub use self::builder::*;
+
 use async_std::fs::File;
 use async_std::io::BufReader;
 use blocks::{BlockHeader, Tipset, TipsetKeys};
@@ -12,7 +16,6 @@ use futures::AsyncRead;
 use ipld_blockstore::BlockStore;
 use log::{debug, info};
 use net_utils::FetchProgress;
-use networks::DEFAULT_GENESIS;
 use state_manager::StateManager;
 use std::error::Error as StdError;
 use std::sync::Arc;
@@ -22,8 +25,8 @@ use url::Url;
 #[cfg(feature = "testing")]
 pub const EXPORT_SR_40: &[u8] = std::include_bytes!("export40.car");
 
-/// Uses an optional file path or the default genesis to parse the genesis and determine if
-/// chain store has existing data for the given genesis.
+/// Uses an optional file path or the genesis of the network of the state manager to parse the
+/// genesis and determine if chain store has existing data for the given genesis.
 pub async fn initialize_genesis<BS>(
     genesis_fp: Option<&String>,
     state_manager: &StateManager<BS>,
@@ -31,17 +34,29 @@ pub async fn initialize_genesis<BS>(
 where
     BS: BlockStore + Send + Sync + 'static,
 {
-    let genesis = match genesis_fp {
-        Some(path) => {
+    let network = state_manager.network_config();
+    let genesis = match (
+        genesis_fp.or_else(|| network.genesis_file.as_ref()),
+        network.genesis_car,
+    ) {
+        (Some(path), _) => {
             let file = File::open(path).await?;
             let reader = BufReader::new(file);
             process_car(reader, state_manager.chain_store()).await?
         }
-        None => {
-            debug!("No specified genesis in config. Using default genesis.");
-            let reader = BufReader::<&[u8]>::new(DEFAULT_GENESIS);
+        (None, Some(genesis_car)) => {
+            debug!(
+                "No specified genesis in config. Using the {} genesis.",
+                network.name
+            );
+            let reader = BufReader::<&[u8]>::new(genesis_car);
             process_car(reader, state_manager.chain_store()).await?
         }
+        (None, None) => {
+            return Err(
+                format!("no genesis file was given for the {} network", network.name).into(),
+            )
+        }
     };
 
     info!("Initialized genesis: {}", genesis);
@@ -0,0 +1,389 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Structural diffs of hamts and amts. Both trees are walked side by side and subtrees linked
+//! by the same Cid are skipped, so only the nodes on the path to a change are loaded.
+//! Nodes are decoded as raw ipld, which covers every hamt and amt version used by the actors.
+
+use blockstore::BlockStore;
+use cid::Cid;
+use ipld::Ipld;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+
+/// Values of the visited entries on the old and new side, keyed by hamt key or amt index.
+type Entries<K> = BTreeMap<K, (Option<Ipld>, Option<Ipld>)>;
+
+/// Bit width of the amts serialized without one in their root (actors v0 and v2).
+const AMT_V0_BIT_WIDTH: u32 = 3;
+
+fn load_node<BS: BlockStore>(bs: &BS, cid: &Cid) -> Result<Ipld, Box<dyn StdError>> {
+    Ok(bs
+        .get(cid)?
+        .ok_or_else(|| format!("node {} not found in store", cid))?)
+}
+
+fn record<K: Ord>(entries: &mut Entries<K>, key: K, value: Ipld, old: bool) {
+    let entry = entries.entry(key).or_default();
+    if old {
+        entry.0 = Some(value);
+    } else {
+        entry.1 = Some(value);
+    }
+}
+
+fn changed_keys<K>(entries: Entries<K>) -> Vec<K> {
+    entries
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(key, _)| key)
+        .collect()
+}
+
+fn int(ipld: &Ipld) -> Result<u64, Box<dyn StdError>> {
+    match ipld {
+        Ipld::Integer(i) if *i >= 0 => Ok(*i as u64),
+        _ => Err("expected an unsigned integer".into()),
+    }
+}
+
+/// Returns the keys of the entries added, removed or modified going from the hamt at `old_root`
+/// to the hamt at `new_root`.
+pub(crate) fn hamt_changed_keys<BS: BlockStore>(
+    bs: &BS,
+    old_root: &Cid,
+    new_root: &Cid,
+) -> Result<Vec<Vec<u8>>, Box<dyn StdError>> {
+    let mut entries = Entries::new();
+    if old_root != new_root {
+        diff_hamt_nodes(
+            bs,
+            load_node(bs, old_root)?,
+            load_node(bs, new_root)?,
+            &mut entries,
+        )?;
+    }
+    Ok(changed_keys(entries))
+}
+
+enum HamtPointer {
+    Link(Cid),
+    Values(Vec<(Vec<u8>, Ipld)>),
+}
+
+fn hamt_pointer(ipld: Ipld) -> Result<HamtPointer, Box<dyn StdError>> {
+    match ipld {
+        Ipld::Link(cid) => Ok(HamtPointer::Link(cid)),
+        Ipld::List(kvs) => kvs
+            .into_iter()
+            .map(|kv| match kv {
+                Ipld::List(kv) => match <[Ipld; 2]>::try_from(kv) {
+                    Ok([Ipld::Bytes(key), value]) => Ok((key, value)),
+                    _ => Err("invalid hamt key value pair".into()),
+                },
+                _ => Err("invalid hamt key value pair".into()),
+            })
+            .collect::<Result<_, Box<dyn StdError>>>()
+            .map(HamtPointer::Values),
+        // Hamts before actors v3 serialize pointers as a map keyed by the pointer kind.
+        Ipld::Map(mut map) => match (map.remove("0"), map.remove("1")) {
+            (Some(link), None) => hamt_pointer(link),
+            (None, Some(kvs)) => hamt_pointer(kvs),
+            _ => Err("invalid hamt pointer".into()),
+        },
+        _ => Err("invalid hamt pointer".into()),
+    }
+}
+
+/// Decodes a hamt node into its pointers, keyed by their index in the node.
+fn hamt_pointers(node: Ipld) -> Result<BTreeMap<u32, HamtPointer>, Box<dyn StdError>> {
+    let (bitfield, pointers) = match node {
+        Ipld::List(node) => match <[Ipld; 2]>::try_from(node) {
+            Ok([Ipld::Bytes(bitfield), Ipld::List(pointers)]) => (bitfield, pointers),
+            _ => return Err("invalid hamt node".into()),
+        },
+        _ => return Err("invalid hamt node".into()),
+    };
+
+    // The bitfield is a big endian integer with its leading zero bytes trimmed, pointers are
+    // stored in increasing index order.
+    let indexes: Vec<u32> = (0..bitfield.len() as u32 * 8)
+        .filter(|i| bitfield[bitfield.len() - 1 - (*i as usize / 8)] & (1 << (i % 8)) != 0)
+        .collect();
+    if indexes.len() != pointers.len() {
+        return Err("hamt bitfield does not match its pointers".into());
+    }
+    indexes
+        .into_iter()
+        .zip(pointers)
+        .map(|(idx, pointer)| Ok((idx, hamt_pointer(pointer)?)))
+        .collect()
+}
+
+fn collect_hamt<BS: BlockStore>(
+    bs: &BS,
+    pointer: HamtPointer,
+    entries: &mut Entries<Vec<u8>>,
+    old: bool,
+) -> Result<(), Box<dyn StdError>> {
+    match pointer {
+        HamtPointer::Values(kvs) => {
+            for (key, value) in kvs {
+                record(entries, key, value, old);
+            }
+        }
+        HamtPointer::Link(cid) => {
+            for (_, pointer) in hamt_pointers(load_node(bs, &cid)?)? {
+                collect_hamt(bs, pointer, entries, old)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn diff_hamt_nodes<BS: BlockStore>(
+    bs: &BS,
+    old: Ipld,
+    new: Ipld,
+    entries: &mut Entries<Vec<u8>>,
+) -> Result<(), Box<dyn StdError>> {
+    let mut old = hamt_pointers(old)?;
+    for (idx, new_pointer) in hamt_pointers(new)? {
+        match (old.remove(&idx), new_pointer) {
+            (Some(HamtPointer::Link(a)), HamtPointer::Link(b)) => {
+                if a != b {
+                    diff_hamt_nodes(bs, load_node(bs, &a)?, load_node(bs, &b)?, entries)?;
+                }
+            }
+            // A bucket may have been split into a child node or collapsed back, the entries
+            // are matched up by key once both sides are collected.
+            (old_pointer, new_pointer) => {
+                if let Some(old_pointer) = old_pointer {
+                    collect_hamt(bs, old_pointer, entries, true)?;
+                }
+                collect_hamt(bs, new_pointer, entries, false)?;
+            }
+        }
+    }
+    for (_, old_pointer) in old {
+        collect_hamt(bs, old_pointer, entries, true)?;
+    }
+    Ok(())
+}
+
+/// Returns the indexes of the entries added, removed or modified going from the amt at
+/// `old_root` to the amt at `new_root`.
+pub(crate) fn amt_changed_indexes<BS: BlockStore>(
+    bs: &BS,
+    old_root: &Cid,
+    new_root: &Cid,
+) -> Result<Vec<u64>, Box<dyn StdError>> {
+    if old_root == new_root {
+        return Ok(Vec::new());
+    }
+    let mut entries = Entries::new();
+
+    let (bit_width, mut old_height, old) = amt_root(load_node(bs, old_root)?)?;
+    let (new_bit_width, mut new_height, new) = amt_root(load_node(bs, new_root)?)?;
+    if bit_width != new_bit_width {
+        return Err(format!("amt bit widths differ: {} and {}", bit_width, new_bit_width).into());
+    }
+    let width = 1 << bit_width;
+
+    // The index range of a node covers the one of every shorter tree through its first child,
+    // so descend the taller tree until both heights match.
+    let (mut old, mut new) = (Some(old), Some(new));
+    while old_height > new_height {
+        if let Some(node) = old.take() {
+            old = amt_first_child(bs, node, width, old_height, &mut entries, true)?;
+        }
+        old_height -= 1;
+    }
+    while new_height > old_height {
+        if let Some(node) = new.take() {
+            new = amt_first_child(bs, node, width, new_height, &mut entries, false)?;
+        }
+        new_height -= 1;
+    }
+
+    match (old, new) {
+        (Some(old), Some(new)) => diff_amt_nodes(bs, old, new, width, old_height, 0, &mut entries)?,
+        (Some(old), None) => collect_amt(bs, old, width, old_height, 0, &mut entries, true)?,
+        (None, Some(new)) => collect_amt(bs, new, width, new_height, 0, &mut entries, false)?,
+        (None, None) => (),
+    }
+    Ok(changed_keys(entries))
+}
+
+/// Links of an inner node or values of a leaf, keyed by their index in the node.
+#[derive(Default)]
+struct AmtNode {
+    links: BTreeMap<u64, Cid>,
+    values: BTreeMap<u64, Ipld>,
+}
+
+/// Decodes an amt root into its bit width, height and root node.
+fn amt_root(root: Ipld) -> Result<(u32, u32, AmtNode), Box<dyn StdError>> {
+    let (bit_width, height, node) = match root {
+        Ipld::List(root) => match <[Ipld; 4]>::try_from(root) {
+            Ok([bit_width, height, _count, node]) => (int(&bit_width)? as u32, height, node),
+            Err(root) => match <[Ipld; 3]>::try_from(root) {
+                Ok([height, _count, node]) => (AMT_V0_BIT_WIDTH, height, node),
+                Err(_) => return Err("invalid amt root".into()),
+            },
+        },
+        _ => return Err("invalid amt root".into()),
+    };
+    Ok((
+        bit_width,
+        int(&height)? as u32,
+        amt_node(node, 1 << bit_width)?,
+    ))
+}
+
+fn amt_node(node: Ipld, width: u64) -> Result<AmtNode, Box<dyn StdError>> {
+    let (bitmap, links, values) = match node {
+        Ipld::List(node) => match <[Ipld; 3]>::try_from(node) {
+            Ok([Ipld::Bytes(bitmap), Ipld::List(links), Ipld::List(values)]) => {
+                (bitmap, links, values)
+            }
+            _ => return Err("invalid amt node".into()),
+        },
+        _ => return Err("invalid amt node".into()),
+    };
+
+    let indexes: Vec<u64> = (0..width)
+        .filter(|i| {
+            bitmap
+                .get(*i as usize / 8)
+                .map_or(false, |b| b & (1 << (i % 8)) != 0)
+        })
+        .collect();
+    if indexes.len() != links.len() + values.len() {
+        return Err("amt bitmap does not match its entries".into());
+    }
+
+    let mut res = AmtNode::default();
+    if values.is_empty() {
+        for (idx, link) in indexes.into_iter().zip(links) {
+            match link {
+                Ipld::Link(cid) => {
+                    res.links.insert(idx, cid);
+                }
+                _ => return Err("invalid amt link".into()),
+            }
+        }
+    } else {
+        res.values = indexes.into_iter().zip(values).collect();
+    }
+    Ok(res)
+}
+
+fn load_amt_node<BS: BlockStore>(
+    bs: &BS,
+    cid: &Cid,
+    width: u64,
+) -> Result<AmtNode, Box<dyn StdError>> {
+    amt_node(load_node(bs, cid)?, width)
+}
+
+/// Number of indexes covered by each entry of a node at `height`.
+fn amt_span(width: u64, height: u32) -> u64 {
+    width.pow(height)
+}
+
+fn collect_amt<BS: BlockStore>(
+    bs: &BS,
+    node: AmtNode,
+    width: u64,
+    height: u32,
+    offset: u64,
+    entries: &mut Entries<u64>,
+    old: bool,
+) -> Result<(), Box<dyn StdError>> {
+    for (idx, value) in node.values {
+        record(entries, offset + idx, value, old);
+    }
+    for (idx, cid) in node.links {
+        let child = load_amt_node(bs, &cid, width)?;
+        let offset = offset + idx * amt_span(width, height);
+        collect_amt(bs, child, width, height - 1, offset, entries, old)?;
+    }
+    Ok(())
+}
+
+/// Collects the entries outside of the first child of `node` and returns that child.
+fn amt_first_child<BS: BlockStore>(
+    bs: &BS,
+    mut node: AmtNode,
+    width: u64,
+    height: u32,
+    entries: &mut Entries<u64>,
+    old: bool,
+) -> Result<Option<AmtNode>, Box<dyn StdError>> {
+    let first = node.links.remove(&0);
+    collect_amt(bs, node, width, height, 0, entries, old)?;
+    first.map(|cid| load_amt_node(bs, &cid, width)).transpose()
+}
+
+fn diff_amt_nodes<BS: BlockStore>(
+    bs: &BS,
+    old: AmtNode,
+    new: AmtNode,
+    width: u64,
+    height: u32,
+    offset: u64,
+    entries: &mut Entries<u64>,
+) -> Result<(), Box<dyn StdError>> {
+    // Leaves hold at most `width` values, equal ones are dropped when the keys are collected.
+    for (idx, value) in old.values {
+        record(entries, offset + idx, value, true);
+    }
+    for (idx, value) in new.values {
+        record(entries, offset + idx, value, false);
+    }
+
+    let span = amt_span(width, height);
+    let mut old_links = old.links;
+    for (idx, new_cid) in new.links {
+        let child_offset = offset + idx * span;
+        match old_links.remove(&idx) {
+            Some(old_cid) if old_cid == new_cid => (),
+            Some(old_cid) => diff_amt_nodes(
+                bs,
+                load_amt_node(bs, &old_cid, width)?,
+                load_amt_node(bs, &new_cid, width)?,
+                width,
+                height - 1,
+                child_offset,
+                entries,
+            )?,
+            None => {
+                let new_child = load_amt_node(bs, &new_cid, width)?;
+                collect_amt(
+                    bs,
+                    new_child,
+                    width,
+                    height - 1,
+                    child_offset,
+                    entries,
+                    false,
+                )?
+            }
+        }
+    }
+    for (idx, old_cid) in old_links {
+        let old_child = load_amt_node(bs, &old_cid, width)?;
+        collect_amt(
+            bs,
+            old_child,
+            width,
+            height - 1,
+            offset + idx * span,
+            entries,
+            true,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,701 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::adt::{amt_changed_indexes, hamt_changed_keys};
+use actor::{is_miner_actor, is_multisig_actor, market, miner, multisig, power};
+use address::Address;
+use blockstore::BlockStore;
+use cid::Cid;
+use clock::ChainEpoch;
+use fil_types::StateRoot;
+use num_bigint::bigint_ser;
+use serde::{Deserialize, Serialize};
+use state_tree::StateTree;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use vm::{ActorState, TokenAmount};
+
+/// Actors added, removed and modified between two state roots.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StateDiff {
+    pub added: Vec<ActorEntry>,
+    pub removed: Vec<ActorEntry>,
+    pub modified: Vec<ActorChange>,
+}
+
+/// Actor as it is stored in the state tree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorEntry {
+    #[serde(with = "address::json")]
+    pub address: Address,
+    #[serde(with = "cid::json")]
+    pub code: Cid,
+    #[serde(with = "cid::json")]
+    pub head: Cid,
+    pub nonce: u64,
+    #[serde(with = "bigint_ser::json")]
+    pub balance: TokenAmount,
+}
+
+impl ActorEntry {
+    pub fn new(address: Address, actor: &ActorState) -> Self {
+        Self {
+            address,
+            code: actor.code,
+            head: actor.state,
+            nonce: actor.sequence,
+            balance: actor.balance.clone(),
+        }
+    }
+}
+
+/// Actor present in both state trees whose code, head, nonce or balance changed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ActorChange {
+    pub before: ActorEntry,
+    pub after: ActorEntry,
+    /// Typed state changes, only set for known actor types whose state head changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<ActorStateChanges>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ActorStateChanges {
+    Miner(MinerChanges),
+    Market(MarketChanges),
+    Power(PowerChanges),
+    Multisig(MultisigChanges),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AddressChange {
+    #[serde(with = "address::json")]
+    pub before: Address,
+    #[serde(with = "address::json")]
+    pub after: Address,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MinerChanges {
+    pub owner: Option<AddressChange>,
+    pub worker: Option<AddressChange>,
+    pub sectors_added: Vec<SectorSummary>,
+    pub sectors_removed: Vec<SectorSummary>,
+    pub sectors_modified: Vec<SectorChange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SectorSummary {
+    pub sector_number: u64,
+    #[serde(with = "cid::json")]
+    pub sealed_cid: Cid,
+    pub deal_ids: Vec<u64>,
+    pub activation: ChainEpoch,
+    pub expiration: ChainEpoch,
+    #[serde(with = "bigint_ser::json")]
+    pub initial_pledge: TokenAmount,
+}
+
+impl From<miner::SectorOnChainInfo> for SectorSummary {
+    fn from(info: miner::SectorOnChainInfo) -> Self {
+        Self {
+            sector_number: info.sector_number,
+            sealed_cid: info.sealed_cid,
+            deal_ids: info.deal_ids,
+            activation: info.activation,
+            expiration: info.expiration,
+            initial_pledge: info.initial_pledge,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SectorChange {
+    pub before: SectorSummary,
+    pub after: SectorSummary,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MarketChanges {
+    pub deals_added: Vec<DealSummary>,
+    pub deals_removed: Vec<u64>,
+    pub deal_states_changed: Vec<DealStateChange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DealSummary {
+    #[serde(rename = "DealID")]
+    pub deal_id: u64,
+    #[serde(with = "address::json")]
+    pub client: Address,
+    #[serde(with = "address::json")]
+    pub provider: Address,
+    #[serde(with = "cid::json", rename = "PieceCID")]
+    pub piece_cid: Cid,
+    pub piece_size: u64,
+    pub verified_deal: bool,
+    pub start_epoch: ChainEpoch,
+    pub end_epoch: ChainEpoch,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DealStateFields {
+    pub sector_start_epoch: ChainEpoch,
+    pub last_updated_epoch: ChainEpoch,
+    pub slash_epoch: ChainEpoch,
+}
+
+impl From<market::DealState> for DealStateFields {
+    fn from(st: market::DealState) -> Self {
+        Self {
+            sector_start_epoch: st.sector_start_epoch,
+            last_updated_epoch: st.last_updated_epoch,
+            slash_epoch: st.slash_epoch,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DealStateChange {
+    #[serde(rename = "DealID")]
+    pub deal_id: u64,
+    pub before: Option<DealStateFields>,
+    pub after: Option<DealStateFields>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PowerChanges {
+    pub total_before: power::Claim,
+    pub total_after: power::Claim,
+    pub claims_changed: Vec<ClaimChange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClaimChange {
+    #[serde(with = "address::json")]
+    pub miner: Address,
+    pub before: Option<power::Claim>,
+    pub after: Option<power::Claim>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MultisigChanges {
+    #[serde(with = "address::json::vec")]
+    pub signers_added: Vec<Address>,
+    #[serde(with = "address::json::vec")]
+    pub signers_removed: Vec<Address>,
+    pub threshold: Option<ThresholdChange>,
+    pub transactions_added: Vec<i64>,
+    pub transactions_removed: Vec<i64>,
+    pub transactions_modified: Vec<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ThresholdChange {
+    pub before: usize,
+    pub after: usize,
+}
+
+/// Root of the actors hamt, versioned state roots wrap it with the state tree version.
+fn actors_root<BS: BlockStore>(bs: &BS, root: &Cid) -> Cid {
+    match bs.get::<StateRoot>(root) {
+        Ok(Some(StateRoot { actors, .. })) => actors,
+        _ => *root,
+    }
+}
+
+/// Diffs the actors hamts of both state roots and returns the actors added, removed and modified
+/// going from `old_root` to `new_root`. Subtrees that did not change are skipped, so only the
+/// changed actors are loaded. Miner, market, power and multisig actors whose state changed are
+/// additionally diffed field by field.
+pub fn diff_state_roots<BS: BlockStore>(
+    bs: &BS,
+    old_root: &Cid,
+    new_root: &Cid,
+) -> Result<StateDiff, Box<dyn StdError>> {
+    let mut diff = StateDiff::default();
+    if old_root == new_root {
+        return Ok(diff);
+    }
+
+    let old_tree = StateTree::new_from_root(bs, old_root)?;
+    let new_tree = StateTree::new_from_root(bs, new_root)?;
+    let keys = hamt_changed_keys(bs, &actors_root(bs, old_root), &actors_root(bs, new_root))?;
+    for key in keys {
+        let addr = Address::from_bytes(&key)?;
+        match (old_tree.get_actor(&addr)?, new_tree.get_actor(&addr)?) {
+            (None, Some(new)) => diff.added.push(ActorEntry::new(addr, &new)),
+            (Some(old), None) => diff.removed.push(ActorEntry::new(addr, &old)),
+            (Some(old), Some(new)) => {
+                let changes = if old.state != new.state {
+                    diff_actor_states(bs, &addr, &old, &new)?
+                } else {
+                    None
+                };
+                diff.modified.push(ActorChange {
+                    before: ActorEntry::new(addr, &old),
+                    after: ActorEntry::new(addr, &new),
+                    changes,
+                });
+            }
+            (None, None) => (),
+        }
+    }
+
+    // Order by address string to match the other commands, the hamt keys come out in byte order.
+    diff.added.sort_by_key(|e| e.address.to_string());
+    diff.removed.sort_by_key(|e| e.address.to_string());
+    diff.modified.sort_by_key(|c| c.after.address.to_string());
+
+    Ok(diff)
+}
+
+fn diff_actor_states<BS: BlockStore>(
+    bs: &BS,
+    addr: &Address,
+    old: &ActorState,
+    new: &ActorState,
+) -> Result<Option<ActorStateChanges>, Box<dyn StdError>> {
+    let market_addr: &Address = market::ADDRESS;
+    let power_addr: &Address = power::ADDRESS;
+
+    if is_miner_actor(&old.code) && is_miner_actor(&new.code) {
+        Ok(Some(ActorStateChanges::Miner(diff_miner(bs, old, new)?)))
+    } else if addr == market_addr {
+        Ok(Some(ActorStateChanges::Market(diff_market(bs, old, new)?)))
+    } else if addr == power_addr {
+        Ok(Some(ActorStateChanges::Power(diff_power(bs, old, new)?)))
+    } else if is_multisig_actor(&old.code) && is_multisig_actor(&new.code) {
+        Ok(Some(ActorStateChanges::Multisig(diff_multisig(
+            bs, old, new,
+        )?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn address_change(before: Address, after: Address) -> Option<AddressChange> {
+    if before != after {
+        Some(AddressChange { before, after })
+    } else {
+        None
+    }
+}
+
+fn diff_miner<BS: BlockStore>(
+    bs: &BS,
+    old: &ActorState,
+    new: &ActorState,
+) -> Result<MinerChanges, Box<dyn StdError>> {
+    let old_st = miner::State::load(bs, old)?;
+    let new_st = miner::State::load(bs, new)?;
+    let old_info = old_st.info(bs)?;
+    let new_info = new_st.info(bs)?;
+
+    let mut changes = MinerChanges {
+        owner: address_change(old_info.owner, new_info.owner),
+        worker: address_change(old_info.worker, new_info.worker),
+        ..Default::default()
+    };
+
+    // Only the sectors whose entries changed are loaded, in sector number order.
+    for num in amt_changed_indexes(bs, old_st.sectors_root(), new_st.sectors_root())? {
+        let before = old_st.get_sector(bs, num)?.map(SectorSummary::from);
+        let after = new_st.get_sector(bs, num)?.map(SectorSummary::from);
+        match (before, after) {
+            (None, Some(after)) => changes.sectors_added.push(after),
+            (Some(before), None) => changes.sectors_removed.push(before),
+            (Some(before), Some(after)) if before != after => changes
+                .sectors_modified
+                .push(SectorChange { before, after }),
+            _ => (),
+        }
+    }
+
+    Ok(changes)
+}
+
+fn diff_market<BS: BlockStore>(
+    bs: &BS,
+    old: &ActorState,
+    new: &ActorState,
+) -> Result<MarketChanges, Box<dyn StdError>> {
+    let old_st = market::State::load(bs, old)?;
+    let new_st = market::State::load(bs, new)?;
+    let mut changes = MarketChanges::default();
+
+    let old_proposals = old_st.proposals(bs)?;
+    let new_proposals = new_st.proposals(bs)?;
+    for id in amt_changed_indexes(bs, old_st.proposals_root(), new_st.proposals_root())? {
+        match (old_proposals.get(id)?, new_proposals.get(id)?) {
+            (None, Some(proposal)) => changes.deals_added.push(DealSummary {
+                deal_id: id,
+                client: proposal.client,
+                provider: proposal.provider,
+                piece_cid: proposal.piece_cid,
+                piece_size: proposal.piece_size.0,
+                verified_deal: proposal.verified_deal,
+                start_epoch: proposal.start_epoch,
+                end_epoch: proposal.end_epoch,
+            }),
+            (Some(_), None) => changes.deals_removed.push(id),
+            _ => (),
+        }
+    }
+
+    let old_states = old_st.states(bs)?;
+    let new_states = new_st.states(bs)?;
+    for id in amt_changed_indexes(bs, old_st.states_root(), new_st.states_root())? {
+        let before = old_states.get(id)?.map(DealStateFields::from);
+        let after = new_states.get(id)?.map(DealStateFields::from);
+        if before != after {
+            changes.deal_states_changed.push(DealStateChange {
+                deal_id: id,
+                before,
+                after,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+fn claims_eq(a: &Option<power::Claim>, b: &Option<power::Claim>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.raw_byte_power == b.raw_byte_power && a.quality_adj_power == b.quality_adj_power
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn diff_power<BS: BlockStore>(
+    bs: &BS,
+    old: &ActorState,
+    new: &ActorState,
+) -> Result<PowerChanges, Box<dyn StdError>> {
+    let old_st = power::State::load(bs, old)?;
+    let new_st = power::State::load(bs, new)?;
+
+    // Only the claims whose entries changed are loaded.
+    let mut claims_changed = Vec::new();
+    for key in hamt_changed_keys(bs, old_st.claims_root(), new_st.claims_root())? {
+        let miner = Address::from_bytes(&key)?;
+        let before = old_st.miner_power(bs, &miner)?;
+        let after = new_st.miner_power(bs, &miner)?;
+        if !claims_eq(&before, &after) {
+            claims_changed.push(ClaimChange {
+                miner,
+                before,
+                after,
+            });
+        }
+    }
+
+    Ok(PowerChanges {
+        total_before: old_st.total_power(),
+        total_after: new_st.total_power(),
+        claims_changed,
+    })
+}
+
+fn diff_multisig<BS: BlockStore>(
+    bs: &BS,
+    old: &ActorState,
+    new: &ActorState,
+) -> Result<MultisigChanges, Box<dyn StdError>> {
+    let old_st = multisig::State::load(bs, old)?;
+    let new_st = multisig::State::load(bs, new)?;
+
+    let mut changes = MultisigChanges {
+        signers_added: new_st
+            .signers()
+            .iter()
+            .filter(|s| !old_st.signers().contains(s))
+            .copied()
+            .collect(),
+        signers_removed: old_st
+            .signers()
+            .iter()
+            .filter(|s| !new_st.signers().contains(s))
+            .copied()
+            .collect(),
+        ..Default::default()
+    };
+    let (before, after) = (
+        old_st.num_approvals_threshold(),
+        new_st.num_approvals_threshold(),
+    );
+    if before != after {
+        changes.threshold = Some(ThresholdChange { before, after });
+    }
+
+    let mut old_txns: HashMap<i64, multisig::Transaction> = old_st
+        .pending_transactions(bs)?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+    for txn in new_st.pending_transactions(bs)? {
+        match old_txns.remove(&txn.id) {
+            None => changes.transactions_added.push(txn.id),
+            Some(old) if old != txn => changes.transactions_modified.push(txn.id),
+            Some(_) => (),
+        }
+    }
+    changes.transactions_removed = old_txns.into_iter().map(|(id, _)| id).collect();
+
+    changes.transactions_added.sort_unstable();
+    changes.transactions_removed.sort_unstable();
+    changes.transactions_modified.sort_unstable();
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actor::actorv5;
+    use cid::Code::Blake2b256;
+    use db::MemoryDB;
+    use fil_types::{
+        PaddedPieceSize, RegisteredPoStProof, RegisteredSealProof, StateTreeVersion, HAMT_BIT_WIDTH,
+    };
+    use serde::Serialize;
+
+    fn actor(seq: u64) -> ActorState {
+        ActorState::new(
+            cid::new_from_cbor(&[1], Blake2b256),
+            cid::new_from_cbor(&[2], Blake2b256),
+            seq.into(),
+            seq,
+        )
+    }
+
+    #[test]
+    fn diff_added_removed_modified() {
+        let db = MemoryDB::default();
+        let mut tree = StateTree::new(&db, StateTreeVersion::V0).unwrap();
+        tree.set_actor(&Address::new_id(100), actor(1)).unwrap();
+        tree.set_actor(&Address::new_id(101), actor(1)).unwrap();
+        let old_root = tree.flush().unwrap();
+
+        tree.delete_actor(&Address::new_id(100)).unwrap();
+        tree.set_actor(&Address::new_id(101), actor(2)).unwrap();
+        tree.set_actor(&Address::new_id(102), actor(1)).unwrap();
+        let new_root = tree.flush().unwrap();
+
+        let diff = diff_state_roots(&db, &old_root, &new_root).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].address, Address::new_id(102));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].address, Address::new_id(100));
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].before.nonce, 1);
+        assert_eq!(diff.modified[0].after.nonce, 2);
+        assert!(diff.modified[0].changes.is_none());
+
+        let same = diff_state_roots(&db, &new_root, &new_root).unwrap();
+        assert!(same.added.is_empty() && same.removed.is_empty() && same.modified.is_empty());
+    }
+
+    /// Stores `state` and returns the root of a state tree holding it under `addr`.
+    fn tree_with_state<S: Serialize>(db: &MemoryDB, addr: &Address, code: &Cid, state: &S) -> Cid {
+        let head = db.put(state, Blake2b256).unwrap();
+        let mut tree = StateTree::new(db, StateTreeVersion::V3).unwrap();
+        tree.set_actor(addr, ActorState::new(*code, head, 0.into(), 0))
+            .unwrap();
+        tree.flush().unwrap()
+    }
+
+    fn sector(sector_number: u64, expiration: ChainEpoch) -> actorv5::miner::SectorOnChainInfo {
+        actorv5::miner::SectorOnChainInfo {
+            sector_number,
+            seal_proof: RegisteredSealProof::StackedDRG2KiBV1P1,
+            sealed_cid: cid::new_from_cbor(&[sector_number as u8], Blake2b256),
+            deal_ids: vec![],
+            activation: 1,
+            expiration,
+            deal_weight: Default::default(),
+            verified_deal_weight: Default::default(),
+            initial_pledge: Default::default(),
+            expected_day_reward: Default::default(),
+            expected_storage_pledge: Default::default(),
+            replaced_sector_age: 0,
+            replaced_day_reward: Default::default(),
+        }
+    }
+
+    #[test]
+    fn diff_miner_sectors() {
+        let db = MemoryDB::default();
+        let addr = Address::new_id(1000);
+        let code = &*actorv5::MINER_ACTOR_CODE_ID;
+        let info = actorv5::miner::MinerInfo::new(
+            Address::new_id(100),
+            Address::new_id(101),
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow2KiBV1,
+        )
+        .unwrap();
+        let info = db.put(&info, Blake2b256).unwrap();
+
+        let mut st = actorv5::miner::State::new(&db, info, 0, 0).unwrap();
+        st.put_sectors(&db, vec![sector(1, 100), sector(2, 100)])
+            .unwrap();
+        let old_root = tree_with_state(&db, &addr, code, &st);
+
+        let mut st = actorv5::miner::State::new(&db, info, 0, 0).unwrap();
+        st.put_sectors(&db, vec![sector(2, 200), sector(3, 100)])
+            .unwrap();
+        let new_root = tree_with_state(&db, &addr, code, &st);
+
+        let diff = diff_state_roots(&db, &old_root, &new_root).unwrap();
+        assert_eq!(diff.modified.len(), 1);
+        let changes = match &diff.modified[0].changes {
+            Some(ActorStateChanges::Miner(changes)) => changes,
+            other => panic!("expected miner changes, got {:?}", other),
+        };
+        assert!(changes.owner.is_none() && changes.worker.is_none());
+        assert_eq!(
+            changes.sectors_added,
+            vec![SectorSummary::from(miner::SectorOnChainInfo::from(sector(
+                3, 100
+            )))]
+        );
+        assert_eq!(changes.sectors_removed.len(), 1);
+        assert_eq!(changes.sectors_removed[0].sector_number, 1);
+        assert_eq!(changes.sectors_modified.len(), 1);
+        assert_eq!(changes.sectors_modified[0].before.expiration, 100);
+        assert_eq!(changes.sectors_modified[0].after.expiration, 200);
+    }
+
+    fn deal(client: u64) -> actorv5::market::DealProposal {
+        actorv5::market::DealProposal {
+            piece_cid: cid::new_from_cbor(&[client as u8], Blake2b256),
+            piece_size: PaddedPieceSize(2048),
+            verified_deal: false,
+            client: Address::new_id(client),
+            provider: Address::new_id(1000),
+            label: String::new(),
+            start_epoch: 10,
+            end_epoch: 20,
+            storage_price_per_epoch: Default::default(),
+            provider_collateral: Default::default(),
+            client_collateral: Default::default(),
+        }
+    }
+
+    fn deal_state(sector_start_epoch: ChainEpoch) -> actorv5::market::DealState {
+        actorv5::market::DealState {
+            sector_start_epoch,
+            last_updated_epoch: -1,
+            slash_epoch: -1,
+        }
+    }
+
+    #[test]
+    fn diff_market_deals() {
+        let db = MemoryDB::default();
+        let addr = &*actorv5::STORAGE_MARKET_ACTOR_ADDR;
+        let code = &*actorv5::MARKET_ACTOR_CODE_ID;
+
+        let mut st = actorv5::market::State::new(&db).unwrap();
+        let mut proposals = actorv5::market::DealArray::load(&st.proposals, &db).unwrap();
+        proposals.set(0, deal(100)).unwrap();
+        proposals.set(1, deal(101)).unwrap();
+        st.proposals = proposals.flush().unwrap();
+        let mut states = actorv5::market::DealMetaArray::load(&st.states, &db).unwrap();
+        states.set(0, deal_state(-1)).unwrap();
+        st.states = states.flush().unwrap();
+        let old_root = tree_with_state(&db, addr, code, &st);
+
+        // Deal 1000 grows the proposals array by a level.
+        let mut proposals = actorv5::market::DealArray::load(&st.proposals, &db).unwrap();
+        proposals.delete(0).unwrap();
+        proposals.set(1000, deal(102)).unwrap();
+        st.proposals = proposals.flush().unwrap();
+        let mut states = actorv5::market::DealMetaArray::load(&st.states, &db).unwrap();
+        states.delete(0).unwrap();
+        states.set(1, deal_state(15)).unwrap();
+        st.states = states.flush().unwrap();
+        let new_root = tree_with_state(&db, addr, code, &st);
+
+        let diff = diff_state_roots(&db, &old_root, &new_root).unwrap();
+        assert_eq!(diff.modified.len(), 1);
+        let changes = match &diff.modified[0].changes {
+            Some(ActorStateChanges::Market(changes)) => changes,
+            other => panic!("expected market changes, got {:?}", other),
+        };
+        assert_eq!(changes.deals_added.len(), 1);
+        assert_eq!(changes.deals_added[0].deal_id, 1000);
+        assert_eq!(changes.deals_added[0].client, Address::new_id(102));
+        assert_eq!(changes.deals_removed, vec![0]);
+        assert_eq!(changes.deal_states_changed.len(), 2);
+        assert_eq!(changes.deal_states_changed[0].deal_id, 0);
+        assert!(changes.deal_states_changed[0].after.is_none());
+        assert_eq!(changes.deal_states_changed[1].deal_id, 1);
+        assert!(changes.deal_states_changed[1].before.is_none());
+        assert_eq!(
+            changes.deal_states_changed[1]
+                .after
+                .as_ref()
+                .map(|s| s.sector_start_epoch),
+            Some(15)
+        );
+    }
+
+    fn multisig(db: &MemoryDB, signers: Vec<Address>) -> actorv5::multisig::State {
+        actorv5::multisig::State {
+            signers,
+            num_approvals_threshold: 1,
+            next_tx_id: actorv5::multisig::TxnID(0),
+            initial_balance: Default::default(),
+            start_epoch: 0,
+            unlock_duration: 0,
+            pending_txs: actorv5::make_empty_map::<_, ()>(db, HAMT_BIT_WIDTH)
+                .flush()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn diff_multisig_signers() {
+        let db = MemoryDB::default();
+        let addr = Address::new_id(1000);
+        let code = &*actorv5::MULTISIG_ACTOR_CODE_ID;
+
+        let st = multisig(&db, vec![Address::new_id(100), Address::new_id(101)]);
+        let old_root = tree_with_state(&db, &addr, code, &st);
+        let st = multisig(&db, vec![Address::new_id(101), Address::new_id(102)]);
+        let new_root = tree_with_state(&db, &addr, code, &st);
+
+        let diff = diff_state_roots(&db, &old_root, &new_root).unwrap();
+        assert_eq!(diff.modified.len(), 1);
+        let changes = match &diff.modified[0].changes {
+            Some(ActorStateChanges::Multisig(changes)) => changes,
+            other => panic!("expected multisig changes, got {:?}", other),
+        };
+        assert_eq!(changes.signers_added, vec![Address::new_id(102)]);
+        assert_eq!(changes.signers_removed, vec![Address::new_id(100)]);
+        assert!(changes.threshold.is_none());
+        assert!(changes.transactions_added.is_empty());
+    }
+}
@@ -1093,7 +1093,7 @@ where
     ))
 }
 
-fn gen_rand_next_epoch(start_epoch: ChainEpoch, deal_id: DealID) -> ChainEpoch {
+pub fn gen_rand_next_epoch(start_epoch: ChainEpoch, deal_id: DealID) -> ChainEpoch {
     let offset = deal_id as i64 % DEAL_UPDATES_INTERVAL;
     let q = QuantSpec {
         unit: DEAL_UPDATES_INTERVAL,
@@ -780,7 +780,7 @@ impl Actor {
         })?;
 
         for (i, precommit) in precommits.iter().enumerate() {
-            let interactive_epoch = precommit.pre_commit_epoch + PRE_COMMIT_CHALLENGE_DELAY;
+            let interactive_epoch = precommit.pre_commit_epoch + pre_commit_challenge_delay();
             if rt.curr_epoch() <= interactive_epoch {
                 return Err(actor_error!(
                     ErrForbidden,
@@ -1435,7 +1435,7 @@ impl Actor {
             rt,
             SealVerifyParams {
                 sealed_cid: precommit.info.sealed_cid,
-                interactive_epoch: precommit.pre_commit_epoch + PRE_COMMIT_CHALLENGE_DELAY,
+                interactive_epoch: precommit.pre_commit_epoch + pre_commit_challenge_delay(),
                 seal_rand_epoch: precommit.info.seal_rand_epoch,
                 proof: params.proof,
                 deal_ids: precommit.info.deal_ids.clone(),
@@ -102,11 +102,8 @@ pub const SEALED_CID_PREFIX: cid::Prefix = cid::Prefix {
 pub fn can_pre_commit_seal_proof(proof: RegisteredSealProof) -> bool {
     use RegisteredSealProof::*;
 
-    #[cfg(feature = "devnet")]
-    {
-        if matches!(proof, StackedDRG2KiBV1 | StackedDRG2KiBV1P1) {
-            return true;
-        }
+    if matches!(proof, StackedDRG2KiBV1 | StackedDRG2KiBV1P1) {
+        return network_policy().allow_2kib_sectors;
     }
 
     matches!(proof, StackedDRG32GiBV1P1 | StackedDRG64GiBV1P1)
@@ -128,9 +125,9 @@ pub fn max_prove_commit_duration(proof: RegisteredSealProof) -> Option<ChainEpoc
     use RegisteredSealProof::*;
     match proof {
         StackedDRG32GiBV1 | StackedDRG2KiBV1 | StackedDRG8MiBV1 | StackedDRG512MiBV1
-        | StackedDRG64GiBV1 => Some(EPOCHS_IN_DAY + PRE_COMMIT_CHALLENGE_DELAY),
+        | StackedDRG64GiBV1 => Some(EPOCHS_IN_DAY + pre_commit_challenge_delay()),
         StackedDRG32GiBV1P1 | StackedDRG64GiBV1P1 | StackedDRG512MiBV1P1 | StackedDRG8MiBV1P1
-        | StackedDRG2KiBV1P1 => Some(30 * EPOCHS_IN_DAY + PRE_COMMIT_CHALLENGE_DELAY),
+        | StackedDRG2KiBV1P1 => Some(30 * EPOCHS_IN_DAY + pre_commit_challenge_delay()),
         _ => None,
     }
 }
@@ -164,10 +161,9 @@ pub const MAX_PRE_COMMIT_RANDOMNESS_LOOKBACK: ChainEpoch = EPOCHS_IN_DAY + CHAIN
 
 /// Number of epochs between publishing the precommit and when the challenge for interactive PoRep is drawn
 /// used to ensure it is not predictable by miner.
-#[cfg(not(feature = "devnet"))]
-pub const PRE_COMMIT_CHALLENGE_DELAY: ChainEpoch = 150;
-#[cfg(feature = "devnet")]
-pub const PRE_COMMIT_CHALLENGE_DELAY: ChainEpoch = 10;
+pub fn pre_commit_challenge_delay() -> ChainEpoch {
+    network_policy().pre_commit_challenge_delay
+}
 
 /// Lookback from the deadline's challenge window opening from which to sample chain randomness for the challenge seed.
 
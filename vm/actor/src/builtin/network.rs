@@ -1,9 +1,12 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use clock::ChainEpoch;
 pub use clock::EPOCH_DURATION_SECONDS;
 pub use fil_types::BLOCKS_PER_EPOCH as EXPECTED_LEADERS_PER_EPOCH;
 use num_bigint::BigInt;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
 
 pub const SECONDS_IN_HOUR: i64 = 3600;
 pub const SECONDS_IN_DAY: i64 = 86400;
@@ -25,3 +28,59 @@ lazy_static! {
     /// Quality multiplier for verified deals in a sector
     pub static ref VERIFIED_DEAL_WEIGHT_MULTIPLIER: BigInt = BigInt::from(100);
 }
+
+/// Policy of the network the node runs, set once at startup. Mainnet's policy applies if none
+/// is set.
+pub static NETWORK_POLICY: OnceCell<NetworkPolicy> = OnceCell::new();
+
+/// Returns the policy of the network the node runs.
+pub fn network_policy() -> &'static NetworkPolicy {
+    NETWORK_POLICY.get_or_init(NetworkPolicy::default)
+}
+
+/// Actor policy values which differ between networks, small test networks accepting smaller
+/// miners and sectors than mainnet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NetworkPolicy {
+    /// Minimum raw power, in bytes, of a miner to be eligible for consensus.
+    pub consensus_miner_min_power: u64,
+    /// Minimum size, in bytes, of verified deals and data cap allowances.
+    pub minimum_verified_deal_size: u64,
+    /// Number of epochs between publishing the precommit and when the challenge for interactive
+    /// PoRep is drawn.
+    pub pre_commit_challenge_delay: ChainEpoch,
+    /// Whether 2KiB sectors can be pre-committed.
+    pub allow_2kib_sectors: bool,
+}
+
+impl NetworkPolicy {
+    pub const MAINNET: NetworkPolicy = NetworkPolicy {
+        consensus_miner_min_power: 10 << 40,
+        minimum_verified_deal_size: 1 << 20,
+        pre_commit_challenge_delay: 150,
+        allow_2kib_sectors: false,
+    };
+
+    pub const CALIBNET: NetworkPolicy = NetworkPolicy {
+        consensus_miner_min_power: 32 << 30,
+        ..NetworkPolicy::MAINNET
+    };
+
+    pub const INTEROPNET: NetworkPolicy = NetworkPolicy {
+        consensus_miner_min_power: 2 << 30,
+        ..NetworkPolicy::MAINNET
+    };
+
+    pub const DEVNET: NetworkPolicy = NetworkPolicy {
+        consensus_miner_min_power: 2048,
+        minimum_verified_deal_size: 256,
+        pre_commit_challenge_delay: 10,
+        allow_2kib_sectors: true,
+    };
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use super::network_policy;
 use fil_types::{RegisteredPoStProof, StoragePower};
 
 /// Returns the minimum storage power required for each seal proof types.
@@ -16,16 +17,9 @@ pub fn consensus_miner_min_power(p: RegisteredPoStProof) -> Result<StoragePower,
         | StackedDRGWindow8MiBV1
         | StackedDRGWindow512MiBV1
         | StackedDRGWindow32GiBV1
-        | StackedDRGWindow64GiBV1 => {
-            if cfg!(feature = "devnet") {
-                return Ok(StoragePower::from(2048));
-            }
-            if cfg!(feature = "interopnet") {
-                return Ok(StoragePower::from(2 << 30));
-            }
-
-            Ok(StoragePower::from(10u64 << 40))
-        }
+        | StackedDRGWindow64GiBV1 => Ok(StoragePower::from(
+            network_policy().consensus_miner_min_power,
+        )),
         Invalid(i) => Err(format!("unsupported proof type: {}", i)),
     }
 }
@@ -61,7 +61,7 @@ impl Actor {
         BS: BlockStore,
         RT: Runtime<BS>,
     {
-        if &params.allowance < &MINIMUM_VERIFIED_DEAL_SIZE {
+        if params.allowance < minimum_verified_deal_size() {
             return Err(actor_error!(
                 ErrIllegalArgument,
                 "Allowance {} below minimum deal size for add verifier {}",
@@ -192,7 +192,7 @@ impl Actor {
         // The caller will be verified by checking table below
         rt.validate_immediate_caller_accept_any()?;
 
-        if params.allowance < *MINIMUM_VERIFIED_DEAL_SIZE {
+        if params.allowance < minimum_verified_deal_size() {
             return Err(actor_error!(
                 ErrIllegalArgument,
                 "Allowance {} below MinVerifiedDealSize for add verified client {}",
@@ -336,7 +336,7 @@ impl Actor {
             )
         })?;
 
-        if params.deal_size < *MINIMUM_VERIFIED_DEAL_SIZE {
+        if params.deal_size < minimum_verified_deal_size() {
             return Err(actor_error!(
                 ErrIllegalArgument,
                 "Verified Dealsize {} is below minimum in usedbytes",
@@ -380,7 +380,7 @@ impl Actor {
             };
 
             let new_vc_cap = vc_cap - &params.deal_size;
-            if new_vc_cap < *MINIMUM_VERIFIED_DEAL_SIZE {
+            if new_vc_cap < minimum_verified_deal_size() {
                 // Delete entry if remaining DataCap is less than MinVerifiedDealSize.
                 // Will be restored later if the deal did not get activated with a ProvenSector.
                 verified_clients
@@ -429,7 +429,7 @@ impl Actor {
         RT: Runtime<BS>,
     {
         rt.validate_immediate_caller_is(std::iter::once(&*STORAGE_MARKET_ACTOR_ADDR))?;
-        if params.deal_size < *MINIMUM_VERIFIED_DEAL_SIZE {
+        if params.deal_size < minimum_verified_deal_size() {
             return Err(actor_error!(
                 ErrIllegalArgument,
                 "Below minimum VerifiedDealSize requested in RestoreBytes: {}",
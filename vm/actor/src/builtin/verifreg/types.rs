@@ -1,20 +1,15 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::network_policy;
 use address::Address;
 use encoding::tuple::*;
 use fil_types::StoragePower;
 use num_bigint::bigint_ser;
-use num_traits::FromPrimitive;
 
-#[cfg(not(feature = "devnet"))]
-lazy_static! {
-    pub static ref MINIMUM_VERIFIED_DEAL_SIZE: StoragePower = StoragePower::from_i32(1 << 20).unwrap(); // placeholder
-}
-
-#[cfg(feature = "devnet")]
-lazy_static! {
-    pub static ref MINIMUM_VERIFIED_DEAL_SIZE: StoragePower = StoragePower::from_i32(256).unwrap(); // placeholder
+/// Minimum size of verified deals and data cap allowances.
+pub fn minimum_verified_deal_size() -> StoragePower {
+    StoragePower::from(network_policy().minimum_verified_deal_size)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
@@ -178,6 +178,28 @@ impl State {
         }
     }
 
+    /// Root of the deal proposals array, indexed by deal id.
+    pub fn proposals_root(&self) -> &Cid {
+        match self {
+            State::V0(st) => &st.proposals,
+            State::V2(st) => &st.proposals,
+            State::V3(st) => &st.proposals,
+            State::V4(st) => &st.proposals,
+            State::V5(st) => &st.proposals,
+        }
+    }
+
+    /// Root of the deal states array, indexed by deal id.
+    pub fn states_root(&self) -> &Cid {
+        match self {
+            State::V0(st) => &st.states,
+            State::V2(st) => &st.states,
+            State::V3(st) => &st.states,
+            State::V4(st) => &st.states,
+            State::V5(st) => &st.states,
+        }
+    }
+
     /// Consume state to return just total funds locked
     pub fn total_locked(&self) -> TokenAmount {
         match self {
@@ -293,6 +315,19 @@ impl<BS> DealProposals<'_, BS> {
             }
         }
     }
+
+    pub fn get(&self, key: u64) -> Result<Option<DealProposal>, Box<dyn Error>>
+    where
+        BS: BlockStore,
+    {
+        match self {
+            DealProposals::V0(dp) => Ok(dp.get(key)?.cloned().map(From::from)),
+            DealProposals::V2(dp) => Ok(dp.get(key)?.cloned().map(From::from)),
+            DealProposals::V3(dp) => Ok(dp.get(key as usize)?.cloned().map(From::from)),
+            DealProposals::V4(dp) => Ok(dp.get(key as usize)?.cloned().map(From::from)),
+            DealProposals::V5(dp) => Ok(dp.get(key as usize)?.cloned().map(From::from)),
+        }
+    }
 }
 
 #[derive(Serialize)]
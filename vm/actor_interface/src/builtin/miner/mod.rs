@@ -400,6 +400,17 @@ impl State {
         }
     }
 
+    /// Root of the sectors array, indexed by sector number.
+    pub fn sectors_root(&self) -> &Cid {
+        match self {
+            State::V0(st) => &st.sectors,
+            State::V2(st) => &st.sectors,
+            State::V3(st) => &st.sectors,
+            State::V4(st) => &st.sectors,
+            State::V5(st) => &st.sectors,
+        }
+    }
+
     /// Number of post period deadlines.
     pub fn num_deadlines(&self) -> u64 {
         match self {
@@ -7,6 +7,7 @@ pub mod init;
 pub mod market;
 pub mod miner;
 pub mod multisig;
+pub mod paych;
 pub mod power;
 pub mod reward;
 pub mod system;
@@ -59,6 +60,15 @@ pub fn is_miner_actor(code: &Cid) -> bool {
         || code == &*actorv5::MINER_ACTOR_CODE_ID
 }
 
+/// Returns true if the code belongs to a multisig actor.
+pub fn is_multisig_actor(code: &Cid) -> bool {
+    code == &*actorv0::MULTISIG_ACTOR_CODE_ID
+        || code == &*actorv2::MULTISIG_ACTOR_CODE_ID
+        || code == &*actorv3::MULTISIG_ACTOR_CODE_ID
+        || code == &*actorv4::MULTISIG_ACTOR_CODE_ID
+        || code == &*actorv5::MULTISIG_ACTOR_CODE_ID
+}
+
 // pub fn actor_name_by_code(code: &Cid) -> String {
 //     if actorv0::is_builtin_actor(code) {
 //     } else if actorv2::is_builtin_actor(code) {
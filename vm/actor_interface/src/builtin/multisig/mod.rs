@@ -1,14 +1,35 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::ActorVersion;
+use address::Address;
+use clock::ChainEpoch;
+use integer_encoding::VarInt;
 use ipld_blockstore::BlockStore;
 use serde::Serialize;
 use std::error::Error;
-use vm::ActorState;
+use vm::{ActorState, MethodNum, Serialized, TokenAmount};
 
 /// Multisig actor method.
 pub type Method = actorv3::multisig::Method;
 
+/// Multisig constructor parameters.
+pub type ConstructorParams = actorv5::multisig::ConstructorParams;
+/// Multisig propose parameters.
+pub type ProposeParams = actorv5::multisig::ProposeParams;
+/// Multisig approve and cancel parameters.
+pub type TxnIDParams = actorv5::multisig::TxnIDParams;
+/// Multisig add signer parameters.
+pub type AddSignerParams = actorv5::multisig::AddSignerParams;
+/// Multisig remove signer parameters.
+pub type RemoveSignerParams = actorv5::multisig::RemoveSignerParams;
+/// Multisig swap signer parameters.
+pub type SwapSignerParams = actorv5::multisig::SwapSignerParams;
+/// Data hashed to reference a pending proposal.
+pub type ProposalHashData<'a> = actorv5::multisig::ProposalHashData<'a>;
+/// Multisig transaction id.
+pub type TxnID = actorv5::multisig::TxnID;
+
 /// Multisig actor state.
 #[derive(Serialize)]
 #[serde(untagged)]
@@ -54,4 +75,229 @@ impl State {
             Err(format!("Unknown actor code {}", actor.code).into())
         }
     }
+
+    /// Returns the signers of the multisig.
+    pub fn signers(&self) -> &[Address] {
+        match self {
+            State::V0(st) => &st.signers,
+            State::V2(st) => &st.signers,
+            State::V3(st) => &st.signers,
+            State::V4(st) => &st.signers,
+            State::V5(st) => &st.signers,
+        }
+    }
+
+    /// Returns the number of approvals required to execute a transaction.
+    pub fn num_approvals_threshold(&self) -> usize {
+        match self {
+            State::V0(st) => st.num_approvals_threshold,
+            State::V2(st) => st.num_approvals_threshold,
+            State::V3(st) => st.num_approvals_threshold,
+            State::V4(st) => st.num_approvals_threshold,
+            State::V5(st) => st.num_approvals_threshold,
+        }
+    }
+
+    /// Returns the balance locked at the start of vesting.
+    pub fn initial_balance(&self) -> TokenAmount {
+        match self {
+            State::V0(st) => st.initial_balance.clone(),
+            State::V2(st) => st.initial_balance.clone(),
+            State::V3(st) => st.initial_balance.clone(),
+            State::V4(st) => st.initial_balance.clone(),
+            State::V5(st) => st.initial_balance.clone(),
+        }
+    }
+
+    /// Returns the epoch vesting starts at.
+    pub fn start_epoch(&self) -> ChainEpoch {
+        match self {
+            State::V0(st) => st.start_epoch,
+            State::V2(st) => st.start_epoch,
+            State::V3(st) => st.start_epoch,
+            State::V4(st) => st.start_epoch,
+            State::V5(st) => st.start_epoch,
+        }
+    }
+
+    /// Returns the number of epochs over which the initial balance vests.
+    pub fn unlock_duration(&self) -> ChainEpoch {
+        match self {
+            State::V0(st) => st.unlock_duration,
+            State::V2(st) => st.unlock_duration,
+            State::V3(st) => st.unlock_duration,
+            State::V4(st) => st.unlock_duration,
+            State::V5(st) => st.unlock_duration,
+        }
+    }
+
+    /// Returns the amount still locked at the given epoch.
+    pub fn locked_balance(&self, height: ChainEpoch) -> TokenAmount {
+        let elapsed = height - self.start_epoch();
+        match self {
+            State::V0(st) => st.amount_locked(elapsed),
+            State::V2(st) => st.amount_locked(elapsed),
+            State::V3(st) => st.amount_locked(elapsed),
+            State::V4(st) => st.amount_locked(elapsed),
+            State::V5(st) => st.amount_locked(elapsed),
+        }
+    }
+
+    /// Loads all pending transactions of the multisig.
+    pub fn pending_transactions<BS: BlockStore>(
+        &self,
+        store: &BS,
+    ) -> Result<Vec<Transaction>, Box<dyn Error>> {
+        let mut txns = Vec::new();
+        match self {
+            State::V0(st) => {
+                let map = actorv0::make_map_with_root(&st.pending_txs, store)?;
+                map.for_each(|k, tx: &actorv0::multisig::Transaction| {
+                    txns.push(Transaction {
+                        id: parse_txn_key(&k.0)?,
+                        to: tx.to,
+                        value: tx.value.clone(),
+                        method: tx.method,
+                        params: tx.params.clone(),
+                        approved: tx.approved.clone(),
+                    });
+                    Ok(())
+                })?;
+            }
+            State::V2(st) => {
+                let map = actorv2::make_map_with_root(&st.pending_txs, store)?;
+                map.for_each(|k, tx: &actorv2::multisig::Transaction| {
+                    txns.push(Transaction {
+                        id: parse_txn_key(&k.0)?,
+                        to: tx.to,
+                        value: tx.value.clone(),
+                        method: tx.method,
+                        params: tx.params.clone(),
+                        approved: tx.approved.clone(),
+                    });
+                    Ok(())
+                })?;
+            }
+            State::V3(st) => {
+                let map = actorv3::make_map_with_root(&st.pending_txs, store)?;
+                map.for_each(|k, tx: &actorv3::multisig::Transaction| {
+                    txns.push(Transaction {
+                        id: parse_txn_key(&k.0)?,
+                        to: tx.to,
+                        value: tx.value.clone(),
+                        method: tx.method,
+                        params: tx.params.clone(),
+                        approved: tx.approved.clone(),
+                    });
+                    Ok(())
+                })?;
+            }
+            State::V4(st) => {
+                let map = actorv4::make_map_with_root(&st.pending_txs, store)?;
+                map.for_each(|k, tx: &actorv4::multisig::Transaction| {
+                    txns.push(Transaction {
+                        id: parse_txn_key(&k.0)?,
+                        to: tx.to,
+                        value: tx.value.clone(),
+                        method: tx.method,
+                        params: tx.params.clone(),
+                        approved: tx.approved.clone(),
+                    });
+                    Ok(())
+                })?;
+            }
+            State::V5(st) => {
+                let map = actorv5::make_map_with_root(&st.pending_txs, store)?;
+                map.for_each(|k, tx: &actorv5::multisig::Transaction| {
+                    txns.push(Transaction {
+                        id: parse_txn_key(&k.0)?,
+                        to: tx.to,
+                        value: tx.value.clone(),
+                        method: tx.method,
+                        params: tx.params.clone(),
+                        approved: tx.approved.clone(),
+                    });
+                    Ok(())
+                })?;
+            }
+        }
+        txns.sort_by_key(|tx| tx.id);
+        Ok(txns)
+    }
+}
+
+/// Returns the serialized init actor `Exec` parameters which construct a multisig actor of the
+/// given actors version.
+pub fn exec_params(
+    version: ActorVersion,
+    signers: Vec<Address>,
+    num_approvals_threshold: usize,
+    unlock_duration: ChainEpoch,
+    start_epoch: ChainEpoch,
+) -> Result<Serialized, Box<dyn Error>> {
+    let params = match version {
+        ActorVersion::V0 => Serialized::serialize(actorv0::init::ExecParams {
+            code_cid: *actorv0::MULTISIG_ACTOR_CODE_ID,
+            constructor_params: Serialized::serialize(actorv0::multisig::ConstructorParams {
+                signers,
+                num_approvals_threshold,
+                unlock_duration,
+            })?,
+        })?,
+        ActorVersion::V2 => Serialized::serialize(actorv2::init::ExecParams {
+            code_cid: *actorv2::MULTISIG_ACTOR_CODE_ID,
+            constructor_params: Serialized::serialize(actorv2::multisig::ConstructorParams {
+                signers,
+                num_approvals_threshold,
+                unlock_duration,
+                start_epoch,
+            })?,
+        })?,
+        ActorVersion::V3 => Serialized::serialize(actorv3::init::ExecParams {
+            code_cid: *actorv3::MULTISIG_ACTOR_CODE_ID,
+            constructor_params: Serialized::serialize(actorv3::multisig::ConstructorParams {
+                signers,
+                num_approvals_threshold,
+                unlock_duration,
+                start_epoch,
+            })?,
+        })?,
+        ActorVersion::V4 => Serialized::serialize(actorv4::init::ExecParams {
+            code_cid: *actorv4::MULTISIG_ACTOR_CODE_ID,
+            constructor_params: Serialized::serialize(actorv4::multisig::ConstructorParams {
+                signers,
+                num_approvals_threshold,
+                unlock_duration,
+                start_epoch,
+            })?,
+        })?,
+        ActorVersion::V5 => Serialized::serialize(actorv5::init::ExecParams {
+            code_cid: *actorv5::MULTISIG_ACTOR_CODE_ID,
+            constructor_params: Serialized::serialize(ConstructorParams {
+                signers,
+                num_approvals_threshold,
+                unlock_duration,
+                start_epoch,
+            })?,
+        })?,
+    };
+    Ok(params)
+}
+
+/// Pending multisig transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub id: i64,
+    pub to: Address,
+    pub value: TokenAmount,
+    pub method: MethodNum,
+    pub params: Serialized,
+    pub approved: Vec<Address>,
+}
+
+/// Transaction ids are keyed in the pending transactions map as signed varints.
+fn parse_txn_key(key: &[u8]) -> Result<i64, Box<dyn Error>> {
+    i64::decode_var(key)
+        .map(|(id, _)| id)
+        .ok_or_else(|| "Invalid transaction id key".into())
 }
@@ -0,0 +1,157 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::{ActorVersion, Array};
+use address::Address;
+use cid::Cid;
+use clock::ChainEpoch;
+use ipld_blockstore::BlockStore;
+use serde::Serialize;
+use std::error::Error;
+use vm::{ActorState, Serialized, TokenAmount};
+
+/// Payment channel actor method.
+pub type Method = actorv3::paych::Method;
+
+/// Signed voucher which redeems funds from a payment channel.
+pub type SignedVoucher = actorv5::paych::SignedVoucher;
+/// Extra verification method attached to a voucher.
+pub type ModVerifyParams = actorv5::paych::ModVerifyParams;
+/// Lane merge of a voucher.
+pub type Merge = actorv5::paych::Merge;
+/// Payment channel update channel state parameters.
+pub type UpdateChannelStateParams = actorv5::paych::UpdateChannelStateParams;
+/// State of a payment channel lane.
+pub type LaneState = actorv5::paych::LaneState;
+
+/// Payment channel actor state.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum State {
+    V0(actorv0::paych::State),
+    V2(actorv2::paych::State),
+    V3(actorv3::paych::State),
+    V4(actorv4::paych::State),
+    V5(actorv5::paych::State),
+}
+
+impl State {
+    pub fn load<BS>(store: &BS, actor: &ActorState) -> Result<State, Box<dyn Error>>
+    where
+        BS: BlockStore,
+    {
+        if actor.code == *actorv0::PAYCH_ACTOR_CODE_ID {
+            Ok(store
+                .get(&actor.state)?
+                .map(State::V0)
+                .ok_or("Actor state doesn't exist in store")?)
+        } else if actor.code == *actorv2::PAYCH_ACTOR_CODE_ID {
+            Ok(store
+                .get(&actor.state)?
+                .map(State::V2)
+                .ok_or("Actor state doesn't exist in store")?)
+        } else if actor.code == *actorv3::PAYCH_ACTOR_CODE_ID {
+            Ok(store
+                .get(&actor.state)?
+                .map(State::V3)
+                .ok_or("Actor state doesn't exist in store")?)
+        } else if actor.code == *actorv4::PAYCH_ACTOR_CODE_ID {
+            Ok(store
+                .get(&actor.state)?
+                .map(State::V4)
+                .ok_or("Actor state doesn't exist in store")?)
+        } else if actor.code == *actorv5::PAYCH_ACTOR_CODE_ID {
+            Ok(store
+                .get(&actor.state)?
+                .map(State::V5)
+                .ok_or("Actor state doesn't exist in store")?)
+        } else {
+            Err(format!("Unknown actor code {}", actor.code).into())
+        }
+    }
+
+    /// Returns the address which funds the channel.
+    pub fn from(&self) -> Address {
+        match self {
+            State::V0(st) => st.from,
+            State::V2(st) => st.from,
+            State::V3(st) => st.from,
+            State::V4(st) => st.from,
+            State::V5(st) => st.from,
+        }
+    }
+
+    /// Returns the recipient of the channel.
+    pub fn to(&self) -> Address {
+        match self {
+            State::V0(st) => st.to,
+            State::V2(st) => st.to,
+            State::V3(st) => st.to,
+            State::V4(st) => st.to,
+            State::V5(st) => st.to,
+        }
+    }
+
+    /// Returns the amount redeemed through the channel, paid out on collect.
+    pub fn to_send(&self) -> TokenAmount {
+        match self {
+            State::V0(st) => st.to_send.clone(),
+            State::V2(st) => st.to_send.clone(),
+            State::V3(st) => st.to_send.clone(),
+            State::V4(st) => st.to_send.clone(),
+            State::V5(st) => st.to_send.clone(),
+        }
+    }
+
+    /// Returns the epoch at which the channel can be collected, zero if it is not settling.
+    pub fn settling_at(&self) -> ChainEpoch {
+        match self {
+            State::V0(st) => st.settling_at,
+            State::V2(st) => st.settling_at,
+            State::V3(st) => st.settling_at,
+            State::V4(st) => st.settling_at,
+            State::V5(st) => st.settling_at,
+        }
+    }
+
+    /// Returns the lane states of the channel, keyed by lane id.
+    pub fn lane_states<BS: BlockStore>(
+        &self,
+        store: &BS,
+    ) -> Result<Vec<(u64, LaneState)>, Box<dyn Error>> {
+        let (root, version) = match self {
+            State::V0(st) => (&st.lane_states, ActorVersion::V0),
+            State::V2(st) => (&st.lane_states, ActorVersion::V2),
+            State::V3(st) => (&st.lane_states, ActorVersion::V3),
+            State::V4(st) => (&st.lane_states, ActorVersion::V4),
+            State::V5(st) => (&st.lane_states, ActorVersion::V5),
+        };
+        let lanes = Array::<_, LaneState>::load(root, store, version)?;
+        let mut states = Vec::new();
+        lanes.for_each(|lane, state| {
+            states.push((lane, state.clone()));
+            Ok(())
+        })?;
+        Ok(states)
+    }
+}
+
+/// Returns the serialized init actor `Exec` parameters which construct a payment channel actor
+/// of the given actors version.
+pub fn exec_params(
+    version: ActorVersion,
+    from: Address,
+    to: Address,
+) -> Result<Serialized, Box<dyn Error>> {
+    let code_cid: Cid = match version {
+        ActorVersion::V0 => *actorv0::PAYCH_ACTOR_CODE_ID,
+        ActorVersion::V2 => *actorv2::PAYCH_ACTOR_CODE_ID,
+        ActorVersion::V3 => *actorv3::PAYCH_ACTOR_CODE_ID,
+        ActorVersion::V4 => *actorv4::PAYCH_ACTOR_CODE_ID,
+        ActorVersion::V5 => *actorv5::PAYCH_ACTOR_CODE_ID,
+    };
+    Ok(Serialized::serialize(actorv5::init::ExecParams {
+        code_cid,
+        constructor_params: Serialized::serialize(actorv5::paych::ConstructorParams { from, to })?,
+    })?)
+}
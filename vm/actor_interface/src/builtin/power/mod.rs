@@ -3,6 +3,7 @@
 
 use crate::FilterEstimate;
 use address::Address;
+use cid::Cid;
 use fil_types::StoragePower;
 use ipld_blockstore::BlockStore;
 use num_bigint::bigint_ser;
@@ -127,6 +128,17 @@ impl State {
         }
     }
 
+    /// Root of the claims hamt, keyed by miner address.
+    pub fn claims_root(&self) -> &Cid {
+        match self {
+            State::V0(st) => &st.claims,
+            State::V2(st) => &st.claims,
+            State::V3(st) => &st.claims,
+            State::V4(st) => &st.claims,
+            State::V5(st) => &st.claims,
+        }
+    }
+
     /// Loads power for a given miner, if exists.
     pub fn list_all_miners<BS: BlockStore>(&self, s: &BS) -> Result<Vec<Address>, Box<dyn Error>> {
         match self {
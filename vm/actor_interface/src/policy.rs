@@ -1,6 +1,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub use actorv5::{network_policy, NetworkPolicy, NETWORK_POLICY};
 use clock::ChainEpoch;
 
 pub const CHAIN_FINALITY: ChainEpoch = actorv0::miner::CHAIN_FINALITY;
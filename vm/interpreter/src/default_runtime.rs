@@ -3,7 +3,7 @@
 
 use super::gas_block_store::GasBlockStore;
 use super::gas_tracker::{price_list_by_epoch, GasCharge, GasTracker, PriceList};
-use super::{CircSupplyCalc, LookbackStateGetter, Rand};
+use super::{CircSupplyCalc, ExecutionTrace, LookbackStateGetter, Rand};
 use actor::{
     account, actorv0,
     actorv2::{self, ActorDowncast},
@@ -22,7 +22,8 @@ use fil_types::{PieceInfo, RegisteredSealProof, SealVerifyInfo, WindowPoStVerify
 use forest_encoding::{blake2b_256, to_vec, Cbor};
 use ipld_blockstore::BlockStore;
 use log::debug;
-use message::{Message, UnsignedMessage};
+use message::{Message, MessageReceipt, UnsignedMessage};
+use networks::UpgradeSchedule;
 use num_bigint::BigInt;
 use num_traits::Zero;
 use rayon::prelude::*;
@@ -36,6 +37,7 @@ use std::collections::{HashMap, HashSet};
 use std::error::Error as StdError;
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::time::Instant;
 use vm::{
     actor_error, ActorError, ActorState, ExitCode, MethodNum, Serialized, TokenAmount,
     EMPTY_ARR_CID, METHOD_SEND,
@@ -83,6 +85,7 @@ impl MessageInfo for VMMsg {
 /// Implementation of the Runtime trait.
 pub struct DefaultRuntime<'db, 'vm, BS, R, C, LB, V, P = DefaultNetworkParams> {
     version: NetworkVersion,
+    upgrades: &'vm UpgradeSchedule,
     state: &'vm mut StateTree<'db, BS>,
     store: GasBlockStore<'db, BS>,
     gas_tracker: Rc<RefCell<GasTracker>>,
@@ -104,6 +107,9 @@ pub struct DefaultRuntime<'db, 'vm, BS, R, C, LB, V, P = DefaultNetworkParams> {
 
     base_fee: TokenAmount,
 
+    /// Traces of the sends made by the current call, only recorded when tracing.
+    traces: Vec<ExecutionTrace>,
+
     verifier: PhantomData<V>,
     params: PhantomData<P>,
 }
@@ -121,6 +127,7 @@ where
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         version: NetworkVersion,
+        upgrades: &'vm UpgradeSchedule,
         state: &'vm mut StateTree<'db, BS>,
         store: &'db BS,
         gas_used: i64,
@@ -136,7 +143,7 @@ where
         circ_supply_calc: &'vm C,
         lb_state: &'vm LB,
     ) -> Result<Self, ActorError> {
-        let price_list = price_list_by_epoch(epoch);
+        let price_list = price_list_by_epoch(epoch, upgrades);
         let gas_tracker = Rc::new(RefCell::new(GasTracker::new(message.gas_limit(), gas_used)));
         let gas_block_store = GasBlockStore {
             price_list: price_list.clone(),
@@ -170,6 +177,7 @@ where
 
         Ok(DefaultRuntime {
             version,
+            upgrades,
             state,
             store: gas_block_store,
             gas_tracker,
@@ -185,6 +193,7 @@ where
             circ_supply_calc,
             lb_state,
             base_fee,
+            traces: Vec::new(),
             allow_internal: true,
             caller_validated: false,
             params: PhantomData,
@@ -198,6 +207,16 @@ where
         self.gas_tracker.borrow_mut().charge_gas(gas)
     }
 
+    /// Enables recording an execution trace of the sends made through the runtime.
+    pub fn enable_tracing(&mut self) {
+        self.gas_tracker.borrow_mut().enable_tracing()
+    }
+
+    /// Returns the trace of the last send made through the runtime, if tracing is enabled.
+    pub fn take_execution_trace(&mut self) -> Option<ExecutionTrace> {
+        self.traces.pop()
+    }
+
     /// Returns gas used by runtime.
     /// * Will borrow gas tracker RefCell, do not call if a mutable reference exists
     pub fn gas_used(&self) -> i64 {
@@ -333,7 +352,11 @@ where
         let prev_val = self.caller_validated;
         let prev_depth = self.depth;
         let prev_msg = self.vm_msg.clone();
-        let res = self.execute_send(msg, gas_cost);
+        let res = if self.gas_tracker.borrow().is_tracing() {
+            self.traced_execute_send(msg, gas_cost)
+        } else {
+            self.execute_send(msg, gas_cost)
+        };
 
         // Reset values back to their values before the call
         self.vm_msg = prev_msg;
@@ -343,6 +366,43 @@ where
         res
     }
 
+    /// Executes the send while recording its gas charges and subcalls in a new trace, which is
+    /// added to the traces of the caller.
+    fn traced_execute_send(
+        &mut self,
+        msg: &UnsignedMessage,
+        gas_cost: Option<GasCharge>,
+    ) -> Result<Serialized, ActorError> {
+        let parent_charges = self.gas_tracker.borrow_mut().replace_trace(Vec::new());
+        let parent_traces = std::mem::take(&mut self.traces);
+        let gas_before = self.gas_used();
+        let start = Instant::now();
+
+        let res = self.execute_send(msg, gas_cost);
+
+        let duration = start.elapsed();
+        let gas_charges = self.gas_tracker.borrow_mut().replace_trace(parent_charges);
+        let subcalls = std::mem::replace(&mut self.traces, parent_traces);
+        let (return_data, exit_code, error) = match &res {
+            Ok(ret) => (ret.clone(), ExitCode::Ok, None),
+            Err(e) => (Serialized::default(), e.exit_code(), Some(e.to_string())),
+        };
+        self.traces.push(ExecutionTrace {
+            msg: msg.clone(),
+            msg_rct: MessageReceipt {
+                return_data,
+                exit_code,
+                gas_used: self.gas_used() - gas_before,
+            },
+            error,
+            duration,
+            gas_charges,
+            subcalls,
+        });
+
+        res
+    }
+
     /// Helper function to handle all of the execution logic folded into single result.
     /// This is necessary to follow to follow the same control flow of the go implementation
     /// cleanly without doing anything memory unsafe.
@@ -616,7 +676,7 @@ where
         rand_epoch: ChainEpoch,
         entropy: &[u8],
     ) -> Result<Randomness, ActorError> {
-        let r = if rand_epoch > networks::UPGRADE_HYPERDRIVE_HEIGHT {
+        let r = if rand_epoch > self.upgrades.hyperdrive {
             self.rand
                 .get_chain_randomness_looking_forward(personalization, rand_epoch, entropy)
                 .map_err(|e| e.downcast_fatal("could not get randomness"))?
@@ -635,7 +695,7 @@ where
         rand_epoch: ChainEpoch,
         entropy: &[u8],
     ) -> Result<Randomness, ActorError> {
-        let r = if rand_epoch > networks::UPGRADE_HYPERDRIVE_HEIGHT {
+        let r = if rand_epoch > self.upgrades.hyperdrive {
             self.rand
                 .get_beacon_randomness_looking_forward(personalization, rand_epoch, entropy)
                 .map_err(|e| e.downcast_fatal("could not get randomness"))?
@@ -3,7 +3,7 @@
 
 use super::gas_tracker::{GasTracker, PriceList};
 use cid::{Cid, Code};
-use db::{Error, Store};
+use db::{Column, Error, Store};
 use forest_encoding::{de::DeserializeOwned, ser::Serialize, to_vec};
 use ipld_blockstore::BlockStore;
 use std::cell::RefCell;
@@ -92,6 +92,44 @@ where
     {
         self.store.bulk_delete(keys)
     }
+    fn read_column<K>(&self, column: Column, key: K) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.store.read_column(column, key)
+    }
+    fn write_column<K, V>(&self, column: Column, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.store.write_column(column, key, value)
+    }
+    fn delete_column<K>(&self, column: Column, key: K) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.store.delete_column(column, key)
+    }
+    fn exists_column<K>(&self, column: Column, key: K) -> Result<bool, Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.store.exists_column(column, key)
+    }
+    fn bulk_write_column<K, V>(&self, column: Column, values: &[(K, V)]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.store.bulk_write_column(column, values)
+    }
+    fn bulk_delete_column<K>(&self, column: Column, keys: &[K]) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.store.bulk_delete_column(column, keys)
+    }
 }
 
 #[cfg(test)]
@@ -100,6 +138,7 @@ mod tests {
     use crate::price_list_by_epoch;
     use cid::Code::Blake2b256;
     use db::MemoryDB;
+    use networks::NetworkConfig;
     use vm::{ActorError, ExitCode};
 
     #[test]
@@ -110,7 +149,7 @@ mod tests {
                 ipld_get_base: 4,
                 ipld_put_base: 2,
                 ipld_put_per_byte: 1,
-                ..price_list_by_epoch(0)
+                ..price_list_by_epoch(0, &NetworkConfig::mainnet().upgrades)
             },
             gas: Rc::new(RefCell::new(GasTracker::new(5000, 0))),
             store: &db,
@@ -129,7 +168,7 @@ mod tests {
         let gbs = GasBlockStore {
             price_list: PriceList {
                 ipld_put_base: 12,
-                ..price_list_by_epoch(0)
+                ..price_list_by_epoch(0, &NetworkConfig::mainnet().upgrades)
             },
             gas: Rc::new(RefCell::new(GasTracker::new(10, 0))),
             store: &db,
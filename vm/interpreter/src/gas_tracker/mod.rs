@@ -6,11 +6,16 @@ mod price_list;
 
 pub use self::gas_charge::GasCharge;
 pub use self::price_list::{price_list_by_epoch, PriceList};
+use crate::GasTrace;
+use std::time::Instant;
 use vm::{actor_error, ActorError, ExitCode};
 
 pub(crate) struct GasTracker {
     gas_available: i64,
     gas_used: i64,
+    /// Charges recorded for the current call, only set when tracing.
+    trace: Option<Vec<GasTrace>>,
+    last_charge: Instant,
 }
 
 impl GasTracker {
@@ -18,12 +23,46 @@ impl GasTracker {
         Self {
             gas_available,
             gas_used,
+            trace: None,
+            last_charge: Instant::now(),
+        }
+    }
+
+    /// Enables recording the gas charges.
+    pub fn enable_tracing(&mut self) {
+        self.trace = Some(Vec::new());
+        self.last_charge = Instant::now();
+    }
+
+    /// Returns true if gas charges are being recorded.
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Replaces the recorded gas charges, returns the charges recorded so far.
+    pub fn replace_trace(&mut self, charges: Vec<GasTrace>) -> Vec<GasTrace> {
+        self.last_charge = Instant::now();
+        match &mut self.trace {
+            Some(trace) => std::mem::replace(trace, charges),
+            None => Vec::new(),
         }
     }
 
     /// Safely consumes gas and returns an out of gas error if there is not sufficient
     /// enough gas remaining for charge.
     pub fn charge_gas(&mut self, charge: GasCharge) -> Result<(), ActorError> {
+        if let Some(trace) = &mut self.trace {
+            let now = Instant::now();
+            trace.push(GasTrace {
+                name: charge.name.to_owned(),
+                total_gas: charge.total(),
+                compute_gas: charge.compute_gas,
+                storage_gas: charge.storage_gas,
+                time_taken: now - self.last_charge,
+            });
+            self.last_charge = now;
+        }
+
         let to_use = charge.total();
         let used = self.gas_used + to_use;
         if used > self.gas_available {
@@ -62,4 +101,22 @@ mod tests {
         assert_eq!(t.gas_used(), 20);
         assert!(t.charge_gas(GasCharge::new("", 1, 0)).is_err())
     }
+
+    #[test]
+    fn gas_tracker_trace() {
+        let mut t = GasTracker::new(20, 0);
+        t.charge_gas(GasCharge::new("untraced", 1, 0)).unwrap();
+        t.enable_tracing();
+        t.charge_gas(GasCharge::new("first", 2, 1)).unwrap();
+        let parent = t.replace_trace(Vec::new());
+        t.charge_gas(GasCharge::new("second", 4, 0)).unwrap();
+        let child = t.replace_trace(parent);
+
+        let names = |trace: &[GasTrace]| trace.iter().map(|g| g.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&child), vec!["second"]);
+        let parent = t.replace_trace(Vec::new());
+        assert_eq!(names(&parent), vec!["first"]);
+        assert_eq!(parent[0].total_gas, 3);
+        assert_eq!(t.gas_used(), 8);
+    }
 }
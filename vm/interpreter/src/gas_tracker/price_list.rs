@@ -9,7 +9,7 @@ use fil_types::{
     AggregateSealVerifyProofAndInfos, PieceInfo, RegisteredPoStProof, RegisteredSealProof,
     SealVerifyInfo, WindowPoStVerifyInfo,
 };
-use networks::UPGRADE_CALICO_HEIGHT;
+use networks::UpgradeSchedule;
 use num_traits::Zero;
 use vm::{MethodNum, TokenAmount, METHOD_SEND};
 
@@ -508,8 +508,8 @@ impl PriceList {
 }
 
 /// Returns gas price list by Epoch for gas consumption.
-pub fn price_list_by_epoch(epoch: ChainEpoch) -> PriceList {
-    if epoch < UPGRADE_CALICO_HEIGHT {
+pub fn price_list_by_epoch(epoch: ChainEpoch, upgrades: &UpgradeSchedule) -> PriceList {
+    if epoch < upgrades.calico {
         BASE_PRICES.clone()
     } else {
         CALICO_PRICES.clone()
@@ -8,9 +8,11 @@ mod default_runtime;
 mod gas_block_store;
 mod gas_tracker;
 mod rand;
+pub mod trace;
 mod vm;
 
 pub use self::default_runtime::*;
 pub use self::gas_tracker::*;
 pub use self::rand::*;
+pub use self::trace::{ExecutionTrace, GasTrace};
 pub use self::vm::*;
@@ -0,0 +1,153 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use message::{MessageReceipt, UnsignedMessage};
+use std::time::Duration;
+
+/// Gas charge recorded while tracing the execution of a message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasTrace {
+    pub name: String,
+    pub total_gas: i64,
+    pub compute_gas: i64,
+    pub storage_gas: i64,
+    /// Time elapsed since the previous charge of the same call.
+    pub time_taken: Duration,
+}
+
+/// Trace of the execution of a message, including the internal sends made by the actors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionTrace {
+    pub msg: UnsignedMessage,
+    pub msg_rct: MessageReceipt,
+    pub error: Option<String>,
+    pub duration: Duration,
+    pub gas_charges: Vec<GasTrace>,
+    pub subcalls: Vec<ExecutionTrace>,
+}
+
+#[cfg(feature = "json")]
+pub mod json {
+    use super::*;
+    use message::{message_receipt, unsigned_message};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Wrapper for serializing and deserializing an ExecutionTrace from JSON.
+    #[derive(Deserialize, Serialize)]
+    #[serde(transparent)]
+    pub struct ExecutionTraceJson(#[serde(with = "self")] pub ExecutionTrace);
+
+    /// Wrapper for serializing an ExecutionTrace reference to JSON.
+    #[derive(Serialize)]
+    #[serde(transparent)]
+    pub struct ExecutionTraceJsonRef<'a>(#[serde(with = "self")] pub &'a ExecutionTrace);
+
+    impl From<ExecutionTraceJson> for ExecutionTrace {
+        fn from(wrapper: ExecutionTraceJson) -> Self {
+            wrapper.0
+        }
+    }
+
+    /// Gas trace in the format used by Lotus, durations are in nanoseconds.
+    #[derive(Serialize, Deserialize)]
+    struct GasTraceJson {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "tg")]
+        total_gas: i64,
+        #[serde(rename = "cg")]
+        compute_gas: i64,
+        #[serde(rename = "sg")]
+        storage_gas: i64,
+        #[serde(rename = "tt")]
+        time_taken: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct JsonHelper {
+        #[serde(with = "unsigned_message::json")]
+        msg: UnsignedMessage,
+        #[serde(with = "message_receipt::json")]
+        msg_rct: MessageReceipt,
+        error: Option<String>,
+        duration: u64,
+        gas_charges: Vec<GasTraceJson>,
+        subcalls: Vec<ExecutionTraceJson>,
+    }
+
+    pub fn serialize<S>(trace: &ExecutionTrace, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        JsonHelper {
+            msg: trace.msg.clone(),
+            msg_rct: trace.msg_rct.clone(),
+            error: trace.error.clone(),
+            duration: trace.duration.as_nanos() as u64,
+            gas_charges: trace
+                .gas_charges
+                .iter()
+                .map(|g| GasTraceJson {
+                    name: g.name.clone(),
+                    total_gas: g.total_gas,
+                    compute_gas: g.compute_gas,
+                    storage_gas: g.storage_gas,
+                    time_taken: g.time_taken.as_nanos() as u64,
+                })
+                .collect(),
+            subcalls: trace
+                .subcalls
+                .iter()
+                .cloned()
+                .map(ExecutionTraceJson)
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<ExecutionTrace, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let trace: JsonHelper = Deserialize::deserialize(deserializer)?;
+        Ok(ExecutionTrace {
+            msg: trace.msg,
+            msg_rct: trace.msg_rct,
+            error: trace.error,
+            duration: Duration::from_nanos(trace.duration),
+            gas_charges: trace
+                .gas_charges
+                .into_iter()
+                .map(|g| GasTrace {
+                    name: g.name,
+                    total_gas: g.total_gas,
+                    compute_gas: g.compute_gas,
+                    storage_gas: g.storage_gas,
+                    time_taken: Duration::from_nanos(g.time_taken),
+                })
+                .collect(),
+            subcalls: trace.subcalls.into_iter().map(|s| s.0).collect(),
+        })
+    }
+
+    pub mod opt {
+        use super::{ExecutionTrace, ExecutionTraceJson, ExecutionTraceJsonRef};
+        use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S>(v: &Option<ExecutionTrace>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            v.as_ref().map(ExecutionTraceJsonRef).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<ExecutionTrace>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let v: Option<ExecutionTraceJson> = Deserialize::deserialize(deserializer)?;
+            Ok(v.map(|v| v.0))
+        }
+    }
+}
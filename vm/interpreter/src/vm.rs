@@ -3,7 +3,7 @@
 
 use super::{
     gas_tracker::{price_list_by_epoch, GasCharge},
-    DefaultRuntime, Rand,
+    DefaultRuntime, ExecutionTrace, Rand,
 };
 use actor::{
     actorv0::reward::AwardBlockRewardParams, cron, miner, reward, system, BURNT_FUNDS_ACTOR_ADDR,
@@ -21,7 +21,7 @@ use forest_encoding::Cbor;
 use ipld_blockstore::BlockStore;
 use log::debug;
 use message::{ChainMessage, Message, MessageReceipt, UnsignedMessage};
-use networks::{UPGRADE_ACTORS_V4_HEIGHT, UPGRADE_CLAUS_HEIGHT};
+use networks::UpgradeSchedule;
 use num_bigint::{BigInt, Sign};
 use num_traits::Zero;
 use state_tree::StateTree;
@@ -70,8 +70,10 @@ pub struct VM<'db, 'r, DB, R, N, C, LB, V = FullVerifier, P = DefaultNetworkPara
     base_fee: BigInt,
     registered_actors: HashSet<Cid>,
     network_version_getter: N,
+    upgrades: &'r UpgradeSchedule,
     circ_supply_calc: &'r C,
     lb_state: &'r LB,
+    tracing: bool,
     verifier: PhantomData<V>,
     params: PhantomData<P>,
 }
@@ -94,6 +96,7 @@ where
         rand: &'r R,
         base_fee: BigInt,
         network_version_getter: N,
+        upgrades: &'r UpgradeSchedule,
         circ_supply_calc: &'r C,
         lb_state: &'r LB,
     ) -> Result<Self, String> {
@@ -101,6 +104,7 @@ where
         let registered_actors = HashSet::new();
         Ok(VM {
             network_version_getter,
+            upgrades,
             state,
             store,
             epoch,
@@ -109,11 +113,18 @@ where
             registered_actors,
             circ_supply_calc,
             lb_state,
+            tracing: false,
             verifier: PhantomData,
             params: PhantomData,
         })
     }
 
+    /// Enables recording an execution trace for each message applied, which is returned in
+    /// the [ApplyRet] of the message.
+    pub fn enable_tracing(&mut self) {
+        self.tracing = true;
+    }
+
     /// Registers an actor that is not part of the set of default builtin actors by providing the
     /// code cid.
     pub fn register_actor(&mut self, code_cid: Cid) -> bool {
@@ -180,7 +191,7 @@ where
         store: Arc<impl BlockStore + Send + Sync>,
     ) -> Result<Option<Cid>, Box<dyn StdError>> {
         match epoch {
-            x if x == UPGRADE_ACTORS_V4_HEIGHT => {
+            x if x == self.upgrades.actors_v4 => {
                 let start = std::time::Instant::now();
                 log::info!("Running actors_v4 state migration");
                 // need to flush since we run_cron before the migration
@@ -218,18 +229,7 @@ where
         let mut receipts = Vec::new();
         let mut processed = HashSet::<Cid>::default();
 
-        for i in parent_epoch..epoch {
-            if i > parent_epoch {
-                // run cron for null rounds if any
-                if let Err(e) = self.run_cron(i, callback.as_mut()) {
-                    log::error!("Beginning of epoch cron failed to run: {}", e);
-                }
-            }
-            if let Some(new_state) = self.migrate_state(i, store.clone())? {
-                self.state = StateTree::new_from_root(self.store, &new_state)?
-            }
-            self.epoch = i + 1;
-        }
+        self.run_null_rounds(parent_epoch, epoch, store, callback.as_mut())?;
 
         for block in messages.iter() {
             let mut penalty = Default::default();
@@ -312,9 +312,34 @@ where
         Ok(receipts)
     }
 
+    /// Runs the cron of the null rounds between `parent_epoch` and `epoch` and the state
+    /// migrations scheduled from `parent_epoch` up to `epoch`, leaving the VM at `epoch`.
+    pub fn run_null_rounds(
+        &mut self,
+        parent_epoch: ChainEpoch,
+        epoch: ChainEpoch,
+        store: std::sync::Arc<impl BlockStore + Send + Sync>,
+        mut callback: Option<&mut impl FnMut(&Cid, &ChainMessage, &ApplyRet) -> Result<(), String>>,
+    ) -> Result<(), Box<dyn StdError>> {
+        for i in parent_epoch..epoch {
+            if i > parent_epoch {
+                // run cron for null rounds if any
+                if let Err(e) = self.run_cron(i, callback.as_deref_mut()) {
+                    log::error!("Beginning of epoch cron failed to run: {}", e);
+                }
+            }
+            if let Some(new_state) = self.migrate_state(i, store.clone())? {
+                self.state = StateTree::new_from_root(self.store, &new_state)?
+            }
+            self.epoch = i + 1;
+        }
+        Ok(())
+    }
+
     /// Applies single message through vm and returns result from execution.
     pub fn apply_implicit_message(&mut self, msg: &UnsignedMessage) -> ApplyRet {
-        let (return_data, _, act_err) = self.send(msg, None);
+        let (return_data, rt, act_err) = self.send(msg, None);
+        let exec_trace = rt.and_then(|mut rt| rt.take_execution_trace());
 
         ApplyRet {
             msg_receipt: MessageReceipt {
@@ -329,6 +354,7 @@ where
             act_error: act_err,
             penalty: BigInt::zero(),
             miner_tip: BigInt::zero(),
+            exec_trace,
         }
     }
 
@@ -337,7 +363,7 @@ where
     pub fn apply_message(&mut self, msg: &ChainMessage) -> Result<ApplyRet, String> {
         check_message(msg.message())?;
 
-        let pl = price_list_by_epoch(self.epoch());
+        let pl = price_list_by_epoch(self.epoch(), self.upgrades);
         let ser_msg = msg.marshal_cbor().map_err(|e| e.to_string())?;
         let msg_gas_cost = pl.on_chain_message(ser_msg.len());
         let cost_total = msg_gas_cost.total();
@@ -354,6 +380,7 @@ where
                     "Out of gas ({} > {})", cost_total, msg.gas_limit())),
                 penalty: &self.base_fee * cost_total,
                 miner_tip: BigInt::zero(),
+                exec_trace: None,
             });
         }
 
@@ -371,6 +398,7 @@ where
                     penalty: miner_penalty_amount,
                     act_error: Some(actor_error!(SysErrSenderInvalid; "Sender invalid")),
                     miner_tip: 0.into(),
+                    exec_trace: None,
                 });
             }
         };
@@ -386,6 +414,7 @@ where
                 penalty: miner_penalty_amount,
                 act_error: Some(actor_error!(SysErrSenderInvalid; "send not from account actor")),
                 miner_tip: 0.into(),
+                exec_trace: None,
             });
         };
 
@@ -401,6 +430,7 @@ where
                 act_error: Some(actor_error!(SysErrSenderStateInvalid;
                     "actor sequence invalid: {} != {}", msg.sequence(), from_act.sequence)),
                 miner_tip: 0.into(),
+                exec_trace: None,
             });
         };
 
@@ -417,6 +447,7 @@ where
                 act_error: Some(actor_error!(SysErrSenderStateInvalid;
                     "actor balance less than needed: {} < {}", from_act.balance, gas_cost)),
                 miner_tip: 0.into(),
+                exec_trace: None,
             });
         };
 
@@ -462,7 +493,7 @@ where
             }
         }
 
-        let gas_used = if let Some(mut rt) = rt {
+        let (gas_used, exec_trace) = if let Some(mut rt) = rt {
             if !ret_data.is_empty() {
                 if let Err(e) = rt.charge_gas(rt.price_list().on_chain_return_value(ret_data.len()))
                 {
@@ -470,11 +501,8 @@ where
                     ret_data = Serialized::default();
                 }
             }
-            if rt.gas_used() < 0 {
-                0
-            } else {
-                rt.gas_used()
-            }
+            let gas_used = if rt.gas_used() < 0 { 0 } else { rt.gas_used() };
+            (gas_used, rt.take_execution_trace())
         } else {
             return Err(format!("send returned None runtime: {:?}", act_err));
         };
@@ -550,6 +578,7 @@ where
             penalty: miner_penalty,
             act_error: act_err,
             miner_tip,
+            exec_trace,
         })
     }
 
@@ -564,8 +593,10 @@ where
         Option<DefaultRuntime<'db, '_, DB, R, C, LB, V, P>>,
         Option<ActorError>,
     ) {
+        let tracing = self.tracing;
         let res = DefaultRuntime::new(
             (self.network_version_getter)(self.epoch),
+            self.upgrades,
             &mut self.state,
             self.store,
             0,
@@ -583,10 +614,15 @@ where
         );
 
         match res {
-            Ok(mut rt) => match rt.send(msg, gas_cost) {
-                Ok(ser) => (ser, Some(rt), None),
-                Err(actor_err) => (Serialized::default(), Some(rt), Some(actor_err)),
-            },
+            Ok(mut rt) => {
+                if tracing {
+                    rt.enable_tracing();
+                }
+                match rt.send(msg, gas_cost) {
+                    Ok(ser) => (ser, Some(rt), None),
+                    Err(actor_err) => (Serialized::default(), Some(rt), Some(actor_err)),
+                }
+            }
             Err(e) => (Serialized::default(), None, Some(e)),
         }
     }
@@ -597,11 +633,11 @@ where
         msg: &ChainMessage,
         exit_code: ExitCode,
     ) -> Result<bool, Box<dyn StdError>> {
-        if self.epoch <= UPGRADE_ACTORS_V4_HEIGHT {
+        if self.epoch <= self.upgrades.actors_v4 {
             // Check to see if we should burn funds. We avoid burning on successful
             // window post. This won't catch _indirect_ window post calls, but this
             // is the best we can get for now.
-            if self.epoch > UPGRADE_CLAUS_HEIGHT
+            if self.epoch > self.upgrades.claus
                 && exit_code.is_success()
                 && msg.method_num() == miner::Method::SubmitWindowedPoSt as u64
             {
@@ -734,6 +770,8 @@ pub struct ApplyRet {
     pub penalty: BigInt,
     /// Tip given to miner from message.
     pub miner_tip: BigInt,
+    /// Trace of the execution of the message, if tracing is enabled in the VM.
+    pub exec_trace: Option<ExecutionTrace>,
 }
 
 /// Does some basic checks on the Message to see if the fields are valid.
@@ -12,6 +12,7 @@ use interpreter::{CircSupplyCalc, DefaultRuntime, LookbackStateGetter, Rand};
 use ipld_blockstore::BlockStore;
 use ipld_hamt::Hamt;
 use message::UnsignedMessage;
+use networks::NetworkConfig;
 use state_tree::StateTree;
 use std::collections::HashSet;
 use std::error::Error as StdError;
@@ -144,8 +145,10 @@ fn transfer_test() {
     let registered = HashSet::new();
 
     let lookback = MockStateLB(&store);
+    let upgrades = NetworkConfig::mainnet().upgrades;
     let mut runtime = DefaultRuntime::<_, _, _, _, MockVerifier>::new(
         NetworkVersion::V0,
+        &upgrades,
         &mut state,
         &store,
         0,